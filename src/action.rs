@@ -2,7 +2,7 @@ use {
     super::component_name::ComponentName,
     crate::{
         app_error::AppError,
-        tg::td_enums::{TdChatList, TdMessageReplyToMessage},
+        tg::td_enums::{TdChatList, TdFile, TdMessageReplyToMessage},
     },
     crossterm::event::{KeyCode, KeyModifiers},
     ratatui::layout::Rect,
@@ -102,20 +102,247 @@ pub enum Action {
     /// SendMessageEdited action with a `i64` and a `String`.
     /// The first parameter is the `message_id` and the second parameter is the `text`.
     SendMessageEdited(i64, String),
-    /// GetChatHistory action.
-    GetChatHistory,
+    /// SendPhoto action with a `String`, a `String` and an optional
+    /// `TdMessageReplyToMessage`.
+    /// The first parameter is the path of the photo and the second one is the
+    /// caption.
+    SendPhoto(String, String, Option<TdMessageReplyToMessage>),
+    /// GetChatHistory action with a `i64`.
+    /// The parameter is the `chat_id` of the chat whose history is loaded.
+    GetChatHistory(i64),
     /// DeleteMessages action.
     /// The first parameter is the `message_ids` and the second parameter is the `revoke`.
     /// If `revoke` is true, the message will be deleted for everyone.
     /// If `revoke` is false, the message will be deleted only for the current user.
     DeleteMessages(Vec<i64>, bool),
+    /// ForwardMessages action.
+    /// The first parameter is the `chat_id` of the chat to forward the
+    /// messages to, the second parameter is the `from_chat_id` and the third
+    /// parameter is the `message_ids`.
+    ForwardMessages(i64, i64, Vec<i64>),
     /// ViewAllMessages action.
     ViewAllMessages,
+    /// ViewMessages action with an `i64` and a `Vec<i64>`.
+    /// It is used to mark as read the messages shown in the chat window.
+    /// The first parameter is the `chat_id` and the second one is the list
+    /// of `message_id`.
+    ViewMessages(i64, Vec<i64>),
+    /// OpenChat action with a `i64`.
+    /// It is used to inform Telegram that a chat has been opened, and to load
+    /// the information shown in the header of the chat.
+    /// The parameter is the `chat_id`.
+    OpenChat(i64),
+    /// SetAutoDeleteTime action with a `i64` and a `i32`.
+    /// It is used to set the time after which the new messages of a chat are
+    /// deleted.
+    /// The first parameter is the `chat_id` and the second one is the time
+    /// in seconds, 0 to disable it.
+    SetAutoDeleteTime(i64, i32),
+    /// PinMessage action with two `i64` and a `bool`.
+    /// It is used to pin a message of a chat.
+    /// The first parameter is the `chat_id`, the second one is the
+    /// `message_id` and the third one is whether to notify the members of the
+    /// group.
+    PinMessage(i64, i64, bool),
+    /// UnpinMessage action with two `i64`.
+    /// It is used to unpin a message of a chat.
+    /// The first parameter is the `chat_id` and the second one is the
+    /// `message_id`.
+    UnpinMessage(i64, i64),
+    /// GetMessageViewers action with two `i64`.
+    /// It is used to get the users that have read a message of a group.
+    /// The first parameter is the `chat_id` and the second one is the
+    /// `message_id`.
+    GetMessageViewers(i64, i64),
+    /// ClearChatHistory action with a `i64` and two `bool`.
+    /// It is used to delete all the messages of a chat.
+    /// The first parameter is the `chat_id`, the second one is whether to
+    /// remove the chat from the chat list too and the third one is whether to
+    /// delete the messages for the other users too.
+    ClearChatHistory(i64, bool, bool),
+    /// ToggleBlockUser action with a `i64` and a `bool`.
+    /// It is used to block a user, or to unblock them if they are blocked.
+    /// The first parameter is the `user_id` and the second one is whether to
+    /// report the private chat with the user as spam when blocking them.
+    ToggleBlockUser(i64, bool),
+    /// JoinChat action with a `i64`.
+    /// It is used to join a chat.
+    /// The parameter is the `chat_id`.
+    JoinChat(i64),
+    /// JoinChatByLink action with a `String`.
+    /// It is used to join a chat by the username of a public chat or by an
+    /// invite link.
+    /// The parameter is the username or the link.
+    JoinChatByLink(String),
+    /// OpenByUsername action with a `String`.
+    /// It is used to open a public chat by its username, or by a link to it.
+    /// The parameter is the username or the link.
+    OpenByUsername(String),
+    /// GetMessageLink action with two `i64`.
+    /// It is used to copy the link of a message to the clipboard.
+    /// The first parameter is the `chat_id` and the second one is the
+    /// `message_id`.
+    GetMessageLink(i64, i64),
+    /// LoadScheduledMessages action with an `i64`.
+    /// It is used to load the scheduled messages of a chat.
+    /// The parameter is the `chat_id`.
+    LoadScheduledMessages(i64),
+    /// SendScheduledMessageNow action with two `i64`.
+    /// It is used to send a scheduled message immediately.
+    /// The first parameter is the `chat_id` and the second one is the
+    /// `message_id`.
+    SendScheduledMessageNow(i64, i64),
+    /// LoadChatMembers action with an `i64`.
+    /// It is used to load the members of a group shown by the member list.
+    /// The parameter is the `chat_id`.
+    LoadChatMembers(i64),
+    /// OpenPrivateChat action with an `i64`.
+    /// It is used to open the private chat with a user, creating it if
+    /// needed.
+    /// The parameter is the `user_id`.
+    OpenPrivateChat(i64),
+    /// LoadStickerSets action.
+    /// It is used to load the favorite and the recent stickers and the
+    /// installed sticker sets shown by the sticker picker.
+    LoadStickerSets,
+    /// SendSticker action with two `i64`.
+    /// It is used to send a sticker to a chat.
+    /// The first parameter is the `chat_id` and the second one is the id of
+    /// the sticker.
+    SendSticker(i64, i64),
+    /// PlayVoiceNote action with a `i64`.
+    /// It is used to play the voice note of a message of the open chat, or to
+    /// stop it if it is being played.
+    /// The parameter is the `message_id`.
+    PlayVoiceNote(i64),
+    /// OpenMedia action with a `i64`.
+    /// It is used to open the media of a message of the open chat,
+    /// downloading it first if needed: the voice notes and the audio files
+    /// are played, the other media are opened with the default application
+    /// of the system.
+    /// The parameter is the `message_id`.
+    OpenMedia(i64),
+    /// PlayAudio action with a `i64` and a `String`.
+    /// It is used to play an audio file of a message once it is downloaded.
+    /// The first parameter is the `message_id` and the second one is the
+    /// local path of the file.
+    PlayAudio(i64, String),
+    /// FileProgress action with a `TdFile`.
+    /// It is used to update the progress of a download shown by the panel
+    /// of the downloads.
+    FileProgress(TdFile),
+    /// SaveContact action with a `i64`.
+    /// It is used to add the contact shared by a message of the open chat to
+    /// the contacts of the user.
+    /// The parameter is the `message_id`.
+    SaveContact(i64),
+    /// DataChanged action.
+    /// It is used to redraw the user interface after a batch of updates
+    /// received from TDLib has been applied.
+    DataChanged,
+    /// PressInlineButton action with an `i64` and a `String`.
+    /// It is used to send the callback query of a button of the inline
+    /// keyboard of a message of the open chat to its bot.
+    /// The parameters are the `message_id` and the data of the button.
+    PressInlineButton(i64, String),
+    /// CancelDownload action with an `i32`.
+    /// It is used to cancel the download of a file.
+    /// The parameter is the `file_id`.
+    CancelDownload(i32),
+    /// LoadSessions action.
+    /// It is used to load the active sessions of the account.
+    LoadSessions,
+    /// TerminateSession action with an `i64`.
+    /// It is used to terminate another session of the account.
+    /// The parameter is the `session_id`.
+    TerminateSession(i64),
+    /// LeaveChat action with a `i64`.
+    /// It is used to leave a chat.
+    /// The parameter is the `chat_id`.
+    LeaveChat(i64),
+    /// CreateSecretChat action with a `i64`.
+    /// It is used to start a secret chat with a user.
+    /// The parameter is the `user_id`.
+    CreateSecretChat(i64),
+    /// SetOnline action with a `bool`.
+    /// It is used to set the presence of the current user.
+    /// If the parameter is true, the user goes online, otherwise offline.
+    SetOnline(bool),
+    /// ToggleOnline action.
+    /// It is used to toggle the presence of the current user.
+    ToggleOnline,
+    /// SubmitAuthorization action with a `Vec<String>`.
+    /// It is used to send the input requested by the current authorization
+    /// state, one value for each input field.
+    SubmitAuthorization(Vec<String>),
+    /// TryLogOut action, it is used to try to log out.
+    /// It asks the core window to show the log out confirmation.
+    TryLogOut,
+    /// LogOut action.
+    /// It is used to log out, delete the local data of the session and log in
+    /// again.
+    LogOut,
+    /// Notify action with a `String` and a `String`.
+    /// It is used to show a desktop notification for a message received in a
+    /// chat that is not open.
+    /// The first parameter is the title of the chat and the second one is the
+    /// preview of the message.
+    Notify(String, String),
+    /// ToggleDoNotDisturb action.
+    /// It is used to enable or disable the notifications for the current
+    /// session.
+    ToggleDoNotDisturb,
+    /// TogglePrivacyMode action.
+    /// It is used to obscure or reveal the contents of the messages on the
+    /// screen.
+    TogglePrivacyMode,
+    /// SendScheduledMessage action with an `i64`, a `String` and an `i32`.
+    /// It is used to schedule a message.
+    /// The parameters are the `chat_id`, the text of the message and the
+    /// Unix time the message is sent at.
+    SendScheduledMessage(i64, String, i32),
+    /// SetChatTheme action with an optional `String`.
+    /// It is used to set the theme of the open chat.
+    /// The parameter is the name of the file of the theme, `None` to use the
+    /// default theme.
+    SetChatTheme(Option<String>),
+    /// SetAlias action with an `i64` and an optional `String`.
+    /// It is used to set the alias of a user.
+    /// The parameters are the `user_id` and the alias, `None` to remove it.
+    SetAlias(i64, Option<String>),
+    /// ReloadConfig action.
+    /// It is used to reload the configuration files without restarting the
+    /// application.
+    ReloadConfig,
+    /// ToggleBell action.
+    /// It is used to enable or disable the terminal bell on new messages for
+    /// the current session.
+    ToggleBell,
+    /// ToggleStatusBar action.
+    /// It shows or hides the status bar, the choice is saved.
+    ToggleStatusBar,
+    /// ToggleTitleBar action.
+    /// It shows or hides the title bar, the choice is saved.
+    ToggleTitleBar,
+    /// Bell action with a `bool`.
+    /// It is used to ring the terminal bell for a message received in a chat
+    /// that is not muted.
+    /// The parameter is whether the message has been received in the open
+    /// chat.
+    Bell(bool),
 
     /// Focus action with a `ComponentName`.
     FocusComponent(ComponentName),
     /// Unfocus action.
     UnfocusComponent,
+    /// FocusNext action.
+    /// It focuses the next component in the focus cycle of the
+    /// configuration.
+    FocusNext,
+    /// FocusPrevious action.
+    /// It focuses the previous component in the focus cycle of the
+    /// configuration.
+    FocusPrevious,
     /// Toggle ChatList action.
     ToggleChatList,
     /// Increase ChatList size action.
@@ -130,10 +357,21 @@ pub enum Action {
     Key(KeyCode, Modifiers),
     /// Update area action with a rectangular area.
     UpdateArea(Rect),
+    /// MouseClick action with the column and the row of a left click.
+    /// It is sent to the focused component after the `CoreWindow` has
+    /// focused the component under the click.
+    MouseClick(u16, u16),
     /// ShowChatWindowReply action.
     ShowChatWindowReply,
     /// HideChatWindowReply action.
     HideChatWindowReply,
+    /// CancelReply action.
+    /// It stops replying to a message, hiding the preview of the message.
+    CancelReply,
+    /// ShowChatWindowQuoteReply action.
+    /// It is like `ShowChatWindowReply`, but the text of the selected message
+    /// is quoted in the prompt.
+    ShowChatWindowQuoteReply,
 
     /// ChatListNext action.
     ChatListNext,
@@ -143,11 +381,186 @@ pub enum Action {
     ChatListUnselect,
     /// ChatListOpen action.
     ChatListOpen,
+    /// ChatListJoin action.
+    /// It joins the selected chat.
+    ChatListJoin,
+    /// ChatListLeave action.
+    /// It asks to confirm leaving the selected chat.
+    ChatListLeave,
+    /// ChatListClearHistory action.
+    /// It asks to confirm the deletion of the history of the selected chat.
+    ChatListClearHistory,
+    /// ChatListCreateSecretChat action.
+    /// It starts a secret chat with the user of the selected private chat.
+    ChatListCreateSecretChat,
+    /// JumpToChatIndex action with the index of a chat in the chat list.
+    /// It is used to select and open the chat, it is parsed from the
+    /// `jump_to_chat_N` commands, where `N` starts from 1.
+    JumpToChatIndex(usize),
+    /// JumpToChat action with the id of a chat.
+    /// It is used to select and open the chat in the chat list.
+    JumpToChat(i64),
+    /// ShowRecentChats action.
+    /// It shows the switcher of the recently opened chats, or selects the
+    /// next chat of the switcher if it is already shown.
+    ShowRecentChats,
+    /// ShowStickerPicker action.
+    /// It shows the sticker picker for the open chat.
+    ShowStickerPicker,
+    /// HideStickerPicker action.
+    /// It hides the sticker picker.
+    HideStickerPicker,
+    /// ShowScheduledMessages action.
+    /// It shows the panel of the scheduled messages of the open chat.
+    ShowScheduledMessages,
+    /// HideScheduledMessages action.
+    /// It hides the panel of the scheduled messages.
+    HideScheduledMessages,
+    /// ShowMemberList action.
+    /// It shows the member list of the open group.
+    ShowMemberList,
+    /// HideMemberList action.
+    /// It hides the member list.
+    HideMemberList,
+    /// ShowDownloads action.
+    /// It shows the panel of the downloads.
+    ShowDownloads,
+    /// HideDownloads action.
+    /// It hides the panel of the downloads.
+    HideDownloads,
+    /// ShowSessions action.
+    /// It shows the panel of the account and of its active sessions.
+    ShowSessions,
+    /// HideSessions action.
+    /// It hides the panel of the sessions.
+    HideSessions,
+    /// ShowKeymap action.
+    /// It shows the panel of the key bindings, where the keys can be rebound.
+    ShowKeymap,
+    /// HideKeymap action.
+    /// It hides the panel of the key bindings.
+    HideKeymap,
+    /// ChatListFilter action.
+    /// It is used to start typing a filter of the chats by name, the keys are
+    /// used by the chat list until `ChatListFilterDone`.
+    ChatListFilter,
+    /// ChatListFilterDone action.
+    /// It is sent by the chat list when the filter is no longer typed.
+    ChatListFilterDone,
+    /// ChatListCycleSort action.
+    /// It is used to switch to the next order of the chats, see
+    /// `ChatListSort`.
+    ChatListCycleSort,
+    /// ChatListNextFolder action.
+    /// It is used to show the chats of the next chat folder.
+    ChatListNextFolder,
+    /// ChatListPreviousFolder action.
+    /// It is used to show the chats of the previous chat folder.
+    ChatListPreviousFolder,
+    /// ChatListNextUnread action.
+    /// It is used to select the next chat with unread messages, wrapping
+    /// around the end of the list.
+    ChatListNextUnread,
+    /// ChatListPreviousUnread action.
+    /// It is used to select the previous chat with unread messages, wrapping
+    /// around the start of the list.
+    ChatListPreviousUnread,
+    /// SwitchChatFolder action with an `Option<i32>`.
+    /// It is used to show only the chats of a chat folder.
+    /// The parameter is the `chat_folder_id`, `None` for all the chats.
+    SwitchChatFolder(Option<i32>),
 
     /// ChatWindowNext action.
     ChatWindowNext,
     /// ChatWindowPrevious action.
     ChatWindowPrevious,
+    /// ChatWindowGoToTop action.
+    /// It selects the oldest loaded message and loads the older ones.
+    ChatWindowGoToTop,
+    /// ChatWindowGoToBottom action.
+    /// It selects the newest message.
+    ChatWindowGoToBottom,
+    /// ChatWindowToggleSelection action.
+    /// It starts or stops the selection of a range of messages, that is
+    /// extended by moving the selected message.
+    ChatWindowToggleSelection,
+    /// ChatWindowForward action.
+    /// It asks to choose the chat to forward the selected messages to.
+    ChatWindowForward,
+    /// ChatWindowJumpToPinned action.
+    /// It selects the pinned message shown in the banner of the chat, and
+    /// shows the next pinned message in the banner.
+    ChatWindowJumpToPinned,
+    /// ChatWindowTogglePin action.
+    /// It pins the selected message, or unpins it if it is pinned.
+    ChatWindowTogglePin,
+    /// ChatWindowPinNotify action.
+    /// It pins the selected message notifying the members of the group.
+    ChatWindowPinNotify,
+    /// ChatWindowMessageViewers action.
+    /// It shows the users that have read the selected message.
+    ChatWindowMessageViewers,
+    /// ChatWindowToggleWrap action.
+    /// It toggles the wrapping of the messages.
+    ChatWindowToggleWrap,
+    /// ChatWindowScrollRight action.
+    /// It scrolls the messages to the right when they are not wrapped.
+    ChatWindowScrollRight,
+    /// ChatWindowScrollLeft action.
+    /// It scrolls the messages to the left when they are not wrapped.
+    ChatWindowScrollLeft,
+    /// ChatWindowToggleBlock action.
+    /// It asks to confirm blocking or unblocking the user of the open private
+    /// chat.
+    ChatWindowToggleBlock,
+    /// ChatWindowBlockReportSpam action.
+    /// It asks to confirm blocking the user of the open private chat and
+    /// reporting the chat as spam.
+    ChatWindowBlockReportSpam,
+    /// ChatWindowCopyLink action.
+    /// It copies the link of the selected message to the clipboard.
+    ChatWindowCopyLink,
+    /// ChatWindowPlayVoiceNote action.
+    /// It plays the voice note of the selected message, or stops it if it is
+    /// being played.
+    ChatWindowPlayVoiceNote,
+    /// ChatWindowOpenMedia action.
+    /// It opens the media of the selected message.
+    ChatWindowOpenMedia,
+    /// ChatWindowSaveContact action.
+    /// It adds the contact shared by the selected message to the contacts.
+    ChatWindowSaveContact,
+    /// ChatWindowSendBotCommand action.
+    /// It sends a bot command of the selected message to the bot of the open
+    /// chat.
+    ChatWindowSendBotCommand,
+    /// ChatWindowInlineKeyboard action.
+    /// It shows the buttons of the inline keyboard of the selected message,
+    /// to press one of them.
+    ChatWindowInlineKeyboard,
+    /// ChatWindowSearch action.
+    /// It is used to start typing a search of the loaded messages, the keys
+    /// are used by the chat window until `ChatWindowSearchDone`.
+    ChatWindowSearch,
+    /// ChatWindowSearchDone action.
+    /// It is sent by the chat window when the search is no longer typed.
+    ChatWindowSearchDone,
+    /// ChatWindowSearchNext action.
+    /// It selects the next older message matching the search.
+    ChatWindowSearchNext,
+    /// ChatWindowSearchPrevious action.
+    /// It selects the next newer message matching the search.
+    ChatWindowSearchPrevious,
+    /// ChatWindowSetAlias action.
+    /// It writes the command that sets the alias of the sender of the
+    /// selected message in the prompt.
+    ChatWindowSetAlias,
+    /// ChatWindowDismissPinned action.
+    /// It hides the pinned message banner of the open chat for the session.
+    ChatWindowDismissPinned,
+    /// ChatWindowAutoDelete action.
+    /// It shows the presets of the auto-delete timer of the open chat.
+    ChatWindowAutoDelete,
     /// ChatWindowUnselect action.
     ChatWindowUnselect,
     /// ChatWindowDeleteForEveryone action.
@@ -157,10 +570,22 @@ pub enum Action {
     /// It is used to delete a message only for the current user.
     ChatWindowDeleteForMe,
     /// ChatWindowCopy action.
+    /// It is used to copy the selected message, see `CopyMessage`.
     ChatWindowCopy,
     /// ChatWindowEdit action.
     ChatWindowEdit,
+    /// ChatWindowToggleSpoiler action.
+    /// It is used to reveal or hide the spoilers of the selected message.
+    ChatWindowToggleSpoiler,
+    /// ChatWindowOpenUrl action.
+    /// It is used to open the URLs of the selected message in the browser.
+    ChatWindowOpenUrl,
 
+    /// EditAlias action with an `i64` and a `String`.
+    /// This action is used to write the command that sets the alias of a
+    /// user in the prompt.
+    /// The parameters are the `user_id` and the current alias.
+    EditAlias(i64, String),
     /// EditMessage action with a `String`.
     /// This action is used to edit a message.
     /// The first parameter is the `message_id` and the second parameter is the `text`.
@@ -169,6 +594,15 @@ pub enum Action {
     /// This event is used to reply to a message.
     /// The first parameter is the `message_id` and the second parameter is the `text`.
     ReplyMessage(i64, String),
+    /// QuoteReplyMessage action with a `String`.
+    /// This action is used to reply to a message quoting its text.
+    /// The first parameter is the `message_id` and the second parameter is the `text`.
+    QuoteReplyMessage(i64, String),
+    /// CopyMessage action with a `i64`.
+    /// This action is used to copy the text of a message of the open chat to
+    /// the clipboard.
+    /// The parameter is the `message_id`.
+    CopyMessage(i64),
 }
 /// Implement the `Action` enum.
 impl Action {
@@ -198,24 +632,86 @@ impl FromStr for Action {
             "focus_chat" => Ok(Action::FocusComponent(ComponentName::Chat)),
             "focus_prompt" => Ok(Action::FocusComponent(ComponentName::Prompt)),
             "unfocus_component" => Ok(Action::UnfocusComponent),
+            "focus_next" => Ok(Action::FocusNext),
+            "focus_previous" => Ok(Action::FocusPrevious),
             "toggle_chat_list" => Ok(Action::ToggleChatList),
             "increase_chat_list_size" => Ok(Action::IncreaseChatListSize),
             "decrease_chat_list_size" => Ok(Action::DecreaseChatListSize),
             "increase_prompt_size" => Ok(Action::IncreasePromptSize),
             "decrease_prompt_size" => Ok(Action::DecreasePromptSize),
+            "toggle_online" => Ok(Action::ToggleOnline),
+            "try_log_out" => Ok(Action::TryLogOut),
+            "toggle_do_not_disturb" => Ok(Action::ToggleDoNotDisturb),
+            "toggle_privacy_mode" => Ok(Action::TogglePrivacyMode),
+            "toggle_bell" => Ok(Action::ToggleBell),
+            "toggle_status_bar" => Ok(Action::ToggleStatusBar),
+            "toggle_title_bar" => Ok(Action::ToggleTitleBar),
+            "reload_config" => Ok(Action::ReloadConfig),
+            "show_recent_chats" => Ok(Action::ShowRecentChats),
+            "show_sticker_picker" => Ok(Action::ShowStickerPicker),
+            "show_scheduled_messages" => Ok(Action::ShowScheduledMessages),
+            "show_member_list" => Ok(Action::ShowMemberList),
+            "show_downloads" => Ok(Action::ShowDownloads),
+            "show_sessions" => Ok(Action::ShowSessions),
+            "show_keymap" => Ok(Action::ShowKeymap),
             "chat_list_next" => Ok(Action::ChatListNext),
             "chat_list_previous" => Ok(Action::ChatListPrevious),
             "chat_list_unselect" => Ok(Action::ChatListUnselect),
             "chat_list_open" => Ok(Action::ChatListOpen),
+            "chat_list_create_secret_chat" => Ok(Action::ChatListCreateSecretChat),
+            "chat_list_clear_history" => Ok(Action::ChatListClearHistory),
+            "chat_list_join" => Ok(Action::ChatListJoin),
+            "chat_list_leave" => Ok(Action::ChatListLeave),
+            "chat_list_cycle_sort" => Ok(Action::ChatListCycleSort),
+            "chat_list_filter" => Ok(Action::ChatListFilter),
+            "chat_list_next_folder" => Ok(Action::ChatListNextFolder),
+            "chat_list_previous_folder" => Ok(Action::ChatListPreviousFolder),
+            "chat_list_next_unread" => Ok(Action::ChatListNextUnread),
+            "chat_list_previous_unread" => Ok(Action::ChatListPreviousUnread),
             "chat_window_next" => Ok(Action::ChatWindowNext),
             "chat_window_previous" => Ok(Action::ChatWindowPrevious),
+            "chat_window_go_to_top" => Ok(Action::ChatWindowGoToTop),
+            "chat_window_go_to_bottom" => Ok(Action::ChatWindowGoToBottom),
             "chat_window_unselect" => Ok(Action::ChatWindowUnselect),
+            "chat_window_toggle_selection" => Ok(Action::ChatWindowToggleSelection),
+            "chat_window_forward" => Ok(Action::ChatWindowForward),
             "chat_window_delete_for_everyone" => Ok(Action::ChatWindowDeleteForEveryone),
             "chat_window_delete_for_me" => Ok(Action::ChatWindowDeleteForMe),
             "chat_window_copy" => Ok(Action::ChatWindowCopy),
             "chat_window_edit" => Ok(Action::ChatWindowEdit),
             "chat_window_reply" => Ok(Action::ShowChatWindowReply),
-            _ => Err(AppError::InvalidAction(s.to_string())),
+            "cancel_reply" => Ok(Action::CancelReply),
+            "chat_window_quote_reply" => Ok(Action::ShowChatWindowQuoteReply),
+            "chat_window_toggle_spoiler" => Ok(Action::ChatWindowToggleSpoiler),
+            "chat_window_open_url" => Ok(Action::ChatWindowOpenUrl),
+            "chat_window_auto_delete" => Ok(Action::ChatWindowAutoDelete),
+            "chat_window_jump_to_pinned" => Ok(Action::ChatWindowJumpToPinned),
+            "chat_window_dismiss_pinned" => Ok(Action::ChatWindowDismissPinned),
+            "chat_window_toggle_pin" => Ok(Action::ChatWindowTogglePin),
+            "chat_window_pin_notify" => Ok(Action::ChatWindowPinNotify),
+            "chat_window_message_viewers" => Ok(Action::ChatWindowMessageViewers),
+            "chat_window_toggle_wrap" => Ok(Action::ChatWindowToggleWrap),
+            "chat_window_toggle_block" => Ok(Action::ChatWindowToggleBlock),
+            "chat_window_copy_link" => Ok(Action::ChatWindowCopyLink),
+            "chat_window_play_voice_note" => Ok(Action::ChatWindowPlayVoiceNote),
+            "chat_window_open_media" => Ok(Action::ChatWindowOpenMedia),
+            "chat_window_save_contact" => Ok(Action::ChatWindowSaveContact),
+            "chat_window_send_bot_command" => Ok(Action::ChatWindowSendBotCommand),
+            "chat_window_inline_keyboard" => Ok(Action::ChatWindowInlineKeyboard),
+            "chat_window_search" => Ok(Action::ChatWindowSearch),
+            "chat_window_search_next" => Ok(Action::ChatWindowSearchNext),
+            "chat_window_search_previous" => Ok(Action::ChatWindowSearchPrevious),
+            "chat_window_set_alias" => Ok(Action::ChatWindowSetAlias),
+            "chat_window_block_report_spam" => Ok(Action::ChatWindowBlockReportSpam),
+            "chat_window_scroll_right" => Ok(Action::ChatWindowScrollRight),
+            "chat_window_scroll_left" => Ok(Action::ChatWindowScrollLeft),
+            _ => match s
+                .strip_prefix("jump_to_chat_")
+                .and_then(|n| n.parse::<usize>().ok())
+            {
+                Some(n) if n > 0 => Ok(Action::JumpToChatIndex(n - 1)),
+                _ => Err(AppError::InvalidAction(s.to_string())),
+            },
         }
     }
 }
@@ -1,15 +1,20 @@
 use crate::{
     action::Action,
+    app_error::AppError,
+    app_state::AppState,
     cli::CliArgs,
-    configs::custom::{
-        app_custom::AppConfig, keymap_custom::KeymapConfig, palette_custom::PaletteConfig,
-        telegram_custom::TelegramConfig, theme_custom::ThemeConfig,
+    configs::{
+        config_file::ConfigFile,
+        custom::{
+            app_custom::AppConfig, keymap_custom::KeymapConfig, palette_custom::PaletteConfig,
+            telegram_custom::TelegramConfig, theme_custom::ThemeConfig,
+        },
     },
     tg::tg_context::TgContext,
 };
 use ratatui::style::Style;
 use std::sync::{atomic::AtomicBool, Arc, Mutex, MutexGuard};
-use std::{io, sync::atomic::Ordering};
+use std::{collections::HashMap, io, sync::atomic::Ordering};
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 
 /// Generate a function that returns a style based on the theme configuration.
@@ -73,6 +78,8 @@ pub struct AppContext {
     palette_config: Mutex<PaletteConfig>,
     /// The Telegram configuration.
     tg_config: Mutex<TelegramConfig>,
+    /// The application state that is persisted across restarts.
+    app_state: Mutex<AppState>,
     /// An unbounded receiver that receives action for processing.
     /// This is used to send actions from the main loop to the main loop.
     /// A copy of this receiver is passed to all components.
@@ -84,6 +91,22 @@ pub struct AppContext {
     /// A boolean flag that represents whether the application should quit or
     /// not.
     quit: AtomicBool,
+    /// A boolean flag that represents whether the notifications are disabled
+    /// for the current session.
+    do_not_disturb: AtomicBool,
+    /// A boolean flag that represents whether the contents of the messages
+    /// are hidden on the screen for the current session.
+    privacy_mode: AtomicBool,
+    /// A boolean flag that represents whether the terminal bell rings on new
+    /// messages for the current session.
+    bell: AtomicBool,
+    /// A boolean flag that represents whether the next key is captured to be
+    /// bound to an action, so that it is not handled by the key bindings.
+    capturing_key: AtomicBool,
+    /// The name of the file of the theme in use, it is different from
+    /// `theme_filename` of the application configuration when the open chat
+    /// has its own theme.
+    theme_filename: Mutex<String>,
     /// The Telegram context.
     tg_context: Arc<TgContext>,
     /// The CLI arguments for the application.
@@ -99,31 +122,43 @@ impl AppContext {
     /// * `theme_config` - The theme configuration.
     /// * `palette_config` - The palette configuration.
     /// * `telegram_config` - The Telegram configuration.
+    /// * `app_state` - The application state.
     /// * `tg_context` - The Telegram context.
     ///
     /// # Returns
     /// * `Result<Self, io::Error>` - An Ok result containing the new instance
     ///   of the `App` struct or an error.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         app_config: AppConfig,
         keymap_config: KeymapConfig,
         theme_config: ThemeConfig,
         palette_config: PaletteConfig,
         telegram_config: TelegramConfig,
+        app_state: AppState,
         tg_context: TgContext,
         cli_args: CliArgs,
     ) -> Result<Self, io::Error> {
         let (action_tx, action_rx) = tokio::sync::mpsc::unbounded_channel::<Action>();
         let quit = false;
+        let bell = app_config.bell_on_new_message;
+        let theme_filename = app_config.theme_filename.clone();
+        tg_context.set_aliases(Self::aliases(&app_config, &app_state));
         Ok(Self {
             app_config: Mutex::new(app_config),
             keymap_config: Mutex::new(keymap_config),
             palette_config: Mutex::new(palette_config),
             theme_config: Mutex::new(theme_config),
             tg_config: Mutex::new(telegram_config),
+            app_state: Mutex::new(app_state),
             action_rx: Mutex::new(action_rx),
             action_tx: Mutex::new(action_tx),
             quit: AtomicBool::new(quit),
+            do_not_disturb: AtomicBool::new(false),
+            privacy_mode: AtomicBool::new(false),
+            bell: AtomicBool::new(bell),
+            capturing_key: AtomicBool::new(false),
+            theme_filename: Mutex::new(theme_filename),
             tg_context: Arc::new(tg_context),
             cli_args: Mutex::new(cli_args),
         })
@@ -179,6 +214,183 @@ impl AppContext {
     pub fn telegram_config(&self) -> MutexGuard<'_, TelegramConfig> {
         self.tg_config.lock().unwrap()
     }
+    /// Reload the application, keymap, palette and theme configurations from
+    /// their files.
+    /// The configurations are replaced only if all of them are valid,
+    /// otherwise the current ones are kept.
+    /// The settings read only at startup, e.g. `frame_rate` and
+    /// `mouse_support`, and the Telegram configuration still need a restart.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or the error of the first
+    ///   configuration that can not be loaded.
+    pub fn reload_config(&self) -> Result<(), AppError<()>> {
+        let app_config = AppConfig::try_get_config()?;
+        let palette_config = PaletteConfig::load(&app_config.theme_filename)?;
+        let theme_config = ThemeConfig::load(&app_config.theme_filename, &palette_config.palette)?;
+        let keymap_config = KeymapConfig::try_get_config()?;
+        *self.theme_filename.lock().unwrap() = app_config.theme_filename.clone();
+        *self.app_config() = app_config;
+        *self.palette_config() = palette_config;
+        *self.theme_config() = theme_config;
+        *self.keymap_config() = keymap_config;
+        self.tg_context
+            .set_aliases(Self::aliases(&self.app_config(), &self.app_state()));
+        self.apply_chat_theme(self.tg_context.open_chat_id())
+    }
+    /// Reload the keymap configuration from its files, e.g. after a key has
+    /// been bound at runtime.
+    /// The current keymap configuration is kept if the new one is not valid.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or the error of the keymap
+    ///   configuration.
+    pub fn reload_keymap_config(&self) -> Result<(), AppError<()>> {
+        let keymap_config = KeymapConfig::try_get_config()?;
+        *self.keymap_config() = keymap_config;
+        Ok(())
+    }
+    /// Get the aliases of the users: the ones of the configuration,
+    /// overridden by the ones set at runtime.
+    ///
+    /// # Arguments
+    /// * `app_config` - The application configuration.
+    /// * `app_state` - The application state.
+    ///
+    /// # Returns
+    /// * `HashMap<i64, String>` - The aliases by user id.
+    fn aliases(app_config: &AppConfig, app_state: &AppState) -> HashMap<i64, String> {
+        let mut aliases = app_config.aliases.clone();
+        aliases.extend(
+            app_state
+                .aliases
+                .iter()
+                .filter_map(|(user_id, alias)| Some((user_id.parse().ok()?, alias.clone()))),
+        );
+        aliases
+    }
+    /// Forget the last opened chat, e.g. because it no longer exists, and
+    /// save the application state, so that it is not restored on the next
+    /// run.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or an error if the application
+    ///   state can not be saved.
+    pub fn clear_last_open_chat(&self) -> Result<(), AppError<()>> {
+        let mut app_state = self.app_state();
+        app_state.clear_last_open_chat();
+        app_state.save()?;
+        Ok(())
+    }
+    /// Set the alias of a user, it is saved in the application state.
+    ///
+    /// # Arguments
+    /// * `user_id` - The id of the user.
+    /// * `alias` - The alias, `None` to remove the one set at runtime.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or an error if the application
+    ///   state can not be saved.
+    pub fn set_alias(&self, user_id: i64, alias: Option<String>) -> Result<(), AppError<()>> {
+        let mut app_state = self.app_state();
+        app_state.set_alias(user_id, alias);
+        app_state.save()?;
+        self.tg_context
+            .set_aliases(Self::aliases(&self.app_config(), &app_state));
+        Ok(())
+    }
+    /// Show or hide the status bar, it is saved as `show_status_bar` in the
+    /// custom `app.toml`.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or an error if the
+    ///   configuration can not be saved.
+    pub fn toggle_status_bar(&self) -> Result<(), AppError<()>> {
+        let show_status_bar = !self.app_config().show_status_bar;
+        self.app_config().show_status_bar = show_status_bar;
+        AppConfig::save(&[("show_status_bar", toml::Value::Boolean(show_status_bar))])?;
+        Ok(())
+    }
+    /// Show or hide the title bar, it is saved as `show_title_bar` in the
+    /// custom `app.toml`.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or an error if the
+    ///   configuration can not be saved.
+    pub fn toggle_title_bar(&self) -> Result<(), AppError<()>> {
+        let show_title_bar = !self.app_config().show_title_bar;
+        self.app_config().show_title_bar = show_title_bar;
+        AppConfig::save(&[("show_title_bar", toml::Value::Boolean(show_title_bar))])?;
+        Ok(())
+    }
+    /// Use the theme, and its palette, of the given file.
+    /// Nothing is loaded if the theme is already in use.
+    ///
+    /// # Arguments
+    /// * `theme_filename` - The name of the file of the theme, in the
+    ///   configuration directory.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or an error if the theme can
+    ///   not be loaded, in that case the current theme is kept.
+    pub fn apply_theme(&self, theme_filename: &str) -> Result<(), AppError<()>> {
+        if *self.theme_filename.lock().unwrap() == theme_filename {
+            return Ok(());
+        }
+        let palette_config = PaletteConfig::load(theme_filename)?;
+        let theme_config = ThemeConfig::load(theme_filename, &palette_config.palette)?;
+        *self.palette_config() = palette_config;
+        *self.theme_config() = theme_config;
+        *self.theme_filename.lock().unwrap() = theme_filename.to_string();
+        Ok(())
+    }
+    /// Use the theme of a chat, or the default one if the chat does not have
+    /// its own theme.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or an error if the theme can
+    ///   not be loaded.
+    pub fn apply_chat_theme(&self, chat_id: i64) -> Result<(), AppError<()>> {
+        let chat_theme = self.app_state().chat_theme(chat_id).map(str::to_string);
+        let theme_filename = chat_theme.unwrap_or_else(|| self.app_config().theme_filename.clone());
+        self.apply_theme(&theme_filename)
+    }
+    /// Set the theme of a chat and use it, the theme is saved in the
+    /// application state.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `theme_filename` - The name of the file of the theme, `None` to use
+    ///   the default theme.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or an error if the theme can
+    ///   not be loaded, in that case it is not set.
+    pub fn set_chat_theme(
+        &self,
+        chat_id: i64,
+        theme_filename: Option<String>,
+    ) -> Result<(), AppError<()>> {
+        let default_theme_filename = self.app_config().theme_filename.clone();
+        self.apply_theme(theme_filename.as_ref().unwrap_or(&default_theme_filename))?;
+        let mut app_state = self.app_state();
+        app_state.set_chat_theme(chat_id, theme_filename);
+        app_state.save()?;
+        Ok(())
+    }
+    /// Get the application state.
+    /// This function takes the lock on the application state and returns the
+    /// application state.
+    /// The application state is a shared resource and is protected by a mutex.
+    ///
+    /// # Returns
+    /// * `MutexGuard<'_, AppState>` - The application state.
+    pub fn app_state(&self) -> MutexGuard<'_, AppState> {
+        self.app_state.lock().unwrap()
+    }
     /// Get the action receiver.
     /// This function takes the lock on the action receiver and returns the action
     /// receiver.
@@ -214,6 +426,67 @@ impl AppContext {
     pub fn quit_store(&self, value: bool) {
         self.quit.store(value, Ordering::Release);
     }
+    /// Get the do not disturb flag.
+    /// This function returns whether the notifications are disabled for the
+    /// current session.
+    ///
+    /// # Returns
+    /// * `bool` - The value of the do not disturb flag.
+    pub fn do_not_disturb(&self) -> bool {
+        self.do_not_disturb.load(Ordering::Acquire)
+    }
+    /// Set the do not disturb flag.
+    /// This function enables or disables the notifications for the current
+    /// session.
+    pub fn set_do_not_disturb(&self, value: bool) {
+        self.do_not_disturb.store(value, Ordering::Release);
+    }
+    /// Get the privacy mode flag.
+    /// This function returns whether the contents of the messages, the
+    /// previews of the chat list and the prompt are obscured when drawn.
+    ///
+    /// # Returns
+    /// * `bool` - The value of the privacy mode flag.
+    pub fn privacy_mode(&self) -> bool {
+        self.privacy_mode.load(Ordering::Acquire)
+    }
+    /// Set the privacy mode flag.
+    /// This function obscures or reveals the contents of the messages for
+    /// the current session, the data is not changed.
+    pub fn set_privacy_mode(&self, value: bool) {
+        self.privacy_mode.store(value, Ordering::Release);
+    }
+    /// Get the bell flag.
+    /// This function returns whether the terminal bell rings on new messages
+    /// for the current session. It is initialized with
+    /// `bell_on_new_message` of the application configuration.
+    ///
+    /// # Returns
+    /// * `bool` - The value of the bell flag.
+    pub fn bell(&self) -> bool {
+        self.bell.load(Ordering::Acquire)
+    }
+    /// Set the bell flag.
+    /// This function enables or disables the terminal bell on new messages
+    /// for the current session.
+    pub fn set_bell(&self, value: bool) {
+        self.bell.store(value, Ordering::Release);
+    }
+    /// Get the capturing key flag.
+    /// This function returns whether the next key is captured to be bound to
+    /// an action, see `KeymapWindow`.
+    ///
+    /// # Returns
+    /// * `bool` - The value of the capturing key flag.
+    pub fn capturing_key(&self) -> bool {
+        self.capturing_key.load(Ordering::Acquire)
+    }
+    /// Set the capturing key flag.
+    /// While it is set, the keys are not mapped to the actions of the
+    /// `core_window` key bindings.
+    pub fn set_capturing_key(&self, value: bool) {
+        self.capturing_key.store(value, Ordering::Release);
+    }
     /// Get the Telegram context.
     /// This function returns the Telegram context.
     /// The Telegram context is a shared resource and the contained variables are
@@ -253,12 +526,36 @@ impl AppContext {
         chat_list,
         item_unread_counter
     );
+    theme_style_generate!(
+        style_chat_list_item_chat_name_match,
+        chat_list,
+        item_chat_name_match
+    );
 
     // ===== CHAT =====
     theme_style_generate!(style_chat, chat, self);
     theme_style_generate!(style_chat_chat_name, chat, chat_name);
     theme_style_generate!(style_chat_message_myself_name, chat, message_myself_name);
     theme_style_generate!(style_chat_message_other_name, chat, message_other_name);
+    /// Get the style of the name of a sender in group chats, chosen from the
+    /// `message_sender_name_<n>` palette of the theme by the id of the sender.
+    /// It falls back to the style of the names of the other users if the
+    /// palette is empty.
+    ///
+    /// # Arguments
+    /// * `sender_id` - The id of the sender.
+    ///
+    /// # Returns
+    /// * `Style` - The style of the name.
+    pub fn style_chat_message_sender_name(&self, sender_id: i64) -> Style {
+        if !self.app_config().theme_enable {
+            return Style::default();
+        }
+        match self.theme_config().sender_name_style(sender_id) {
+            Some(style) => style.as_style(),
+            None => self.style_chat_message_other_name(),
+        }
+    }
     theme_style_generate!(
         style_chat_message_myself_content,
         chat,
@@ -290,6 +587,18 @@ impl AppContext {
         chat,
         message_other_reply_content
     );
+    theme_style_generate!(style_code_block, chat, code_block);
+    theme_style_generate!(style_chat_message_selected, chat, message_selected);
+    theme_style_generate!(style_chat_message_forwarded, chat, message_forwarded);
+    theme_style_generate!(style_chat_pinned_message, chat, pinned_message);
+    theme_style_generate!(style_chat_message_search_match, chat, message_search_match);
+    theme_style_generate!(style_chat_message_service, chat, message_service);
+    theme_style_generate!(style_chat_message_reply_target, chat, message_reply_target);
+    theme_style_generate!(
+        style_chat_message_inline_button,
+        chat,
+        message_inline_button
+    );
 
     // ===== PROMPT =====
     theme_style_generate!(style_prompt, prompt, self);
@@ -304,6 +613,11 @@ impl AppContext {
         prompt,
         message_preview_text
     );
+    theme_style_generate!(
+        style_prompt_message_length_limit,
+        prompt,
+        message_length_limit
+    );
 
     // ===== REPLY MESSAGE =====
     theme_style_generate!(style_reply_message, reply_message, self);
@@ -335,6 +649,18 @@ impl AppContext {
     );
     theme_style_generate!(style_status_bar_open_chat_text, status_bar, open_chat_text);
     theme_style_generate!(style_status_bar_open_chat_name, status_bar, open_chat_name);
+    theme_style_generate!(
+        style_status_bar_connection_ready,
+        status_bar,
+        connection_ready
+    );
+    theme_style_generate!(
+        style_status_bar_connection_not_ready,
+        status_bar,
+        connection_not_ready
+    );
+    theme_style_generate!(style_status_bar_error_message, status_bar, error_message);
+    theme_style_generate!(style_status_bar_info_message, status_bar, info_message);
 
     // ===== TITLE BAR =====
     theme_style_generate!(style_title_bar, title_bar, self);
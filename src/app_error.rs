@@ -26,6 +26,8 @@ pub enum AppError<T> {
     AlreadyBound,
     /// It is an invalid color.
     InvalidColor(String),
+    /// It is an invalid proxy configuration.
+    InvalidProxy(String),
 }
 impl<T> From<io::Error> for AppError<T> {
     fn from(error: io::Error) -> Self {
@@ -60,6 +62,9 @@ impl<T> Display for AppError<T> {
             Self::InvalidColor(color) => {
                 write!(f, "Invalid color: {}", color)
             }
+            Self::InvalidProxy(reason) => {
+                write!(f, "Invalid proxy: {}", reason)
+            }
         }
     }
 }
@@ -0,0 +1,234 @@
+use crate::{configs, utils::tgt_profile_config_dir};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The name of the file that stores the application state.
+pub const APP_STATE_FILENAME: &str = "state.toml";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+/// `AppState` is a struct that represents the state of the application that
+/// must survive a restart.
+/// Unlike the configuration files, it is written by the application itself and
+/// it is not meant to be edited by the user.
+pub struct AppState {
+    /// The identifier of the last opened chat.
+    pub last_open_chat_id: Option<i64>,
+    /// The index of the last selected item in the chat list.
+    pub chat_list_selected: Option<usize>,
+    /// The width of the chat list, as a percentage of the width of the
+    /// terminal.
+    pub chat_list_size: Option<u16>,
+    /// The height of the prompt, in rows.
+    pub prompt_size: Option<u16>,
+    /// The order of the chats of the chat list, as in `chat_list_sort` of
+    /// `app.toml`, that it overrides once the order is cycled.
+    pub chat_list_sort: Option<String>,
+    /// The name of the theme file used by a chat instead of `theme_filename`,
+    /// by chat id.
+    #[serde(default)]
+    pub chat_themes: HashMap<String, String>,
+    /// The aliases of the users set at runtime, by user id.
+    /// They override the aliases of the configuration.
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+}
+/// Implementation of the `AppState` struct.
+impl AppState {
+    /// Get the path of the application state file.
+    ///
+    /// # Returns
+    /// * `io::Result<PathBuf>` - The path of the application state file.
+    pub fn path() -> io::Result<PathBuf> {
+        Ok(tgt_profile_config_dir()?.join(APP_STATE_FILENAME))
+    }
+    /// Load the application state from the default state file.
+    /// If the file does not exist or cannot be parsed, the default state is
+    /// returned.
+    ///
+    /// # Returns
+    /// * `Self` - The loaded application state.
+    pub fn load() -> Self {
+        match Self::path() {
+            Ok(path) => Self::load_from(&path),
+            Err(e) => {
+                tracing::warn!("Unable to get the app state path: {}", e);
+                Self::default()
+            }
+        }
+    }
+    /// Load the application state from the specified file.
+    /// If the file does not exist or cannot be parsed, the default state is
+    /// returned.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the application state file.
+    ///
+    /// # Returns
+    /// * `Self` - The loaded application state.
+    pub fn load_from(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+        match configs::deserialize_to_config::<Self>(path) {
+            Ok(state) => state,
+            Err(e) => {
+                tracing::warn!("Unable to load the app state {}: {}", path.display(), e);
+                Self::default()
+            }
+        }
+    }
+    /// Save the application state to the default state file.
+    ///
+    /// # Returns
+    /// * `io::Result<()>` - An Ok result or an error.
+    pub fn save(&self) -> io::Result<()> {
+        self.save_to(&Self::path()?)
+    }
+    /// Save the application state to the specified file.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the application state file.
+    ///
+    /// # Returns
+    /// * `io::Result<()>` - An Ok result or an error.
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        let content = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, content)
+    }
+    /// Record the chat that has been opened from the chat list.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The identifier of the opened chat.
+    /// * `index` - The index of the opened chat in the chat list.
+    pub fn set_last_open_chat(&mut self, chat_id: i64, index: usize) {
+        self.last_open_chat_id = Some(chat_id);
+        self.chat_list_selected = Some(index);
+    }
+    /// Get the name of the theme file used by a chat.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The identifier of the chat.
+    ///
+    /// # Returns
+    /// * `Option<&str>` - The name of the theme file, `None` if the chat uses
+    ///   the default theme.
+    pub fn chat_theme(&self, chat_id: i64) -> Option<&str> {
+        self.chat_themes
+            .get(&chat_id.to_string())
+            .map(String::as_str)
+    }
+    /// Set the name of the theme file used by a chat.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The identifier of the chat.
+    /// * `theme_filename` - The name of the theme file, `None` to use the
+    ///   default theme.
+    pub fn set_chat_theme(&mut self, chat_id: i64, theme_filename: Option<String>) {
+        match theme_filename {
+            Some(theme_filename) => {
+                self.chat_themes.insert(chat_id.to_string(), theme_filename);
+            }
+            None => {
+                self.chat_themes.remove(&chat_id.to_string());
+            }
+        }
+    }
+    /// Set the alias of a user.
+    ///
+    /// # Arguments
+    /// * `user_id` - The identifier of the user.
+    /// * `alias` - The alias, `None` to remove it.
+    pub fn set_alias(&mut self, user_id: i64, alias: Option<String>) {
+        match alias {
+            Some(alias) => {
+                self.aliases.insert(user_id.to_string(), alias);
+            }
+            None => {
+                self.aliases.remove(&user_id.to_string());
+            }
+        }
+    }
+    /// Forget the last opened chat, e.g. because it no longer exists.
+    pub fn clear_last_open_chat(&mut self) {
+        self.last_open_chat_id = None;
+        self.chat_list_selected = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::app_state::AppState;
+
+    #[test]
+    fn test_app_state_save_and_load() {
+        let path = std::env::temp_dir().join("tgt_test_app_state.toml");
+        let mut state = AppState::default();
+        state.set_last_open_chat(-1001234567890, 3);
+        state.save_to(&path).unwrap();
+        let loaded = AppState::load_from(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, state);
+    }
+
+    #[test]
+    fn test_app_state_load_missing_file() {
+        let path = std::env::temp_dir().join("tgt_test_app_state_missing.toml");
+        let state = AppState::load_from(&path);
+        assert_eq!(state, AppState::default());
+    }
+
+    #[test]
+    fn test_app_state_chat_list_size() {
+        let path = std::env::temp_dir().join("tgt_test_app_state_chat_list_size.toml");
+        let state = AppState {
+            chat_list_size: Some(15),
+            prompt_size: Some(5),
+            chat_list_sort: Some("alphabetical".to_string()),
+            ..Default::default()
+        };
+        state.save_to(&path).unwrap();
+        let loaded = AppState::load_from(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.chat_list_size, Some(15));
+        assert_eq!(loaded.prompt_size, Some(5));
+        assert_eq!(loaded.chat_list_sort.as_deref(), Some("alphabetical"));
+        assert_eq!(loaded.last_open_chat_id, None);
+    }
+
+    #[test]
+    fn test_app_state_set_alias() {
+        let mut state = AppState::default();
+        state.set_alias(42, Some("Alice".to_string()));
+        assert_eq!(state.aliases.get("42").map(String::as_str), Some("Alice"));
+        state.set_alias(42, None);
+        assert!(state.aliases.is_empty());
+    }
+
+    #[test]
+    fn test_app_state_clear_last_open_chat() {
+        let mut state = AppState::default();
+        state.set_last_open_chat(42, 1);
+        state.clear_last_open_chat();
+        assert_eq!(state.last_open_chat_id, None);
+        assert_eq!(state.chat_list_selected, None);
+    }
+
+    #[test]
+    fn test_app_state_chat_themes() {
+        let path = std::env::temp_dir().join("tgt_test_app_state_chat_themes.toml");
+        let mut state = AppState::default();
+        state.set_chat_theme(-1001234567890, Some("work.toml".to_string()));
+        state.set_chat_theme(42, Some("friends.toml".to_string()));
+        state.set_chat_theme(42, None);
+        state.save_to(&path).unwrap();
+        let loaded = AppState::load_from(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.chat_theme(-1001234567890), Some("work.toml"));
+        assert_eq!(loaded.chat_theme(42), None);
+        assert_eq!(loaded, state);
+    }
+}
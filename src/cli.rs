@@ -1,3 +1,7 @@
+use crate::{
+    configs::custom::keymap_custom::KeymapFormat,
+    utils::{TGT_DATA_DIR, TGT_LOG_PATH, TGT_PROFILE},
+};
 use clap::Parser;
 // use clap::Subcommand;
 
@@ -39,6 +43,70 @@ pub struct TelegramCli {
         help = "Send a message to a chat"
     )]
     send_message: Option<Vec<String>>,
+
+    #[arg(
+        long,
+        visible_alias = "lc",
+        help = "List the chats and exit",
+        default_value_t = false
+    )]
+    list_chats: bool,
+
+    #[arg(
+        long,
+        requires = "list_chats",
+        help = "Print the list of chats as JSON",
+        default_value_t = false
+    )]
+    json: bool,
+
+    #[arg(
+        long,
+        requires = "list_chats",
+        value_name = "LIMIT",
+        help = "The maximum number of chats to list"
+    )]
+    limit: Option<usize>,
+
+    #[arg(
+        long,
+        env = TGT_PROFILE,
+        value_name = "NAME",
+        help = "The profile to use, it has its own database, logs and config, e.g. for another account"
+    )]
+    profile: Option<String>,
+
+    #[arg(
+        long,
+        env = TGT_DATA_DIR,
+        value_name = "DIR",
+        help = "The directory where the Telegram database is stored, it overrides the telegram config"
+    )]
+    data_dir: Option<String>,
+
+    #[arg(
+        long,
+        env = TGT_LOG_PATH,
+        value_name = "PATH",
+        help = "The path of the TDLib log file, it overrides the telegram config"
+    )]
+    log_path: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Export the key bindings in use, the default ones merged with the custom ones, to a file and exit"
+    )]
+    export_keymap: Option<String>,
+
+    #[arg(
+        long,
+        requires = "export_keymap",
+        value_enum,
+        default_value_t = KeymapFormat::Toml,
+        help = "The format of the exported key bindings"
+    )]
+    keymap_format: KeymapFormat,
 }
 
 impl TelegramCli {
@@ -50,6 +118,46 @@ impl TelegramCli {
     pub fn send_message(&self) -> Option<&Vec<String>> {
         self.send_message.as_ref()
     }
+    /// Get the list chats flag.
+    pub fn list_chats(&self) -> bool {
+        self.list_chats
+    }
+    /// Get the JSON flag, used to print the list of chats as JSON.
+    pub fn json(&self) -> bool {
+        self.json
+    }
+    /// Get the maximum number of chats to list.
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+    /// Check if a command is given, i.e. if the application runs as a CLI
+    /// instead of a TUI. The paths do not count as commands.
+    pub fn is_command(&self) -> bool {
+        self.logout
+            || self.send_message.is_some()
+            || self.list_chats
+            || self.export_keymap.is_some()
+    }
+    /// Get the name of the profile.
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+    /// Get the directory where the Telegram database is stored.
+    pub fn data_dir(&self) -> Option<&str> {
+        self.data_dir.as_deref()
+    }
+    /// Get the path of the TDLib log file.
+    pub fn log_path(&self) -> Option<&str> {
+        self.log_path.as_deref()
+    }
+    /// Get the path of the file where the key bindings are exported.
+    pub fn export_keymap(&self) -> Option<&str> {
+        self.export_keymap.as_deref()
+    }
+    /// Get the format of the exported key bindings.
+    pub fn keymap_format(&self) -> KeymapFormat {
+        self.keymap_format
+    }
 }
 
 // #[derive(Parser, Debug)]
@@ -14,10 +14,24 @@ pub enum ComponentName {
     Prompt,
     /// The reply message window.
     ReplyMessage,
+    /// The sticker picker.
+    StickerPicker,
+    /// The panel of the scheduled messages.
+    ScheduledMessages,
+    /// The member list of the open group.
+    MemberList,
+    /// The panel of the downloads.
+    Downloads,
+    /// The panel of the account and of its sessions.
+    Sessions,
+    /// The panel of the key bindings, where the keys can be rebound.
+    Keymap,
     /// The title bar.
     TitleBar,
     /// The status bar.
     StatusBar,
+    /// The login window.
+    Auth,
 }
 
 impl Display for ComponentName {
@@ -30,6 +44,13 @@ impl Display for ComponentName {
             ComponentName::TitleBar => write!(f, "Title Bar"),
             ComponentName::StatusBar => write!(f, "Status Bar"),
             ComponentName::ReplyMessage => write!(f, "Reply Message"),
+            ComponentName::StickerPicker => write!(f, "Sticker Picker"),
+            ComponentName::ScheduledMessages => write!(f, "Scheduled Messages"),
+            ComponentName::MemberList => write!(f, "Members"),
+            ComponentName::Downloads => write!(f, "Downloads"),
+            ComponentName::Sessions => write!(f, "Account"),
+            ComponentName::Keymap => write!(f, "Key Bindings"),
+            ComponentName::Auth => write!(f, "Log In"),
         }
     }
 }
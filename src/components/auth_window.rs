@@ -0,0 +1,451 @@
+use {
+    crate::{
+        action::Action,
+        app_context::AppContext,
+        components::component_traits::{Component, HandleFocus},
+    },
+    crossterm::event::KeyCode,
+    qrcode::{render::unicode::Dense1x2, QrCode},
+    ratatui::{
+        layout::{Alignment, Constraint, Flex, Layout, Position, Rect},
+        text::{Line, Span},
+        widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    },
+    std::{io, sync::Arc},
+    tdlib_rs::enums::AuthorizationState,
+    tokio::sync::mpsc::UnboundedSender,
+};
+
+/// The width of the `AuthWindow` when no QR code is displayed.
+const AUTH_WINDOW_WIDTH: u16 = 60;
+
+/// `AuthField` is a struct that represents an input field of the
+/// `AuthWindow`.
+struct AuthField {
+    /// The label of the field.
+    label: String,
+    /// The text entered by the user.
+    value: String,
+    /// Indicates whether the text of the field is hidden, e.g. for passwords.
+    masked: bool,
+}
+/// Implementation of the `AuthField` struct.
+impl AuthField {
+    /// Create a new instance of the `AuthField` struct.
+    ///
+    /// # Arguments
+    /// * `label` - The label of the field.
+    /// * `masked` - Whether the text of the field is hidden.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `AuthField` struct.
+    fn new(label: &str, masked: bool) -> Self {
+        Self {
+            label: label.to_string(),
+            value: String::new(),
+            masked,
+        }
+    }
+    /// Get the text of the field as it is displayed.
+    ///
+    /// # Returns
+    /// * `String` - The text of the field, masked if needed.
+    fn display_value(&self) -> String {
+        if self.masked {
+            "*".repeat(self.value.chars().count())
+        } else {
+            self.value.clone()
+        }
+    }
+}
+
+/// `AuthWindow` is a struct that represents the window used to log in.
+/// It is responsible for managing the layout and rendering of the login
+/// information, such as the QR code of the login link, and for collecting the
+/// input requested by the current authorization state.
+pub struct AuthWindow {
+    /// The application context.
+    app_context: Arc<AppContext>,
+    /// The name of the `AuthWindow`.
+    name: String,
+    /// An unbounded sender that send action for processing.
+    action_tx: Option<UnboundedSender<Action>>,
+    /// Indicates whether the `AuthWindow` is focused or not.
+    focused: bool,
+    /// The login link to confirm on another device, shown as a QR code.
+    qr_code_link: Option<String>,
+    /// The description of the current authorization state.
+    description: Vec<String>,
+    /// The input fields requested by the current authorization state.
+    fields: Vec<AuthField>,
+    /// The index of the selected input field.
+    selected_field: usize,
+    /// The error returned by Telegram for the last submitted input.
+    error: Option<String>,
+    /// Indicates whether the input has been submitted and the window is
+    /// waiting for the next authorization state.
+    waiting: bool,
+}
+/// Implementation of the `AuthWindow` struct.
+impl AuthWindow {
+    /// Create a new instance of the `AuthWindow` struct.
+    ///
+    /// # Arguments
+    /// * `app_context` - An Arc wrapped AppContext struct.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `AuthWindow` struct.
+    pub fn new(app_context: Arc<AppContext>) -> Self {
+        let name = "".to_string();
+        let action_tx = None;
+        let focused = false;
+        let qr_code_link = None;
+        let description = vec![];
+        let fields = vec![];
+        let selected_field = 0;
+        let error = None;
+        let waiting = true;
+        AuthWindow {
+            app_context,
+            name,
+            action_tx,
+            focused,
+            qr_code_link,
+            description,
+            fields,
+            selected_field,
+            error,
+            waiting,
+        }
+    }
+    /// Set the name of the `AuthWindow`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the `AuthWindow`.
+    ///
+    /// # Returns
+    /// * `Self` - The modified instance of the `AuthWindow`.
+    pub fn with_name(mut self, name: impl AsRef<str>) -> Self {
+        self.name = name.as_ref().to_string();
+        self
+    }
+    /// Set the login link to show as a QR code.
+    ///
+    /// # Arguments
+    /// * `link` - The login link to confirm on another device.
+    pub fn set_qr_code_link(&mut self, link: Option<String>) {
+        self.qr_code_link = link;
+    }
+    /// Set the error to show below the input fields.
+    /// The input fields can be edited and submitted again.
+    ///
+    /// # Arguments
+    /// * `error` - The error returned by Telegram.
+    pub fn set_error(&mut self, error: Option<String>) {
+        self.error = error;
+        self.waiting = false;
+    }
+    /// Show a message explaining why the client can not connect to
+    /// Telegram, e.g. because the API credentials are missing. There are no
+    /// input fields, the user can only quit.
+    ///
+    /// # Arguments
+    /// * `message` - The lines of the message.
+    pub fn set_setup_error(&mut self, message: Vec<String>) {
+        self.description = message;
+        self.fields = vec![];
+        self.selected_field = 0;
+        self.qr_code_link = None;
+        self.error = None;
+        self.waiting = false;
+    }
+    /// Set the current authorization state.
+    /// Each authorization state that needs an input of the user is mapped to
+    /// the input fields of the `AuthWindow`.
+    ///
+    /// # Arguments
+    /// * `state` - The current authorization state.
+    pub fn set_authorization_state(&mut self, state: &AuthorizationState) {
+        let (description, fields, qr_code_link) = match state {
+            AuthorizationState::WaitPhoneNumber => (
+                vec![
+                    "Enter your phone number, including the country calling code.".to_string(),
+                    "Leave it empty to log in with a QR code.".to_string(),
+                ],
+                vec![AuthField::new("Phone number", false)],
+                None,
+            ),
+            AuthorizationState::WaitOtherDeviceConfirmation(x) => (
+                vec![
+                    "Scan the QR code from Telegram on your phone:".to_string(),
+                    "Settings > Devices > Link Desktop Device".to_string(),
+                ],
+                vec![],
+                Some(x.link.clone()),
+            ),
+            AuthorizationState::WaitEmailAddress(_) => (
+                vec!["Enter the email address used to log in.".to_string()],
+                vec![AuthField::new("Email address", false)],
+                None,
+            ),
+            AuthorizationState::WaitEmailCode(_) => (
+                vec!["Enter the code sent to your email address.".to_string()],
+                vec![AuthField::new("Email code", false)],
+                None,
+            ),
+            AuthorizationState::WaitCode(x) => (
+                vec![format!(
+                    "Enter the verification code sent to {}.",
+                    x.code_info.phone_number
+                )],
+                vec![AuthField::new("Code", false)],
+                None,
+            ),
+            AuthorizationState::WaitRegistration(_) => (
+                vec!["Enter your name to create a new account.".to_string()],
+                vec![
+                    AuthField::new("First name", false),
+                    AuthField::new("Last name", false),
+                ],
+                None,
+            ),
+            AuthorizationState::WaitPassword(x) => {
+                let mut description =
+                    vec!["Enter your two-step verification password.".to_string()];
+                if !x.password_hint.is_empty() {
+                    description.push(format!("Hint: {}", x.password_hint));
+                }
+                (description, vec![AuthField::new("Password", true)], None)
+            }
+            _ => (vec!["Connecting to Telegram...".to_string()], vec![], None),
+        };
+        self.description = description;
+        self.fields = fields;
+        self.selected_field = 0;
+        self.qr_code_link = qr_code_link;
+        self.error = None;
+        self.waiting = self.fields.is_empty();
+    }
+    /// Render a text as a QR code made of unicode half blocks.
+    /// The colors are inverted, so that the code can be scanned on a terminal
+    /// with a dark background.
+    ///
+    /// # Arguments
+    /// * `text` - The text to encode.
+    ///
+    /// # Returns
+    /// * `Option<Vec<String>>` - The lines of the QR code, or `None` if the
+    ///   text can not be encoded.
+    pub fn qr_code_lines(text: &str) -> Option<Vec<String>> {
+        match QrCode::new(text.as_bytes()) {
+            Ok(code) => Some(
+                code.render::<Dense1x2>()
+                    .dark_color(Dense1x2::Light)
+                    .light_color(Dense1x2::Dark)
+                    .build()
+                    .lines()
+                    .map(|line| line.to_string())
+                    .collect(),
+            ),
+            Err(e) => {
+                tracing::error!("Failed to render the QR code: {}", e);
+                None
+            }
+        }
+    }
+    /// Submit the values of the input fields.
+    /// If the selected field is not the last one, the next field is selected
+    /// instead.
+    fn submit(&mut self) {
+        if self.waiting || self.fields.is_empty() {
+            return;
+        }
+        if self.selected_field + 1 < self.fields.len() {
+            self.selected_field += 1;
+            return;
+        }
+        let values = self
+            .fields
+            .iter()
+            .map(|field| field.value.trim().to_string())
+            .collect();
+        if let Some(tx) = self.action_tx.as_ref() {
+            tx.send(Action::SubmitAuthorization(values)).unwrap();
+        }
+        self.error = None;
+        self.waiting = true;
+    }
+    /// Get the lines to display in the `AuthWindow`.
+    ///
+    /// # Arguments
+    /// * `area` - The area available to draw the `AuthWindow`.
+    ///
+    /// # Returns
+    /// * `(Vec<Line>, Option<u16>)` - The lines to display and the index of
+    ///   the line of the selected input field.
+    fn lines(&self, area: Rect) -> (Vec<Line<'static>>, Option<u16>) {
+        let mut lines: Vec<Line> = vec![];
+        if let Some(link) = self.qr_code_link.as_ref() {
+            match Self::qr_code_lines(link) {
+                Some(qr_code)
+                    if qr_code.len() as u16 + 8 <= area.height
+                        && qr_code[0].chars().count() as u16 + 2 <= area.width =>
+                {
+                    lines.extend(qr_code.into_iter().map(Line::from));
+                    lines.push(Line::from(""));
+                }
+                _ => {
+                    lines.push(Line::from("Confirm this login link on another device:"));
+                    lines.push(Line::from(link.clone()));
+                    lines.push(Line::from(""));
+                }
+            }
+        }
+        for (i, description) in self.description.iter().enumerate() {
+            if i == 0 {
+                lines.push(Line::from(Span::styled(
+                    description.clone(),
+                    self.app_context.style_item_selected(),
+                )));
+            } else {
+                lines.push(Line::from(description.clone()));
+            }
+        }
+        let mut cursor_line = None;
+        if !self.fields.is_empty() {
+            lines.push(Line::from(""));
+        }
+        for (i, field) in self.fields.iter().enumerate() {
+            let label_style = if i == self.selected_field {
+                self.app_context.style_item_selected()
+            } else {
+                self.app_context.style_prompt_message_preview_text()
+            };
+            if i == self.selected_field {
+                cursor_line = Some(lines.len() as u16);
+            }
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}: ", field.label), label_style),
+                Span::styled(
+                    field.display_value(),
+                    self.app_context.style_prompt_message_text(),
+                ),
+            ]));
+        }
+        lines.push(Line::from(""));
+        if let Some(error) = self.error.as_ref() {
+            lines.push(Line::from(Span::styled(
+                error.clone(),
+                self.app_context.style_status_bar_press_key_key(),
+            )));
+        } else if self.waiting && !self.fields.is_empty() {
+            lines.push(Line::from("Please wait..."));
+        }
+        lines.push(Line::from(Span::styled(
+            "Enter: confirm, Tab: next field, Esc: quit",
+            self.app_context.style_status_bar_press_key_text(),
+        )));
+        (lines, cursor_line)
+    }
+}
+
+/// Implement the `HandleFocus` trait for the `AuthWindow` struct.
+/// This trait allows the `AuthWindow` to be focused or unfocused.
+impl HandleFocus for AuthWindow {
+    /// Set the `focused` flag for the `AuthWindow`.
+    fn focus(&mut self) {
+        self.focused = true;
+    }
+    /// Set the `focused` flag for the `AuthWindow`.
+    fn unfocus(&mut self) {
+        self.focused = false;
+    }
+}
+
+/// Implement the `Component` trait for the `AuthWindow` struct.
+impl Component for AuthWindow {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> io::Result<()> {
+        self.action_tx = Some(tx);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) {
+        if !self.focused {
+            return;
+        }
+        match action {
+            Action::Key(key, modifiers) => match key {
+                KeyCode::Esc => {
+                    if let Some(tx) = self.action_tx.as_ref() {
+                        tx.send(Action::Quit).unwrap();
+                    }
+                }
+                KeyCode::Enter => self.submit(),
+                KeyCode::Tab | KeyCode::Down if !self.fields.is_empty() => {
+                    self.selected_field = (self.selected_field + 1) % self.fields.len();
+                }
+                KeyCode::BackTab | KeyCode::Up if !self.fields.is_empty() => {
+                    self.selected_field =
+                        (self.selected_field + self.fields.len() - 1) % self.fields.len();
+                }
+                KeyCode::Backspace => {
+                    if let Some(field) = self.fields.get_mut(self.selected_field) {
+                        field.value.pop();
+                    }
+                }
+                KeyCode::Char(c) if !modifiers.control && !modifiers.alt => {
+                    if let Some(field) = self.fields.get_mut(self.selected_field) {
+                        field.value.push(c);
+                    }
+                }
+                _ => {}
+            },
+            Action::Paste(text) => {
+                if let Some(field) = self.fields.get_mut(self.selected_field) {
+                    field.value.push_str(text.trim());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> io::Result<()> {
+        let (lines, cursor_line) = self.lines(area);
+        let width = lines
+            .iter()
+            .map(|line| line.width() as u16 + 4)
+            .max()
+            .unwrap_or_default()
+            .max(AUTH_WINDOW_WIDTH)
+            .min(area.width);
+        let [popup_area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(self.app_context.style_border_component_focused())
+            .title(self.name.as_str());
+        let paragraph = Paragraph::new(lines.clone())
+            .block(block)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: false });
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(paragraph, popup_area);
+
+        if let (Some(line), false) = (cursor_line, self.waiting) {
+            let line_width = lines[line as usize].width() as u16;
+            let inner_width = popup_area.width.saturating_sub(2);
+            let x = popup_area.x + 1 + inner_width.saturating_sub(line_width) / 2 + line_width;
+            let y = popup_area.y + 1 + line;
+            if x < popup_area.right() && y < popup_area.bottom() {
+                frame.set_cursor_position(Position::new(x, y));
+            }
+        }
+        Ok(())
+    }
+}
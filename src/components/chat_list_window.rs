@@ -2,20 +2,103 @@ use crate::action::Action;
 use crate::app_context::AppContext;
 use crate::component_name::ComponentName::Prompt;
 use crate::components::component_traits::{Component, HandleFocus};
+use crate::components::confirmation::Confirmation;
 use crate::event::Event;
 use crate::tg::message_entry::MessageEntry;
-use ratatui::layout::Rect;
+use crate::utils::{fuzzy_match, obscure_line, truncate_to_width};
+use crossterm::event::KeyCode;
+use ratatui::layout::{Constraint, Layout, Position, Rect};
 use ratatui::symbols::border::PLAIN;
 use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::block::{Block, Title};
 use ratatui::widgets::Borders;
-use ratatui::widgets::{List, ListDirection, ListState};
+use ratatui::widgets::{List, ListDirection, ListState, Tabs};
 use ratatui::Frame;
 use std::sync::Arc;
-use tdlib_rs::enums::{ChatList, UserStatus};
-use tdlib_rs::types::User;
+use std::time::{Duration, Instant};
+use tdlib_rs::enums::{ChatList, SecretChatState, UserStatus};
+use tdlib_rs::types::{ChatListFolder, User};
 use tokio::sync::mpsc::UnboundedSender;
 
+/// `ChatListSort` is an enum that represents the order of the chats in the
+/// chat list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ChatListSort {
+    /// The chats are ordered by the last activity, like in Telegram.
+    #[default]
+    Activity,
+    /// The chats with unread messages are shown first, both groups are
+    /// ordered by the last activity.
+    UnreadFirst,
+    /// The chats are ordered by name.
+    Alphabetical,
+}
+/// Implementation of the `ChatListSort` enum.
+impl ChatListSort {
+    /// Get the next order of the chats, used to cycle the orders.
+    ///
+    /// # Returns
+    /// * `Self` - The next order.
+    pub fn next(self) -> Self {
+        match self {
+            ChatListSort::Activity => ChatListSort::UnreadFirst,
+            ChatListSort::UnreadFirst => ChatListSort::Alphabetical,
+            ChatListSort::Alphabetical => ChatListSort::Activity,
+        }
+    }
+    /// Get the name of the order, as in `chat_list_sort` of `app.toml`.
+    ///
+    /// # Returns
+    /// * `&'static str` - The name of the order.
+    pub fn name(self) -> &'static str {
+        match self {
+            ChatListSort::Activity => "activity",
+            ChatListSort::UnreadFirst => "unread_first",
+            ChatListSort::Alphabetical => "alphabetical",
+        }
+    }
+    /// Sort the chats, that are given in the order of the last activity.
+    /// The sort is stable, so the chats that are equal keep the order of the
+    /// last activity.
+    ///
+    /// # Arguments
+    /// * `chat_list` - The chats to sort.
+    pub fn sort(self, chat_list: &mut [ChatListEntry]) {
+        match self {
+            ChatListSort::Activity => {}
+            ChatListSort::UnreadFirst => chat_list.sort_by_key(|chat| chat.unread_count == 0),
+            ChatListSort::Alphabetical => {
+                chat_list.sort_by_cached_key(|chat| chat.chat_name.to_lowercase())
+            }
+        }
+    }
+}
+/// Implement the `From` trait for `&str`.
+/// An unknown value falls back to the order of the last activity.
+impl From<&str> for ChatListSort {
+    fn from(value: &str) -> Self {
+        match value {
+            "activity" => ChatListSort::Activity,
+            "unread_first" => ChatListSort::UnreadFirst,
+            "alphabetical" => ChatListSort::Alphabetical,
+            _ => {
+                tracing::warn!("Unknown chat list sort: {}, using activity", value);
+                ChatListSort::Activity
+            }
+        }
+    }
+}
+/// Implement the `Display` trait for `ChatListSort`.
+impl std::fmt::Display for ChatListSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChatListSort::Activity => write!(f, "activity"),
+            ChatListSort::UnreadFirst => write!(f, "unread first"),
+            ChatListSort::Alphabetical => write!(f, "alphabetical"),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ChatListEntry {
     chat_id: i64,
@@ -28,6 +111,17 @@ pub struct ChatListEntry {
     last_read_inbox_message_id: Option<i64>,
     /// Identifier of the last read outgoing message
     last_read_outbox_message_id: Option<i64>,
+    /// The state of the secret chat, `None` if the chat is not secret.
+    secret_chat_state: Option<SecretChatState>,
+    /// The indices of the characters of the name matched by the filter of
+    /// the chat list.
+    name_matches: Vec<usize>,
+    /// Whether the chat has messages scheduled to be sent.
+    has_scheduled_messages: bool,
+    /// Whether the chat is pinned in the chat list.
+    is_pinned: bool,
+    /// Whether the notifications of the chat are muted.
+    is_muted: bool,
 }
 impl Default for ChatListEntry {
     fn default() -> Self {
@@ -45,6 +139,11 @@ impl ChatListEntry {
             unread_count: 0,
             last_read_inbox_message_id: None,
             last_read_outbox_message_id: None,
+            secret_chat_state: None,
+            name_matches: vec![],
+            has_scheduled_messages: false,
+            is_pinned: false,
+            is_muted: false,
         }
     }
 
@@ -69,11 +168,82 @@ impl ChatListEntry {
     pub fn set_last_read_inbox_message_id(&mut self, last_read_inbox_message_id: i64) {
         self.last_read_inbox_message_id = Some(last_read_inbox_message_id);
     }
+    pub fn set_secret_chat_state(&mut self, secret_chat_state: SecretChatState) {
+        self.secret_chat_state = Some(secret_chat_state);
+    }
+    pub fn set_has_scheduled_messages(&mut self, has_scheduled_messages: bool) {
+        self.has_scheduled_messages = has_scheduled_messages;
+    }
     pub fn set_last_read_outbox_message_id(&mut self, last_read_outbox_message_id: i64) {
         self.last_read_outbox_message_id = Some(last_read_outbox_message_id);
     }
+    pub fn set_is_pinned(&mut self, is_pinned: bool) {
+        self.is_pinned = is_pinned;
+    }
+    pub fn set_is_muted(&mut self, is_muted: bool) {
+        self.is_muted = is_muted;
+    }
+
+    /// Get the spans of the name of the chat, with the characters matched by
+    /// the filter of the chat list highlighted.
+    /// The name is truncated with an ellipsis if it is wider than
+    /// `max_width`.
+    ///
+    /// # Arguments
+    /// * `app_context` - The application context.
+    /// * `max_width` - The maximum display width of the name.
+    ///
+    /// # Returns
+    /// * `Vec<Span>` - The spans of the name.
+    fn chat_name_spans(&self, app_context: &AppContext, max_width: usize) -> Vec<Span<'static>> {
+        let style = app_context.style_chat_list_item_chat_name();
+        let chat_name = truncate_to_width(&self.chat_name, max_width);
+        if self.name_matches.is_empty() {
+            return vec![Span::styled(chat_name, style)];
+        }
+        let match_style = app_context.style_chat_list_item_chat_name_match();
+        // The ellipsis of a truncated name is never highlighted.
+        let kept_chars = if chat_name == self.chat_name {
+            usize::MAX
+        } else {
+            chat_name.chars().count() - 1
+        };
+        let mut spans = vec![];
+        let mut current = String::new();
+        let mut current_matched = false;
+        for (i, c) in chat_name.chars().enumerate() {
+            let matched = i < kept_chars && self.name_matches.contains(&i);
+            if matched != current_matched && !current.is_empty() {
+                let current_style = if current_matched { match_style } else { style };
+                spans.push(Span::styled(std::mem::take(&mut current), current_style));
+            }
+            current_matched = matched;
+            current.push(c);
+        }
+        let current_style = if current_matched { match_style } else { style };
+        spans.push(Span::styled(current, current_style));
+        spans
+    }
 
-    fn get_text_styled(&self, app_context: &AppContext) -> Text {
+    /// Get the text of the chat in the list: the first line has the name of
+    /// the chat on the left, truncated to fit, and the pinned and muted
+    /// icons, the unread counter and the time of the last message aligned to
+    /// the right; the second line has the preview of the last message, if
+    /// `show_chat_previews` is enabled.
+    ///
+    /// # Arguments
+    /// * `app_context` - The application context.
+    /// * `number` - The number shown before the name, if any.
+    /// * `width` - The width of the list, in terminal columns.
+    ///
+    /// # Returns
+    /// * `Text` - The styled text of the chat.
+    fn get_text_styled(
+        &self,
+        app_context: &AppContext,
+        number: Option<usize>,
+        width: usize,
+    ) -> Text {
         let mut online_symbol = "";
         let mut verificated_symbol = "";
         if let Some(user) = &self.user {
@@ -95,35 +265,90 @@ impl ChatListEntry {
 
         let preview_lines = -1;
         let mut entry = Text::default();
-        entry.extend(vec![Line::from(vec![
+        let mut spans = vec![
+            number.map_or_else(Span::default, |number| {
+                Span::styled(
+                    format!("{} ", number),
+                    app_context.style_chat_list_item_unread_counter(),
+                )
+            }),
             Span::raw(online_symbol),
-            Span::styled(
-                self.chat_name.clone(),
-                app_context.style_chat_list_item_chat_name(),
-            ),
+            Span::raw(match self.secret_chat_state {
+                Some(SecretChatState::Ready) => "🔒 ",
+                Some(SecretChatState::Pending) => "🔒⏳ ",
+                Some(SecretChatState::Closed) => "🔒✖ ",
+                None => "",
+            }),
+            Span::raw(if self.has_scheduled_messages {
+                "⏰ "
+            } else {
+                ""
+            }),
+        ];
+        let verificated_span = Span::raw(if verificated_symbol.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", verificated_symbol)
+        });
+        let right_spans = vec![
             Span::raw(" "),
+            Span::raw(if self.is_pinned { "📌" } else { "" }),
+            Span::raw(if self.is_muted { "🔕" } else { "" }),
             Span::styled(
-                unread_info,
+                if unread_info.is_empty() {
+                    unread_info
+                } else {
+                    format!(" {}", unread_info)
+                },
                 app_context.style_chat_list_item_unread_counter(),
             ),
             Span::raw(" "),
-            Span::raw(verificated_symbol),
-            Span::raw(" | "),
             self.last_message.as_ref().map_or_else(Span::default, |e| {
                 e.timestamp().get_span_styled(app_context)
             }),
-        ])]);
-        entry.extend(self.last_message.as_ref().map_or_else(Line::default, |e| {
+        ];
+        let spans_width = |spans: &[Span]| spans.iter().map(Span::width).sum::<usize>();
+        let fixed_width =
+            spans_width(&spans) + verificated_span.width() + spans_width(&right_spans);
+        spans.extend(self.chat_name_spans(app_context, width.saturating_sub(fixed_width)));
+        spans.push(verificated_span);
+        // The padding aligns the right spans to the right.
+        let padding = width.saturating_sub(spans_width(&spans) + spans_width(&right_spans));
+        spans.push(Span::raw(" ".repeat(padding)));
+        spans.extend(right_spans);
+        entry.extend(vec![Line::from(spans)]);
+        if !app_context.app_config().show_chat_previews {
+            return entry;
+        }
+        let mut preview = self.last_message.as_ref().map_or_else(Line::default, |e| {
             e.get_lines_styled_with_style(
                 app_context.style_chat_list_item_message_content(),
+                app_context.style_code_block(),
                 preview_lines,
             )[0]
             .clone()
-        }));
+        });
+        if app_context.privacy_mode() {
+            obscure_line(&mut preview);
+        }
+        entry.extend(preview);
 
         entry
     }
 }
+/// The confirmation of the deletion of the history of a chat, with its
+/// options.
+struct ClearHistoryConfirmation {
+    /// The id of the chat.
+    chat_id: i64,
+    /// The name of the chat.
+    chat_name: String,
+    /// Whether to remove the chat from the chat list too.
+    remove_from_chat_list: bool,
+    /// Whether to delete the messages for the other users too, `None` if they
+    /// can be deleted only for the user.
+    revoke: Option<bool>,
+}
 /// `ChatListWindow` is a struct that represents a window for displaying a list
 /// of chat items. It is responsible for managing the layout and rendering of
 /// the chat list.
@@ -140,6 +365,34 @@ pub struct ChatListWindow {
     chat_list_state: ListState,
     /// Indicates whether the `ChatListWindow` is focused or not.
     focused: bool,
+    /// Indicates whether the selection saved in the application state has
+    /// still to be restored.
+    restore_selection: bool,
+    /// The order of the chats in the list.
+    sort: ChatListSort,
+    /// The chat folder whose chats are shown, `None` for all the chats.
+    chat_folder_id: Option<i32>,
+    /// The area of the items of the list, used to find the clicked chat.
+    list_area: Rect,
+    /// The index of the last clicked chat and the time of the click, used to
+    /// detect a double click.
+    last_click: Option<(usize, Instant)>,
+    /// The confirmation of the deletion of the history of a chat, `None` if
+    /// it is not shown.
+    clear_history_confirmation: Option<ClearHistoryConfirmation>,
+    /// The id and the name of the chat to leave, shown in a confirmation,
+    /// `None` if it is not shown.
+    leave_confirmation: Option<(i64, String)>,
+    /// The id of the chat to select as soon as it is shown in the list, e.g.
+    /// a chat that has just been joined or the chat selected with the filter.
+    pending_jump_chat_id: Option<i64>,
+    /// The filter of the chats by name that is being typed, `None` if the
+    /// chats are not filtered.
+    filter: Option<String>,
+    /// Indicates whether a chat has been opened since the `ChatListWindow`
+    /// was focused, so that the messages to forward are not canceled when
+    /// the prompt is focused to confirm them.
+    chat_opened: bool,
 }
 /// Implementation of the `ChatListWindow` struct.
 impl ChatListWindow {
@@ -156,6 +409,20 @@ impl ChatListWindow {
         let chat_list = vec![];
         let chat_list_state = ListState::default();
         let focused = false;
+        let restore_selection = true;
+        // The order cycled in the last session overrides the configured one.
+        let sort = match app_context.app_state().chat_list_sort.as_deref() {
+            Some(sort) => ChatListSort::from(sort),
+            None => ChatListSort::from(app_context.app_config().chat_list_sort.as_str()),
+        };
+        let chat_folder_id = None;
+        let list_area = Rect::default();
+        let last_click = None;
+        let clear_history_confirmation = None;
+        let leave_confirmation = None;
+        let pending_jump_chat_id = None;
+        let filter = None;
+        let chat_opened = false;
 
         ChatListWindow {
             app_context,
@@ -164,6 +431,16 @@ impl ChatListWindow {
             chat_list,
             chat_list_state,
             focused,
+            restore_selection,
+            sort,
+            chat_folder_id,
+            list_area,
+            last_click,
+            clear_history_confirmation,
+            leave_confirmation,
+            pending_jump_chat_id,
+            filter,
+            chat_opened,
         }
     }
     /// Set the name of the `ChatListWindow`.
@@ -184,7 +461,7 @@ impl ChatListWindow {
                 if i == self.chat_list.len() / 2 {
                     if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
                         event_tx
-                            .send(Event::LoadChats(ChatList::Main.into(), 20))
+                            .send(Event::LoadChats(self.chat_list_to_load().into(), 20))
                             .unwrap();
                     }
                 }
@@ -213,32 +490,512 @@ impl ChatListWindow {
         };
         self.chat_list_state.select(Some(i));
     }
+    /// Select the next or the previous chat with unread messages, starting
+    /// from the selected chat and wrapping around the list.
+    /// Nothing changes if no chat has unread messages.
+    ///
+    /// # Arguments
+    /// * `forward` - Whether to select the next chat or the previous one.
+    fn select_unread(&mut self, forward: bool) {
+        let len = self.chat_list.len();
+        if len == 0 {
+            return;
+        }
+        // Without a selection, the search starts from the first chat or from
+        // the last one.
+        let start = match self.chat_list_state.selected() {
+            Some(i) if forward => i + 1,
+            Some(i) => i + len - 1,
+            None if forward => 0,
+            None => len - 1,
+        };
+        let index = (0..len)
+            .map(|offset| {
+                if forward {
+                    (start + offset) % len
+                } else {
+                    (start + len - offset) % len
+                }
+            })
+            .find(|i| self.chat_list[*i].unread_count > 0);
+        match index {
+            Some(index) => self.chat_list_state.select(Some(index)),
+            None => self
+                .app_context
+                .tg_context()
+                .set_info_message("There are no unread chats"),
+        }
+    }
     /// Unselect the chat item in the list.
     fn unselect(&mut self) {
         self.chat_list_state.select(None);
+        self.app_context.tg_context().cancel_forward_messages();
+    }
+    /// Get the Telegram chat list of the shown chats.
+    ///
+    /// # Returns
+    /// * `ChatList` - The chat list of the selected folder, or the main one.
+    fn chat_list_to_load(&self) -> ChatList {
+        match self.chat_folder_id {
+            Some(chat_folder_id) => ChatList::Folder(ChatListFolder { chat_folder_id }),
+            None => ChatList::Main,
+        }
+    }
+    /// Select the next or the previous chat folder, "All chats" comes before
+    /// the first folder.
+    ///
+    /// # Arguments
+    /// * `forward` - Whether to select the next folder or the previous one.
+    fn cycle_folder(&self, forward: bool) {
+        let mut folder_ids: Vec<Option<i32>> = vec![None];
+        folder_ids.extend(
+            self.app_context
+                .tg_context()
+                .chat_folders()
+                .iter()
+                .map(|folder| Some(folder.id)),
+        );
+        let i = folder_ids
+            .iter()
+            .position(|id| *id == self.chat_folder_id)
+            .unwrap_or_default();
+        let len = folder_ids.len();
+        let i = if forward {
+            (i + 1) % len
+        } else {
+            (i + len - 1) % len
+        };
+        if let Some(tx) = self.command_tx.as_ref() {
+            tx.send(Action::SwitchChatFolder(folder_ids[i])).unwrap();
+        }
+    }
+    /// Show only the chats of a chat folder.
+    /// The chats of the folder are loaded, because Telegram sends the
+    /// positions of the chats in a folder only after it is loaded.
+    ///
+    /// # Arguments
+    /// * `chat_folder_id` - The chat folder, `None` for all the chats.
+    fn switch_chat_folder(&mut self, chat_folder_id: Option<i32>) {
+        if self.chat_folder_id == chat_folder_id {
+            return;
+        }
+        self.chat_folder_id = chat_folder_id;
+        self.chat_list_state.select(None);
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx
+                .send(Event::LoadChats(self.chat_list_to_load().into(), 20))
+                .unwrap();
+        }
+    }
+    /// Draw the tabs of the chat folders, with the selected one highlighted.
+    ///
+    /// # Arguments
+    /// * `frame` - A mutable reference to the frame.
+    /// * `area` - The area of the tabs.
+    fn draw_folder_tabs(&self, frame: &mut Frame<'_>, area: Rect) {
+        let tg_context = self.app_context.tg_context();
+        let chat_folders = tg_context.chat_folders();
+        let mut titles = vec!["All chats".to_string()];
+        titles.extend(chat_folders.iter().map(|folder| folder.title.clone()));
+        let selected = self
+            .chat_folder_id
+            .and_then(|id| chat_folders.iter().position(|folder| folder.id == id))
+            .map(|i| i + 1)
+            .unwrap_or_default();
+        let tabs = Tabs::new(titles)
+            .select(selected)
+            .style(self.app_context.style_chat_list())
+            .highlight_style(self.app_context.style_chat_list_item_selected())
+            .divider("|")
+            .padding(" ", " ");
+        frame.render_widget(tabs, area);
+    }
+    /// Switch to the next order of the chats, and save it in the application
+    /// state.
+    /// The selected chat stays selected, even if its position changes.
+    fn cycle_sort(&mut self) {
+        let selected_chat_id = self
+            .chat_list_state
+            .selected()
+            .and_then(|i| self.chat_list.get(i))
+            .map(|chat| chat.chat_id);
+        self.sort = self.sort.next();
+        let mut app_state = self.app_context.app_state();
+        app_state.chat_list_sort = Some(self.sort.name().to_string());
+        if let Err(e) = app_state.save() {
+            tracing::error!("Failed to save the app state: {}", e);
+        }
+        self.sort.sort(&mut self.chat_list);
+        if let Some(chat_id) = selected_chat_id {
+            self.chat_list_state.select(
+                self.chat_list
+                    .iter()
+                    .position(|chat| chat.chat_id == chat_id),
+            );
+        }
+    }
+    /// Keep only the chats whose name matches the filter, ordered by the
+    /// quality of the match. The chats with the same score keep their order.
+    fn apply_filter(&mut self) {
+        let Some(filter) = self.filter.as_ref().filter(|filter| !filter.is_empty()) else {
+            return;
+        };
+        let case_sensitive = self
+            .app_context
+            .app_config()
+            .chat_list_filter_case_sensitive;
+        let mut scored: Vec<(i32, ChatListEntry)> = std::mem::take(&mut self.chat_list)
+            .into_iter()
+            .filter_map(|mut chat| {
+                let (score, name_matches) = fuzzy_match(filter, &chat.chat_name, case_sensitive)?;
+                chat.name_matches = name_matches;
+                Some((score, chat))
+            })
+            .collect();
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.chat_list = scored.into_iter().map(|(_, chat)| chat).collect();
+    }
+    /// Handle a key while the filter of the chat list is typed.
+    /// `Enter` opens the selected chat and `Esc` stops filtering, both keep
+    /// the chat selected in the whole list.
+    ///
+    /// # Arguments
+    /// * `key` - The pressed key.
+    fn handle_filter_key(&mut self, key: KeyCode) {
+        let Some(filter) = self.filter.as_mut() else {
+            return;
+        };
+        match key {
+            KeyCode::Char(c) => {
+                filter.push(c);
+                self.chat_list_state.select(Some(0));
+            }
+            KeyCode::Backspace => {
+                filter.pop();
+                self.chat_list_state.select(Some(0));
+            }
+            KeyCode::Down => self.next(),
+            KeyCode::Up => self.previous(),
+            KeyCode::Enter => {
+                self.stop_filter();
+                self.confirm_selection();
+            }
+            KeyCode::Esc => self.stop_filter(),
+            _ => {}
+        }
+    }
+    /// Stop filtering the chats, the selected chat stays selected.
+    fn stop_filter(&mut self) {
+        if self.filter.take().is_none() {
+            return;
+        }
+        self.pending_jump_chat_id = self
+            .chat_list_state
+            .selected()
+            .and_then(|i| self.chat_list.get(i))
+            .map(|chat| chat.chat_id);
+        if let Some(tx) = self.command_tx.as_ref() {
+            tx.send(Action::ChatListFilterDone).unwrap();
+        }
+    }
+    /// Restore the selection saved in the application state.
+    /// The saved chat is searched by its identifier, because its position in
+    /// the list may be changed since the last run. If the chat no longer
+    /// exists, the saved index is used when it is still in range.
+    fn restore_selection(&mut self) {
+        if !self.restore_selection || self.chat_list.is_empty() {
+            return;
+        }
+        self.restore_selection = false;
+
+        let app_state = self.app_context.app_state();
+        let selected = app_state
+            .last_open_chat_id
+            .and_then(|chat_id| {
+                self.chat_list
+                    .iter()
+                    .position(|chat| chat.chat_id == chat_id)
+            })
+            .or(app_state
+                .chat_list_selected
+                .filter(|i| *i < self.chat_list.len()));
+        self.chat_list_state.select(selected);
+    }
+    /// Get the index of the chat drawn at a row of the list.
+    ///
+    /// # Arguments
+    /// * `column` - The column of the position.
+    /// * `row` - The row of the position.
+    ///
+    /// # Returns
+    /// * `Option<usize>` - The index of the chat, or `None` if there is no
+    ///   chat at the position.
+    fn index_at(&self, column: u16, row: u16) -> Option<usize> {
+        if !self.list_area.contains(Position::new(column, row)) {
+            return None;
+        }
+        let mut y = self.list_area.y;
+        for (i, item) in self
+            .chat_list
+            .iter()
+            .enumerate()
+            .skip(self.chat_list_state.offset())
+        {
+            y += item
+                .get_text_styled(&self.app_context, None, self.list_area.width as usize)
+                .height() as u16;
+            if row < y {
+                return Some(i);
+            }
+        }
+        None
+    }
+    /// Handle a left click on the list.
+    /// The clicked chat is selected, and it is opened if
+    /// `mouse_single_click_open` is enabled or if the same chat has been
+    /// clicked within `mouse_double_click_ms`.
+    ///
+    /// # Arguments
+    /// * `column` - The column of the click.
+    /// * `row` - The row of the click.
+    fn click(&mut self, column: u16, row: u16) {
+        let Some(index) = self.index_at(column, row) else {
+            return;
+        };
+        let app_config = self.app_context.app_config();
+        let threshold = Duration::from_millis(app_config.mouse_double_click_ms);
+        let now = Instant::now();
+        let double_click = self
+            .last_click
+            .is_some_and(|(i, time)| i == index && now.duration_since(time) <= threshold);
+        let open = app_config.mouse_single_click_open || double_click;
+        drop(app_config);
+
+        self.chat_list_state.select(Some(index));
+        if open {
+            self.last_click = None;
+            self.confirm_selection();
+        } else {
+            self.last_click = Some((index, now));
+        }
+    }
+    /// Start a secret chat with the user of the selected private chat.
+    fn create_secret_chat(&self) {
+        let Some(chat) = self
+            .chat_list_state
+            .selected()
+            .and_then(|i| self.chat_list.get(i))
+        else {
+            return;
+        };
+        if let (Some(user), None) = (chat.user.as_ref(), chat.secret_chat_state.as_ref()) {
+            if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+                event_tx.send(Event::CreateSecretChat(user.id)).unwrap();
+            }
+        }
+    }
+    /// Ask to confirm the deletion of the history of the selected chat.
+    fn clear_history(&mut self) {
+        let Some(chat) = self
+            .chat_list_state
+            .selected()
+            .and_then(|i| self.chat_list.get(i))
+        else {
+            return;
+        };
+        let can_revoke = self
+            .app_context
+            .tg_context()
+            .chats()
+            .get(&chat.chat_id)
+            .is_some_and(|chat| chat.can_be_deleted_for_all_users);
+        self.clear_history_confirmation = Some(ClearHistoryConfirmation {
+            chat_id: chat.chat_id,
+            chat_name: chat.chat_name.clone(),
+            remove_from_chat_list: false,
+            revoke: can_revoke.then_some(false),
+        });
+    }
+    /// Handle a key while the confirmation of the deletion of the history of
+    /// a chat is shown.
+    ///
+    /// # Arguments
+    /// * `key` - The pressed key.
+    fn handle_clear_history_confirmation(&mut self, key: KeyCode) {
+        let Some(confirmation) = self.clear_history_confirmation.as_mut() else {
+            return;
+        };
+        match (Confirmation::answer(key), key) {
+            (Some(true), _) => {
+                if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+                    event_tx
+                        .send(Event::ClearChatHistory(
+                            confirmation.chat_id,
+                            confirmation.remove_from_chat_list,
+                            confirmation.revoke.unwrap_or(false),
+                        ))
+                        .unwrap();
+                }
+                self.clear_history_confirmation = None;
+            }
+            (Some(false), _) => self.clear_history_confirmation = None,
+            (None, KeyCode::Char('r')) => {
+                confirmation.remove_from_chat_list = !confirmation.remove_from_chat_list;
+            }
+            (None, KeyCode::Char('b')) => {
+                if let Some(revoke) = confirmation.revoke.as_mut() {
+                    *revoke = !*revoke;
+                }
+            }
+            _ => {}
+        }
+    }
+    /// Draw the confirmation of the deletion of the history of a chat in the
+    /// middle of the given area.
+    ///
+    /// # Arguments
+    /// * `frame` - A mutable reference to the frame to be rendered.
+    /// * `area` - The area of the chat list.
+    fn draw_clear_history_confirmation(&self, frame: &mut Frame<'_>, area: Rect) {
+        let Some(confirmation) = self.clear_history_confirmation.as_ref() else {
+            return;
+        };
+        let check = |checked: bool| if checked { "[x]" } else { "[ ]" };
+        let key_style = self.app_context.style_status_bar_press_key_key();
+        let text_style = self.app_context.style_status_bar_press_key_text();
+        let mut confirmation_popup = Confirmation::new("Clear History", "clear")
+            .line(format!("Clear the history of {}?", confirmation.chat_name))
+            .line("")
+            .line(vec![
+                Span::styled("r", key_style),
+                Span::styled(
+                    format!(
+                        ": {} remove from the chat list",
+                        check(confirmation.remove_from_chat_list)
+                    ),
+                    text_style,
+                ),
+            ]);
+        if let Some(revoke) = confirmation.revoke {
+            confirmation_popup = confirmation_popup.line(vec![
+                Span::styled("b", key_style),
+                Span::styled(
+                    format!(": {} delete for both sides", check(revoke)),
+                    text_style,
+                ),
+            ]);
+        }
+        confirmation_popup
+            .style(self.app_context.style_chat_list())
+            .draw(&self.app_context, frame, area);
+    }
+    /// Ask to confirm leaving the selected chat.
+    fn leave(&mut self) {
+        if let Some(chat) = self
+            .chat_list_state
+            .selected()
+            .and_then(|i| self.chat_list.get(i))
+        {
+            self.leave_confirmation = Some((chat.chat_id, chat.chat_name.clone()));
+        }
+    }
+    /// Join the selected chat.
+    fn join(&self) {
+        if let Some(chat) = self
+            .chat_list_state
+            .selected()
+            .and_then(|i| self.chat_list.get(i))
+        {
+            if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+                event_tx.send(Event::JoinChat(chat.chat_id)).unwrap();
+            }
+        }
+    }
+    /// Handle a key while the confirmation of leaving a chat is shown.
+    ///
+    /// # Arguments
+    /// * `key` - The pressed key.
+    fn handle_leave_confirmation(&mut self, key: KeyCode) {
+        let Some((chat_id, _)) = self.leave_confirmation else {
+            return;
+        };
+        match Confirmation::answer(key) {
+            Some(true) => {
+                if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+                    event_tx.send(Event::LeaveChat(chat_id)).unwrap();
+                }
+                self.leave_confirmation = None;
+            }
+            Some(false) => self.leave_confirmation = None,
+            None => {}
+        }
+    }
+    /// Draw the confirmation of leaving a chat in the middle of the given
+    /// area.
+    ///
+    /// # Arguments
+    /// * `frame` - A mutable reference to the frame to be rendered.
+    /// * `area` - The area of the chat list.
+    fn draw_leave_confirmation(&self, frame: &mut Frame<'_>, area: Rect) {
+        let Some((_, chat_name)) = self.leave_confirmation.as_ref() else {
+            return;
+        };
+        Confirmation::new("Leave", "leave")
+            .line(format!("Leave {}?", chat_name))
+            .style(self.app_context.style_chat_list())
+            .draw(&self.app_context, frame, area);
+    }
+    /// Select and open the chat at an index of the list.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the chat in the list.
+    fn jump_to(&mut self, index: usize) {
+        if index < self.chat_list.len() {
+            self.chat_list_state.select(Some(index));
+            self.confirm_selection();
+        }
     }
     /// Confirm the selection of the chat item in the list.
     fn confirm_selection(&mut self) {
         if let Some(i) = self.chat_list_state.selected() {
             if let Some(chat) = self.chat_list.get(i) {
-                self.app_context
-                    .tg_context()
-                    .set_open_chat_user(chat.user.clone());
-                self.app_context.tg_context().set_open_chat_id(chat.chat_id);
-                self.app_context.tg_context().clear_open_chat_messages();
-                self.app_context
-                    .action_tx()
-                    .send(Action::FocusComponent(Prompt))
-                    .unwrap();
+                let mut app_state = self.app_context.app_state();
+                app_state.set_last_open_chat(chat.chat_id, i);
+                if let Err(e) = app_state.save() {
+                    tracing::error!("Failed to save the app state: {}", e);
+                }
+                drop(app_state);
 
-                if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
-                    self.app_context.tg_context().set_from_message_id(0);
-                    // Load chat history
-                    event_tx.send(Event::GetChatHistory).unwrap();
+                self.open_chat(chat.chat_id, chat.user.clone());
+            }
+        }
+    }
+    /// Open a chat, even if it is not shown in the list, e.g. a public chat
+    /// the user is not a member of.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `user` - The user of the chat, if it is a private chat.
+    fn open_chat(&mut self, chat_id: i64, user: Option<User>) {
+        self.chat_opened = true;
+        self.app_context.tg_context().set_open_chat_user(user);
+        self.app_context.tg_context().set_open_chat_id(chat_id);
+        self.app_context.tg_context().clear_open_chat_messages();
+        self.app_context
+            .action_tx()
+            .send(Action::FocusComponent(Prompt))
+            .unwrap();
 
-                    // Mark all unread messages as read
-                    event_tx.send(Event::ViewAllMessages).unwrap();
-                }
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            self.app_context.tg_context().set_from_message_id(0);
+            event_tx.send(Event::OpenChat(chat_id)).unwrap();
+            // Load chat history
+            event_tx.send(Event::GetChatHistory(chat_id)).unwrap();
+
+            // Mark all unread messages as read, unless they are marked as
+            // read when they are shown by the chat window.
+            if !self.app_context.app_config().mark_read_on_scroll {
+                event_tx.send(Event::ViewAllMessages).unwrap();
             }
         }
     }
@@ -250,10 +1007,19 @@ impl HandleFocus for ChatListWindow {
     /// Set the `focused` flag for the `ChatListWindow`.
     fn focus(&mut self) {
         self.focused = true;
+        self.chat_opened = false;
     }
     /// Set the `focused` flag for the `ChatListWindow`.
+    /// The messages to forward are canceled if the chat list is left without
+    /// choosing the chat to forward them to.
     fn unfocus(&mut self) {
+        if self.focused && !self.chat_opened {
+            self.app_context.tg_context().cancel_forward_messages();
+        }
         self.focused = false;
+        self.clear_history_confirmation = None;
+        self.leave_confirmation = None;
+        self.filter = None;
     }
 }
 
@@ -265,11 +1031,56 @@ impl Component for ChatListWindow {
     }
 
     fn update(&mut self, action: Action) {
+        if self.clear_history_confirmation.is_some() {
+            // The other actions are ignored, they are sent also for the keys
+            // of the confirmation.
+            if let Action::Key(key, _) = action {
+                self.handle_clear_history_confirmation(key);
+            }
+            return;
+        }
+        if self.leave_confirmation.is_some() {
+            if let Action::Key(key, _) = action {
+                self.handle_leave_confirmation(key);
+            }
+            return;
+        }
+        if self.filter.is_some() {
+            if let Action::Key(key, _) = action {
+                self.handle_filter_key(key);
+            }
+            return;
+        }
         match action {
             Action::ChatListNext => self.next(),
             Action::ChatListPrevious => self.previous(),
             Action::ChatListUnselect => self.unselect(),
             Action::ChatListOpen => self.confirm_selection(),
+            Action::ChatListCreateSecretChat => self.create_secret_chat(),
+            Action::ChatListClearHistory => self.clear_history(),
+            Action::ChatListJoin => self.join(),
+            Action::ChatListLeave => self.leave(),
+            Action::ChatListCycleSort => self.cycle_sort(),
+            Action::ChatListFilter => self.filter = Some(String::new()),
+            Action::ChatListNextFolder => self.cycle_folder(true),
+            Action::ChatListPreviousFolder => self.cycle_folder(false),
+            Action::ChatListNextUnread => self.select_unread(true),
+            Action::ChatListPreviousUnread => self.select_unread(false),
+            Action::SwitchChatFolder(chat_folder_id) => self.switch_chat_folder(chat_folder_id),
+            Action::MouseClick(column, row) => self.click(column, row),
+            Action::JumpToChatIndex(index) => self.jump_to(index),
+            Action::JumpToChat(chat_id) => {
+                match self.chat_list.iter().position(|c| c.chat_id == chat_id) {
+                    Some(index) => self.jump_to(index),
+                    None => {
+                        // The chat is selected when it is shown in the list,
+                        // e.g. after it has been joined.
+                        let user = self.app_context.tg_context().users().get(&chat_id).cloned();
+                        self.open_chat(chat_id, user);
+                        self.pending_jump_chat_id = Some(chat_id);
+                    }
+                }
+            }
             _ => {}
         }
     }
@@ -280,28 +1091,73 @@ impl Component for ChatListWindow {
         } else {
             self.app_context.style_chat_list()
         };
-        if let Ok(Some(items)) = self.app_context.tg_context().get_chats_index() {
+        if let Ok(Some(mut items)) = self
+            .app_context
+            .tg_context()
+            .get_chats_index(self.chat_folder_id)
+        {
+            self.sort.sort(&mut items);
             self.chat_list = items;
+            self.apply_filter();
         }
-        let items = self
-            .chat_list
-            .iter()
-            .map(|item| item.get_text_styled(&self.app_context));
-        let block = Block::default()
+        self.restore_selection();
+        if let Some(index) = self
+            .pending_jump_chat_id
+            .and_then(|chat_id| self.chat_list.iter().position(|c| c.chat_id == chat_id))
+        {
+            self.pending_jump_chat_id = None;
+            self.chat_list_state.select(Some(index));
+        }
+        let show_numbers = self.app_context.app_config().show_chat_list_numbers;
+        // The block has only the left border.
+        let item_width = area.width.saturating_sub(1) as usize;
+        let items = self.chat_list.iter().enumerate().map(|(i, item)| {
+            let number = (show_numbers && i < 9).then_some(i + 1);
+            item.get_text_styled(&self.app_context, number, item_width)
+        });
+        let filter_line = self
+            .filter
+            .as_ref()
+            .map(|filter| Line::from(format!("/{}", filter)));
+        let mut block = Block::default()
             .border_set(PLAIN)
             .border_style(style_border_focused)
             .borders(Borders::TOP | Borders::LEFT | Borders::BOTTOM)
-            .title(Title::from(self.name.as_str()));
+            .title(Title::from(match self.sort {
+                ChatListSort::Activity => self.name.clone(),
+                sort => format!("{} ({})", self.name, sort),
+            }));
+        if let Some(filter_line) = filter_line.clone() {
+            block = block.title_bottom(filter_line);
+        }
 
         let list = List::new(items)
-            .block(block)
+            .block(block.clone())
             .style(self.app_context.style_chat_list())
             .highlight_style(self.app_context.style_chat_list_item_selected())
             .direction(ListDirection::TopToBottom);
         // .highlight_symbol("➤ ")
         // .repeat_highlight_symbol(true)
 
+        // The tabs of the folders are shown only if the user has folders.
+        let area = if self.app_context.tg_context().chat_folders().is_empty() {
+            area
+        } else {
+            let [tabs_area, list_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+            self.draw_folder_tabs(frame, tabs_area);
+            list_area
+        };
+        self.list_area = block.inner(area);
         frame.render_stateful_widget(list, area, &mut self.chat_list_state);
+        if let Some(filter_line) = filter_line {
+            frame.set_cursor_position(Position::new(
+                area.x + 1 + filter_line.width() as u16,
+                area.bottom().saturating_sub(1),
+            ));
+        }
+        self.draw_clear_history_confirmation(frame, area);
+        self.draw_leave_confirmation(frame, area);
         Ok(())
     }
 }
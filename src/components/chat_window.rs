@@ -1,23 +1,68 @@
 use crate::{
     action::Action,
     app_context::AppContext,
-    components::component_traits::{Component, HandleFocus},
+    component_name::ComponentName,
+    components::{
+        component_traits::{Component, HandleFocus},
+        confirmation::Confirmation,
+        list_popup::ListPopup,
+    },
     event::Event,
-    tg::message_entry::MessageEntry,
+    tg::{
+        message_entry::{MessageContentType, MessageEntry},
+        tg_context::TgContext,
+    },
+    utils::find_ignore_case,
 };
 use arboard::Clipboard;
+use crossterm::event::KeyCode;
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Position, Rect},
+    style::Style,
     symbols::{
         border::{self, Set},
         line,
     },
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListDirection, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListDirection, ListItem, ListState, Paragraph},
 };
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
+use tdlib_rs::enums::{ChatType, InlineKeyboardButtonType};
+use tdlib_rs::types::InlineKeyboardButton;
 use tokio::sync::mpsc::UnboundedSender;
 
+/// The items listed by the chooser of the `ChatWindow`.
+enum ChooserKind {
+    /// The URLs of the selected message, opened in the browser.
+    Url,
+    /// The bot commands of the selected message, sent to the bot.
+    BotCommand,
+    /// The buttons of the inline keyboard of the message with the given id,
+    /// pressed.
+    InlineButton(i64, Vec<InlineKeyboardButton>),
+    /// The presets of the auto-delete timer of the open chat, see
+    /// `AUTO_DELETE_PRESETS`.
+    AutoDelete,
+}
+
+/// A popup of the `ChatWindow` listing some items, one of them is chosen by
+/// moving the selection and pressing enter, or by pressing its number.
+struct Chooser {
+    /// The kind of the items, it tells what is done with the chosen one.
+    kind: ChooserKind,
+    /// The text of the items.
+    items: Vec<String>,
+    /// The list of the items.
+    popup: ListPopup,
+}
+
+/// The number of columns the messages are scrolled horizontally at a time,
+/// when they are not wrapped.
+const HORIZONTAL_SCROLL_STEP: usize = 4;
+
+/// The presets of the auto-delete timer of a chat, in seconds.
+const AUTO_DELETE_PRESETS: [i32; 4] = [0, 86400, 7 * 86400, 31 * 86400];
+
 /// `ChatWindow` is a struct that represents a window for displaying a chat.
 /// It is responsible for managing the layout and rendering of the chat window.
 pub struct ChatWindow {
@@ -33,6 +78,69 @@ pub struct ChatWindow {
     message_list_state: ListState,
     /// Indicates whether the `ChatWindow` is focused or not.
     focused: bool,
+    /// The ids of the messages of the open chat whose spoilers are revealed.
+    revealed_spoilers: HashSet<i64>,
+    /// The chooser of the URLs, the bot commands or the inline buttons of
+    /// the selected message, or of the auto-delete timer of the open chat,
+    /// `None` if it is not shown.
+    chooser: Option<Chooser>,
+    /// The id of the chat and the id of the message whose viewers are shown in
+    /// a popup, `None` if the popup is not shown.
+    viewers_popup: Option<(i64, i64)>,
+    /// The confirmation of blocking or unblocking the user of the open private
+    /// chat: the id of the user, whether they are blocked and whether to
+    /// report the chat as spam. `None` if it is not shown.
+    block_confirmation: Option<(i64, bool, bool)>,
+    /// Indicates whether the messages are wrapped, otherwise they can be
+    /// scrolled horizontally.
+    wrap_messages: bool,
+    /// The number of columns the messages are scrolled horizontally, when
+    /// they are not wrapped.
+    horizontal_offset: usize,
+    /// The width of the widest line of the messages when they were last drawn
+    /// without wrapping, it limits the horizontal scrolling.
+    widest_line: usize,
+    /// The id of the open chat when the `ChatWindow` was last drawn.
+    drawn_chat_id: i64,
+    /// The id of the last read message of the open chat when it was opened,
+    /// `None` if there were no unread messages.
+    /// It is taken before the messages are marked as read.
+    last_read_message_id: Option<i64>,
+    /// The id of the chat whose messages have been scrolled to the first
+    /// unread message, it is used to scroll only once when a chat is opened.
+    /// See `unread_offset`.
+    scrolled_to_unread_chat_id: i64,
+    /// The number of loaded messages when the last read message was last
+    /// searched, the older messages are loaded until it is found.
+    /// See `load_unread`.
+    unread_loaded: usize,
+    /// The ids of the messages of the open chat marked as read because they
+    /// have been shown, when `mark_read_on_scroll` is enabled.
+    viewed_message_ids: HashSet<i64>,
+    /// The heights of the message items when the list was last drawn, used
+    /// to find the messages shown in the list.
+    item_heights: Vec<usize>,
+    /// The height of the area of the list when it was last drawn.
+    list_height: usize,
+    /// The id of the message where the selection of a range of messages
+    /// started, `None` if no range is being selected.
+    /// The range goes from this message to the selected one.
+    selection_anchor: Option<i64>,
+    /// The index of the pinned message shown in the banner of the open chat.
+    pinned_index: usize,
+    /// The ids of the chats whose pinned message banner has been dismissed.
+    pinned_dismissed: HashSet<i64>,
+    /// The id of the pinned message to select, and the number of loaded
+    /// messages when it was last searched, `None` if there is no jump to a
+    /// pinned message in progress.
+    /// The older messages are loaded until the pinned message is found.
+    pending_pinned_jump: Option<(i64, usize)>,
+    /// The search of the loaded messages, `None` if the messages are not
+    /// searched.
+    /// The messages containing it are highlighted, they are not changed.
+    search: Option<String>,
+    /// Indicates whether the search is being typed.
+    search_typing: bool,
 }
 /// Implementation of the `ChatWindow` struct.
 impl ChatWindow {
@@ -49,6 +157,26 @@ impl ChatWindow {
         let message_list = vec![];
         let message_list_state = ListState::default();
         let focused = false;
+        let revealed_spoilers = HashSet::new();
+        let chooser = None;
+        let viewers_popup = None;
+        let block_confirmation = None;
+        let wrap_messages = true;
+        let horizontal_offset = 0;
+        let widest_line = 0;
+        let drawn_chat_id = 0;
+        let last_read_message_id = None;
+        let scrolled_to_unread_chat_id = 0;
+        let unread_loaded = 0;
+        let viewed_message_ids = HashSet::new();
+        let item_heights = vec![];
+        let list_height = 0;
+        let selection_anchor = None;
+        let pinned_index = 0;
+        let pinned_dismissed = HashSet::new();
+        let pending_pinned_jump = None;
+        let search = None;
+        let search_typing = false;
         ChatWindow {
             app_context,
             name,
@@ -56,6 +184,26 @@ impl ChatWindow {
             message_list,
             message_list_state,
             focused,
+            revealed_spoilers,
+            chooser,
+            viewers_popup,
+            block_confirmation,
+            wrap_messages,
+            horizontal_offset,
+            widest_line,
+            drawn_chat_id,
+            last_read_message_id,
+            scrolled_to_unread_chat_id,
+            unread_loaded,
+            viewed_message_ids,
+            item_heights,
+            list_height,
+            selection_anchor,
+            pinned_index,
+            pinned_dismissed,
+            pending_pinned_jump,
+            search,
+            search_typing,
         }
     }
     /// Set the name of the `ChatWindow`.
@@ -74,12 +222,6 @@ impl ChatWindow {
     fn next(&mut self) {
         let i = match self.message_list_state.selected() {
             Some(i) => {
-                if i == self.message_list.len() / 2 {
-                    if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
-                        event_tx.send(Event::GetChatHistory).unwrap();
-                    }
-                }
-
                 if i == 0 {
                     0
                 } else {
@@ -95,13 +237,7 @@ impl ChatWindow {
     fn previous(&mut self) {
         let i = match self.message_list_state.selected() {
             Some(i) => {
-                if i == self.message_list.len() / 2 {
-                    if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
-                        event_tx.send(Event::GetChatHistory).unwrap();
-                    }
-                }
-
-                if i >= self.message_list.len() - 1 {
+                if i >= self.message_list.len().saturating_sub(1) {
                     i
                 } else {
                     i + 1
@@ -110,40 +246,625 @@ impl ChatWindow {
             None => 0,
         };
         self.message_list_state.select(Some(i));
+
+        // The list is drawn from bottom to top, so the last item is the oldest
+        // loaded message.
+        if i + 1 >= self.message_list.len() {
+            self.load_older_messages();
+        }
+    }
+
+    /// Select the oldest loaded message, and request the older ones.
+    fn go_to_top(&mut self) {
+        if self.message_list.is_empty() {
+            return;
+        }
+        self.message_list_state
+            .select(Some(self.message_list.len() - 1));
+        self.load_older_messages();
+    }
+
+    /// Select the newest message, scrolling the list to the bottom.
+    fn go_to_bottom(&mut self) {
+        self.message_list_state.select(Some(0));
+        *self.message_list_state.offset_mut() = 0;
+    }
+
+    /// Request the messages older than the oldest loaded message.
+    /// The older messages are appended at the end of the list, so the index of
+    /// the selected message does not change after they are loaded.
+    /// No request is sent while another one is still pending.
+    fn load_older_messages(&self) {
+        let tg_context = self.app_context.tg_context();
+        if tg_context.is_loading_chat_history() {
+            return;
+        }
+        if let Some(event_tx) = tg_context.event_tx().as_ref() {
+            tg_context.set_is_loading_chat_history(true);
+            event_tx
+                .send(Event::GetChatHistory(tg_context.open_chat_id()))
+                .unwrap();
+        };
+    }
+
+    /// Jump to the pinned message shown in the banner, and show the next
+    /// pinned message in the banner.
+    fn jump_to_pinned(&mut self) {
+        let tg_context = self.app_context.tg_context();
+        let Some(pinned) = tg_context
+            .pinned_messages()
+            .get(&tg_context.open_chat_id())
+            .filter(|pinned| !pinned.is_empty())
+            .map(|pinned| pinned.iter().map(|m| m.id()).collect::<Vec<i64>>())
+        else {
+            return;
+        };
+        let index = self.pinned_index % pinned.len();
+        self.pinned_index = (index + 1) % pinned.len();
+        self.pending_pinned_jump = Some((pinned[index], 0));
+        self.select_pending_pinned();
+    }
+
+    /// Select the pinned message of the jump in progress, if any.
+    /// If it is not loaded yet, the older messages are requested, and the
+    /// jump is abandoned when no more messages can be loaded.
+    fn select_pending_pinned(&mut self) {
+        let Some((message_id, loaded)) = self.pending_pinned_jump else {
+            return;
+        };
+        if let Some(i) = self.message_list.iter().position(|m| m.id() == message_id) {
+            self.message_list_state.select(Some(i));
+            self.pending_pinned_jump = None;
+        } else if self.app_context.tg_context().is_loading_chat_history() {
+            // Wait for the pending request
+        } else if loaded != self.message_list.len() {
+            self.pending_pinned_jump = Some((message_id, self.message_list.len()));
+            self.load_older_messages();
+        } else {
+            self.pending_pinned_jump = None;
+        }
+    }
+
+    /// Hide the pinned message banner of the open chat for the session.
+    fn dismiss_pinned(&mut self) {
+        self.pinned_dismissed
+            .insert(self.app_context.tg_context().open_chat_id());
+    }
+
+    /// Get the banner of the pinned message of the open chat.
+    ///
+    /// # Returns
+    /// * `Option<Line>` - The banner, `None` if the chat has no pinned
+    ///   message or its banner has been dismissed.
+    fn pinned_banner(&self) -> Option<Line<'static>> {
+        let tg_context = self.app_context.tg_context();
+        let chat_id = tg_context.open_chat_id();
+        if self.pinned_dismissed.contains(&chat_id) {
+            return None;
+        }
+        let pinned_messages = tg_context.pinned_messages();
+        let pinned = pinned_messages.get(&chat_id).filter(|p| !p.is_empty())?;
+        let index = self.pinned_index % pinned.len();
+        let preview = pinned[index].message_content_to_string_masked();
+        let preview = preview.lines().next().unwrap_or_default();
+        let title = if pinned.len() > 1 {
+            format!("📌 Pinned ({}/{}): ", index + 1, pinned.len())
+        } else {
+            "📌 Pinned: ".to_string()
+        };
+        Some(Line::from(vec![
+            Span::raw(title),
+            Span::raw(preview.to_string()),
+        ]))
+    }
+
+    /// Ask to confirm blocking or unblocking the user of the open chat, if it
+    /// is a private chat.
+    ///
+    /// # Arguments
+    /// * `report_spam` - Whether to report the chat as spam when blocking the
+    ///   user.
+    fn confirm_block(&mut self, report_spam: bool) {
+        let tg_context = self.app_context.tg_context();
+        let chat_id = tg_context.open_chat_id();
+        let Some(ChatType::Private(private)) = tg_context
+            .chats()
+            .get(&chat_id)
+            .map(|chat| chat.r#type.clone())
+        else {
+            return;
+        };
+        let blocked = tg_context.is_chat_blocked(chat_id);
+        self.block_confirmation = Some((private.user_id, blocked, report_spam && !blocked));
+    }
+
+    /// Handle a key while the confirmation of blocking or unblocking a user
+    /// is shown.
+    ///
+    /// # Arguments
+    /// * `key` - The pressed key.
+    fn handle_block_confirmation(&mut self, key: KeyCode) {
+        let Some((user_id, _, report_spam)) = self.block_confirmation else {
+            return;
+        };
+        match Confirmation::answer(key) {
+            Some(true) => {
+                if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+                    event_tx
+                        .send(Event::ToggleBlockUser(user_id, report_spam))
+                        .unwrap();
+                }
+                self.block_confirmation = None;
+            }
+            Some(false) => self.block_confirmation = None,
+            None => {}
+        }
+    }
+
+    /// Draw the confirmation of blocking or unblocking a user in the middle
+    /// of the given area.
+    ///
+    /// # Arguments
+    /// * `frame` - A mutable reference to the frame to be rendered.
+    /// * `area` - The area of the chat window.
+    fn draw_block_confirmation(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let Some((user_id, blocked, report_spam)) = self.block_confirmation else {
+            return;
+        };
+        let name = self
+            .app_context
+            .tg_context()
+            .try_name_from_chats_or_users(user_id)
+            .unwrap_or_else(|| user_id.to_string());
+        let (title, question) = match (blocked, report_spam) {
+            (true, _) => ("Unblock", format!("Unblock {}?", name)),
+            (false, false) => ("Block", format!("Block {}?", name)),
+            (false, true) => ("Block", format!("Block {} and report spam?", name)),
+        };
+        Confirmation::new(title, &title.to_lowercase())
+            .line(question)
+            .style(self.app_context.style_chat())
+            .draw(&self.app_context, frame, area);
+    }
+
+    /// Toggle the wrapping of the messages.
+    /// The horizontal scrolling is reset.
+    fn toggle_wrap(&mut self) {
+        self.wrap_messages = !self.wrap_messages;
+        self.horizontal_offset = 0;
+    }
+
+    /// Scroll the messages horizontally, when they are not wrapped.
+    ///
+    /// # Arguments
+    /// * `right` - Whether to scroll to the right or to the left.
+    fn scroll_horizontally(&mut self, right: bool) {
+        if self.wrap_messages {
+            return;
+        }
+        self.horizontal_offset = if right {
+            (self.horizontal_offset + HORIZONTAL_SCROLL_STEP)
+                .min(self.widest_line.saturating_sub(1))
+        } else {
+            self.horizontal_offset
+                .saturating_sub(HORIZONTAL_SCROLL_STEP)
+        };
+    }
+
+    /// Remove the first characters of a line, to scroll it horizontally.
+    ///
+    /// # Arguments
+    /// * `line` - The line to scroll.
+    /// * `offset` - The number of characters to remove.
+    ///
+    /// # Returns
+    /// * `Line` - The scrolled line, with the same style and alignment.
+    fn scroll_line<'a>(line: &Line<'a>, offset: usize) -> Line<'a> {
+        let mut skip = offset;
+        let spans: Vec<Span> = line
+            .spans
+            .iter()
+            .filter_map(|span| {
+                let len = span.content.chars().count();
+                if skip >= len {
+                    skip -= len;
+                    return None;
+                }
+                let content: String = span.content.chars().skip(skip).collect();
+                skip = 0;
+                Some(Span::styled(content, span.style))
+            })
+            .collect();
+        let mut scrolled = Line::from(spans).style(line.style);
+        scrolled.alignment = line.alignment;
+        scrolled
+    }
+
+    /// Start typing a search of the loaded messages.
+    fn start_search(&mut self) {
+        self.search = Some(String::new());
+        self.search_typing = true;
+    }
+
+    /// Stop typing the search, the messages stay highlighted.
+    fn stop_search_typing(&mut self) {
+        if !self.search_typing {
+            return;
+        }
+        self.search_typing = false;
+        if let Some(tx) = self.action_tx.as_ref() {
+            tx.send(Action::ChatWindowSearchDone).unwrap();
+        }
+    }
+
+    /// Stop searching the messages, removing the highlights.
+    fn clear_search(&mut self) {
+        self.stop_search_typing();
+        self.search = None;
+    }
+
+    /// Handle a key while the search is typed.
+    /// `Up` and `Down` select an older or a newer matching message, `Enter`
+    /// stops typing and `Esc` clears the search.
+    ///
+    /// # Arguments
+    /// * `key` - The pressed key.
+    fn handle_search_key(&mut self, key: KeyCode) {
+        let Some(search) = self.search.as_mut() else {
+            return;
+        };
+        match key {
+            KeyCode::Char(c) => {
+                search.push(c);
+                self.select_newest_search_match();
+            }
+            KeyCode::Backspace => {
+                search.pop();
+                self.select_newest_search_match();
+            }
+            KeyCode::Up => self.select_search_match(true),
+            KeyCode::Down => self.select_search_match(false),
+            KeyCode::Enter => self.stop_search_typing(),
+            KeyCode::Esc => self.clear_search(),
+            _ => {}
+        }
+    }
+
+    /// Get the indexes of the loaded messages that contain the search,
+    /// ignoring the case.
+    ///
+    /// # Returns
+    /// * `Vec<usize>` - The indexes of the messages, from the newest one.
+    fn search_matches(&self) -> Vec<usize> {
+        let Some(search) = self.search.as_ref().filter(|search| !search.is_empty()) else {
+            return vec![];
+        };
+        self.message_list
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| {
+                // The text hidden by the spoilers is not searched until it
+                // is revealed.
+                let content = if self.revealed_spoilers.contains(&message.id()) {
+                    message.message_content_to_string()
+                } else {
+                    message.message_content_to_string_masked()
+                };
+                !find_ignore_case(search, &content).is_empty()
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Select the newest message that contains the search.
+    fn select_newest_search_match(&mut self) {
+        if let Some(i) = self.search_matches().first() {
+            self.message_list_state.select(Some(*i));
+        }
+    }
+
+    /// Select the next older or newer message that contains the search,
+    /// going around at the oldest and at the newest loaded message.
+    ///
+    /// # Arguments
+    /// * `older` - Whether to select an older message or a newer one.
+    fn select_search_match(&mut self, older: bool) {
+        let matches = self.search_matches();
+        let selected = self.message_list_state.selected();
+        let next = if older {
+            matches
+                .iter()
+                .find(|i| selected.is_none_or(|selected| **i > selected))
+                .or(matches.first())
+        } else {
+            matches
+                .iter()
+                .rev()
+                .find(|i| selected.is_none_or(|selected| **i < selected))
+                .or(matches.last())
+        };
+        if let Some(i) = next {
+            self.message_list_state.select(Some(*i));
+        }
+    }
+
+    /// Highlight the occurrences of the search in a line.
+    ///
+    /// # Arguments
+    /// * `line` - The line to highlight.
+    /// * `search` - The search.
+    /// * `style` - The style patched on the occurrences.
+    ///
+    /// # Returns
+    /// * `Line` - The highlighted line, with the same style and alignment.
+    fn highlight_line<'a>(line: &Line<'a>, search: &str, style: Style) -> Line<'a> {
+        let content: String = line.spans.iter().map(|s| s.content.as_ref()).collect();
+        let occurrences = find_ignore_case(search, &content);
+        if occurrences.is_empty() {
+            return line.clone();
+        }
+        let mut spans: Vec<Span> = vec![];
+        let mut index = 0;
+        for span in line.spans.iter() {
+            let mut part = String::new();
+            let mut part_matched = false;
+            for c in span.content.chars() {
+                let matched = occurrences.iter().any(|o| o.contains(&index));
+                if matched != part_matched && !part.is_empty() {
+                    let part_style = if part_matched {
+                        span.style.patch(style)
+                    } else {
+                        span.style
+                    };
+                    spans.push(Span::styled(std::mem::take(&mut part), part_style));
+                }
+                part_matched = matched;
+                part.push(c);
+                index += 1;
+            }
+            if !part.is_empty() {
+                let part_style = if part_matched {
+                    span.style.patch(style)
+                } else {
+                    span.style
+                };
+                spans.push(Span::styled(part, part_style));
+            }
+        }
+        let mut highlighted = Line::from(spans).style(line.style);
+        highlighted.alignment = line.alignment;
+        highlighted
+    }
+
+    /// Draw the search of the loaded messages, with the position of the
+    /// selected message among the matching ones.
+    ///
+    /// # Arguments
+    /// * `frame` - The frame to draw on.
+    /// * `area` - The area of the search.
+    /// * `matches` - The indexes of the messages that contain the search.
+    /// * `border_style` - The style of the borders.
+    fn draw_search(
+        &self,
+        frame: &mut ratatui::Frame<'_>,
+        area: Rect,
+        matches: &[usize],
+        border_style: Style,
+    ) {
+        let Some(search) = self.search.as_ref() else {
+            return;
+        };
+        let search_text = format!("/{}", search);
+        let position = self
+            .message_list_state
+            .selected()
+            .and_then(|selected| matches.iter().position(|i| *i == selected))
+            .map_or("-".to_string(), |position| (position + 1).to_string());
+        let count = match (search.is_empty(), matches.len()) {
+            (true, _) => String::new(),
+            (false, 0) => "  no matches".to_string(),
+            (false, len) => format!("  {}/{}", position, len),
+        };
+        if self.search_typing {
+            frame.set_cursor_position(Position::new(
+                area.x + 1 + Line::from(search_text.as_str()).width() as u16,
+                area.y,
+            ));
+        }
+        let block = Block::new()
+            .border_style(border_style)
+            .borders(Borders::LEFT | Borders::RIGHT)
+            .style(self.app_context.style_chat());
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::raw(search_text),
+                Span::styled(count, self.app_context.style_timestamp()),
+            ]))
+            .block(block),
+            area,
+        );
     }
 
     /// Unselect the message item in the list.
     fn unselect(&mut self) {
         self.message_list_state.select(None);
+        self.selection_anchor = None;
     }
 
-    /// Delete the selected message item in the list.
+    /// Start or stop the selection of a range of messages.
+    /// The range starts from the selected message, or from the newest one if
+    /// no message is selected.
+    fn toggle_selection(&mut self) {
+        if self.selection_anchor.take().is_some() || self.message_list.is_empty() {
+            return;
+        }
+        let selected = self.message_list_state.selected().unwrap_or(0);
+        self.message_list_state.select(Some(selected));
+        self.selection_anchor = self.message_list.get(selected).map(|m| m.id());
+    }
+
+    /// Get the indexes of the selected messages in the list.
+    /// They are the range of messages being selected, or the selected
+    /// message.
+    ///
+    /// # Returns
+    /// * `Vec<usize>` - The indexes of the selected messages.
+    fn selected_indexes(&self) -> Vec<usize> {
+        let Some(selected) = self.message_list_state.selected() else {
+            return vec![];
+        };
+        let anchor = self
+            .selection_anchor
+            .and_then(|id| self.message_list.iter().position(|m| m.id() == id))
+            .unwrap_or(selected);
+        (anchor.min(selected)..=anchor.max(selected))
+            .filter(|i| *i < self.message_list.len())
+            .collect()
+    }
+
+    /// Delete the selected message items in the list.
+    /// Only the messages sent by the current user are deleted.
     ///
     /// # Arguments
     /// * `revoke` - A boolean flag indicating whether the message should be revoked or not.
     fn delete_selected(&mut self, revoke: bool) {
-        if let Some(selected) = self.message_list_state.selected() {
-            if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
-                let sender_id = self.message_list[selected].sender_id();
-                if sender_id != self.app_context.tg_context().me() {
-                    return;
-                }
-                let message_id = self.message_list[selected].id();
-                event_tx
-                    .send(Event::DeleteMessages(vec![message_id], revoke))
-                    .unwrap();
+        let me = self.app_context.tg_context().me();
+        let message_ids: Vec<i64> = self
+            .selected_indexes()
+            .into_iter()
+            .filter(|i| self.message_list[*i].sender_id() == me)
+            .map(|i| self.message_list[i].id())
+            .collect();
+        if message_ids.is_empty() {
+            return;
+        }
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx
+                .send(Event::DeleteMessages(message_ids.clone(), revoke))
+                .unwrap();
+            for message_id in message_ids {
                 self.app_context.tg_context().delete_message(message_id);
             }
         }
+        self.selection_anchor = None;
+    }
+
+    /// Forward the selected message items in the list.
+    /// The chat list is focused to choose the chat to forward them to, they
+    /// are forwarded when the user confirms them in the prompt of that chat.
+    fn forward_selected(&mut self) {
+        let message_ids: Vec<i64> = self
+            .selected_indexes()
+            .into_iter()
+            .map(|i| self.message_list[i].id())
+            .collect();
+        if message_ids.is_empty() {
+            return;
+        }
+        let tg_context = self.app_context.tg_context();
+        tg_context.set_forward_messages(tg_context.open_chat_id(), message_ids);
+        self.selection_anchor = None;
+        if let Some(action_tx) = self.action_tx.as_ref() {
+            action_tx
+                .send(Action::FocusComponent(ComponentName::ChatList))
+                .unwrap();
+        }
     }
 
     /// Copy the selected message item in the list.
     fn copy_selected(&self) {
         if let Some(selected) = self.message_list_state.selected() {
-            let message = self.message_list[selected].message_content_to_string();
-            if let Ok(mut clipboard) = Clipboard::new() {
-                clipboard.set_text(message).unwrap();
+            let message_id = self.message_list[selected].id();
+            if let Some(action_tx) = self.action_tx.as_ref() {
+                action_tx.send(Action::CopyMessage(message_id)).unwrap();
+            }
+        }
+    }
+
+    /// Copy the link of the selected message item in the list.
+    fn copy_link_selected(&self) {
+        if let Some(selected) = self.message_list_state.selected() {
+            let tg_context = self.app_context.tg_context();
+            let message_id = self.message_list[selected].id();
+            if let Some(event_tx) = tg_context.event_tx().as_ref() {
+                event_tx
+                    .send(Event::GetMessageLink(tg_context.open_chat_id(), message_id))
+                    .unwrap();
+            };
+        }
+    }
+
+    /// Play the voice note of the selected message item in the list.
+    fn play_voice_note_selected(&self) {
+        if let Some(selected) = self.message_list_state.selected() {
+            let message_id = self.message_list[selected].id();
+            if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+                event_tx.send(Event::PlayVoiceNote(message_id)).unwrap();
+            };
+        }
+    }
+
+    /// Open the media of the selected message item in the list.
+    fn open_media_selected(&self) {
+        let Some(selected) = self.message_list_state.selected() else {
+            return;
+        };
+        let message = &self.message_list[selected];
+        if !message.content_type().has_media() {
+            self.app_context
+                .tg_context()
+                .set_error_message("The selected message has no media to open");
+            return;
+        }
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx.send(Event::OpenMedia(message.id())).unwrap();
+        };
+    }
+
+    /// Add the contact shared by the selected message to the contacts of the
+    /// user.
+    fn save_contact_selected(&self) {
+        let Some(selected) = self.message_list_state.selected() else {
+            return;
+        };
+        let message = &self.message_list[selected];
+        if message.content_type() != MessageContentType::Contact {
+            self.app_context
+                .tg_context()
+                .set_error_message("The selected message is not a contact");
+            return;
+        }
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx.send(Event::SaveContact(message.id())).unwrap();
+        };
+    }
+
+    /// Copy the text of a message to the clipboard.
+    /// The name of the sender is included if `copy_message_with_sender` is
+    /// enabled in the application configuration.
+    ///
+    /// # Arguments
+    /// * `message_id` - The id of the message to copy.
+    fn copy_message(&self, message_id: i64) {
+        let Some(message) = self.message_list.iter().find(|m| m.id() == message_id) else {
+            return;
+        };
+        let mut text = message.message_content_to_string_masked();
+        if self.app_context.app_config().copy_message_with_sender {
+            let sender = self
+                .app_context
+                .tg_context()
+                .try_name_from_chats_or_users(message.sender_id())
+                .unwrap_or_default();
+            text = format!("{}: {}", sender, text);
+        }
+        match Clipboard::new() {
+            Ok(mut clipboard) => {
+                if let Err(e) = clipboard.set_text(text) {
+                    tracing::error!("Failed to copy the message {}: {}", message_id, e);
+                }
             }
+            Err(e) => tracing::error!("Failed to access the clipboard: {}", e),
         }
     }
 
@@ -164,15 +885,563 @@ impl ChatWindow {
         }
     }
 
+    /// Write the command that sets the alias of the sender of the selected
+    /// message in the prompt.
+    fn set_alias_selected(&self) {
+        let Some(message) = self
+            .message_list_state
+            .selected()
+            .and_then(|i| self.message_list.get(i))
+        else {
+            return;
+        };
+        let tg_context = self.app_context.tg_context();
+        // The prompt commands are sent as messages in the chats with a bot.
+        if tg_context.is_bot_chat(tg_context.open_chat_id()) {
+            tg_context.set_error_message("The aliases can not be set from a chat with a bot");
+            return;
+        }
+        if let Some(event_tx) = tg_context.event_tx().as_ref() {
+            event_tx
+                .send(Event::EditAlias(message.sender_id()))
+                .unwrap();
+        };
+    }
+
+    /// Show the users that have read the selected message item in the list.
+    /// Only the viewers of the messages sent by the user are shown.
+    fn show_viewers_selected(&mut self) {
+        let Some(selected) = self.message_list_state.selected() else {
+            return;
+        };
+        let tg_context = self.app_context.tg_context();
+        if self.message_list[selected].sender_id() != tg_context.me() {
+            return;
+        }
+        let chat_id = tg_context.open_chat_id();
+        let message_id = self.message_list[selected].id();
+        self.viewers_popup = Some((chat_id, message_id));
+        if let Some(event_tx) = tg_context.event_tx().as_ref() {
+            event_tx
+                .send(Event::GetMessageViewers(chat_id, message_id))
+                .unwrap();
+        };
+    }
+
+    /// Draw the popup with the viewers of a message in the middle of the
+    /// given area.
+    ///
+    /// # Arguments
+    /// * `frame` - A mutable reference to the frame to be rendered.
+    /// * `area` - The area of the chat window.
+    fn draw_viewers_popup(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let Some((chat_id, message_id)) = self.viewers_popup else {
+            return;
+        };
+        let tg_context = self.app_context.tg_context();
+        let (title, lines) = match tg_context.message_viewers(chat_id, message_id) {
+            None => ("Viewers".to_string(), vec![Line::from("Loading...")]),
+            Some(None) => ("Viewers".to_string(), vec![Line::from("Not available")]),
+            Some(Some(viewers)) if viewers.is_empty() => {
+                ("Viewers".to_string(), vec![Line::from("Not read yet")])
+            }
+            Some(Some(viewers)) => (
+                format!("Viewers ({})", viewers.len()),
+                viewers
+                    .iter()
+                    .map(|user_id| {
+                        Line::from(
+                            tg_context
+                                .try_name_from_chats_or_users(*user_id)
+                                .unwrap_or_else(|| user_id.to_string()),
+                        )
+                    })
+                    .collect(),
+            ),
+        };
+        let [popup_area] = Layout::horizontal([Constraint::Length(32)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] =
+            Layout::vertical([Constraint::Length((lines.len() as u16).saturating_add(2))])
+                .flex(Flex::Center)
+                .areas(popup_area);
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(self.app_context.style_border_component_focused())
+            .style(self.app_context.style_chat())
+            .title(title);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(Paragraph::new(lines).block(block), popup_area);
+    }
+
+    /// Pin the selected message item in the list, or unpin it if it is
+    /// pinned and `notify` is false.
+    ///
+    /// # Arguments
+    /// * `notify` - Whether to notify the members of the group when the
+    ///   message is pinned.
+    fn pin_selected(&self, notify: bool) {
+        let Some(selected) = self.message_list_state.selected() else {
+            return;
+        };
+        let tg_context = self.app_context.tg_context();
+        let chat_id = tg_context.open_chat_id();
+        let message_id = self.message_list[selected].id();
+        let is_pinned = tg_context
+            .pinned_messages()
+            .get(&chat_id)
+            .is_some_and(|pinned| pinned.iter().any(|m| m.id() == message_id));
+        if let Some(event_tx) = tg_context.event_tx().as_ref() {
+            let event = if is_pinned && !notify {
+                Event::UnpinMessage(chat_id, message_id)
+            } else {
+                Event::PinMessage(chat_id, message_id, notify)
+            };
+            event_tx.send(event).unwrap();
+        };
+    }
+
+    /// Reveal or hide the spoilers of the selected message item in the list.
+    fn toggle_spoiler_selected(&mut self) {
+        if let Some(selected) = self.message_list_state.selected() {
+            let message_id = self.message_list[selected].id();
+            if !self.revealed_spoilers.remove(&message_id) {
+                self.revealed_spoilers.insert(message_id);
+            }
+        }
+    }
+
+    /// Open the URL of the selected message item in the list.
+    /// If the message contains more than one URL, a chooser is shown.
+    fn open_url_selected(&mut self) {
+        if let Some(selected) = self.message_list_state.selected() {
+            let urls = self.message_list[selected].urls();
+            match urls.len() {
+                0 => {}
+                1 => Self::open_url(&urls[0]),
+                _ => self.show_chooser(ChooserKind::Url, urls, 0),
+            }
+        }
+    }
+
+    /// Send a bot command of the selected message to the bot of the open
+    /// chat. When the message contains more than one command, they are shown
+    /// in the chooser.
+    fn send_bot_command_selected(&mut self) {
+        let Some(selected) = self.message_list_state.selected() else {
+            return;
+        };
+        let tg_context = self.app_context.tg_context();
+        if !tg_context.is_bot_chat(tg_context.open_chat_id()) {
+            tg_context.set_error_message("The open chat is not a chat with a bot");
+            return;
+        }
+        let commands = self.message_list[selected].bot_commands();
+        match commands.len() {
+            0 => tg_context.set_error_message("The selected message has no bot commands"),
+            1 => self.send_bot_command(&commands[0]),
+            _ => self.show_chooser(ChooserKind::BotCommand, commands, 0),
+        }
+    }
+
+    /// Show the buttons of the inline keyboard of the selected message in
+    /// the chooser, or press the button if it is the only one.
+    fn inline_keyboard_selected(&mut self) {
+        let Some(selected) = self.message_list_state.selected() else {
+            return;
+        };
+        let message_id = self.message_list[selected].id();
+        let buttons = self.message_list[selected].inline_buttons();
+        match buttons.len() {
+            0 => self
+                .app_context
+                .tg_context()
+                .set_error_message("The selected message has no buttons"),
+            1 => self.press_inline_button(message_id, &buttons[0]),
+            _ => {
+                let items = buttons.iter().map(|b| b.text.clone()).collect();
+                self.show_chooser(ChooserKind::InlineButton(message_id, buttons), items, 0);
+            }
+        }
+    }
+
+    /// Press a button of the inline keyboard of a message.
+    /// The callback buttons are sent to the bot, the URLs are opened in the
+    /// browser, and the inline queries are written in the prompt, mentioning
+    /// the bot.
+    ///
+    /// # Arguments
+    /// * `message_id` - The id of the message.
+    /// * `button` - The button to press.
+    fn press_inline_button(&self, message_id: i64, button: &InlineKeyboardButton) {
+        let tg_context = self.app_context.tg_context();
+        match &button.r#type {
+            InlineKeyboardButtonType::Callback(callback) => {
+                if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+                    event_tx
+                        .send(Event::PressInlineButton(message_id, callback.data.clone()))
+                        .unwrap();
+                }
+            }
+            InlineKeyboardButtonType::Url(url) => Self::open_url(&url.url),
+            InlineKeyboardButtonType::LoginUrl(url) => Self::open_url(&url.url),
+            InlineKeyboardButtonType::WebApp(web_app) => Self::open_url(&web_app.url),
+            InlineKeyboardButtonType::SwitchInline(switch_inline) => {
+                let sender_id = self
+                    .message_list
+                    .iter()
+                    .find(|m| m.id() == message_id)
+                    .map(|m| m.sender_id())
+                    .unwrap_or_default();
+                let username = tg_context.users().get(&sender_id).and_then(|user| {
+                    user.usernames
+                        .as_ref()
+                        .and_then(|usernames| usernames.active_usernames.first().cloned())
+                });
+                let Some(username) = username else {
+                    tg_context.set_error_message("The username of the bot is not known");
+                    return;
+                };
+                if let Some(action_tx) = self.action_tx.as_ref() {
+                    action_tx
+                        .send(Action::FocusComponent(ComponentName::Prompt))
+                        .unwrap();
+                    action_tx
+                        .send(Action::Paste(format!(
+                            "@{} {}",
+                            username, switch_inline.query
+                        )))
+                        .unwrap();
+                }
+            }
+            InlineKeyboardButtonType::User(user) => {
+                if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+                    event_tx.send(Event::OpenPrivateChat(user.user_id)).unwrap();
+                }
+            }
+            InlineKeyboardButtonType::CallbackWithPassword(_)
+            | InlineKeyboardButtonType::CallbackGame
+            | InlineKeyboardButtonType::Buy => {
+                tg_context.set_error_message("This button is not supported");
+            }
+        }
+    }
+
+    /// Send a bot command to the open chat.
+    ///
+    /// # Arguments
+    /// * `command` - The command to send, e.g. `/start`.
+    fn send_bot_command(&self, command: &str) {
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx
+                .send(Event::SendMessage(command.to_string(), None))
+                .unwrap();
+        }
+    }
+
+    /// Open a URL with the default browser of the system.
+    ///
+    /// # Arguments
+    /// * `url` - The URL to open.
+    fn open_url(url: &str) {
+        if let Err(e) = open::that_detached(url) {
+            tracing::error!("Failed to open the URL {}: {}", url, e);
+        }
+    }
+
+    /// Show the chooser.
+    ///
+    /// # Arguments
+    /// * `kind` - The kind of the items.
+    /// * `items` - The text of the items.
+    /// * `selected` - The index of the item selected at first.
+    fn show_chooser(&mut self, kind: ChooserKind, items: Vec<String>, selected: usize) {
+        // The chooser is closed by the `ChatWindow`, its popup has no action
+        // handler.
+        let mut popup = ListPopup::new(Arc::clone(&self.app_context), Action::ChatWindowUnselect);
+        popup.select(Some(selected));
+        self.chooser = Some(Chooser { kind, items, popup });
+    }
+
+    /// Handle an action while the chooser is shown.
+    /// The item can be chosen by moving the selection and pressing enter, or
+    /// by pressing its number.
+    ///
+    /// # Arguments
+    /// * `action` - The action to handle.
+    fn update_chooser(&mut self, action: Action) {
+        let Some(chooser) = self.chooser.as_mut() else {
+            return;
+        };
+        let len = chooser.items.len();
+        let chosen = match action {
+            Action::ChatWindowNext => {
+                chooser.popup.move_selection(len, 1);
+                None
+            }
+            Action::ChatWindowPrevious => {
+                chooser.popup.move_selection(len, -1);
+                None
+            }
+            Action::ChatWindowUnselect => {
+                self.chooser = None;
+                None
+            }
+            Action::Key(KeyCode::Enter, _) => chooser.popup.selected(),
+            Action::Key(KeyCode::Char(c), _) => c
+                .to_digit(10)
+                .map(|d| d as usize)
+                .filter(|d| *d >= 1 && *d <= len)
+                .map(|d| d - 1),
+            _ => None,
+        };
+        let Some(i) = chosen else {
+            return;
+        };
+        let Some(chooser) = self.chooser.take() else {
+            return;
+        };
+        let Some(item) = chooser.items.get(i) else {
+            return;
+        };
+        match &chooser.kind {
+            ChooserKind::Url => Self::open_url(item),
+            ChooserKind::BotCommand => self.send_bot_command(item),
+            ChooserKind::InlineButton(message_id, buttons) => {
+                if let Some(button) = buttons.get(i) {
+                    self.press_inline_button(*message_id, button);
+                }
+            }
+            ChooserKind::AutoDelete => {
+                if let (Some(seconds), Some(event_tx)) = (
+                    AUTO_DELETE_PRESETS.get(i).copied(),
+                    self.app_context.tg_context().event_tx().as_ref(),
+                ) {
+                    let chat_id = self.app_context.tg_context().open_chat_id();
+                    event_tx
+                        .send(Event::SetAutoDeleteTime(chat_id, seconds))
+                        .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Draw the chooser in the middle of the given area.
+    ///
+    /// # Arguments
+    /// * `frame` - A mutable reference to the frame to be rendered.
+    /// * `area` - The area of the chat window.
+    fn draw_chooser(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let Some(chooser) = self.chooser.as_mut() else {
+            return;
+        };
+        let items: Vec<ListItem> = chooser
+            .items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| ListItem::new(format!("{}. {}", i + 1, item)))
+            .collect();
+        let title = match chooser.kind {
+            ChooserKind::Url => "Open URL",
+            ChooserKind::BotCommand => "Send command",
+            ChooserKind::InlineButton(..) => "Press button",
+            ChooserKind::AutoDelete => "Auto-delete",
+        };
+        let width = chooser
+            .items
+            .iter()
+            .map(|item| item.chars().count() + 7)
+            .chain([title.len() + 2])
+            .max()
+            .unwrap_or_default() as u16;
+        let inner_area = chooser.popup.draw_block(
+            frame,
+            area,
+            (
+                Constraint::Length(width),
+                Constraint::Length(items.len() as u16 + 2),
+            ),
+            title,
+        );
+        let list = List::new(items).highlight_style(self.app_context.style_item_selected());
+        frame.render_stateful_widget(list, inner_area, &mut chooser.popup.list_state);
+    }
+
+    /// Show the chooser of the auto-delete timer of the open chat, with the
+    /// current timer selected.
+    fn show_auto_delete_chooser(&mut self) {
+        if self.app_context.tg_context().open_chat_id() == 0 {
+            return;
+        }
+        let current = self.app_context.tg_context().open_chat_auto_delete_time();
+        let selected = AUTO_DELETE_PRESETS
+            .iter()
+            .position(|seconds| *seconds == current)
+            .unwrap_or_default();
+        let items = AUTO_DELETE_PRESETS
+            .iter()
+            .map(|seconds| TgContext::auto_delete_time_text(*seconds))
+            .collect();
+        self.show_chooser(ChooserKind::AutoDelete, items, selected);
+    }
+
+    /// Get the offset of the list that shows the first unread message of the
+    /// open chat.
+    /// The list is drawn from bottom to top, so the offset is the index of the
+    /// message drawn at the bottom. It is chosen so that the first unread
+    /// message is at the top of the list, followed by the newer messages.
+    /// If there are no unread messages the offset is 0, i.e. the last message.
+    ///
+    /// # Arguments
+    /// * `heights` - The heights of the message items of the list.
+    /// * `list_height` - The height of the area of the list.
+    ///
+    /// # Returns
+    /// * `Option<usize>` - The offset, or `None` if the first unread message
+    ///   is not loaded yet.
+    fn unread_offset(&self, heights: &[usize], list_height: usize) -> Option<usize> {
+        let Some(last_read_message_id) = self.last_read_message_id else {
+            return Some(0);
+        };
+        let last_read = self
+            .message_list
+            .iter()
+            .position(|message| message.id() == last_read_message_id)?;
+        let Some(first_unread) = last_read.checked_sub(1) else {
+            return Some(0);
+        };
+        let mut offset = first_unread;
+        let mut height = heights[first_unread];
+        while offset > 0 && height + heights[offset - 1] <= list_height {
+            offset -= 1;
+            height += heights[offset];
+        }
+        Some(offset)
+    }
+
+    /// Load the messages of the open chat until its last read message is
+    /// loaded, so that the messages can be scrolled to the first unread
+    /// message, see `unread_offset`.
+    /// As for the jump to a pinned message, the older messages are requested,
+    /// and the scroll is abandoned when no more messages can be loaded.
+    fn load_unread(&mut self) {
+        let open_chat_id = self.app_context.tg_context().open_chat_id();
+        let Some(last_read_message_id) = self.last_read_message_id else {
+            return;
+        };
+        if open_chat_id == self.scrolled_to_unread_chat_id
+            || self.message_list.is_empty()
+            || self
+                .message_list
+                .iter()
+                .any(|message| message.id() == last_read_message_id)
+        {
+            return;
+        }
+        if self.app_context.tg_context().is_loading_chat_history() {
+            // Wait for the pending request
+        } else if self.unread_loaded != self.message_list.len() {
+            self.unread_loaded = self.message_list.len();
+            self.load_older_messages();
+        } else {
+            self.scrolled_to_unread_chat_id = open_chat_id;
+        }
+    }
+
+    /// Get the range of the messages shown in the list, from the layout of
+    /// the last time it was drawn.
+    /// As the list does when it is drawn, the offset is moved to show the
+    /// selected message, so that the range is right after a scroll too.
+    ///
+    /// # Returns
+    /// * `Range<usize>` - The indexes of the shown messages.
+    fn visible_range(&self) -> std::ops::Range<usize> {
+        let heights = &self.item_heights[..self.item_heights.len().min(self.message_list.len())];
+        let mut offset = self.message_list_state.offset().min(heights.len());
+        if let Some(selected) = self
+            .message_list_state
+            .selected()
+            .filter(|i| *i < heights.len())
+        {
+            offset = offset.min(selected);
+            while offset < selected
+                && heights[offset..=selected].iter().sum::<usize>() > self.list_height
+            {
+                offset += 1;
+            }
+        }
+        // The list is drawn from bottom to top, starting from the offset.
+        let mut end = offset;
+        let mut height = 0;
+        while end < heights.len() && height < self.list_height {
+            height += heights[end];
+            end += 1;
+        }
+        offset..end
+    }
+
+    /// Mark as read the unread messages of the open chat shown in the list,
+    /// when `mark_read_on_scroll` is enabled.
+    /// It is called when the shown messages can change: when the messages
+    /// are scrolled or selected, and when the messages of the open chat are
+    /// loaded or received.
+    /// Each message is reported to the backend only once.
+    fn view_visible_messages(&mut self) {
+        if !self.app_context.app_config().mark_read_on_scroll {
+            return;
+        }
+        let tg_context = self.app_context.tg_context();
+        let last_read_inbox_message_id = tg_context.last_read_inbox_message_id();
+        let me = tg_context.me();
+        let mut message_ids = vec![];
+        for message in &self.message_list[self.visible_range()] {
+            if message.id() > last_read_inbox_message_id
+                && message.sender_id() != me
+                && self.viewed_message_ids.insert(message.id())
+            {
+                message_ids.push(message.id());
+            }
+        }
+        if message_ids.is_empty() {
+            return;
+        }
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx
+                .send(Event::ViewMessages(tg_context.open_chat_id(), message_ids))
+                .unwrap();
+        }
+    }
+
+    /// Take the last read message of the open chat, if it had unread
+    /// messages when it was opened, see `unread_offset`.
+    fn take_last_read_message_id(&mut self) {
+        self.last_read_message_id = if self.app_context.app_config().jump_to_unread {
+            self.app_context
+                .tg_context()
+                .open_chat_last_read_message_id()
+        } else {
+            None
+        };
+    }
+
     /// Reply to the selected message item in the list.
-    fn reply_selected(&self) {
+    ///
+    /// # Arguments
+    /// * `quote` - A boolean flag indicating whether the text of the message
+    ///   should be quoted in the reply or not.
+    fn reply_selected(&self, quote: bool) {
         if let Some(selected) = self.message_list_state.selected() {
             let message_id = self.message_list[selected].id();
             let text = self.message_list[selected].message_content_to_string();
             if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
-                event_tx
-                    .send(Event::ReplyMessage(message_id, text))
-                    .unwrap();
+                let event = if quote {
+                    Event::QuoteReplyMessage(message_id, text)
+                } else {
+                    Event::ReplyMessage(message_id, text)
+                };
+                event_tx.send(event).unwrap();
             }
         }
     }
@@ -184,10 +1453,16 @@ impl HandleFocus for ChatWindow {
     /// Set the `focused` flag for the `ChatWindow`.
     fn focus(&mut self) {
         self.focused = true;
+        self.view_visible_messages();
     }
     /// Set the `focused` flag for the `ChatWindow`.
     fn unfocus(&mut self) {
         self.focused = false;
+        self.chooser = None;
+        self.viewers_popup = None;
+        self.block_confirmation = None;
+        self.selection_anchor = None;
+        self.clear_search();
     }
 }
 
@@ -199,17 +1474,99 @@ impl Component for ChatWindow {
     }
 
     fn update(&mut self, action: Action) {
+        // The popups take the actions of the keys, the other actions, e.g.
+        // `DataChanged`, are handled also while a popup is shown.
+        let from_key = !matches!(action, Action::DataChanged | Action::CopyMessage(_));
+        if self.chooser.is_some() && from_key {
+            self.update_chooser(action);
+            return;
+        }
+        if self.block_confirmation.is_some() && from_key {
+            // The other actions are ignored, they are sent also for the keys
+            // of the confirmation.
+            if let Action::Key(key, _) = action {
+                self.handle_block_confirmation(key);
+            }
+            return;
+        }
+        if self.search_typing && from_key {
+            // The other actions are ignored, they are sent also for the keys
+            // of the search.
+            if let Action::Key(key, _) = action {
+                self.handle_search_key(key);
+            }
+            return;
+        }
+        if self.viewers_popup.is_some() {
+            match action {
+                Action::ChatWindowUnselect
+                | Action::ChatWindowMessageViewers
+                | Action::Key(KeyCode::Esc, _) => {
+                    self.viewers_popup = None;
+                    return;
+                }
+                Action::ChatWindowNext | Action::ChatWindowPrevious => self.viewers_popup = None,
+                _ => {}
+            }
+        }
+        let shows_other_messages = matches!(
+            action,
+            Action::ChatWindowNext
+                | Action::ChatWindowPrevious
+                | Action::ChatWindowGoToTop
+                | Action::ChatWindowGoToBottom
+                | Action::ChatWindowJumpToPinned
+                | Action::ChatWindowSearchNext
+                | Action::ChatWindowSearchPrevious
+                | Action::DataChanged
+        );
         match action {
             Action::ChatWindowNext => self.next(),
             Action::ChatWindowPrevious => self.previous(),
+            Action::ChatWindowGoToTop => self.go_to_top(),
+            Action::ChatWindowGoToBottom => self.go_to_bottom(),
             Action::ChatWindowUnselect => self.unselect(),
+            Action::ChatWindowToggleSelection => self.toggle_selection(),
+            Action::ChatWindowForward => self.forward_selected(),
+            Action::ChatWindowAutoDelete => self.show_auto_delete_chooser(),
+            Action::ChatWindowJumpToPinned => self.jump_to_pinned(),
+            Action::ChatWindowDismissPinned => self.dismiss_pinned(),
+            Action::ChatWindowTogglePin => self.pin_selected(false),
+            Action::ChatWindowPinNotify => self.pin_selected(true),
+            Action::ChatWindowMessageViewers => self.show_viewers_selected(),
+            Action::ChatWindowToggleWrap => self.toggle_wrap(),
+            Action::ChatWindowCopyLink => self.copy_link_selected(),
+            Action::ChatWindowPlayVoiceNote => self.play_voice_note_selected(),
+            Action::ChatWindowOpenMedia => self.open_media_selected(),
+            Action::ChatWindowSaveContact => self.save_contact_selected(),
+            Action::ChatWindowSendBotCommand => self.send_bot_command_selected(),
+            Action::ChatWindowInlineKeyboard => self.inline_keyboard_selected(),
+            Action::ChatWindowSetAlias => self.set_alias_selected(),
+            Action::ChatWindowToggleBlock => self.confirm_block(false),
+            Action::ChatWindowBlockReportSpam => self.confirm_block(true),
+            Action::ChatWindowScrollRight => self.scroll_horizontally(true),
+            Action::ChatWindowScrollLeft => self.scroll_horizontally(false),
+            Action::ChatWindowSearch => self.start_search(),
+            Action::ChatWindowSearchNext => self.select_search_match(true),
+            Action::ChatWindowSearchPrevious => self.select_search_match(false),
+            Action::Key(KeyCode::Esc, _) => {
+                self.selection_anchor = None;
+                self.clear_search();
+            }
             Action::ChatWindowDeleteForEveryone => self.delete_selected(true),
             Action::ChatWindowDeleteForMe => self.delete_selected(false),
             Action::ChatWindowCopy => self.copy_selected(),
+            Action::CopyMessage(message_id) => self.copy_message(message_id),
             Action::ChatWindowEdit => self.edit_selected(),
-            Action::ShowChatWindowReply => self.reply_selected(),
+            Action::ShowChatWindowReply => self.reply_selected(false),
+            Action::ShowChatWindowQuoteReply => self.reply_selected(true),
+            Action::ChatWindowToggleSpoiler => self.toggle_spoiler_selected(),
+            Action::ChatWindowOpenUrl => self.open_url_selected(),
             _ => {}
         }
+        if shows_other_messages {
+            self.view_visible_messages();
+        }
     }
 
     fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> std::io::Result<()> {
@@ -217,13 +1574,46 @@ impl Component for ChatWindow {
             self.message_list_state.select(None);
         }
 
+        let open_chat_id = self.app_context.tg_context().open_chat_id();
+        if open_chat_id != self.drawn_chat_id {
+            self.drawn_chat_id = open_chat_id;
+            self.take_last_read_message_id();
+            self.unread_loaded = 0;
+            self.pinned_index = 0;
+            self.pending_pinned_jump = None;
+            self.viewed_message_ids.clear();
+            self.revealed_spoilers.clear();
+            self.clear_search();
+        }
+
         self.message_list
-            .clone_from(&self.app_context.tg_context().open_chat_messages());
+            .clone_from(&self.app_context.tg_context().snapshot().open_chat_messages);
+        for message in self.message_list.iter_mut() {
+            message.set_spoilers_revealed(self.revealed_spoilers.contains(&message.id()));
+        }
+        self.select_pending_pinned();
+        if !self.focused {
+            self.load_unread();
+        }
 
+        let pinned_banner = self.pinned_banner();
         let chat_layout = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Min(2), Constraint::Percentage(100)])
+            .constraints([
+                Constraint::Min(2),
+                Constraint::Length(if pinned_banner.is_some() { 1 } else { 0 }),
+                Constraint::Percentage(100),
+            ])
             .split(area);
+        let banner_area = chat_layout[1];
+        let [list_area, search_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(if self.search.is_some() { 1 } else { 0 }),
+        ])
+        .areas(chat_layout[2]);
+        let chat_layout = [chat_layout[0], list_area];
+        let search_matches = self.search_matches();
+        let search_style = self.app_context.style_chat_message_search_match();
 
         let border = Set {
             top_left: line::NORMAL.vertical_right,
@@ -239,46 +1629,118 @@ impl Component for ChatWindow {
 
         let mut is_unread_outbox = true;
         let mut is_unread_inbox = true;
-        let wrap_width = (area.width / 2) as i32;
-        let items = self.message_list.iter().map(|message_entry| {
-            let (myself, name_style, content_style, alignment) = if message_entry.sender_id()
-                == self.app_context.tg_context().me()
-            {
-                if message_entry.id() == self.app_context.tg_context().last_read_outbox_message_id()
+        let selected_range: HashSet<usize> = if self.selection_anchor.is_some() {
+            self.selected_indexes().into_iter().collect()
+        } else {
+            HashSet::new()
+        };
+        // In compact mode the header is hidden for the messages that continue
+        // the group of the previous (older) message.
+        let app_config = self.app_context.app_config();
+        let (compact_messages, compact_window, max_wrap_width) = (
+            app_config.compact_messages,
+            app_config.compact_messages_window_seconds,
+            app_config.max_wrap_width,
+        );
+        drop(app_config);
+        // -1 means no wrap
+        let wrap_width = match (self.wrap_messages, area.width / 2) {
+            (false, _) => -1,
+            (true, width) if max_wrap_width > 0 => width.min(max_wrap_width) as i32,
+            (true, width) => width as i32,
+        };
+        let mut widest_line = 0;
+        let reply_message_id = self.app_context.tg_context().reply_message_id();
+        let items = self
+            .message_list
+            .iter()
+            .enumerate()
+            .map(|(i, message_entry)| {
+                let (myself, alignment) =
+                    if message_entry.sender_id() == self.app_context.tg_context().me() {
+                        if message_entry.id()
+                            == self.app_context.tg_context().last_read_outbox_message_id()
+                        {
+                            is_unread_outbox = false;
+                        }
+                        (true, Alignment::Right)
+                    } else {
+                        if message_entry.id()
+                            == self.app_context.tg_context().last_read_inbox_message_id()
+                        {
+                            is_unread_inbox = false;
+                        }
+                        (false, Alignment::Left)
+                    };
+                let alignment = if message_entry.is_service() {
+                    Alignment::Center
+                } else {
+                    alignment
+                };
+                let (text, content_lines) = message_entry.get_text_styled(
+                    myself,
+                    &self.app_context,
+                    is_unread_outbox,
+                    wrap_width,
+                    !compact_messages
+                        || !self.message_list.get(i + 1).is_some_and(|previous| {
+                            message_entry.is_grouped_with(previous, compact_window)
+                        }),
+                );
+                let mut text = text.alignment(alignment);
+                // Only the content is highlighted, the search does not look
+                // into the header.
+                if let Some(search) = self
+                    .search
+                    .as_ref()
+                    .filter(|_| search_matches.binary_search(&i).is_ok())
                 {
-                    is_unread_outbox = false;
+                    for line in text.lines[content_lines].iter_mut() {
+                        *line = Self::highlight_line(line, search, search_style);
+                    }
                 }
-                (
-                    true,
-                    self.app_context.style_chat_message_myself_name(),
-                    self.app_context.style_chat_message_myself_content(),
-                    Alignment::Right,
-                )
-            } else {
-                if message_entry.id() == self.app_context.tg_context().last_read_inbox_message_id()
-                {
-                    is_unread_inbox = false;
+                if !self.wrap_messages {
+                    widest_line = widest_line.max(text.width());
+                    if self.horizontal_offset > 0 {
+                        for line in text.lines.iter_mut() {
+                            *line = Self::scroll_line(line, self.horizontal_offset);
+                        }
+                    }
                 }
-                (
-                    false,
-                    self.app_context.style_chat_message_other_name(),
-                    self.app_context.style_chat_message_other_content(),
-                    Alignment::Left,
-                )
-            };
-            ListItem::new(
-                message_entry
-                    .get_text_styled(
-                        myself,
-                        &self.app_context,
-                        is_unread_outbox,
-                        name_style,
-                        content_style,
-                        wrap_width,
-                    )
-                    .alignment(alignment),
-            )
-        });
+                // The style of the spans is patched too, otherwise their
+                // background would hide the one of the selected messages.
+                // The message that is being replied is highlighted until the
+                // reply is sent or canceled.
+                let highlight_style = if selected_range.contains(&i) {
+                    Some(self.app_context.style_chat_message_selected())
+                } else if message_entry.id() == reply_message_id {
+                    Some(self.app_context.style_chat_message_reply_target())
+                } else {
+                    None
+                };
+                if let Some(style) = highlight_style {
+                    text = text.patch_style(style);
+                    for span in text.lines.iter_mut().flat_map(|l| l.spans.iter_mut()) {
+                        span.style = span.style.patch(style);
+                    }
+                }
+                ListItem::new(text)
+            });
+        let items: Vec<ListItem> = items.collect();
+        self.widest_line = widest_line;
+        let heights: Vec<usize> = items.iter().map(|item| item.height()).collect();
+        // Scroll to the first unread message once, when the chat is opened.
+        // It is not done anymore once the user scrolls the messages.
+        if self.focused {
+            self.scrolled_to_unread_chat_id = open_chat_id;
+        }
+        if open_chat_id != self.scrolled_to_unread_chat_id && !self.message_list.is_empty() {
+            let list_height = chat_layout[1].height.saturating_sub(1) as usize;
+            if let Some(offset) = self.unread_offset(&heights, list_height) {
+                *self.message_list_state.offset_mut() = offset;
+                self.scrolled_to_unread_chat_id = open_chat_id;
+            }
+        }
 
         let block = Block::new()
             .border_set(border)
@@ -313,7 +1775,7 @@ impl Component for ChatWindow {
             ),
             Span::raw(" "),
             Span::styled(
-                self.app_context.tg_context().open_chat_user_status(),
+                self.app_context.tg_context().open_chat_status(),
                 self.app_context.style_timestamp(),
             ),
         ]))
@@ -321,7 +1783,25 @@ impl Component for ChatWindow {
         .alignment(Alignment::Center);
 
         frame.render_widget(header, chat_layout[0]);
+        if let Some(pinned_banner) = pinned_banner {
+            let block_banner = Block::new()
+                .border_style(style_border_focused)
+                .borders(Borders::LEFT | Borders::RIGHT)
+                .style(self.app_context.style_chat());
+            frame.render_widget(
+                Paragraph::new(pinned_banner)
+                    .block(block_banner)
+                    .style(self.app_context.style_chat_pinned_message()),
+                banner_area,
+            );
+        }
         frame.render_stateful_widget(list, chat_layout[1], &mut self.message_list_state);
+        self.item_heights = heights;
+        self.list_height = chat_layout[1].height.saturating_sub(1) as usize;
+        self.draw_search(frame, search_area, &search_matches, style_border_focused);
+        self.draw_chooser(frame, chat_layout[1]);
+        self.draw_viewers_popup(frame, chat_layout[1]);
+        self.draw_block_confirmation(frame, chat_layout[1]);
 
         Ok(())
     }
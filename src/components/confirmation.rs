@@ -0,0 +1,137 @@
+use crate::app_context::AppContext;
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Alignment, Constraint, Flex, Layout, Rect},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+
+/// `Confirmation` is a struct that represents a popup asking the user to
+/// confirm an action, e.g. leaving a chat.
+/// It shows a question, followed by the keys to confirm and to cancel: the
+/// action is confirmed with `y` or `Enter` and cancelled with `n` or `Esc`,
+/// see `answer`.
+pub struct Confirmation<'a> {
+    /// The title of the popup.
+    title: &'a str,
+    /// The lines of the question, they can list some options too.
+    lines: Vec<Line<'a>>,
+    /// The description of the key that confirms the action, e.g. `leave`.
+    confirm: &'a str,
+    /// The style of the popup.
+    style: Style,
+}
+
+impl<'a> Confirmation<'a> {
+    /// Create a new instance of the `Confirmation` struct.
+    ///
+    /// # Arguments
+    /// * `title` - The title of the popup.
+    /// * `confirm` - The description of the key that confirms the action.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `Confirmation` struct.
+    pub fn new(title: &'a str, confirm: &'a str) -> Self {
+        Confirmation {
+            title,
+            lines: vec![],
+            confirm,
+            style: Style::default(),
+        }
+    }
+    /// Add a line to the question.
+    ///
+    /// # Arguments
+    /// * `line` - The line to add.
+    ///
+    /// # Returns
+    /// * `Self` - The modified instance of the `Confirmation`.
+    pub fn line(mut self, line: impl Into<Line<'a>>) -> Self {
+        self.lines.push(line.into());
+        self
+    }
+    /// Set the style of the popup.
+    ///
+    /// # Arguments
+    /// * `style` - The style of the popup.
+    ///
+    /// # Returns
+    /// * `Self` - The modified instance of the `Confirmation`.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+    /// Get the answer given by a key.
+    ///
+    /// # Arguments
+    /// * `key` - The pressed key.
+    ///
+    /// # Returns
+    /// * `Option<bool>` - Whether the action is confirmed, `None` if the key
+    ///   does not answer the confirmation.
+    pub fn answer(key: KeyCode) -> Option<bool> {
+        match key {
+            KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => Some(true),
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => Some(false),
+            _ => None,
+        }
+    }
+    /// Draw the popup in the middle of the area, clearing what is under it.
+    ///
+    /// # Arguments
+    /// * `app_context` - The application context.
+    /// * `frame` - The frame to draw on.
+    /// * `area` - The area the popup is centered in.
+    pub fn draw(self, app_context: &AppContext, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let key_style = app_context.style_status_bar_press_key_key();
+        let text_style = app_context.style_status_bar_press_key_text();
+        let mut lines = self.lines;
+        lines.extend([
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("y", key_style),
+                Span::styled(format!(": {}, ", self.confirm), text_style),
+                Span::styled("n", key_style),
+                Span::styled(": cancel", text_style),
+            ]),
+        ]);
+        let width = lines
+            .iter()
+            .map(|line| line.width() as u16 + 4)
+            .max()
+            .unwrap_or_default();
+        let [popup_area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(app_context.style_border_component_focused())
+            .title(self.title);
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .style(self.style)
+            .alignment(Alignment::Center);
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(paragraph, popup_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_answer() {
+        assert_eq!(Confirmation::answer(KeyCode::Char('y')), Some(true));
+        assert_eq!(Confirmation::answer(KeyCode::Enter), Some(true));
+        assert_eq!(Confirmation::answer(KeyCode::Char('N')), Some(false));
+        assert_eq!(Confirmation::answer(KeyCode::Esc), Some(false));
+        assert_eq!(Confirmation::answer(KeyCode::Char('r')), None);
+    }
+}
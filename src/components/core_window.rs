@@ -1,5 +1,5 @@
 use crate::{
-    action::Action,
+    action::{Action, Modifiers},
     app_context::AppContext,
     app_error::AppError,
     component_name::ComponentName,
@@ -7,14 +7,27 @@ use crate::{
         chat_list_window::ChatListWindow,
         chat_window::ChatWindow,
         component_traits::{Component, HandleFocus},
+        confirmation::Confirmation,
+        downloads_window::DownloadsWindow,
+        keymap_window::KeymapWindow,
+        member_list_window::MemberListWindow,
         prompt_window::PromptWindow,
+        scheduled_messages_window::ScheduledMessagesWindow,
+        sessions_window::SessionsWindow,
+        sticker_picker::StickerPicker,
     },
     components::{MAX_CHAT_LIST_SIZE, MAX_PROMPT_SIZE, MIN_CHAT_LIST_SIZE, MIN_PROMPT_SIZE},
     configs::custom::keymap_custom::ActionBinding,
     event::Event,
 };
-use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use crossterm::event::{KeyCode, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Flex, Layout, Position, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
 use std::{collections::HashMap, io, sync::Arc};
+use tdlib_rs::enums::ChatType;
 use tokio::sync::mpsc::UnboundedSender;
 
 use super::reply_message::ReplyMessage;
@@ -47,6 +60,27 @@ pub struct CoreWindow {
     focused: bool,
     /// Indicates whether the reply message should be shown.
     show_reply_message: bool,
+    /// Indicates whether the log out confirmation should be shown.
+    show_log_out_confirmation: bool,
+    /// The recently opened chats shown by the switcher and the index of the
+    /// selected one, `None` if the switcher is not shown.
+    recent_chats: Option<(Vec<i64>, usize)>,
+    /// The popup shown over the chat window, e.g. the sticker picker or the
+    /// panel of the downloads, and the component that had focus before it
+    /// was shown, `None` if no popup is shown.
+    popup: Option<(ComponentName, Option<ComponentName>)>,
+    /// Indicates whether the filter of the chat list is being typed.
+    chat_list_filtering: bool,
+    /// Indicates whether the search of the loaded messages is being typed.
+    chat_window_searching: bool,
+    /// The areas where the components have been drawn, used to find the
+    /// component under the mouse.
+    component_areas: HashMap<ComponentName, Rect>,
+    /// The area where the `CoreWindow` has been drawn.
+    area: Rect,
+    /// Indicates whether the border between the chat list and the chat
+    /// window is being dragged with the mouse.
+    resizing_chat_list: bool,
 }
 
 impl CoreWindow {
@@ -83,6 +117,42 @@ impl CoreWindow {
                     .with_name(ComponentName::ReplyMessage.to_string())
                     .new_boxed(),
             ),
+            (
+                ComponentName::StickerPicker,
+                StickerPicker::new(Arc::clone(&app_context))
+                    .with_name(ComponentName::StickerPicker.to_string())
+                    .new_boxed(),
+            ),
+            (
+                ComponentName::ScheduledMessages,
+                ScheduledMessagesWindow::new(Arc::clone(&app_context))
+                    .with_name(ComponentName::ScheduledMessages.to_string())
+                    .new_boxed(),
+            ),
+            (
+                ComponentName::MemberList,
+                MemberListWindow::new(Arc::clone(&app_context))
+                    .with_name(ComponentName::MemberList.to_string())
+                    .new_boxed(),
+            ),
+            (
+                ComponentName::Downloads,
+                DownloadsWindow::new(Arc::clone(&app_context))
+                    .with_name(ComponentName::Downloads.to_string())
+                    .new_boxed(),
+            ),
+            (
+                ComponentName::Sessions,
+                SessionsWindow::new(Arc::clone(&app_context))
+                    .with_name(ComponentName::Sessions.to_string())
+                    .new_boxed(),
+            ),
+            (
+                ComponentName::Keymap,
+                KeymapWindow::new(Arc::clone(&app_context))
+                    .with_name(ComponentName::Keymap.to_string())
+                    .new_boxed(),
+            ),
         ];
 
         let app_context = app_context;
@@ -90,13 +160,24 @@ impl CoreWindow {
         let action_tx = None;
         let components: HashMap<ComponentName, Box<dyn Component>> =
             components_iter.into_iter().collect();
-        let size_prompt = 3;
-        let size_message_reply = 2;
-        let size_chat_list = 20;
+        let size_prompt = app_context
+            .app_state()
+            .prompt_size
+            .map_or(3, |size| size.clamp(MIN_PROMPT_SIZE, MAX_PROMPT_SIZE));
+        let size_message_reply = 4;
+        let size_chat_list = Self::saved_chat_list_size(&app_context);
         let small_area = false;
         let component_focused = None;
         let focused = true;
         let show_reply_message = false;
+        let show_log_out_confirmation = false;
+        let recent_chats = None;
+        let popup = None;
+        let chat_list_filtering = false;
+        let chat_window_searching = false;
+        let component_areas = HashMap::new();
+        let area = Rect::default();
+        let resizing_chat_list = false;
 
         CoreWindow {
             app_context,
@@ -110,6 +191,14 @@ impl CoreWindow {
             component_focused,
             focused,
             show_reply_message,
+            show_log_out_confirmation,
+            recent_chats,
+            popup,
+            chat_list_filtering,
+            chat_window_searching,
+            component_areas,
+            area,
+            resizing_chat_list,
         }
     }
     /// Set the name of the `CoreWindow`.
@@ -132,37 +221,504 @@ impl CoreWindow {
         self.small_area = small_area;
     }
 
+    /// Get the width of the chat list saved in the application state,
+    /// clamped between `MIN_CHAT_LIST_SIZE` and `MAX_CHAT_LIST_SIZE` because
+    /// the state can be edited by hand.
+    ///
+    /// # Arguments
+    /// * `app_context` - The application context.
+    ///
+    /// # Returns
+    /// * `u16` - The width of the chat list, as a percentage.
+    fn saved_chat_list_size(app_context: &AppContext) -> u16 {
+        app_context.app_state().chat_list_size.map_or(20, |size| {
+            size.clamp(MIN_CHAT_LIST_SIZE, MAX_CHAT_LIST_SIZE)
+        })
+    }
+
     /// Toggle the chat list component.
     pub fn toggle_chat_list(&mut self) {
-        self.size_chat_list = if self.size_chat_list == 0 { 20 } else { 0 };
+        self.size_chat_list = if self.size_chat_list == 0 {
+            Self::saved_chat_list_size(&self.app_context)
+        } else {
+            0
+        };
     }
     /// Increase the size of the chat list component.
     pub fn increase_chat_list_size(&mut self) {
         if self.size_chat_list == MAX_CHAT_LIST_SIZE {
             return;
         }
-        self.size_chat_list += 1;
+        self.set_chat_list_size(self.size_chat_list + 1);
+    }
+    /// Set the size of the chat list component, clamped between
+    /// `MIN_CHAT_LIST_SIZE` and `MAX_CHAT_LIST_SIZE`, and save it in the
+    /// application state.
+    ///
+    /// # Arguments
+    /// * `size` - The width of the chat list, as a percentage of the width of
+    ///   the `CoreWindow`.
+    pub fn set_chat_list_size(&mut self, size: u16) {
+        self.size_chat_list = size.clamp(MIN_CHAT_LIST_SIZE, MAX_CHAT_LIST_SIZE);
+        let mut app_state = self.app_context.app_state();
+        if app_state.chat_list_size == Some(self.size_chat_list) {
+            return;
+        }
+        app_state.chat_list_size = Some(self.size_chat_list);
+        if let Err(e) = app_state.save() {
+            tracing::error!("Failed to save the app state: {}", e);
+        }
     }
     /// Increase the size of the chat list component.
     pub fn increase_size_prompt(&mut self) {
         if self.size_prompt == MAX_PROMPT_SIZE {
             return;
         }
-        self.size_prompt += 1;
+        self.set_prompt_size(self.size_prompt + 1);
+    }
+    /// Set the size of the prompt component, clamped between
+    /// `MIN_PROMPT_SIZE` and `MAX_PROMPT_SIZE`, and save it in the
+    /// application state.
+    ///
+    /// # Arguments
+    /// * `size` - The height of the prompt, in rows.
+    pub fn set_prompt_size(&mut self, size: u16) {
+        self.size_prompt = size.clamp(MIN_PROMPT_SIZE, MAX_PROMPT_SIZE);
+        let mut app_state = self.app_context.app_state();
+        if app_state.prompt_size == Some(self.size_prompt) {
+            return;
+        }
+        app_state.prompt_size = Some(self.size_prompt);
+        if let Err(e) = app_state.save() {
+            tracing::error!("Failed to save the app state: {}", e);
+        }
     }
     /// Decrease the size of the chat list component.
     pub fn decrease_chat_list_size(&mut self) {
         if self.size_chat_list == MIN_CHAT_LIST_SIZE {
             return;
         }
-        self.size_chat_list -= 1;
+        self.set_chat_list_size(self.size_chat_list - 1);
     }
     /// Decrease the size of the chat list component.
     pub fn decrease_size_prompt(&mut self) {
         if self.size_prompt == MIN_PROMPT_SIZE {
             return;
         }
-        self.size_prompt -= 1;
+        self.set_prompt_size(self.size_prompt - 1);
+    }
+    /// Resize the chat list by dragging with the mouse the border between
+    /// the chat list and the chat window.
+    ///
+    /// # Arguments
+    /// * `mouse` - The mouse event.
+    ///
+    /// # Returns
+    /// * `bool` - `true` if the event has been used to resize the chat list.
+    fn handle_chat_list_resize(&mut self, mouse: MouseEvent) -> bool {
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                // The left border of the chat window is the divider.
+                let divider = self
+                    .component_areas
+                    .get(&ComponentName::Chat)
+                    .map(|area| area.x);
+                self.resizing_chat_list = self.size_chat_list > 0
+                    && divider == Some(mouse.column)
+                    && self.area.contains(Position::new(mouse.column, mouse.row));
+                self.resizing_chat_list
+            }
+            MouseEventKind::Drag(MouseButton::Left) if self.resizing_chat_list => {
+                if self.area.width > 0 {
+                    let width = mouse.column.saturating_sub(self.area.x) as u32;
+                    let size = (width * 100 / self.area.width as u32) as u16;
+                    self.size_chat_list = size.clamp(MIN_CHAT_LIST_SIZE, MAX_CHAT_LIST_SIZE);
+                }
+                true
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.resizing_chat_list => {
+                self.resizing_chat_list = false;
+                self.set_chat_list_size(self.size_chat_list);
+                true
+            }
+            _ => false,
+        }
+    }
+    /// Handle a key while the log out confirmation is shown.
+    /// The log out is confirmed with `y` or `Enter` and cancelled with `n` or
+    /// `Esc`.
+    ///
+    /// # Arguments
+    /// * `key` - The key pressed by the user.
+    fn handle_log_out_confirmation(&mut self, key: KeyCode) {
+        match Confirmation::answer(key) {
+            Some(true) => {
+                self.show_log_out_confirmation = false;
+                self.action_tx
+                    .as_ref()
+                    .unwrap_or_else(|| panic!("Failed to get action_tx on CoreWindow"))
+                    .send(Action::LogOut)
+                    .unwrap_or_else(|_| panic!("Failed to send action LogOut from CoreWindow"));
+            }
+            Some(false) => self.show_log_out_confirmation = false,
+            None => {}
+        }
+    }
+    /// Show the switcher of the recently opened chats, with the previous chat
+    /// selected. Nothing is done if the switcher is already shown, the key
+    /// of the switcher is handled by `handle_recent_chats`.
+    fn show_recent_chats(&mut self) {
+        if self.recent_chats.is_some() {
+            return;
+        }
+        let chats = self.app_context.tg_context().recent_chats();
+        if chats.is_empty() {
+            return;
+        }
+        let selected = if chats.len() > 1 { 1 } else { 0 };
+        self.recent_chats = Some((chats, selected));
+    }
+    /// Handle a key while the switcher of the recently opened chats is
+    /// shown.
+    /// The selected chat is opened with `Enter` and the switcher is closed
+    /// with `Esc`. The key of the switcher selects the next chat, like
+    /// `Down` and `Tab`.
+    ///
+    /// # Arguments
+    /// * `key` - The pressed key.
+    /// * `modifiers` - The modifiers of the pressed key.
+    fn handle_recent_chats(&mut self, key: KeyCode, modifiers: Modifiers) {
+        let is_switcher_key = matches!(
+            self.app_context
+                .keymap_config()
+                .core_window
+                .get(&Event::Key(key, modifiers.into())),
+            Some(ActionBinding::Single {
+                action: Action::ShowRecentChats,
+                ..
+            })
+        );
+        let Some((chats, selected)) = self.recent_chats.as_mut() else {
+            return;
+        };
+        match key {
+            _ if is_switcher_key => *selected = (*selected + 1) % chats.len(),
+            KeyCode::Down | KeyCode::Tab => *selected = (*selected + 1) % chats.len(),
+            KeyCode::Up | KeyCode::BackTab => {
+                *selected = (*selected + chats.len() - 1) % chats.len()
+            }
+            KeyCode::Enter => {
+                let chat_id = chats[*selected];
+                self.recent_chats = None;
+                if let Some(tx) = self.action_tx.as_ref() {
+                    tx.send(Action::JumpToChat(chat_id)).unwrap();
+                }
+            }
+            KeyCode::Esc => self.recent_chats = None,
+            _ => {}
+        }
+    }
+    /// Show a popup over the chat window, focusing it until it is hidden.
+    /// Nothing is done if a popup is already shown.
+    ///
+    /// # Arguments
+    /// * `popup` - The name of the component shown as popup.
+    fn show_popup(&mut self, popup: ComponentName) {
+        if self.popup.is_some() {
+            return;
+        }
+        self.popup = Some((popup, self.component_focused));
+        self.set_focus(Some(popup));
+    }
+    /// Hide a popup, focusing again the component that had focus before it
+    /// was shown.
+    /// Nothing is done if the popup is not the shown one.
+    ///
+    /// # Arguments
+    /// * `popup` - The name of the component shown as popup.
+    fn hide_popup(&mut self, popup: ComponentName) {
+        if let Some((_, component_focused)) = self.popup.filter(|(shown, _)| *shown == popup) {
+            self.popup = None;
+            self.set_focus(component_focused);
+        }
+    }
+    /// Show the sticker picker.
+    /// Nothing is shown if there is no open chat.
+    fn show_sticker_picker(&mut self) {
+        if self.app_context.tg_context().open_chat_id() == 0 {
+            self.app_context
+                .tg_context()
+                .set_info_message("Open a chat to send a sticker");
+            return;
+        }
+        self.show_popup(ComponentName::StickerPicker);
+    }
+    /// Show the panel of the scheduled messages of the open chat.
+    /// Nothing is shown if the open chat has no scheduled messages.
+    fn show_scheduled_messages(&mut self) {
+        let tg_context = self.app_context.tg_context();
+        let has_scheduled_messages = tg_context
+            .chats()
+            .get(&tg_context.open_chat_id())
+            .is_some_and(|chat| chat.has_scheduled_messages);
+        if !has_scheduled_messages {
+            tg_context.set_info_message("The open chat has no scheduled messages");
+            return;
+        }
+        self.show_popup(ComponentName::ScheduledMessages);
+    }
+    /// Show the member list of the open group.
+    /// Nothing is shown if the open chat is not a group.
+    fn show_member_list(&mut self) {
+        let tg_context = self.app_context.tg_context();
+        let is_group = tg_context
+            .chats()
+            .get(&tg_context.open_chat_id())
+            .is_some_and(|chat| {
+                matches!(
+                    chat.r#type,
+                    ChatType::BasicGroup(_) | ChatType::Supergroup(_)
+                )
+            });
+        if !is_group {
+            tg_context.set_info_message("The open chat is not a group");
+            return;
+        }
+        self.show_popup(ComponentName::MemberList);
+    }
+    /// Check whether the keys are captured by a popup or by a typing mode,
+    /// so that they are not mapped to actions.
+    /// The keys are used to answer the log out confirmation, by the switcher
+    /// of the recent chats, by the sticker picker, by the panel of the
+    /// scheduled messages, by the member list, by the panel of the
+    /// downloads, by the panel of the sessions, by the panel of the key
+    /// bindings, by the filter of the chat list and by the search of the
+    /// loaded messages.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the keys are captured.
+    fn is_capturing_keys(&self) -> bool {
+        self.show_log_out_confirmation
+            || self.recent_chats.is_some()
+            || self.popup.is_some()
+            || self.chat_list_filtering
+            || self.chat_window_searching
+    }
+    /// Focus the next or the previous component in the focus cycle of the
+    /// configuration. The chat list is skipped if it is hidden.
+    /// If the focused component is not in the cycle, the first or the last
+    /// component is focused.
+    ///
+    /// # Arguments
+    /// * `forward` - Whether to focus the next component or the previous one.
+    fn cycle_focus(&mut self, forward: bool) {
+        let focus_order: Vec<ComponentName> = self
+            .app_context
+            .app_config()
+            .focus_order
+            .iter()
+            .filter(|name| **name != ComponentName::ChatList || self.size_chat_list > 0)
+            .copied()
+            .collect();
+        if focus_order.is_empty() {
+            return;
+        }
+        let len = focus_order.len();
+        let index = match focus_order
+            .iter()
+            .position(|name| Some(*name) == self.component_focused)
+        {
+            Some(i) if forward => (i + 1) % len,
+            Some(i) => (i + len - 1) % len,
+            None if forward => 0,
+            None => len - 1,
+        };
+        if let Some(tx) = self.action_tx.as_ref() {
+            tx.send(Action::FocusComponent(focus_order[index])).unwrap();
+        }
+    }
+    /// Focus a component and unfocus the others.
+    ///
+    /// # Arguments
+    /// * `component_name` - The name of the component to focus, `None` to
+    ///   unfocus all the components.
+    fn set_focus(&mut self, component_name: Option<ComponentName>) {
+        self.component_focused = component_name;
+        for (name, component) in self.components.iter_mut() {
+            if Some(*name) == component_name {
+                component.focus();
+            } else {
+                component.unfocus();
+            }
+        }
+    }
+    /// Get the most relevant actions of the focused component, with their
+    /// labels in the hint line, in the order they are shown.
+    /// The actions are paired with the component whose key bindings are
+    /// used to find their keys.
+    ///
+    /// # Returns
+    /// * `Vec<(ComponentName, Action, &str)>` - The actions of the hints.
+    fn hint_actions(&self) -> Vec<(ComponentName, Action, &'static str)> {
+        let core = |action, label| (ComponentName::CoreWindow, action, label);
+        match self.component_focused {
+            Some(ComponentName::ChatList) => vec![
+                (ComponentName::ChatList, Action::ChatListOpen, "open"),
+                (ComponentName::ChatList, Action::ChatListFilter, "filter"),
+                (
+                    ComponentName::ChatList,
+                    Action::ChatListNextFolder,
+                    "next folder",
+                ),
+                core(Action::FocusNext, "next panel"),
+                core(Action::UnfocusComponent, "unfocus"),
+                core(Action::TryQuit, "quit"),
+            ],
+            Some(ComponentName::Chat) => vec![
+                (ComponentName::Chat, Action::ShowChatWindowReply, "reply"),
+                (ComponentName::Chat, Action::ChatWindowEdit, "edit"),
+                (ComponentName::Chat, Action::ChatWindowCopy, "copy"),
+                (ComponentName::Chat, Action::ChatWindowForward, "forward"),
+                (ComponentName::Chat, Action::ChatWindowSearch, "search"),
+                (
+                    ComponentName::Chat,
+                    Action::ChatWindowOpenMedia,
+                    "open media",
+                ),
+                (
+                    ComponentName::Chat,
+                    Action::ChatWindowDeleteForEveryone,
+                    "delete",
+                ),
+                core(Action::FocusNext, "next panel"),
+                core(Action::UnfocusComponent, "unfocus"),
+            ],
+            Some(ComponentName::Prompt) => vec![
+                core(Action::ShowStickerPicker, "sticker"),
+                core(Action::FocusNext, "next panel"),
+                core(Action::UnfocusComponent, "unfocus"),
+            ],
+            None => vec![
+                core(Action::FocusComponent(ComponentName::ChatList), "chat list"),
+                core(Action::FocusComponent(ComponentName::Chat), "chat"),
+                core(Action::FocusComponent(ComponentName::Prompt), "prompt"),
+                core(Action::ShowRecentChats, "recent chats"),
+                core(Action::TryQuit, "quit"),
+            ],
+            // The popups show their own keys.
+            Some(_) => vec![],
+        }
+    }
+    /// Draw the line with the hints of the key bindings of the focused
+    /// component. The hints that do not fit the width of the area are
+    /// dropped, and the actions without a key binding are skipped.
+    ///
+    /// # Arguments
+    /// * `frame` - A mutable reference to the frame.
+    /// * `area` - The area of the hint line.
+    fn draw_hint_line(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let key_style = self.app_context.style_status_bar_press_key_key();
+        let text_style = self.app_context.style_status_bar_press_key_text();
+        let keymap_config = self.app_context.keymap_config();
+        let mut spans = vec![];
+        let mut width = 0;
+        for (component_name, action, label) in self.hint_actions() {
+            // The shortest key is the easiest to read.
+            let Some(key) = keymap_config
+                .get_key_of_single_action(component_name, action)
+                .iter()
+                .map(|key| key.to_string())
+                .min_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)))
+            else {
+                continue;
+            };
+            let separator = if spans.is_empty() { "" } else { "  " };
+            let hint_width = separator.len() + key.chars().count() + 2 + label.len();
+            if width + hint_width > area.width as usize {
+                break;
+            }
+            width += hint_width;
+            spans.push(Span::styled(separator, text_style));
+            spans.push(Span::styled(key, key_style));
+            spans.push(Span::styled(format!(": {}", label), text_style));
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
+    }
+    /// Draw the switcher of the recently opened chats in the middle of the
+    /// area.
+    ///
+    /// # Arguments
+    /// * `frame` - A mutable reference to the frame.
+    /// * `area` - The area of the `CoreWindow`.
+    fn draw_recent_chats(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        let Some((chats, selected)) = self.recent_chats.as_ref() else {
+            return;
+        };
+        let tg_context = self.app_context.tg_context();
+        let mut lines: Vec<Line> = chats
+            .iter()
+            .enumerate()
+            .map(|(i, chat_id)| {
+                let name = tg_context
+                    .name_from_chats(*chat_id)
+                    .unwrap_or_else(|| chat_id.to_string());
+                if i == *selected {
+                    Line::from(Span::styled(
+                        name,
+                        self.app_context.style_chat_list_item_selected(),
+                    ))
+                } else {
+                    Line::from(name)
+                }
+            })
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            Span::styled("Enter", self.app_context.style_status_bar_press_key_key()),
+            Span::styled(
+                ": open, ",
+                self.app_context.style_status_bar_press_key_text(),
+            ),
+            Span::styled("Esc", self.app_context.style_status_bar_press_key_key()),
+            Span::styled(
+                ": cancel",
+                self.app_context.style_status_bar_press_key_text(),
+            ),
+        ]));
+        let width = lines
+            .iter()
+            .map(|line| line.width() as u16 + 4)
+            .max()
+            .unwrap_or_default()
+            .min(area.width);
+        let [popup_area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(self.app_context.style_border_component_focused())
+            .title("Recent Chats");
+        let paragraph = Paragraph::new(lines)
+            .block(block)
+            .alignment(Alignment::Center);
+
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(paragraph, popup_area);
+    }
+    /// Draw the log out confirmation in the middle of the area.
+    ///
+    /// # Arguments
+    /// * `frame` - A mutable reference to the frame.
+    /// * `area` - The area of the `CoreWindow`.
+    fn draw_log_out_confirmation(&self, frame: &mut ratatui::Frame<'_>, area: Rect) {
+        Confirmation::new("Log Out", "log out")
+            .line("Log out of Telegram?")
+            .line("The local data of the session will be deleted.")
+            .draw(&self.app_context, frame, area);
     }
 }
 /// Implement the `HandleFocus` trait for the `CoreWindow` struct.
@@ -189,6 +745,12 @@ impl Component for CoreWindow {
     }
 
     fn handle_events(&mut self, event: Option<Event>) -> Result<Option<Action>, AppError<Action>> {
+        if self.is_capturing_keys() {
+            return Ok(None);
+        }
+        if let Some(Event::Mouse(mouse)) = event {
+            return Ok(self.handle_mouse_events(mouse)?);
+        }
         let binding = self.app_context.keymap_config();
         let map = binding.get_map_of(self.component_focused);
         if let Some(action_binding) = map.get(&event.unwrap()) {
@@ -205,9 +767,66 @@ impl Component for CoreWindow {
         Ok(Some(Action::Unknown))
     }
 
+    /// Handle the mouse events over the components.
+    /// A left click focuses the component under the mouse, and then the click
+    /// is sent to it as `Action::MouseClick`, e.g. to select or open a chat.
+    /// The mouse wheel moves the selection of the component under the mouse,
+    /// and the horizontal wheel switches the folder of the chat list or
+    /// scrolls the messages of the chat horizontally.
+    fn handle_mouse_events(&mut self, mouse: MouseEvent) -> io::Result<Option<Action>> {
+        if self.handle_chat_list_resize(mouse) {
+            return Ok(None);
+        }
+        let position = Position::new(mouse.column, mouse.row);
+        let Some(component_name) = self
+            .component_areas
+            .iter()
+            .find(|(_, area)| area.contains(position))
+            .map(|(name, _)| *name)
+        else {
+            return Ok(None);
+        };
+        let action = match (mouse.kind, component_name) {
+            (MouseEventKind::Down(MouseButton::Left), _) => {
+                Action::MouseClick(mouse.column, mouse.row)
+            }
+            (MouseEventKind::ScrollUp, ComponentName::ChatList) => Action::ChatListPrevious,
+            (MouseEventKind::ScrollDown, ComponentName::ChatList) => Action::ChatListNext,
+            (MouseEventKind::ScrollUp, ComponentName::Chat) => Action::ChatWindowPrevious,
+            (MouseEventKind::ScrollDown, ComponentName::Chat) => Action::ChatWindowNext,
+            (MouseEventKind::ScrollLeft, ComponentName::ChatList) => Action::ChatListPreviousFolder,
+            (MouseEventKind::ScrollRight, ComponentName::ChatList) => Action::ChatListNextFolder,
+            (MouseEventKind::ScrollLeft, ComponentName::Chat) => Action::ChatWindowScrollLeft,
+            (MouseEventKind::ScrollRight, ComponentName::Chat) => Action::ChatWindowScrollRight,
+            _ => return Ok(None),
+        };
+        // The focus is sent before the returned action, so that the action is
+        // received by the component under the mouse.
+        if self.component_focused != Some(component_name) {
+            if let Some(tx) = self.action_tx.as_ref() {
+                tx.send(Action::FocusComponent(component_name)).unwrap();
+            }
+        }
+        Ok(Some(action))
+    }
+
     fn update(&mut self, action: Action) {
+        if let (true, Action::Key(key, _)) = (self.show_log_out_confirmation, &action) {
+            self.handle_log_out_confirmation(*key);
+            return;
+        }
+        if let (true, Action::Key(key, modifiers)) = (self.recent_chats.is_some(), &action) {
+            self.handle_recent_chats(*key, modifiers.clone());
+            return;
+        }
         match action {
             Action::FocusComponent(component_name) => {
+                if component_name != ComponentName::ChatList {
+                    self.chat_list_filtering = false;
+                }
+                if component_name != ComponentName::Chat {
+                    self.chat_window_searching = false;
+                }
                 self.component_focused = Some(component_name);
                 self.components
                     .get_mut(&component_name)
@@ -218,9 +837,88 @@ impl Component for CoreWindow {
                     .filter(|(name, _)| *name != &component_name)
                     .for_each(|(_, component)| component.unfocus());
             }
+            // The keys of the focus cycle are global, they are ignored while
+            // the keys are captured.
+            Action::FocusNext if !self.is_capturing_keys() => {
+                self.cycle_focus(true);
+            }
+            Action::FocusPrevious if !self.is_capturing_keys() => {
+                self.cycle_focus(false);
+            }
+            Action::ShowRecentChats => {
+                self.show_recent_chats();
+            }
+            Action::ShowStickerPicker => {
+                self.show_sticker_picker();
+            }
+            Action::HideStickerPicker => {
+                self.hide_popup(ComponentName::StickerPicker);
+                return;
+            }
+            Action::ShowScheduledMessages => {
+                self.show_scheduled_messages();
+            }
+            Action::HideScheduledMessages => {
+                self.hide_popup(ComponentName::ScheduledMessages);
+                return;
+            }
+            Action::ShowMemberList => {
+                self.show_member_list();
+            }
+            Action::HideMemberList => {
+                self.hide_popup(ComponentName::MemberList);
+                return;
+            }
+            Action::ShowDownloads => {
+                self.show_popup(ComponentName::Downloads);
+            }
+            Action::HideDownloads => {
+                self.hide_popup(ComponentName::Downloads);
+                return;
+            }
+            Action::ShowSessions => {
+                self.show_popup(ComponentName::Sessions);
+            }
+            Action::HideSessions => {
+                self.hide_popup(ComponentName::Sessions);
+                return;
+            }
+            Action::ShowKeymap => {
+                self.show_popup(ComponentName::Keymap);
+            }
+            Action::HideKeymap => {
+                self.hide_popup(ComponentName::Keymap);
+                return;
+            }
+            Action::JumpToChatIndex(_) | Action::JumpToChat(_) => {
+                // The chat list receives the action even if it is not
+                // focused.
+                self.chat_window_searching = false;
+                self.component_focused = Some(ComponentName::ChatList);
+                for (name, component) in self.components.iter_mut() {
+                    if *name == ComponentName::ChatList {
+                        component.focus();
+                    } else {
+                        component.unfocus();
+                    }
+                }
+            }
+            Action::ChatListFilter => {
+                self.chat_list_filtering = true;
+            }
+            Action::ChatListFilterDone => {
+                self.chat_list_filtering = false;
+            }
+            Action::ChatWindowSearch => {
+                self.chat_window_searching = true;
+            }
+            Action::ChatWindowSearchDone => {
+                self.chat_window_searching = false;
+            }
             Action::UnfocusComponent => {
+                self.chat_list_filtering = false;
+                self.chat_window_searching = false;
                 self.component_focused = None;
-                self.show_reply_message = false;
                 for (_, component) in self.components.iter_mut() {
                     component.unfocus();
                 }
@@ -249,12 +947,28 @@ impl Component for CoreWindow {
                         .unwrap_or_else(|_| panic!("Failed to send action Quit from CoreWindow"));
                 }
             }
-            Action::ShowChatWindowReply => {
+            Action::ShowChatWindowReply | Action::ShowChatWindowQuoteReply => {
                 self.show_reply_message = true;
             }
             Action::HideChatWindowReply => {
                 self.show_reply_message = false;
             }
+            Action::CancelReply => {
+                self.show_reply_message = false;
+                // The prompt receives the action even if it is not focused,
+                // to leave the reply mode.
+                if self.component_focused != Some(ComponentName::Prompt) {
+                    self.components
+                        .get_mut(&ComponentName::Prompt)
+                        .unwrap_or_else(|| {
+                            panic!("Failed to get component: {}", ComponentName::Prompt)
+                        })
+                        .update(Action::CancelReply);
+                }
+            }
+            Action::TryLogOut => {
+                self.show_log_out_confirmation = true;
+            }
             _ => {}
         }
 
@@ -267,6 +981,16 @@ impl Component for CoreWindow {
     }
 
     fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> io::Result<()> {
+        self.component_areas.clear();
+        let area = if self.app_context.app_config().show_hint_line && area.height > 1 {
+            let [area, hint_area] =
+                Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(area);
+            self.draw_hint_line(frame, hint_area);
+            area
+        } else {
+            area
+        };
+        self.area = area;
         let core_layout = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
@@ -279,6 +1003,8 @@ impl Component for CoreWindow {
             .get_mut(&ComponentName::ChatList)
             .unwrap_or_else(|| panic!("Failed to get component: {}", ComponentName::ChatList))
             .draw(frame, core_layout[0])?;
+        self.component_areas
+            .insert(ComponentName::ChatList, core_layout[0]);
 
         let sub_core_layout = Layout::default()
             .direction(Direction::Vertical)
@@ -299,6 +1025,8 @@ impl Component for CoreWindow {
             .get_mut(&ComponentName::Chat)
             .unwrap_or_else(|| panic!("Failed to get component: {}", ComponentName::Chat))
             .draw(frame, sub_core_layout[0])?;
+        self.component_areas
+            .insert(ComponentName::Chat, sub_core_layout[0]);
 
         if self.show_reply_message {
             self.components
@@ -312,7 +1040,144 @@ impl Component for CoreWindow {
             .get_mut(&ComponentName::Prompt)
             .unwrap_or_else(|| panic!("Failed to get component: {}", ComponentName::Prompt))
             .draw(frame, sub_core_layout[2])?;
+        self.component_areas
+            .insert(ComponentName::Prompt, sub_core_layout[2]);
+
+        if let Some((popup, _)) = self.popup {
+            self.components
+                .get_mut(&popup)
+                .unwrap_or_else(|| panic!("Failed to get component: {}", popup))
+                .draw(frame, sub_core_layout[0])?;
+        }
+        if self.recent_chats.is_some() {
+            self.draw_recent_chats(frame, area);
+        }
+        if self.show_log_out_confirmation {
+            self.draw_log_out_confirmation(frame, area);
+        }
 
         Ok(())
     }
 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app_state::AppState,
+        cli::CliArgs,
+        configs::custom::{
+            app_custom::AppConfig, keymap_custom::KeymapConfig, palette_custom::PaletteConfig,
+            telegram_custom::TelegramConfig, theme_custom::ThemeConfig,
+        },
+        tg::tg_context::TgContext,
+    };
+    use clap::Parser;
+    use crossterm::event::KeyModifiers;
+
+    fn app_context() -> Arc<AppContext> {
+        app_context_with(AppState::default())
+    }
+
+    fn app_context_with(app_state: AppState) -> Arc<AppContext> {
+        Arc::new(
+            AppContext::new(
+                AppConfig::default(),
+                KeymapConfig::default(),
+                ThemeConfig::default(),
+                PaletteConfig::default(),
+                TelegramConfig::default(),
+                app_state,
+                TgContext::default(),
+                CliArgs::parse_from(["tgt"]),
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_recent_chats_cycle_with_the_key_of_the_switcher() {
+        let app_context = app_context();
+        [3, 2, 1]
+            .into_iter()
+            .for_each(|chat_id| app_context.tg_context().set_open_chat_id(chat_id));
+        let mut core_window = CoreWindow::new(Arc::clone(&app_context));
+        let modifiers = Modifiers {
+            shift: false,
+            control: false,
+            alt: true,
+            super_: false,
+            hyper: false,
+            meta: false,
+        };
+        // The key is sent before its action, as in `run.rs`.
+        let press_switcher_key = |core_window: &mut CoreWindow| {
+            core_window.update(Action::Key(KeyCode::Char('r'), modifiers.clone()));
+            core_window.update(Action::ShowRecentChats);
+        };
+
+        press_switcher_key(&mut core_window);
+        assert_eq!(core_window.recent_chats, Some((vec![1, 2, 3], 1)));
+        press_switcher_key(&mut core_window);
+        assert_eq!(core_window.recent_chats, Some((vec![1, 2, 3], 2)));
+        press_switcher_key(&mut core_window);
+        assert_eq!(core_window.recent_chats, Some((vec![1, 2, 3], 0)));
+    }
+
+    #[test]
+    fn test_toggle_chat_list_clamps_the_saved_size() {
+        for (saved, expected) in [(0, MIN_CHAT_LIST_SIZE), (200, MAX_CHAT_LIST_SIZE)] {
+            let mut core_window = CoreWindow::new(app_context_with(AppState {
+                chat_list_size: Some(saved),
+                ..Default::default()
+            }));
+            assert_eq!(core_window.size_chat_list, expected);
+            core_window.toggle_chat_list();
+            assert_eq!(core_window.size_chat_list, 0);
+            core_window.toggle_chat_list();
+            assert_eq!(core_window.size_chat_list, expected);
+        }
+    }
+
+    #[test]
+    fn test_mouse_wheel() {
+        let mut core_window = CoreWindow::new(app_context());
+        core_window
+            .component_areas
+            .insert(ComponentName::ChatList, Rect::new(0, 0, 10, 10));
+        core_window
+            .component_areas
+            .insert(ComponentName::Chat, Rect::new(10, 0, 10, 10));
+        let mut wheel = |kind, column| {
+            core_window
+                .handle_mouse_events(MouseEvent {
+                    kind,
+                    column,
+                    row: 5,
+                    modifiers: KeyModifiers::NONE,
+                })
+                .unwrap()
+        };
+
+        assert_eq!(
+            wheel(MouseEventKind::ScrollDown, 5),
+            Some(Action::ChatListNext)
+        );
+        assert_eq!(
+            wheel(MouseEventKind::ScrollLeft, 5),
+            Some(Action::ChatListPreviousFolder)
+        );
+        assert_eq!(
+            wheel(MouseEventKind::ScrollRight, 5),
+            Some(Action::ChatListNextFolder)
+        );
+        assert_eq!(
+            wheel(MouseEventKind::ScrollLeft, 15),
+            Some(Action::ChatWindowScrollLeft)
+        );
+        assert_eq!(
+            wheel(MouseEventKind::ScrollRight, 15),
+            Some(Action::ChatWindowScrollRight)
+        );
+        assert_eq!(wheel(MouseEventKind::ScrollRight, 25), None);
+    }
+}
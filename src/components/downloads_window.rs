@@ -0,0 +1,249 @@
+use crate::{
+    action::Action,
+    app_context::AppContext,
+    components::{
+        component_traits::{Component, HandleFocus},
+        list_popup::ListPopup,
+    },
+    event::Event,
+    tg::td_enums::TdFile,
+    utils::format_size,
+};
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span},
+    widgets::{List, Paragraph},
+};
+use std::{io, sync::Arc};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// The width of the progress bar of a download.
+const PROGRESS_BAR_WIDTH: usize = 10;
+
+/// `DownloadsWindow` is a struct that represents a popup listing the files
+/// downloaded by the user, with the progress of the active downloads.
+/// The active downloads can be canceled, and the completed ones opened with
+/// the default application of the system.
+pub struct DownloadsWindow {
+    /// The application context.
+    app_context: Arc<AppContext>,
+    /// The name of the `DownloadsWindow`.
+    name: String,
+    /// Indicates whether the `DownloadsWindow` is focused or not.
+    focused: bool,
+    /// The list of the downloads.
+    popup: ListPopup,
+}
+
+impl DownloadsWindow {
+    /// Create a new instance of the `DownloadsWindow` struct.
+    ///
+    /// # Arguments
+    /// * `app_context` - An Arc wrapped AppContext struct.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `DownloadsWindow` struct.
+    pub fn new(app_context: Arc<AppContext>) -> Self {
+        let name = "".to_string();
+        let focused = false;
+        let popup = ListPopup::new(Arc::clone(&app_context), Action::HideDownloads);
+
+        DownloadsWindow {
+            app_context,
+            name,
+            focused,
+            popup,
+        }
+    }
+    /// Set the name of the `DownloadsWindow`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the `DownloadsWindow`.
+    ///
+    /// # Returns
+    /// * `Self` - The modified instance of the `DownloadsWindow`.
+    pub fn with_name(mut self, name: impl AsRef<str>) -> Self {
+        self.name = name.as_ref().to_string();
+        self
+    }
+
+    /// Get the state of the selected file.
+    ///
+    /// # Returns
+    /// * `Option<TdFile>` - The state of the file, `None` if no file is
+    ///   selected.
+    fn selected(&self) -> Option<TdFile> {
+        let index = self.popup.selected()?;
+        self.app_context
+            .tg_context()
+            .downloads()
+            .get(index)
+            .map(|entry| entry.file.clone())
+    }
+
+    /// Move the selection by a number of files.
+    ///
+    /// # Arguments
+    /// * `delta` - The number of files to move, negative to move up.
+    fn move_selection(&mut self, delta: isize) {
+        let len = self.app_context.tg_context().downloads().len();
+        self.popup.move_selection(len, delta);
+    }
+
+    /// Open the selected file with the default application of the system,
+    /// if it has been downloaded.
+    fn open_selected(&self) {
+        let Some(file) = self.selected() else {
+            return;
+        };
+        let tg_context = self.app_context.tg_context();
+        if !file.is_downloading_completed {
+            tg_context.set_info_message("The file has not been downloaded yet");
+            return;
+        }
+        if let Err(e) = open::that_detached(&file.path) {
+            tracing::error!("Failed to open {}: {}", file.path, e);
+            tg_context.set_error_message(format!("Failed to open the file: {}", e));
+        }
+    }
+
+    /// Cancel the download of the selected file, if it is not completed.
+    fn cancel_selected(&self) {
+        let Some(file) = self.selected() else {
+            return;
+        };
+        if file.is_downloading_completed {
+            return;
+        }
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx.send(Event::CancelDownload(file.id)).unwrap();
+        }
+    }
+
+    /// Remove the completed downloads from the list, the files are kept on
+    /// the disk.
+    fn clear_completed(&mut self) {
+        self.app_context
+            .tg_context()
+            .downloads()
+            .retain(|entry| !entry.file.is_downloading_completed);
+        self.popup.select(Some(0));
+    }
+
+    /// Get the progress of a download: a bar followed by the percentage and
+    /// the downloaded size.
+    ///
+    /// # Arguments
+    /// * `file` - The state of the file.
+    ///
+    /// # Returns
+    /// * `String` - The progress of the download.
+    fn progress(file: &TdFile) -> String {
+        if file.is_downloading_completed {
+            return format!("done {}", format_size(file.size));
+        }
+        let ratio = if file.size > 0 {
+            (file.downloaded_size as f64 / file.size as f64).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let filled = (ratio * PROGRESS_BAR_WIDTH as f64).round() as usize;
+        format!(
+            "{}{} {:>3}% {}/{}{}",
+            "█".repeat(filled),
+            "░".repeat(PROGRESS_BAR_WIDTH - filled),
+            (ratio * 100.0) as u8,
+            format_size(file.downloaded_size),
+            format_size(file.size),
+            if file.is_downloading_active {
+                ""
+            } else {
+                " (paused)"
+            }
+        )
+    }
+}
+
+/// Implement the `HandleFocus` trait for the `DownloadsWindow` struct.
+/// This trait allows the `DownloadsWindow` to be focused or unfocused.
+impl HandleFocus for DownloadsWindow {
+    /// Set the `focused` flag for the `DownloadsWindow`, selecting the most
+    /// recent download.
+    fn focus(&mut self) {
+        self.focused = true;
+        let len = self.app_context.tg_context().downloads().len();
+        self.popup.select(Some(len.saturating_sub(1)));
+    }
+    /// Set the `focused` flag for the `DownloadsWindow`.
+    fn unfocus(&mut self) {
+        self.focused = false;
+    }
+}
+
+/// Implement the `Component` trait for the `DownloadsWindow` struct.
+impl Component for DownloadsWindow {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> io::Result<()> {
+        self.popup.register_action_handler(tx);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) {
+        if let Action::Key(key, _) = action {
+            match key {
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::Enter => self.open_selected(),
+                KeyCode::Char('c') => self.cancel_selected(),
+                KeyCode::Char('x') => self.clear_completed(),
+                KeyCode::Esc | KeyCode::Char('q') => self.popup.hide(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Draw the `DownloadsWindow` in the middle of the area.
+    fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> io::Result<()> {
+        let inner_area = self.popup.draw_block(
+            frame,
+            area,
+            (Constraint::Percentage(70), Constraint::Percentage(60)),
+            self.name.as_str(),
+        );
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner_area);
+
+        let tg_context = self.app_context.tg_context();
+        let downloads = tg_context.downloads();
+        if downloads.is_empty() {
+            frame.render_widget(Paragraph::new("No downloads"), list_area);
+        } else {
+            self.popup.clamp_selection(downloads.len());
+            let items = downloads.iter().map(|entry| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("{} ", Self::progress(&entry.file)),
+                        self.app_context.style_chat_list_item_message_content(),
+                    ),
+                    Span::styled(
+                        entry.name.clone(),
+                        self.app_context.style_chat_list_item_chat_name(),
+                    ),
+                ])
+            });
+            let list =
+                List::new(items).highlight_style(self.app_context.style_chat_message_selected());
+            frame.render_stateful_widget(list, list_area, &mut self.popup.list_state);
+        }
+
+        let help = self.popup.help_line(&[
+            ("Enter", "open"),
+            ("c", "cancel"),
+            ("x", "clear completed"),
+            ("Esc", "close"),
+        ]);
+        frame.render_widget(Paragraph::new(help), help_area);
+
+        Ok(())
+    }
+}
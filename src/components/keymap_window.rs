@@ -0,0 +1,366 @@
+use crate::{
+    action::Action,
+    app_context::AppContext,
+    components::{
+        component_traits::{Component, HandleFocus},
+        list_popup::ListPopup,
+    },
+    configs::{
+        custom::keymap_custom::{ActionBinding, KeymapConfig, KEYMAP_SECTIONS},
+        raw::keymap_raw::KeymapEntry,
+    },
+    event::Event,
+};
+use crossterm::event::{KeyCode, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span},
+    widgets::{List, Paragraph},
+};
+use std::{collections::HashSet, io, str::FromStr, sync::Arc};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// A command of the keymap configuration that can be rebound.
+struct KeymapCommand {
+    /// The section of `keymap.toml` of the command.
+    section: &'static str,
+    /// The name of the command, e.g. `show_keymap`.
+    command: String,
+    /// The description of the command.
+    description: String,
+    /// The action of the command.
+    action: Action,
+}
+
+/// The state of the rebinding of the selected command.
+enum RebindState {
+    /// No key is being bound.
+    None,
+    /// The next key is captured to be bound to the selected command.
+    Capturing,
+    /// The captured key has warnings, e.g. a conflict, and the binding waits
+    /// for a confirmation.
+    Confirm(Event, Vec<String>),
+}
+
+/// `KeymapWindow` is a struct that represents a popup listing the commands
+/// of the keymap configuration with their keys.
+/// A new key can be bound to the selected command: the binding is saved in
+/// the custom `keymap.toml` and the keymap configuration is reloaded.
+pub struct KeymapWindow {
+    /// The application context.
+    app_context: Arc<AppContext>,
+    /// The name of the `KeymapWindow`.
+    name: String,
+    /// Indicates whether the `KeymapWindow` is focused or not.
+    focused: bool,
+    /// The commands of the default keymap configuration.
+    commands: Vec<KeymapCommand>,
+    /// The list of the commands.
+    popup: ListPopup,
+    /// The state of the rebinding of the selected command.
+    rebind_state: RebindState,
+}
+
+impl KeymapWindow {
+    /// Create a new instance of the `KeymapWindow` struct.
+    ///
+    /// # Arguments
+    /// * `app_context` - An Arc wrapped AppContext struct.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `KeymapWindow` struct.
+    pub fn new(app_context: Arc<AppContext>) -> Self {
+        let name = "".to_string();
+        let focused = false;
+        let commands = vec![];
+        let popup = ListPopup::new(Arc::clone(&app_context), Action::HideKeymap);
+        let rebind_state = RebindState::None;
+
+        KeymapWindow {
+            app_context,
+            name,
+            focused,
+            commands,
+            popup,
+            rebind_state,
+        }
+    }
+    /// Set the name of the `KeymapWindow`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the `KeymapWindow`.
+    ///
+    /// # Returns
+    /// * `Self` - The modified instance of the `KeymapWindow`.
+    pub fn with_name(mut self, name: impl AsRef<str>) -> Self {
+        self.name = name.as_ref().to_string();
+        self
+    }
+
+    /// Load the commands of the default keymap configuration, once per
+    /// command and section.
+    fn load_commands(&mut self) {
+        let raw = match KeymapConfig::default_raw() {
+            Ok(raw) => raw,
+            Err(e) => {
+                tracing::error!("Failed to read the default keymap: {}", e);
+                return;
+            }
+        };
+        let mut seen = HashSet::new();
+        self.commands = KEYMAP_SECTIONS
+            .into_iter()
+            .zip([raw.core_window, raw.chat_list, raw.chat, raw.prompt])
+            .flat_map(|(section, mode)| {
+                mode.into_iter()
+                    .flat_map(|mode| mode.keymap)
+                    .map(move |entry| (section, entry))
+            })
+            .filter(|(section, entry)| seen.insert((*section, entry.command.clone())))
+            .filter_map(|(section, entry)| {
+                let action = Action::from_str(&entry.command).ok()?;
+                Some(KeymapCommand {
+                    section,
+                    description: entry.description.unwrap_or_else(|| entry.command.clone()),
+                    command: entry.command,
+                    action,
+                })
+            })
+            .collect();
+    }
+
+    /// Get the keys bound to a command, as they are written in the keymap
+    /// configuration.
+    ///
+    /// # Arguments
+    /// * `command` - The command.
+    ///
+    /// # Returns
+    /// * `Vec<String>` - The sorted keys.
+    fn keys_of(&self, command: &KeymapCommand) -> Vec<String> {
+        let keymap_config = self.app_context.keymap_config();
+        let mut keys: Vec<String> = keymap_config
+            .get_map_of_section(command.section)
+            .into_iter()
+            .flatten()
+            .filter(|(_, binding)| {
+                matches!(binding, ActionBinding::Single { action, .. } if *action == command.action)
+            })
+            .filter_map(|(key, _)| key.keymap_key())
+            .collect();
+        keys.sort();
+        keys
+    }
+
+    /// Start or stop capturing the next key.
+    ///
+    /// # Arguments
+    /// * `capturing` - Whether the next key is captured.
+    fn set_capturing(&mut self, capturing: bool) {
+        self.rebind_state = if capturing {
+            RebindState::Capturing
+        } else {
+            RebindState::None
+        };
+        self.app_context.set_capturing_key(capturing);
+    }
+
+    /// Handle the key captured for the selected command: the binding is
+    /// saved if it has no warnings, otherwise a confirmation is asked.
+    ///
+    /// # Arguments
+    /// * `key` - The captured key.
+    fn capture_key(&mut self, key: Event) {
+        self.set_capturing(false);
+        let Some(command) = self.popup.selected().and_then(|i| self.commands.get(i)) else {
+            return;
+        };
+        if key.keymap_key().is_none() {
+            self.app_context
+                .tg_context()
+                .set_error_message(format!("The key {} can not be bound", key));
+            return;
+        }
+        let warnings = self.app_context.keymap_config().rebind_warnings(
+            command.section,
+            &key,
+            &command.action,
+        );
+        if warnings.is_empty() {
+            self.save_binding(key);
+        } else {
+            self.rebind_state = RebindState::Confirm(key, warnings);
+        }
+    }
+
+    /// Save the binding of a key to the selected command in the custom
+    /// `keymap.toml`, then reload the keymap configuration.
+    ///
+    /// # Arguments
+    /// * `key` - The key to bind.
+    fn save_binding(&mut self, key: Event) {
+        self.rebind_state = RebindState::None;
+        let Some(command) = self.popup.selected().and_then(|i| self.commands.get(i)) else {
+            return;
+        };
+        let Some(key_name) = key.keymap_key() else {
+            return;
+        };
+        let entry = KeymapEntry {
+            keys: vec![key_name.clone()],
+            command: command.command.clone(),
+            description: Some(command.description.clone()),
+        };
+        let tg_context = self.app_context.tg_context();
+        match KeymapConfig::save_binding(command.section, entry)
+            .and_then(|path| self.app_context.reload_keymap_config().map(|_| path))
+        {
+            Ok(path) => {
+                tracing::info!(
+                    "Bound {} to {} in {}",
+                    key_name,
+                    command.command,
+                    path.display()
+                );
+                tg_context.set_info_message(format!(
+                    "{} bound to {}, saved in {}",
+                    key_name,
+                    command.command,
+                    path.display()
+                ));
+            }
+            Err(e) => {
+                tracing::error!("Failed to bind {}: {}", key_name, e);
+                tg_context.set_error_message(format!("Failed to bind {}: {}", key_name, e));
+            }
+        }
+    }
+}
+
+/// Implement the `HandleFocus` trait for the `KeymapWindow` struct.
+/// This trait allows the `KeymapWindow` to be focused or unfocused.
+impl HandleFocus for KeymapWindow {
+    /// Set the `focused` flag for the `KeymapWindow`, and load the commands
+    /// of the keymap configuration.
+    fn focus(&mut self) {
+        self.focused = true;
+        self.set_capturing(false);
+        if self.commands.is_empty() {
+            self.load_commands();
+        }
+        if self.popup.selected().is_none() {
+            self.popup.select(Some(0));
+        }
+    }
+    /// Set the `focused` flag for the `KeymapWindow`, and stop capturing the
+    /// keys.
+    fn unfocus(&mut self) {
+        self.focused = false;
+        if matches!(self.rebind_state, RebindState::Capturing) {
+            self.set_capturing(false);
+        }
+    }
+}
+
+/// Implement the `Component` trait for the `KeymapWindow` struct.
+impl Component for KeymapWindow {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> io::Result<()> {
+        self.popup.register_action_handler(tx);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) {
+        // The other actions are ignored, they are sent also for the keys of
+        // the panel.
+        let Action::Key(key, modifiers) = action else {
+            return;
+        };
+        let modifiers = KeyModifiers::from(modifiers);
+        match &self.rebind_state {
+            RebindState::Capturing => {
+                if key == KeyCode::Esc && modifiers == KeyModifiers::NONE {
+                    self.set_capturing(false);
+                } else {
+                    self.capture_key(Event::Key(key, modifiers));
+                }
+            }
+            RebindState::Confirm(captured, _) => {
+                let captured = captured.clone();
+                match key {
+                    KeyCode::Char('y') => self.save_binding(captured),
+                    KeyCode::Char('n') | KeyCode::Esc => self.rebind_state = RebindState::None,
+                    _ => {}
+                }
+            }
+            RebindState::None => match key {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.popup.move_selection(self.commands.len(), 1)
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.popup.move_selection(self.commands.len(), -1)
+                }
+                KeyCode::Enter => self.set_capturing(true),
+                KeyCode::Esc | KeyCode::Char('q') => self.popup.hide(),
+                _ => {}
+            },
+        }
+    }
+
+    /// Draw the `KeymapWindow` in the middle of the area.
+    fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> io::Result<()> {
+        let inner_area = self.popup.draw_block(
+            frame,
+            area,
+            (Constraint::Percentage(70), Constraint::Percentage(70)),
+            self.name.as_str(),
+        );
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner_area);
+
+        let name_style = self.app_context.style_chat_list_item_chat_name();
+        let text_style = self.app_context.style_chat_list_item_message_content();
+        let items: Vec<Line> = self
+            .commands
+            .iter()
+            .map(|command| {
+                Line::from(vec![
+                    Span::styled(format!("{:<12}", command.section), text_style),
+                    Span::styled(
+                        format!("{:<24}", self.keys_of(command).join(", ")),
+                        name_style,
+                    ),
+                    Span::styled(command.description.clone(), text_style),
+                ])
+            })
+            .collect();
+        let list = List::new(items).highlight_style(self.app_context.style_chat_message_selected());
+        frame.render_stateful_widget(list, list_area, &mut self.popup.list_state);
+
+        let key_style = self.app_context.style_status_bar_press_key_key();
+        let text_style = self.app_context.style_status_bar_press_key_text();
+        let help = match &self.rebind_state {
+            RebindState::None => self
+                .popup
+                .help_line(&[("Enter", "bind a new key"), ("Esc", "close")]),
+            RebindState::Capturing => Line::from(vec![
+                Span::styled("Press the new key, ", text_style),
+                Span::styled("Esc", key_style),
+                Span::styled(": cancel", text_style),
+            ]),
+            RebindState::Confirm(_, warnings) => Line::from(vec![
+                Span::styled(
+                    format!("{}. Bind anyway? ", warnings.join(", ")),
+                    text_style,
+                ),
+                Span::styled("y", key_style),
+                Span::styled(": yes, ", text_style),
+                Span::styled("n", key_style),
+                Span::styled(": no", text_style),
+            ]),
+        };
+        frame.render_widget(Paragraph::new(help), help_area);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use crate::{action::Action, app_context::AppContext};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, ListState},
+};
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// `ListPopup` is a struct that represents the state shared by the popups
+/// showing a list over the chat window, e.g. the panel of the downloads: the
+/// selection of the list and the action that hides the popup.
+pub struct ListPopup {
+    /// The application context.
+    app_context: Arc<AppContext>,
+    /// An unbounded sender that send action for processing.
+    action_tx: Option<UnboundedSender<Action>>,
+    /// The action sent to hide the popup.
+    hide_action: Action,
+    /// The state of the list.
+    pub list_state: ListState,
+}
+
+impl ListPopup {
+    /// Create a new instance of the `ListPopup` struct.
+    ///
+    /// # Arguments
+    /// * `app_context` - An Arc wrapped AppContext struct.
+    /// * `hide_action` - The action sent to hide the popup.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `ListPopup` struct.
+    pub fn new(app_context: Arc<AppContext>, hide_action: Action) -> Self {
+        let action_tx = None;
+        let list_state = ListState::default();
+
+        ListPopup {
+            app_context,
+            action_tx,
+            hide_action,
+            list_state,
+        }
+    }
+    /// Register the sender used to hide the popup.
+    ///
+    /// # Arguments
+    /// * `tx` - An unbounded sender that send action for processing.
+    pub fn register_action_handler(&mut self, tx: UnboundedSender<Action>) {
+        self.action_tx = Some(tx);
+    }
+    /// Get the index of the selected item.
+    ///
+    /// # Returns
+    /// * `Option<usize>` - The index of the item, `None` if no item is
+    ///   selected.
+    pub fn selected(&self) -> Option<usize> {
+        self.list_state.selected()
+    }
+    /// Select an item of the list.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the item, `None` to clear the selection.
+    pub fn select(&mut self, index: Option<usize>) {
+        self.list_state.select(index);
+    }
+    /// Move the selection by a number of items, stopping at the first and at
+    /// the last one.
+    /// The first item is selected if no item is selected.
+    ///
+    /// # Arguments
+    /// * `len` - The number of items of the list.
+    /// * `delta` - The number of items to move, negative to move up.
+    pub fn move_selection(&mut self, len: usize, delta: isize) {
+        if len == 0 {
+            return;
+        }
+        let selected = match self.list_state.selected() {
+            Some(i) => i.saturating_add_signed(delta).min(len - 1),
+            None => 0,
+        };
+        self.list_state.select(Some(selected));
+    }
+    /// Keep the selection inside the list, e.g. after some items have been
+    /// removed.
+    ///
+    /// # Arguments
+    /// * `len` - The number of items of the list.
+    pub fn clamp_selection(&mut self, len: usize) {
+        if len > 0 && self.list_state.selected().is_none_or(|i| i >= len) {
+            self.list_state.select(Some(len - 1));
+        }
+    }
+    /// Hide the popup.
+    pub fn hide(&self) {
+        if let Some(tx) = self.action_tx.as_ref() {
+            tx.send(self.hide_action.clone()).unwrap();
+        }
+    }
+    /// Draw the bordered block of the popup in the middle of the area,
+    /// clearing what is under it.
+    ///
+    /// # Arguments
+    /// * `frame` - The frame to draw on.
+    /// * `area` - The area the popup is centered in.
+    /// * `size` - The width and the height of the popup.
+    /// * `title` - The title of the popup.
+    ///
+    /// # Returns
+    /// * `Rect` - The area inside the block.
+    pub fn draw_block<'a>(
+        &self,
+        frame: &mut ratatui::Frame<'_>,
+        area: Rect,
+        size: (Constraint, Constraint),
+        title: impl Into<Line<'a>>,
+    ) -> Rect {
+        let [popup_area] = Layout::horizontal([size.0]).flex(Flex::Center).areas(area);
+        let [popup_area] = Layout::vertical([size.1])
+            .flex(Flex::Center)
+            .areas(popup_area);
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(self.app_context.style_border_component_focused())
+            .style(self.app_context.style_chat())
+            .title(title);
+        let inner_area = block.inner(popup_area);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(block, popup_area);
+        inner_area
+    }
+    /// Get the help line of the popup, listing its keys and what they do.
+    ///
+    /// # Arguments
+    /// * `keys` - The keys and their descriptions.
+    ///
+    /// # Returns
+    /// * `Line` - The help line.
+    pub fn help_line(&self, keys: &[(&str, &str)]) -> Line<'static> {
+        let key_style = self.app_context.style_status_bar_press_key_key();
+        let text_style = self.app_context.style_status_bar_press_key_text();
+        let spans = keys.iter().enumerate().flat_map(|(i, (key, description))| {
+            let separator = if i + 1 < keys.len() { ", " } else { "" };
+            [
+                Span::styled(key.to_string(), key_style),
+                Span::styled(format!(": {}{}", description, separator), text_style),
+            ]
+        });
+        Line::from(spans.collect::<Vec<_>>())
+    }
+}
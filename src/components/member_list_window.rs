@@ -0,0 +1,328 @@
+use crate::{
+    action::Action,
+    app_context::AppContext,
+    components::{
+        component_traits::{Component, HandleFocus},
+        list_popup::ListPopup,
+    },
+    event::Event,
+    utils::fuzzy_match,
+};
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Layout, Position, Rect},
+    text::{Line, Span},
+    widgets::{List, Paragraph},
+};
+use std::{io, sync::Arc};
+use tdlib_rs::enums::{ChatMemberStatus, MessageSender, UserStatus};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// The number of members skipped by `PageUp` and `PageDown`.
+const PAGE_SIZE: usize = 10;
+
+/// The role of a member in a group, in the order they are listed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum MemberRole {
+    /// The owner of the group.
+    Owner,
+    /// An administrator of the group.
+    Admin,
+    /// A member without restrictions.
+    Member,
+    /// A member with restrictions.
+    Restricted,
+}
+
+/// A member of a group shown by the `MemberListWindow`.
+struct MemberEntry {
+    /// The id of the user.
+    user_id: i64,
+    /// The full name of the user, followed by the username if any.
+    name: String,
+    /// The role of the user in the group.
+    role: MemberRole,
+    /// The custom title of the owner or of the administrator, if any.
+    title: String,
+    /// Whether the user is online.
+    online: bool,
+}
+
+/// `MemberListWindow` is a struct that represents a popup listing the
+/// members of the open group, with their roles and online status.
+/// The members are filtered by typing their name, and the private chat with
+/// the selected member is opened with `Enter`.
+pub struct MemberListWindow {
+    /// The application context.
+    app_context: Arc<AppContext>,
+    /// The name of the `MemberListWindow`.
+    name: String,
+    /// Indicates whether the `MemberListWindow` is focused or not.
+    focused: bool,
+    /// The filter of the members by name.
+    filter: String,
+    /// The list of the members.
+    popup: ListPopup,
+    /// The ids of the shown members, in the order of the list, updated when
+    /// drawn.
+    shown_user_ids: Vec<i64>,
+}
+
+impl MemberListWindow {
+    /// Create a new instance of the `MemberListWindow` struct.
+    ///
+    /// # Arguments
+    /// * `app_context` - An Arc wrapped AppContext struct.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `MemberListWindow` struct.
+    pub fn new(app_context: Arc<AppContext>) -> Self {
+        let name = "".to_string();
+        let focused = false;
+        let filter = String::new();
+        let popup = ListPopup::new(Arc::clone(&app_context), Action::HideMemberList);
+        let shown_user_ids = vec![];
+
+        MemberListWindow {
+            app_context,
+            name,
+            focused,
+            filter,
+            popup,
+            shown_user_ids,
+        }
+    }
+    /// Set the name of the `MemberListWindow`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the `MemberListWindow`.
+    ///
+    /// # Returns
+    /// * `Self` - The modified instance of the `MemberListWindow`.
+    pub fn with_name(mut self, name: impl AsRef<str>) -> Self {
+        self.name = name.as_ref().to_string();
+        self
+    }
+
+    /// Get the members of the group that match the filter, the owner and the
+    /// administrators first, then the others by name.
+    /// The members that left the group or that are banned are not included.
+    ///
+    /// # Returns
+    /// * `Vec<MemberEntry>` - The members.
+    fn members(&self) -> Vec<MemberEntry> {
+        let tg_context = self.app_context.tg_context();
+        let users = tg_context.users();
+        let mut members: Vec<MemberEntry> = tg_context
+            .chat_members()
+            .iter()
+            .filter_map(|member| {
+                let MessageSender::User(sender) = &member.member_id else {
+                    return None;
+                };
+                let (role, title) = match &member.status {
+                    ChatMemberStatus::Creator(status) => {
+                        (MemberRole::Owner, status.custom_title.clone())
+                    }
+                    ChatMemberStatus::Administrator(status) => {
+                        (MemberRole::Admin, status.custom_title.clone())
+                    }
+                    ChatMemberStatus::Member => (MemberRole::Member, String::new()),
+                    ChatMemberStatus::Restricted(_) => (MemberRole::Restricted, String::new()),
+                    ChatMemberStatus::Left | ChatMemberStatus::Banned(_) => return None,
+                };
+                let user = users.get(&sender.user_id);
+                let name = user.map_or_else(
+                    || sender.user_id.to_string(),
+                    |user| {
+                        let mut name = format!("{} {}", user.first_name, user.last_name)
+                            .trim()
+                            .to_string();
+                        if let Some(username) = user
+                            .usernames
+                            .as_ref()
+                            .and_then(|usernames| usernames.active_usernames.first())
+                        {
+                            name.push_str(&format!(" @{}", username));
+                        }
+                        name
+                    },
+                );
+                let online = user.is_some_and(|user| matches!(user.status, UserStatus::Online(_)));
+                Some(MemberEntry {
+                    user_id: sender.user_id,
+                    name,
+                    role,
+                    title,
+                    online,
+                })
+            })
+            .filter(|member| fuzzy_match(&self.filter, &member.name, false).is_some())
+            .collect();
+        members.sort_by(|a, b| {
+            a.role
+                .cmp(&b.role)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+        members
+    }
+
+    /// Move the selection by a number of members.
+    ///
+    /// # Arguments
+    /// * `delta` - The number of members to move, negative to move up.
+    fn move_selection(&mut self, delta: isize) {
+        self.popup.move_selection(self.shown_user_ids.len(), delta);
+    }
+
+    /// Open the private chat with the selected member, hiding the
+    /// `MemberListWindow`.
+    fn open_selected(&self) {
+        let Some(user_id) = self
+            .popup
+            .selected()
+            .and_then(|i| self.shown_user_ids.get(i))
+        else {
+            return;
+        };
+        self.popup.hide();
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx.send(Event::OpenPrivateChat(*user_id)).unwrap();
+        }
+    }
+}
+
+/// Implement the `HandleFocus` trait for the `MemberListWindow` struct.
+/// This trait allows the `MemberListWindow` to be focused or unfocused.
+impl HandleFocus for MemberListWindow {
+    /// Set the `focused` flag for the `MemberListWindow`, and load the
+    /// members of the open group.
+    fn focus(&mut self) {
+        self.focused = true;
+        self.filter.clear();
+        self.popup.select(Some(0));
+        self.app_context.tg_context().reset_chat_members();
+        let chat_id = self.app_context.tg_context().open_chat_id();
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx.send(Event::LoadChatMembers(chat_id)).unwrap();
+        }
+    }
+    /// Set the `focused` flag for the `MemberListWindow`.
+    fn unfocus(&mut self) {
+        self.focused = false;
+    }
+}
+
+/// Implement the `Component` trait for the `MemberListWindow` struct.
+impl Component for MemberListWindow {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> io::Result<()> {
+        self.popup.register_action_handler(tx);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) {
+        // The other actions are ignored, they are sent also for the keys
+        // typed in the filter.
+        if let Action::Key(key, _) = action {
+            match key {
+                KeyCode::Down => self.move_selection(1),
+                KeyCode::Up => self.move_selection(-1),
+                KeyCode::PageDown => self.move_selection(PAGE_SIZE as isize),
+                KeyCode::PageUp => self.move_selection(-(PAGE_SIZE as isize)),
+                KeyCode::Home => self.popup.select(Some(0)),
+                KeyCode::End => self.popup.select(Some(usize::MAX)),
+                KeyCode::Enter => self.open_selected(),
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.popup.select(Some(0));
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.popup.select(Some(0));
+                }
+                KeyCode::Esc if !self.filter.is_empty() => {
+                    self.filter.clear();
+                    self.popup.select(Some(0));
+                }
+                KeyCode::Esc => self.popup.hide(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Draw the `MemberListWindow` in the middle of the area, with the
+    /// filter above the list of the members.
+    fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> io::Result<()> {
+        let members = self.members();
+        self.shown_user_ids = members.iter().map(|member| member.user_id).collect();
+        self.popup.clamp_selection(members.len());
+
+        let inner_area = self.popup.draw_block(
+            frame,
+            area,
+            (Constraint::Percentage(60), Constraint::Percentage(80)),
+            format!("{} ({})", self.name, members.len()),
+        );
+        let [filter_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner_area);
+
+        let filter_line = Line::from(format!("/{}", self.filter));
+        frame.set_cursor_position(Position::new(
+            filter_area.x + filter_line.width() as u16,
+            filter_area.y,
+        ));
+        frame.render_widget(Paragraph::new(filter_line), filter_area);
+
+        if members.is_empty() {
+            let tg_context = self.app_context.tg_context();
+            let loaded = tg_context.chat_members_loaded().clone();
+            let text = match loaded {
+                Some(Err(e)) => format!("Failed to load the members: {}", e),
+                None if tg_context.chat_members().is_empty() => "Loading...".to_string(),
+                _ => "No members found".to_string(),
+            };
+            frame.render_widget(Paragraph::new(text), list_area);
+        } else {
+            let items = members.iter().map(|member| {
+                // The owner and the administrators are highlighted.
+                let name_style = match member.role {
+                    MemberRole::Owner | MemberRole::Admin => {
+                        self.app_context.style_chat_list_item_unread_counter()
+                    }
+                    MemberRole::Member | MemberRole::Restricted => {
+                        self.app_context.style_chat_list_item_chat_name()
+                    }
+                };
+                let role = match member.role {
+                    _ if !member.title.is_empty() => member.title.clone(),
+                    MemberRole::Owner => "owner".to_string(),
+                    MemberRole::Admin => "admin".to_string(),
+                    MemberRole::Member => "member".to_string(),
+                    MemberRole::Restricted => "restricted".to_string(),
+                };
+                Line::from(vec![
+                    Span::raw(if member.online { "🟢 " } else { "   " }),
+                    Span::styled(member.name.clone(), name_style),
+                    Span::styled(
+                        format!(" {}", role),
+                        self.app_context.style_chat_list_item_message_content(),
+                    ),
+                ])
+            });
+            let list =
+                List::new(items).highlight_style(self.app_context.style_chat_list_item_selected());
+            frame.render_stateful_widget(list, list_area, &mut self.popup.list_state);
+        }
+
+        let help = self
+            .popup
+            .help_line(&[("Enter", "open chat"), ("Esc", "clear filter or close")]);
+        frame.render_widget(Paragraph::new(help), help_area);
+
+        Ok(())
+    }
+}
@@ -1,15 +1,22 @@
-pub const SMALL_AREA_WIDTH: u16 = 100;
-pub const SMALL_AREA_HEIGHT: u16 = 20;
 pub const MAX_CHAT_LIST_SIZE: u16 = 25;
 pub const MIN_CHAT_LIST_SIZE: u16 = 10;
 pub const MAX_PROMPT_SIZE: u16 = 20;
 pub const MIN_PROMPT_SIZE: u16 = 3;
 
+pub mod auth_window;
 pub mod chat_list_window;
 pub mod chat_window;
 pub mod component_traits;
+pub mod confirmation;
 pub mod core_window;
+pub mod downloads_window;
+pub mod keymap_window;
+pub mod list_popup;
+pub mod member_list_window;
 pub mod prompt_window;
 pub mod reply_message;
+pub mod scheduled_messages_window;
+pub mod sessions_window;
 pub mod status_bar;
+pub mod sticker_picker;
 pub mod title_bar;
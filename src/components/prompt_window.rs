@@ -4,9 +4,14 @@ use crate::{
     component_name::ComponentName,
     components::component_traits::{Component, HandleFocus},
     event::Event,
-    tg::td_enums::TdMessageReplyToMessage,
+    tg::{
+        td_enums::TdMessageReplyToMessage,
+        tg_context::{MAX_CAPTION_LENGTH, MAX_MESSAGE_LENGTH},
+    },
+    utils::obscure_text,
 };
-use arboard::Clipboard;
+use arboard::{Clipboard, ImageData};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeDelta};
 use crossterm::event::KeyCode;
 use ratatui::{
     layout::{Position, Rect},
@@ -20,6 +25,7 @@ use ratatui::{
 };
 use std::{io, sync::Arc};
 use tokio::sync::mpsc::UnboundedSender;
+use unicode_width::UnicodeWidthChar;
 
 /// `DirSelection` is an enum that represents the direction of the selection.
 /// It is used to keep track of the direction of the selection when the user
@@ -60,6 +66,18 @@ struct InputCell {
     /// A flag indicating whether the input cell is selected or not.
     selected: bool,
 }
+/// Implement the `InputCell` struct.
+impl InputCell {
+    /// Get the number of columns of the character of the input cell when it
+    /// is displayed, e.g. 2 for the full-width characters and 0 for the
+    /// combining marks and the newline.
+    ///
+    /// # Returns
+    /// * `usize` - The display width of the character.
+    fn width(&self) -> usize {
+        self.c.width().unwrap_or(0)
+    }
+}
 /// `Input` is a struct that represents the input of a prompt.
 /// It is responsible for managing the input of the prompt.
 struct Input {
@@ -89,6 +107,9 @@ struct Input {
     /// The mode of the input.
     /// It is used to keep track of the mode of the input.
     mode: Mode,
+    /// The path of the image pasted from the clipboard.
+    /// The image is sent with the next message, whose text is the caption.
+    image_path: Option<String>,
 }
 /// Implement the `Input` struct.
 impl Input {
@@ -99,14 +120,45 @@ impl Input {
     fn set_command_tx(&mut self, command_tx: UnboundedSender<Action>) {
         self.action_tx = Some(command_tx);
     }
-    /// Get the cursor x position of the `Input` struct.
-    fn cursor_x(&self) -> usize {
-        self.cursor.0
-    }
     /// Get the cursor y position of the `Input` struct.
     fn cursor_y(&self) -> usize {
         self.cursor.1
     }
+    /// Get the column where the cursor is displayed, that is the display
+    /// width of the characters before the cursor in its line.
+    ///
+    /// # Returns
+    /// * `usize` - The display column of the cursor.
+    fn cursor_column(&self) -> usize {
+        self.text[self.cursor.1][..self.cursor.0]
+            .iter()
+            .map(InputCell::width)
+            .sum()
+    }
+    /// Get the index of the character of a line that is displayed at a
+    /// column, or the length of the line if it is shorter.
+    /// A wide character is found from any of its columns, and the combining
+    /// marks are skipped together with the character they modify.
+    ///
+    /// # Arguments
+    /// * `y` - The index of the line.
+    /// * `column` - The display column.
+    ///
+    /// # Returns
+    /// * `usize` - The index of the character.
+    fn index_at_column(&self, y: usize, column: usize) -> usize {
+        let line = &self.text[y];
+        let mut x = 0;
+        let mut width = 0;
+        while x < line.len() && width + line[x].width() <= column {
+            width += line[x].width();
+            x += 1;
+            while x < line.len() && line[x].width() == 0 && line[x].c != '\n' {
+                x += 1;
+            }
+        }
+        x
+    }
     /// Get the text of the `Input` struct.
     fn text(&mut self) -> &Vec<Vec<InputCell>> {
         &self.text
@@ -118,8 +170,10 @@ impl Input {
     /// # Arguments
     /// * `c` - The character to insert.
     fn insert(&mut self, c: char) {
-        // The -2 is to account the cursor at the end of the line.
-        if self.cursor.0 + 1 == (self.area_input.width - 2) as usize {
+        // The -3 is to account the borders and the cursor at the end of the
+        // line.
+        let max_width = self.area_input.width.saturating_sub(3) as usize;
+        if c != '\n' && self.cursor_column() + c.width().unwrap_or(0) > max_width {
             self.insert_newline();
         }
         self.text[self.cursor.1].insert(self.cursor.0, InputCell { c, selected: false });
@@ -173,37 +227,50 @@ impl Input {
         }
     }
     /// Move the cursor to the left.
+    /// The combining marks are skipped together with the character they
+    /// modify.
     fn move_cursor_left(&mut self) {
-        if self.cursor.0 > 0 {
+        let line = &self.text[self.cursor.1];
+        while self.cursor.0 > 0 {
             self.cursor.0 -= 1;
+            if line[self.cursor.0].width() > 0 || line[self.cursor.0].c == '\n' {
+                break;
+            }
         }
     }
     /// Move the cursor to the right.
+    /// The combining marks are skipped together with the character they
+    /// modify.
     fn move_cursor_right(&mut self) {
-        if self.cursor.0 < self.text[self.cursor.1].len() {
+        let line = &self.text[self.cursor.1];
+        if self.cursor.0 < line.len() {
             self.cursor.0 += 1;
+            while self.cursor.0 < line.len()
+                && line[self.cursor.0].width() == 0
+                && line[self.cursor.0].c != '\n'
+            {
+                self.cursor.0 += 1;
+            }
         }
     }
     /// Move the cursor up.
+    /// The cursor keeps its display column, or it is moved to the end of the
+    /// previous line if it is shorter.
     fn move_cursor_up(&mut self) {
         if self.cursor.1 > 0 {
-            // Handle moving cursor to the left if the current line is shorter
-            // than the previous line.
-            if self.cursor.0 > self.text[self.cursor.1 - 1].len() {
-                self.cursor.0 = self.text[self.cursor.1 - 1].len();
-            }
+            let column = self.cursor_column();
             self.cursor.1 -= 1;
+            self.cursor.0 = self.index_at_column(self.cursor.1, column);
         }
     }
     /// Move the cursor down.
+    /// The cursor keeps its display column, or it is moved to the end of the
+    /// next line if it is shorter.
     fn move_cursor_down(&mut self) {
         if self.cursor.1 < self.text.len() - 1 {
-            // Handle moving cursor to the left if the current line is shorter
-            // than the next line.
-            if self.cursor.0 > self.text[self.cursor.1 + 1].len() {
-                self.cursor.0 = self.text[self.cursor.1 + 1].len();
-            }
+            let column = self.cursor_column();
             self.cursor.1 += 1;
+            self.cursor.0 = self.index_at_column(self.cursor.1, column);
         }
     }
     /// Move the cursor to the previous word.
@@ -415,6 +482,74 @@ impl Input {
             clipboard.set_text(text).unwrap();
         }
     }
+    /// Paste the content of the clipboard into the `Input` struct.
+    /// If the clipboard holds an image, it is saved to a temporary file and
+    /// sent with the next message; otherwise the text is pasted.
+    fn paste_from_clipboard(&mut self) {
+        let Ok(mut clipboard) = Clipboard::new() else {
+            return;
+        };
+        // The image support is not available on every platform, in that case
+        // the text is pasted.
+        if let Ok(image) = clipboard.get_image() {
+            match Self::save_image(&image) {
+                Ok(path) => {
+                    self.discard_image();
+                    self.image_path = Some(path);
+                    return;
+                }
+                Err(e) => tracing::error!("Failed to save the pasted image: {}", e),
+            }
+        }
+        if let Ok(text) = clipboard.get_text() {
+            self.paste(text);
+        }
+    }
+    /// Save an image of the clipboard to a temporary PNG file.
+    ///
+    /// # Arguments
+    /// * `image` - The image of the clipboard.
+    ///
+    /// # Returns
+    /// * `Result<String, image::ImageError>` - The path of the file.
+    fn save_image(image: &ImageData) -> Result<String, image::ImageError> {
+        let path = std::env::temp_dir().join(format!(
+            "tgt-clipboard-{}.png",
+            chrono::Utc::now().timestamp_millis()
+        ));
+        image::save_buffer(
+            &path,
+            &image.bytes,
+            image.width as u32,
+            image.height as u32,
+            image::ExtendedColorType::Rgba8,
+        )?;
+        Ok(path.to_string_lossy().to_string())
+    }
+    /// Discard the image pasted from the clipboard, if any, deleting its
+    /// temporary file.
+    fn discard_image(&mut self) {
+        if let Some(path) = self.image_path.take() {
+            if let Err(e) = std::fs::remove_file(&path) {
+                tracing::error!("Failed to delete the pasted image {}: {}", path, e);
+            }
+        }
+    }
+    /// Get the event that sends a message with the given text.
+    /// If an image is ready to be sent, the text is its caption.
+    ///
+    /// # Arguments
+    /// * `text` - The text of the message.
+    /// * `reply_to` - The message that is being replied, if any.
+    ///
+    /// # Returns
+    /// * `Event` - The event that sends the message.
+    fn message_event(&mut self, text: String, reply_to: Option<TdMessageReplyToMessage>) -> Event {
+        match self.image_path.take() {
+            Some(path) => Event::SendPhoto(path, text, reply_to),
+            None => Event::SendMessage(text, reply_to),
+        }
+    }
     /// Paste text into the `Input` struct.
     /// The text is pasted at the current cursor position.
     fn paste(&mut self, text: String) {
@@ -449,18 +584,131 @@ impl Input {
         self.cursor = (0, 0);
     }
 
+    /// Write the command that sets the alias of a user, replacing the text.
+    /// The alias can be edited before sending the command.
+    ///
+    /// # Arguments
+    /// * `user_id` - The id of the user.
+    /// * `alias` - The current alias of the user, empty if it has none.
+    fn edit_alias(&mut self, user_id: i64, alias: String) {
+        self.mode = Mode::Normal;
+        self.text = vec![vec![]];
+        self.set_prompt_size_to_one_focused();
+        self.paste(format!(":alias {} {}", user_id, alias));
+    }
+
+    /// Quote a message in the `Input` struct.
+    /// Each line of the quoted message is prefixed with `> `, and the quote
+    /// is followed by an empty line, where the cursor is placed.
+    /// The quote can be edited before sending the message, it ends at the
+    /// first empty line.
+    ///
+    /// # Arguments
+    /// * `message` - The text of the message to quote.
+    fn quote_message(&mut self, message: String) {
+        self.text = vec![vec![]];
+        self.set_prompt_size_to_one_focused();
+        for line in message.split('\n') {
+            self.paste(format!("> {}\n", line));
+        }
+        self.insert_newline();
+    }
+    /// Stop replying to a message, removing the quote of a quote reply from
+    /// the text.
+    fn cancel_reply(&mut self) {
+        if !matches!(self.mode, Mode::Reply(_)) {
+            return;
+        }
+        self.mode = Mode::Normal;
+        if let Some((_, rest)) = self.split_quote() {
+            self.text = vec![vec![]];
+            self.set_prompt_size_to_one_focused();
+            self.paste(rest);
+        }
+    }
+    /// Split the text of the `Input` struct into the quote and the rest of the
+    /// message.
+    /// The quote is made of the leading lines up to the first empty line, if
+    /// the first line starts with `> `. The `> ` prefixes are removed.
+    /// If there is no empty line, e.g. because it was deleted, the quote is
+    /// made of the leading lines starting with `> `.
+    ///
+    /// # Returns
+    /// * `Option<(String, String)>` - The quote and the rest of the message, or
+    ///   `None` if the message does not start with a quote.
+    fn split_quote(&mut self) -> Option<(String, String)> {
+        let is_quote =
+            |line: &Vec<InputCell>| line.len() >= 2 && line[0].c == '>' && line[1].c == ' ';
+        if !self.text.first().is_some_and(is_quote) {
+            return None;
+        }
+        let is_empty = |line: &Vec<InputCell>| line.iter().all(|cell| cell.c == '\n');
+        let quote_lines = match self.text.iter().position(is_empty) {
+            Some(quote_lines) => quote_lines,
+            None => self
+                .text
+                .iter()
+                .position(|line| !is_quote(line))
+                .unwrap_or(self.text.len()),
+        };
+        let mut quote = String::new();
+        for line in &self.text[..quote_lines] {
+            let skip = if is_quote(line) { 2 } else { 0 };
+            line.iter().skip(skip).for_each(|cell| quote.push(cell.c));
+        }
+        let rest: String = self.text[quote_lines..]
+            .iter()
+            .flat_map(|line| line.iter().map(|cell| cell.c))
+            .collect();
+        Some((
+            quote.trim_end_matches('\n').to_string(),
+            rest.trim_start_matches('\n').to_string(),
+        ))
+    }
     /// Send a message.
     /// The message is sent to the main event loop for processing.
     ///
     /// # Arguments
     /// * `app_context` - An Arc wrapped AppContext struct.
     fn send_message(&mut self, app_context: Arc<AppContext>) {
+        // Telegram rejects the messages that are too long, the text messages
+        // are split by the backend if `split_long_messages` is enabled.
+        let (length, max_length) = (self.length(), self.max_length());
+        let split = app_context.app_config().split_long_messages
+            && self.image_path.is_none()
+            && !matches!(self.mode, Mode::Edit(_));
+        if length > max_length && !split {
+            app_context.tg_context().set_error_message(format!(
+                "The message is too long: {} characters, the limit is {}",
+                length, max_length
+            ));
+            return;
+        }
         if let Some(event_tx) = app_context.tg_context().event_tx().as_ref() {
             match self.mode {
                 Mode::Normal => {
-                    event_tx
-                        .send(Event::SendMessage(self.text_to_string(), None))
-                        .unwrap();
+                    let text = self.text_to_string();
+                    let chat_id = app_context.tg_context().open_chat_id();
+                    // The messages sent to a bot are never taken as prompt
+                    // commands.
+                    let is_bot_chat = app_context.tg_context().is_bot_chat(chat_id);
+                    let command = if is_bot_chat {
+                        None
+                    } else {
+                        Self::command_event(&text, chat_id, Local::now())
+                    };
+                    let event = match command {
+                        Some(Ok(event)) => event,
+                        Some(Err(error)) => {
+                            // The text is kept, so that the command can be
+                            // corrected.
+                            app_context.tg_context().set_error_message(error);
+                            return;
+                        }
+                        None if is_bot_chat => self.message_event(text, None),
+                        None => self.message_event(Self::unescape_command(text), None),
+                    };
+                    event_tx.send(event).unwrap();
                     self.text = vec![vec![]];
                     self.set_prompt_size_to_one_focused();
                 }
@@ -473,15 +721,30 @@ impl Input {
                     self.mode = Mode::Normal;
                 }
                 Mode::Reply(message_id) => {
-                    event_tx
-                        .send(Event::SendMessage(
-                            self.text_to_string(),
-                            Some(TdMessageReplyToMessage {
-                                chat_id: 0, // This must be  0 and not `app_context.tg_context().open_chat_id()` because the tdlib (maybe from the version 1.8.29 or before)  is able to know the chat id from the message_id; it will infer the chat id from the message_id.
-                                message_id,
-                            }),
-                        ))
-                        .unwrap();
+                    // The quote, if any, is sent as a block quote entity at the
+                    // beginning of the message.
+                    let text = match self.split_quote() {
+                        Some((quote, rest)) => {
+                            app_context
+                                .tg_context()
+                                .set_reply_quote_length(quote.encode_utf16().count() as i64);
+                            if rest.is_empty() {
+                                quote
+                            } else {
+                                format!("{}\n{}", quote, rest)
+                            }
+                        }
+                        None => self.text_to_string(),
+                    };
+                    let event = self.message_event(
+                        text,
+                        Some(TdMessageReplyToMessage {
+                            chat_id: 0, // This must be  0 and not `app_context.tg_context().open_chat_id()` because the tdlib (maybe from the version 1.8.29 or before)  is able to know the chat id from the message_id; it will infer the chat id from the message_id.
+                            message_id,
+                            quote: None,
+                        }),
+                    );
+                    event_tx.send(event).unwrap();
                     self.text = vec![vec![]];
                     self.set_prompt_size_to_one_focused();
                     self.mode = Mode::Normal;
@@ -489,6 +752,147 @@ impl Input {
             }
         }
     }
+    /// Remove the escape of a message that starts with `:`, written as `::`
+    /// so that it is not taken for a command, e.g. `::join us` is sent as
+    /// `:join us`.
+    ///
+    /// # Arguments
+    /// * `text` - The text of the prompt.
+    ///
+    /// # Returns
+    /// * `String` - The text of the message.
+    fn unescape_command(text: String) -> String {
+        match text.strip_prefix("::") {
+            Some(rest) => format!(":{}", rest),
+            None => text,
+        }
+    }
+    /// Get the event of a command written in the prompt, e.g. `:join @name`.
+    /// The commands start with `:`, so that they are not taken for the
+    /// commands of the bots, which start with `/`. A message starting with
+    /// `::` is not a command, see `unescape_command`.
+    ///
+    /// # Arguments
+    /// * `text` - The text of the prompt.
+    /// * `chat_id` - The id of the open chat.
+    /// * `now` - The current time, used by the times of `:schedule`.
+    ///
+    /// # Returns
+    /// * `Option<Result<Event, String>>` - The event of the command, or the
+    ///   error if the command is not valid, `None` if the text is not a
+    ///   command and it must be sent as a message.
+    fn command_event(
+        text: &str,
+        chat_id: i64,
+        now: DateTime<Local>,
+    ) -> Option<Result<Event, String>> {
+        let text = text.trim();
+        let (command, argument) = text.split_once(' ').unwrap_or((text, ""));
+        let argument = argument.trim();
+        let event = match command {
+            ":join" if !argument.is_empty() => Event::JoinChatByLink(argument.to_string()),
+            ":open" if !argument.is_empty() => Event::OpenByUsername(argument.to_string()),
+            ":theme" => Event::SetChatTheme((!argument.is_empty()).then(|| argument.to_string())),
+            ":alias" => {
+                let (user_id, alias) = argument.split_once(' ').unwrap_or((argument, ""));
+                let Ok(user_id) = user_id.parse() else {
+                    return Some(Err("Usage: :alias <user id> [alias]".to_string()));
+                };
+                let alias = alias.trim();
+                Event::SetAlias(user_id, (!alias.is_empty()).then(|| alias.to_string()))
+            }
+            ":schedule" => {
+                let Some((when, message)) = argument
+                    .split_once(' ')
+                    .filter(|(_, message)| !message.trim().is_empty())
+                else {
+                    return Some(Err("Usage: :schedule <time> <message>".to_string()));
+                };
+                let Some(send_date) = Self::parse_send_date(when, now) else {
+                    return Some(Err(format!("Invalid time: {}", when)));
+                };
+                if send_date <= now.timestamp() {
+                    return Some(Err(format!("The time {} is in the past", when)));
+                }
+                let Ok(send_date) = i32::try_from(send_date) else {
+                    return Some(Err(format!("The time {} is too far in the future", when)));
+                };
+                Event::SendScheduledMessage(chat_id, message.trim().to_string(), send_date)
+            }
+            _ => return None,
+        };
+        Some(Ok(event))
+    }
+    /// Parse the time a scheduled message is sent at.
+    /// The time is one of:
+    /// * `+<n>m`, `+<n>h` or `+<n>d`, the minutes, hours or days from now.
+    /// * `HH:MM`, today, or tomorrow if the time is already passed.
+    /// * `YYYY-MM-DDTHH:MM`, a date and a time.
+    /// * A Unix timestamp.
+    ///
+    /// # Arguments
+    /// * `when` - The time to parse.
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    /// * `Option<i64>` - The Unix timestamp of the time, `None` if it is not
+    ///   valid.
+    fn parse_send_date(when: &str, now: DateTime<Local>) -> Option<i64> {
+        if let Some(delay) = when.strip_prefix('+') {
+            let unit = delay.chars().last()?;
+            let amount: i64 = delay[..delay.len() - unit.len_utf8()].parse().ok()?;
+            let seconds = match unit {
+                'm' => 60,
+                'h' => 60 * 60,
+                'd' => 24 * 60 * 60,
+                _ => return None,
+            };
+            return now.timestamp().checked_add(amount.checked_mul(seconds)?);
+        }
+        if !when.is_empty() && when.chars().all(|c| c.is_ascii_digit()) {
+            return when.parse().ok();
+        }
+        let date = match NaiveTime::parse_from_str(when, "%H:%M") {
+            Ok(time) => {
+                let date = now.date_naive().and_time(time);
+                if date <= now.naive_local() {
+                    date + TimeDelta::days(1)
+                } else {
+                    date
+                }
+            }
+            Err(_) => NaiveDateTime::parse_from_str(when, "%Y-%m-%dT%H:%M").ok()?,
+        };
+        date.and_local_timezone(Local)
+            .earliest()
+            .map(|date| date.timestamp())
+    }
+    /// Get the length of the text of the `Input` struct as it is counted by
+    /// Telegram, that is in UTF-16 code units.
+    /// The newlines between the lines are kept at the end of the lines, so
+    /// they are counted with the characters, and an empty text has length 0.
+    ///
+    /// # Returns
+    /// * `usize` - The length of the text.
+    fn length(&self) -> usize {
+        self.text
+            .iter()
+            .flatten()
+            .map(|cell| cell.c.len_utf16())
+            .sum()
+    }
+    /// Get the maximum length of the text of the `Input` struct, that is the
+    /// maximum length of a caption if an image is sent with it.
+    ///
+    /// # Returns
+    /// * `usize` - The maximum length of the text.
+    fn max_length(&self) -> usize {
+        if self.image_path.is_some() {
+            MAX_CAPTION_LENGTH
+        } else {
+            MAX_MESSAGE_LENGTH
+        }
+    }
     /// Convert the text of the `Input` struct to a string.
     fn text_to_string(&mut self) -> String {
         // TODO: Parse into markdown
@@ -515,6 +919,7 @@ impl Default for Input {
             correct_prompt_size: 0,
             is_restored: true,
             mode: Mode::Normal,
+            image_path: None,
         }
     }
 }
@@ -586,6 +991,23 @@ impl PromptWindow {
             self.input.area_input = area_input;
         }
     }
+    /// Forward the messages chosen in another chat to the open chat, once
+    /// the user confirms them.
+    fn forward_messages(&self) {
+        let tg_context = self.app_context.tg_context();
+        let Some((from_chat_id, message_ids)) = tg_context.take_forward_messages() else {
+            return;
+        };
+        if let Some(event_tx) = tg_context.event_tx().as_ref() {
+            event_tx
+                .send(Event::ForwardMessages(
+                    tg_context.open_chat_id(),
+                    from_chat_id,
+                    message_ids,
+                ))
+                .unwrap();
+        };
+    }
 }
 
 /// Implement the `HandleFocus` trait for the `PromptWindow` struct.
@@ -596,10 +1018,16 @@ impl HandleFocus for PromptWindow {
         self.focused = true;
     }
     /// Set the `focused` flag for the `PromptWindow`.
+    /// The messages to forward are canceled if the prompt is left without
+    /// confirming them.
     fn unfocus(&mut self) {
+        if self.focused {
+            self.app_context.tg_context().cancel_forward_messages();
+        }
         self.focused = false;
         self.input.mode = Mode::Normal;
         self.input.text = vec![vec![]];
+        self.input.discard_image();
     }
 }
 
@@ -703,11 +1131,7 @@ impl Component for PromptWindow {
                 }
 
                 (KeyCode::Char('v'), Modifiers { control: true, .. }) => {
-                    if let Ok(mut clipboard) = Clipboard::new() {
-                        if let Ok(text) = clipboard.get_text() {
-                            self.input.paste(text);
-                        }
-                    }
+                    self.input.paste_from_clipboard();
                 }
 
                 (KeyCode::Left | KeyCode::Char('b'), Modifiers { control: true, .. }) => {
@@ -722,7 +1146,15 @@ impl Component for PromptWindow {
 
                 (KeyCode::Enter, Modifiers { alt: true, .. }) => {
                     self.input.unselect_all();
-                    self.input.send_message(Arc::clone(&self.app_context));
+                    // The text, if any, is sent before the messages to
+                    // forward, as a comment.
+                    let forwarding = self.app_context.tg_context().forward_messages_count() > 0;
+                    if !forwarding || self.input.length() > 0 {
+                        self.input.send_message(Arc::clone(&self.app_context));
+                    }
+                    if forwarding {
+                        self.forward_messages();
+                    }
                 }
 
                 (KeyCode::Backspace, Modifiers { control: true, .. })
@@ -790,9 +1222,19 @@ impl Component for PromptWindow {
             Action::EditMessage(message_id, message) => {
                 self.input.edit_message(message_id, message);
             }
+            Action::EditAlias(user_id, alias) => {
+                self.input.edit_alias(user_id, alias);
+            }
             Action::ReplyMessage(message_id, _) => {
                 self.input.mode = Mode::Reply(message_id);
             }
+            Action::QuoteReplyMessage(message_id, message) => {
+                self.input.mode = Mode::Reply(message_id);
+                self.input.quote_message(message);
+            }
+            Action::CancelReply => {
+                self.input.cancel_reply();
+            }
             _ => {}
         }
     }
@@ -806,24 +1248,27 @@ impl Component for PromptWindow {
             bottom_left: NORMAL.horizontal_up,
             ..PLAIN
         };
+        // The consecutive cells with the same style are drawn in the same span,
+        // so that the combining marks are drawn with their character.
         let text = self
             .input
             .text()
             .iter()
             .map(|line| {
                 Line::from(
-                    line.iter()
-                        .map(|cell| {
-                            if cell.selected {
+                    line.chunk_by(|a, b| a.selected == b.selected)
+                        .map(|cells| {
+                            let mut content = cells.iter().map(|cell| cell.c).collect::<String>();
+                            if self.app_context.privacy_mode() {
+                                content = obscure_text(&content);
+                            }
+                            if cells[0].selected {
                                 Span::styled(
-                                    cell.c.to_string(),
+                                    content,
                                     self.app_context.style_prompt_message_text_selected(),
                                 )
                             } else {
-                                Span::styled(
-                                    cell.c.to_string(),
-                                    self.app_context.style_prompt_message_text(),
-                                )
+                                Span::styled(content, self.app_context.style_prompt_message_text())
                             }
                         })
                         .collect::<Vec<Span>>(),
@@ -854,11 +1299,39 @@ impl Component for PromptWindow {
             )
         };
 
-        let block = Block::new()
+        let mut block = Block::new()
             .border_set(collapsed_top_and_left_border_set)
             .border_style(style_border_focused)
             .borders(Borders::ALL)
             .title(self.name.as_str());
+        if self.input.image_path.is_some() {
+            block = block.title_top(Line::from(" 1 image ready to send ").right_aligned());
+        }
+        let forward_count = self.app_context.tg_context().forward_messages_count();
+        if forward_count > 0 {
+            let title = if self.focused {
+                format!(
+                    " Forwarding {} messages, alt+enter to send, esc to cancel ",
+                    forward_count
+                )
+            } else {
+                format!(" Forwarding {} messages ", forward_count)
+            };
+            block = block.title_top(Line::from(title).right_aligned());
+        }
+        // The length of the message is shown in red when it is close to the
+        // limit of Telegram.
+        let (length, max_length) = (self.input.length(), self.input.max_length());
+        if self.focused && length > 0 {
+            let style = if length * 10 >= max_length * 9 {
+                self.app_context.style_prompt_message_length_limit()
+            } else {
+                self.app_context.style_prompt_message_preview_text()
+            };
+            block = block.title_bottom(
+                Line::styled(format!(" {}/{} ", length, max_length), style).right_aligned(),
+            );
+        }
 
         let input = Paragraph::new(text).style(style_text).block(block);
 
@@ -866,10 +1339,223 @@ impl Component for PromptWindow {
 
         if self.focused {
             frame.set_cursor_position(Position {
-                x: area.x + self.input.cursor_x() as u16 + 1,
+                x: area.x + self.input.cursor_column() as u16 + 1,
                 y: area.y + self.input.cursor_y() as u16 + 1,
             });
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn input_with(text: &str) -> Input {
+        let mut input = Input {
+            area_input: Rect::new(0, 0, 40, 3),
+            ..Default::default()
+        };
+        text.chars().for_each(|c| input.insert(c));
+        input
+    }
+
+    #[test]
+    fn test_cancel_reply_removes_quote() {
+        let mut input = input_with("");
+        input.mode = Mode::Reply(1);
+        input.quote_message("quoted".to_string());
+        "reply".chars().for_each(|c| input.insert(c));
+        input.cancel_reply();
+        assert!(matches!(input.mode, Mode::Normal));
+        assert_eq!(input.text_to_string(), "reply");
+    }
+
+    #[test]
+    fn test_split_quote() {
+        let mut input = input_with("");
+        input.quote_message("first\nsecond".to_string());
+        "reply".chars().for_each(|c| input.insert(c));
+        assert_eq!(
+            input.split_quote(),
+            Some(("first\nsecond".to_string(), "reply".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_split_quote_without_separator() {
+        let mut input = input_with("");
+        input.paste("> first\n> second\nreply\nmore".to_string());
+        assert_eq!(
+            input.split_quote(),
+            Some(("first\nsecond".to_string(), "reply\nmore".to_string()))
+        );
+
+        let mut input = input_with("");
+        input.paste("> only quote".to_string());
+        assert_eq!(
+            input.split_quote(),
+            Some(("only quote".to_string(), "".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_split_quote_without_quote() {
+        let mut input = input_with("no quote\n> not a quote");
+        assert_eq!(input.split_quote(), None);
+    }
+
+    #[test]
+    fn test_cursor_column_full_width() {
+        let mut input = input_with("日本語");
+        assert_eq!(input.cursor, (3, 0));
+        assert_eq!(input.cursor_column(), 6);
+        input.move_cursor_left();
+        assert_eq!(input.cursor_column(), 4);
+    }
+
+    #[test]
+    fn test_cursor_column_emoji() {
+        let mut input = input_with("a😀b");
+        assert_eq!(input.cursor_column(), 4);
+        input.move_cursor_left();
+        input.move_cursor_left();
+        assert_eq!(input.cursor, (1, 0));
+        assert_eq!(input.cursor_column(), 1);
+    }
+
+    #[test]
+    fn test_cursor_skips_combining_marks() {
+        // An "e" followed by a combining acute accent.
+        let mut input = input_with("e\u{301}x");
+        assert_eq!(input.cursor_column(), 2);
+        input.move_cursor_left();
+        assert_eq!(input.cursor, (2, 0));
+        input.move_cursor_left();
+        assert_eq!(input.cursor, (0, 0));
+        input.move_cursor_right();
+        assert_eq!(input.cursor, (2, 0));
+        assert_eq!(input.cursor_column(), 1);
+    }
+
+    #[test]
+    fn test_move_cursor_vertically_keeps_column() {
+        let mut input = input_with("日本語");
+        input.insert_newline();
+        "abcdef".chars().for_each(|c| input.insert(c));
+        input.move_cursor_left();
+        input.move_cursor_left();
+        input.move_cursor_up();
+        assert_eq!(input.cursor, (2, 0));
+        input.move_cursor_down();
+        assert_eq!(input.cursor, (4, 1));
+        // The column in the middle of a full-width character selects it.
+        input.move_cursor_left();
+        input.move_cursor_up();
+        assert_eq!(input.cursor, (1, 0));
+        assert_eq!(input.cursor_column(), 2);
+    }
+
+    #[test]
+    fn test_insert_wraps_by_display_width() {
+        let mut input = Input {
+            area_input: Rect::new(0, 0, 8, 3),
+            ..Default::default()
+        };
+        "日本語".chars().for_each(|c| input.insert(c));
+        // Only two full-width characters fit in the 5 columns of a line.
+        assert_eq!(input.text.len(), 2);
+        assert_eq!(input.cursor, (1, 1));
+        assert_eq!(input.cursor_column(), 2);
+    }
+
+    #[test]
+    fn test_length_in_utf16_code_units() {
+        assert_eq!(input_with("").length(), 0);
+        let mut input = input_with("a😀日");
+        assert_eq!(input.length(), 4);
+        input.insert_newline();
+        input.insert_newline();
+        // The newlines between the three lines.
+        assert_eq!(input.length(), 6);
+        assert_eq!(input.max_length(), MAX_MESSAGE_LENGTH);
+        input.image_path = Some("image.png".to_string());
+        assert_eq!(input.max_length(), MAX_CAPTION_LENGTH);
+    }
+
+    #[test]
+    fn test_parse_send_date() {
+        let now = Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let at = |day, hour, minute| {
+            Local
+                .with_ymd_and_hms(2025, 1, day, hour, minute, 0)
+                .unwrap()
+                .timestamp()
+        };
+        let parse = |when| Input::parse_send_date(when, now);
+        assert_eq!(parse("+30m"), Some(now.timestamp() + 30 * 60));
+        assert_eq!(parse("+2h"), Some(now.timestamp() + 2 * 60 * 60));
+        assert_eq!(parse("+1d"), Some(at(2, 12, 0)));
+        assert_eq!(parse("18:30"), Some(at(1, 18, 30)));
+        // The time is already passed today.
+        assert_eq!(parse("09:15"), Some(at(2, 9, 15)));
+        assert_eq!(parse("2025-01-05T08:00"), Some(at(5, 8, 0)));
+        assert_eq!(parse("1735732800"), Some(1735732800));
+        assert_eq!(parse("+5s"), None);
+        assert_eq!(parse("+m"), None);
+        assert_eq!(parse("tomorrow"), None);
+        assert_eq!(parse(""), None);
+    }
+
+    #[test]
+    fn test_command_event_schedule() {
+        let now = Local.with_ymd_and_hms(2025, 1, 1, 12, 0, 0).unwrap();
+        let command = |text| Input::command_event(text, 42, now);
+        match command(":schedule +1h see you later") {
+            Some(Ok(Event::SendScheduledMessage(42, message, send_date))) => {
+                assert_eq!(message, "see you later");
+                assert_eq!(i64::from(send_date), now.timestamp() + 60 * 60);
+            }
+            _ => panic!("the message is not scheduled"),
+        }
+        assert!(matches!(command(":schedule +1h"), Some(Err(_))));
+        assert!(matches!(command(":schedule soon hello"), Some(Err(_))));
+        assert!(matches!(command(":schedule 1000 hello"), Some(Err(_))));
+        // The time does not fit the date of Telegram.
+        assert!(matches!(
+            command(":schedule 4102444800 hello"),
+            Some(Err(_))
+        ));
+        assert!(command("hello").is_none());
+        // The commands of the bots are sent as messages.
+        assert!(command("/schedule +1h see you later").is_none());
+    }
+
+    #[test]
+    fn test_command_escape() {
+        let command = |text| Input::command_event(text, 42, Local::now());
+        assert!(command("::join @tgt").is_none());
+        assert!(command("::alias 7 Alice").is_none());
+        assert_eq!(
+            Input::unescape_command("::join @tgt".to_string()),
+            ":join @tgt"
+        );
+        assert_eq!(Input::unescape_command(":)".to_string()), ":)");
+        assert_eq!(Input::unescape_command("hello".to_string()), "hello");
+    }
+
+    #[test]
+    fn test_command_event_alias() {
+        let command = |text| Input::command_event(text, 42, Local::now());
+        assert!(matches!(
+            command(":alias 7 Alice from work"),
+            Some(Ok(Event::SetAlias(7, Some(alias)))) if alias == "Alice from work"
+        ));
+        assert!(matches!(
+            command(":alias 7"),
+            Some(Ok(Event::SetAlias(7, None)))
+        ));
+        assert!(matches!(command(":alias alice Alice"), Some(Err(_))));
+    }
+}
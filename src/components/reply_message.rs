@@ -2,7 +2,9 @@ use {
     crate::{
         action::Action,
         app_context::AppContext,
+        component_name::ComponentName,
         components::component_traits::{Component, HandleFocus},
+        utils::obscure_line,
     },
     ratatui::{
         layout::Rect,
@@ -19,6 +21,8 @@ use {
 
 /// `ReplyMessage` is a struct that represents a window for replying to messages.
 /// It is responsible for managing the layout and rendering of the reply message window.
+/// It shows the sender and the content of the message that is being replied,
+/// above the prompt, until the reply is sent or canceled.
 pub struct ReplyMessage {
     /// The application configuration.
     app_context: Arc<AppContext>,
@@ -76,11 +80,57 @@ impl Component for ReplyMessage {
     }
 
     fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> io::Result<()> {
+        let tg_context = self.app_context.tg_context();
+        let message_id = tg_context.reply_message_id();
+        let message = tg_context
+            .open_chat_messages()
+            .iter()
+            .find(|message| message.id() == message_id)
+            .cloned();
+        let text_style = self.app_context.style_reply_message_message_text();
         let mut text = Text::default();
-        text.extend(vec![Line::from(vec![Span::styled(
-            (*self.app_context.tg_context().reply_message_text()).to_string(),
-            self.app_context.style_reply_message_message_text(),
-        )])]);
+        match message {
+            Some(message) => {
+                text.extend(vec![Line::from(vec![
+                    Span::styled("↩️ Reply to: ", text_style),
+                    Span::styled(
+                        tg_context
+                            .try_name_from_chats_or_users(message.sender_id())
+                            .unwrap_or_default(),
+                        self.app_context.style_reply_message(),
+                    ),
+                ])]);
+                let mut lines = message.get_lines_styled_with_style(
+                    text_style,
+                    self.app_context.style_code_block(),
+                    area.width.saturating_sub(2) as i32,
+                );
+                if self.app_context.privacy_mode() {
+                    lines.iter_mut().for_each(obscure_line);
+                }
+                text.extend(lines);
+            }
+            // The message is not loaded, e.g. it has been deleted.
+            None => {
+                let mut line = Line::from(vec![Span::styled(
+                    (*tg_context.reply_message_text()).to_string(),
+                    text_style,
+                )]);
+                if self.app_context.privacy_mode() {
+                    obscure_line(&mut line);
+                }
+                text.extend(vec![line]);
+            }
+        }
+        let title = match self
+            .app_context
+            .keymap_config()
+            .get_key_of_single_action(ComponentName::CoreWindow, Action::CancelReply)
+            .first()
+        {
+            Some(key) => format!("{} ({}: cancel)", self.name, key),
+            None => self.name.clone(),
+        };
 
         let collapsed_border = Set {
             top_left: NORMAL.vertical_right,
@@ -93,7 +143,7 @@ impl Component for ReplyMessage {
         let block = Block::new()
             .border_set(collapsed_border)
             .borders(Borders::RIGHT | Borders::LEFT | Borders::TOP)
-            .title("Reply Message")
+            .title(title)
             .border_style(self.app_context.style_reply_message())
             .style(self.app_context.style_reply_message());
 
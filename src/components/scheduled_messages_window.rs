@@ -0,0 +1,238 @@
+use crate::{
+    action::Action,
+    app_context::AppContext,
+    components::{
+        component_traits::{Component, HandleFocus},
+        list_popup::ListPopup,
+    },
+    event::Event,
+    tg::message_entry::{DateTimeEntry, MessageEntry},
+};
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span, Text},
+    widgets::{List, Paragraph},
+};
+use std::{io, sync::Arc};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// `ScheduledMessagesWindow` is a struct that represents a popup listing the
+/// scheduled messages of the open chat, with their send times.
+/// The selected message can be edited, deleted or sent immediately.
+pub struct ScheduledMessagesWindow {
+    /// The application context.
+    app_context: Arc<AppContext>,
+    /// The name of the `ScheduledMessagesWindow`.
+    name: String,
+    /// Indicates whether the `ScheduledMessagesWindow` is focused or not.
+    focused: bool,
+    /// The id of the chat whose scheduled messages are shown.
+    chat_id: i64,
+    /// The list of the scheduled messages.
+    popup: ListPopup,
+}
+
+impl ScheduledMessagesWindow {
+    /// Create a new instance of the `ScheduledMessagesWindow` struct.
+    ///
+    /// # Arguments
+    /// * `app_context` - An Arc wrapped AppContext struct.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `ScheduledMessagesWindow` struct.
+    pub fn new(app_context: Arc<AppContext>) -> Self {
+        let name = "".to_string();
+        let focused = false;
+        let chat_id = 0;
+        let popup = ListPopup::new(Arc::clone(&app_context), Action::HideScheduledMessages);
+
+        ScheduledMessagesWindow {
+            app_context,
+            name,
+            focused,
+            chat_id,
+            popup,
+        }
+    }
+    /// Set the name of the `ScheduledMessagesWindow`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the `ScheduledMessagesWindow`.
+    ///
+    /// # Returns
+    /// * `Self` - The modified instance of the `ScheduledMessagesWindow`.
+    pub fn with_name(mut self, name: impl AsRef<str>) -> Self {
+        self.name = name.as_ref().to_string();
+        self
+    }
+
+    /// Get the id and the text of the selected scheduled message.
+    ///
+    /// # Returns
+    /// * `Option<(i64, String)>` - The id and the text of the message, `None`
+    ///   if no message is selected.
+    fn selected(&self) -> Option<(i64, String)> {
+        let index = self.popup.selected()?;
+        self.app_context
+            .tg_context()
+            .scheduled_messages()
+            .get(index)
+            .map(|entry| {
+                (
+                    entry.message.id(),
+                    entry.message.message_content_to_string(),
+                )
+            })
+    }
+
+    /// Send an event to the backend.
+    ///
+    /// # Arguments
+    /// * `event` - The event to send.
+    fn send_event(&self, event: Event) {
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx.send(event).unwrap();
+        }
+    }
+
+    /// Edit the selected message in the prompt, hiding the
+    /// `ScheduledMessagesWindow`.
+    fn edit_selected(&self) {
+        if let Some((message_id, text)) = self.selected() {
+            self.popup.hide();
+            self.send_event(Event::EditMessage(message_id, text));
+        }
+    }
+
+    /// Delete the selected message and load the scheduled messages again.
+    fn delete_selected(&self) {
+        if let Some((message_id, _)) = self.selected() {
+            self.send_event(Event::DeleteMessages(vec![message_id], true));
+            self.send_event(Event::LoadScheduledMessages(self.chat_id));
+        }
+    }
+
+    /// Send the selected message immediately and load the scheduled messages
+    /// again.
+    fn send_selected_now(&self) {
+        if let Some((message_id, _)) = self.selected() {
+            self.send_event(Event::SendScheduledMessageNow(self.chat_id, message_id));
+            self.send_event(Event::LoadScheduledMessages(self.chat_id));
+        }
+    }
+
+    /// Move the selection by a number of messages.
+    ///
+    /// # Arguments
+    /// * `delta` - The number of messages to move, negative to move up.
+    fn move_selection(&mut self, delta: isize) {
+        let len = self.app_context.tg_context().scheduled_messages().len();
+        self.popup.move_selection(len, delta);
+    }
+
+    /// Get the text of a scheduled message in the list: the send time
+    /// followed by the content of the message.
+    ///
+    /// # Arguments
+    /// * `send_date` - The Unix time the message is sent at.
+    /// * `message` - The message.
+    /// * `wrap_width` - The width the content is wrapped at.
+    ///
+    /// # Returns
+    /// * `Text` - The text of the message.
+    fn entry_text(&self, send_date: i32, message: &MessageEntry, wrap_width: i32) -> Text<'static> {
+        let send_time = if send_date == 0 {
+            "When online".to_string()
+        } else {
+            DateTimeEntry::convert_time(send_date)
+        };
+        let mut text = Text::from(Line::from(Span::styled(
+            format!("📅 {}", send_time),
+            self.app_context.style_chat_message_myself_name(),
+        )));
+        text.extend(message.get_lines_styled_with_style(
+            self.app_context.style_chat_message_myself_content(),
+            self.app_context.style_code_block(),
+            wrap_width,
+        ));
+        text
+    }
+}
+
+/// Implement the `HandleFocus` trait for the `ScheduledMessagesWindow` struct.
+/// This trait allows the `ScheduledMessagesWindow` to be focused or
+/// unfocused.
+impl HandleFocus for ScheduledMessagesWindow {
+    /// Set the `focused` flag for the `ScheduledMessagesWindow`, and load the
+    /// scheduled messages of the open chat.
+    fn focus(&mut self) {
+        self.focused = true;
+        self.chat_id = self.app_context.tg_context().open_chat_id();
+        self.popup.select(Some(0));
+        self.app_context.tg_context().scheduled_messages().clear();
+        self.send_event(Event::LoadScheduledMessages(self.chat_id));
+    }
+    /// Set the `focused` flag for the `ScheduledMessagesWindow`.
+    fn unfocus(&mut self) {
+        self.focused = false;
+    }
+}
+
+/// Implement the `Component` trait for the `ScheduledMessagesWindow` struct.
+impl Component for ScheduledMessagesWindow {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> io::Result<()> {
+        self.popup.register_action_handler(tx);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) {
+        if let Action::Key(key, _) = action {
+            match key {
+                KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+                KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+                KeyCode::Char('e') => self.edit_selected(),
+                KeyCode::Char('d') => self.delete_selected(),
+                KeyCode::Char('s') => self.send_selected_now(),
+                KeyCode::Esc | KeyCode::Char('q') => self.popup.hide(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Draw the `ScheduledMessagesWindow` in the middle of the area.
+    fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> io::Result<()> {
+        let inner_area = self.popup.draw_block(
+            frame,
+            area,
+            (Constraint::Percentage(80), Constraint::Percentage(70)),
+            self.name.as_str(),
+        );
+        let [list_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner_area);
+
+        let tg_context = self.app_context.tg_context();
+        let scheduled_messages = tg_context.scheduled_messages();
+        if scheduled_messages.is_empty() {
+            frame.render_widget(Paragraph::new("No scheduled messages"), list_area);
+        } else {
+            self.popup.clamp_selection(scheduled_messages.len());
+            let items = scheduled_messages.iter().map(|entry| {
+                self.entry_text(entry.send_date, &entry.message, list_area.width as i32)
+            });
+            let list =
+                List::new(items).highlight_style(self.app_context.style_chat_message_selected());
+            frame.render_stateful_widget(list, list_area, &mut self.popup.list_state);
+        }
+
+        let help = self.popup.help_line(&[
+            ("e", "edit"),
+            ("d", "delete"),
+            ("s", "send now"),
+            ("Esc", "close"),
+        ]);
+        frame.render_widget(Paragraph::new(help), help_area);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+use crate::{
+    action::Action,
+    app_context::AppContext,
+    components::{
+        component_traits::{Component, HandleFocus},
+        list_popup::ListPopup,
+    },
+    event::Event,
+    tg::{message_entry::DateTimeEntry, tg_context::TgContext},
+    utils::obscure_text,
+};
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    text::{Line, Span, Text},
+    widgets::{List, Paragraph},
+};
+use std::{io, sync::Arc};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// `SessionsWindow` is a struct that represents a popup showing the account
+/// of the user, with its name, phone number and id, followed by the list of
+/// its active sessions.
+/// The other sessions can be terminated, after a confirmation.
+pub struct SessionsWindow {
+    /// The application context.
+    app_context: Arc<AppContext>,
+    /// The name of the `SessionsWindow`.
+    name: String,
+    /// Indicates whether the `SessionsWindow` is focused or not.
+    focused: bool,
+    /// The list of the sessions.
+    popup: ListPopup,
+    /// The id and the description of the session to terminate, `None` if
+    /// the confirmation is not shown.
+    terminate_confirmation: Option<(i64, String)>,
+}
+
+impl SessionsWindow {
+    /// Create a new instance of the `SessionsWindow` struct.
+    ///
+    /// # Arguments
+    /// * `app_context` - An Arc wrapped AppContext struct.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `SessionsWindow` struct.
+    pub fn new(app_context: Arc<AppContext>) -> Self {
+        let name = "".to_string();
+        let focused = false;
+        let popup = ListPopup::new(Arc::clone(&app_context), Action::HideSessions);
+        let terminate_confirmation = None;
+
+        SessionsWindow {
+            app_context,
+            name,
+            focused,
+            popup,
+            terminate_confirmation,
+        }
+    }
+    /// Set the name of the `SessionsWindow`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the `SessionsWindow`.
+    ///
+    /// # Returns
+    /// * `Self` - The modified instance of the `SessionsWindow`.
+    pub fn with_name(mut self, name: impl AsRef<str>) -> Self {
+        self.name = name.as_ref().to_string();
+        self
+    }
+
+    /// Send an event to the backend.
+    ///
+    /// # Arguments
+    /// * `event` - The event to send.
+    fn send_event(&self, event: Event) {
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx.send(event).unwrap();
+        }
+    }
+
+    /// Move the selection by a number of sessions.
+    ///
+    /// # Arguments
+    /// * `delta` - The number of sessions to move, negative to move up.
+    fn move_selection(&mut self, delta: isize) {
+        let len = self.app_context.tg_context().sessions().len();
+        self.popup.move_selection(len, delta);
+    }
+
+    /// Ask the confirmation to terminate the selected session.
+    /// The current session can not be terminated, the user can log out
+    /// instead.
+    fn terminate_selected(&mut self) {
+        let Some(index) = self.popup.selected() else {
+            return;
+        };
+        let tg_context = self.app_context.tg_context();
+        let Some(session) = tg_context.sessions().get(index).cloned() else {
+            return;
+        };
+        if session.is_current {
+            tg_context
+                .set_info_message("The current session can not be terminated, log out instead");
+            return;
+        }
+        self.terminate_confirmation = Some((session.id, TgContext::session_description(&session)));
+    }
+
+    /// Terminate the session of the confirmation.
+    fn confirm_terminate(&mut self) {
+        if let Some((session_id, _)) = self.terminate_confirmation.take() {
+            self.send_event(Event::TerminateSession(session_id));
+        }
+    }
+
+    /// Get the text of the account of the user: the name followed by the
+    /// username, the phone number and the id.
+    /// The phone number is obscured in privacy mode.
+    ///
+    /// # Returns
+    /// * `Text` - The text of the account.
+    fn account_text(&self) -> Text<'static> {
+        let tg_context = self.app_context.tg_context();
+        let me = tg_context.me();
+        let users = tg_context.users();
+        let Some(user) = users.get(&me) else {
+            return Text::from("Loading the account...");
+        };
+        let name_style = self.app_context.style_chat_list_item_chat_name();
+        let text_style = self.app_context.style_chat_list_item_message_content();
+        let mut name = format!("{} {}", user.first_name, user.last_name)
+            .trim()
+            .to_string();
+        if let Some(username) = user
+            .usernames
+            .as_ref()
+            .and_then(|usernames| usernames.active_usernames.first())
+        {
+            name.push_str(&format!(" @{}", username));
+        }
+        let phone = format!("+{}", user.phone_number);
+        let phone = if self.app_context.privacy_mode() {
+            obscure_text(&phone)
+        } else {
+            phone
+        };
+        Text::from(vec![
+            Line::from(Span::styled(name, name_style)),
+            Line::from(vec![
+                Span::styled("Phone: ", text_style),
+                Span::styled(phone, name_style),
+                Span::styled("  Id: ", text_style),
+                Span::styled(me.to_string(), name_style),
+            ]),
+        ])
+    }
+}
+
+/// Implement the `HandleFocus` trait for the `SessionsWindow` struct.
+/// This trait allows the `SessionsWindow` to be focused or unfocused.
+impl HandleFocus for SessionsWindow {
+    /// Set the `focused` flag for the `SessionsWindow`, and load the active
+    /// sessions of the account.
+    fn focus(&mut self) {
+        self.focused = true;
+        self.terminate_confirmation = None;
+        self.popup.select(Some(0));
+        self.app_context.tg_context().sessions().clear();
+        self.send_event(Event::LoadSessions);
+    }
+    /// Set the `focused` flag for the `SessionsWindow`.
+    fn unfocus(&mut self) {
+        self.focused = false;
+    }
+}
+
+/// Implement the `Component` trait for the `SessionsWindow` struct.
+impl Component for SessionsWindow {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> io::Result<()> {
+        self.popup.register_action_handler(tx);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) {
+        // The other actions are ignored, they are sent also for the keys of
+        // the panel.
+        let Action::Key(key, _) = action else {
+            return;
+        };
+        if self.terminate_confirmation.is_some() {
+            match key {
+                KeyCode::Char('y') => self.confirm_terminate(),
+                KeyCode::Char('n') | KeyCode::Esc => self.terminate_confirmation = None,
+                _ => {}
+            }
+            return;
+        }
+        match key {
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Char('t') => self.terminate_selected(),
+            KeyCode::Char('r') => self.send_event(Event::LoadSessions),
+            KeyCode::Esc | KeyCode::Char('q') => self.popup.hide(),
+            _ => {}
+        }
+    }
+
+    /// Draw the `SessionsWindow` in the middle of the area.
+    fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> io::Result<()> {
+        let inner_area = self.popup.draw_block(
+            frame,
+            area,
+            (Constraint::Percentage(70), Constraint::Percentage(70)),
+            self.name.as_str(),
+        );
+        let [account_area, title_area, list_area, help_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner_area);
+
+        frame.render_widget(Paragraph::new(self.account_text()), account_area);
+
+        let name_style = self.app_context.style_chat_list_item_chat_name();
+        let text_style = self.app_context.style_chat_list_item_message_content();
+        let tg_context = self.app_context.tg_context();
+        let sessions = tg_context.sessions();
+        frame.render_widget(
+            Paragraph::new(Span::styled(
+                format!("Active sessions ({})", sessions.len()),
+                name_style,
+            )),
+            title_area,
+        );
+        if sessions.is_empty() {
+            frame.render_widget(Paragraph::new("Loading the sessions..."), list_area);
+        } else {
+            self.popup.clamp_selection(sessions.len());
+            let items = sessions.iter().map(|session| {
+                let last_active = if session.is_current {
+                    "This device".to_string()
+                } else {
+                    format!(
+                        "Last active {}",
+                        DateTimeEntry::convert_time(session.last_active_date)
+                    )
+                };
+                let ip_address = if self.app_context.privacy_mode() {
+                    obscure_text(&session.ip_address)
+                } else {
+                    session.ip_address.clone()
+                };
+                Text::from(vec![
+                    Line::from(Span::styled(
+                        TgContext::session_description(session),
+                        name_style,
+                    )),
+                    Line::from(Span::styled(
+                        format!("  {}, IP {}", last_active, ip_address),
+                        text_style,
+                    )),
+                ])
+            });
+            let list =
+                List::new(items).highlight_style(self.app_context.style_chat_message_selected());
+            frame.render_stateful_widget(list, list_area, &mut self.popup.list_state);
+        }
+
+        let key_style = self.app_context.style_status_bar_press_key_key();
+        let text_style = self.app_context.style_status_bar_press_key_text();
+        let help = match &self.terminate_confirmation {
+            Some((_, description)) => Line::from(vec![
+                Span::styled(
+                    format!("Terminate the session {}? ", description),
+                    text_style,
+                ),
+                Span::styled("y", key_style),
+                Span::styled(": yes, ", text_style),
+                Span::styled("n", key_style),
+                Span::styled(": no", text_style),
+            ]),
+            None => self
+                .popup
+                .help_line(&[("t", "terminate"), ("r", "reload"), ("Esc", "close")]),
+        };
+        frame.render_widget(Paragraph::new(help), help_area);
+
+        Ok(())
+    }
+}
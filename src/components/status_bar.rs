@@ -4,13 +4,16 @@ use {
         app_context::AppContext,
         components::component_traits::{Component, HandleFocus},
         event::Event,
+        tg::tg_context::TgContext,
     },
+    chrono::Local,
     ratatui::{
         layout::{Alignment, Rect},
         text::{Line, Span},
         widgets::{block::Block, Borders, Paragraph, Wrap},
     },
     std::sync::Arc,
+    tdlib_rs::enums::ConnectionState,
     tokio::sync::mpsc::UnboundedSender,
 };
 
@@ -104,7 +107,38 @@ impl Component for StatusBar {
             .tg_context()
             .name_of_open_chat_id()
             .unwrap_or_default();
-        let text = vec![Line::from(vec![
+        let presence = if self.app_context.tg_context().is_online() {
+            "Online"
+        } else {
+            "Offline"
+        };
+        let presence = if self.app_context.do_not_disturb() {
+            format!("{} (do not disturb)", presence)
+        } else {
+            presence.to_string()
+        };
+        let (connection, connection_style) =
+            match self.app_context.tg_context().connection_state().as_ref() {
+                Some(ConnectionState::Ready) => (
+                    TgContext::connection_state_text(&ConnectionState::Ready),
+                    self.app_context.style_status_bar_connection_ready(),
+                ),
+                Some(state) => (
+                    TgContext::connection_state_text(state),
+                    self.app_context.style_status_bar_connection_not_ready(),
+                ),
+                None => (
+                    "Unknown",
+                    self.app_context.style_status_bar_connection_not_ready(),
+                ),
+            };
+        let mut spans = vec![
+            Span::styled(
+                Local::now().format("%H:%M").to_string(),
+                self.app_context.style_status_bar_open_chat_name(),
+            ),
+            //
+            Span::raw("     "),
             Span::styled(
                 "Press ",
                 self.app_context.style_status_bar_message_quit_text(),
@@ -131,6 +165,29 @@ impl Component for StatusBar {
             ),
             //
             Span::raw("     "),
+            Span::styled(
+                "Presence: ",
+                self.app_context.style_status_bar_open_chat_text(),
+            ),
+            Span::styled(presence, self.app_context.style_status_bar_open_chat_name()),
+            //
+            Span::raw("     "),
+            Span::styled(
+                "Connection: ",
+                self.app_context.style_status_bar_open_chat_text(),
+            ),
+            Span::styled(format!(" {} ", connection), connection_style),
+            Span::styled(
+                self.app_context
+                    .tg_context()
+                    .proxy()
+                    .as_ref()
+                    .map(|proxy| format!(" via {}", proxy))
+                    .unwrap_or_default(),
+                self.app_context.style_status_bar_open_chat_text(),
+            ),
+            //
+            Span::raw("     "),
             Span::styled(
                 "Key pressed: ",
                 self.app_context.style_status_bar_press_key_text(),
@@ -151,7 +208,24 @@ impl Component for StatusBar {
                 self.terminal_area.height.to_string(),
                 self.app_context.style_status_bar_size_info_numbers(),
             ),
-        ])];
+        ];
+        if self.app_context.tg_context().playing_voice_note() != 0 {
+            spans.push(Span::raw("     "));
+            spans.push(Span::styled(
+                " 🎤 Playing voice note ",
+                self.app_context.style_status_bar_info_message(),
+            ));
+        }
+        if let Some((message, is_error)) = self.app_context.tg_context().status_message() {
+            let style = if is_error {
+                self.app_context.style_status_bar_error_message()
+            } else {
+                self.app_context.style_status_bar_info_message()
+            };
+            spans.push(Span::raw("     "));
+            spans.push(Span::styled(format!(" {} ", message), style));
+        }
+        let text = vec![Line::from(spans)];
 
         let paragraph = Paragraph::new(text)
             .block(Block::new().title(self.name.as_str()).borders(Borders::ALL))
@@ -0,0 +1,318 @@
+use crate::{
+    action::Action,
+    app_context::AppContext,
+    components::component_traits::{Component, HandleFocus},
+    event::Event,
+};
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use std::{io, sync::Arc};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// The width of a sticker in the grid of the `StickerPicker`.
+const STICKER_CELL_WIDTH: usize = 4;
+/// The maximum number of stickers in a row of the grid.
+const MAX_STICKER_COLUMNS: usize = 10;
+
+/// `StickerPicker` is a struct that represents a popup to choose a sticker to
+/// send to the open chat.
+/// The favorite and the recent stickers are shown first, then the installed
+/// sticker sets. The stickers are previewed by their emoji, in a grid that
+/// is navigated with the arrow keys.
+pub struct StickerPicker {
+    /// The application context.
+    app_context: Arc<AppContext>,
+    /// The name of the `StickerPicker`.
+    name: String,
+    /// An unbounded sender that send action for processing.
+    action_tx: Option<UnboundedSender<Action>>,
+    /// Indicates whether the `StickerPicker` is focused or not.
+    focused: bool,
+    /// The index of the selected sticker set and the index of the selected
+    /// sticker in it.
+    selected: (usize, usize),
+    /// The number of stickers in a row of the grid, updated when drawn.
+    columns: usize,
+    /// The first line of the grid that is shown.
+    scroll: usize,
+}
+
+impl StickerPicker {
+    /// Create a new instance of the `StickerPicker` struct.
+    ///
+    /// # Arguments
+    /// * `app_context` - An Arc wrapped AppContext struct.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `StickerPicker` struct.
+    pub fn new(app_context: Arc<AppContext>) -> Self {
+        let name = "".to_string();
+        let action_tx = None;
+        let focused = false;
+        let selected = (0, 0);
+        let columns = MAX_STICKER_COLUMNS;
+        let scroll = 0;
+
+        StickerPicker {
+            app_context,
+            name,
+            action_tx,
+            focused,
+            selected,
+            columns,
+            scroll,
+        }
+    }
+    /// Set the name of the `StickerPicker`.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the `StickerPicker`.
+    ///
+    /// # Returns
+    /// * `Self` - The modified instance of the `StickerPicker`.
+    pub fn with_name(mut self, name: impl AsRef<str>) -> Self {
+        self.name = name.as_ref().to_string();
+        self
+    }
+
+    /// Get the number of stickers of each sticker set.
+    ///
+    /// # Returns
+    /// * `Vec<usize>` - The number of stickers, in the order of the sets.
+    fn set_lengths(&self) -> Vec<usize> {
+        self.app_context
+            .tg_context()
+            .sticker_sets()
+            .iter()
+            .map(|set| set.stickers.len())
+            .collect()
+    }
+
+    /// Move the selection in the grid of stickers.
+    /// Moving past the first or the last row of a sticker set selects the
+    /// sticker in the same column of the previous or the next set.
+    /// `Tab` and `BackTab` select the first sticker of the next or the
+    /// previous set.
+    ///
+    /// # Arguments
+    /// * `key` - The pressed key.
+    fn move_selection(&mut self, key: KeyCode) {
+        let lengths = self.set_lengths();
+        if lengths.is_empty() {
+            return;
+        }
+        let columns = self.columns.max(1);
+        let (mut set, mut index) = self.selected;
+        set = set.min(lengths.len() - 1);
+        index = index.min(lengths[set].saturating_sub(1));
+        let column = index % columns;
+        match key {
+            KeyCode::Right | KeyCode::Char('l') => {
+                if index + 1 < lengths[set] {
+                    index += 1;
+                } else if set + 1 < lengths.len() {
+                    (set, index) = (set + 1, 0);
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                if index > 0 {
+                    index -= 1;
+                } else if set > 0 {
+                    set -= 1;
+                    index = lengths[set].saturating_sub(1);
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let last = lengths[set].saturating_sub(1);
+                if index + columns <= last {
+                    index += columns;
+                } else if index / columns < last / columns {
+                    index = last;
+                } else if set + 1 < lengths.len() {
+                    set += 1;
+                    index = column.min(lengths[set].saturating_sub(1));
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                if index >= columns {
+                    index -= columns;
+                } else if set > 0 {
+                    set -= 1;
+                    let last = lengths[set].saturating_sub(1);
+                    index = (last / columns * columns + column).min(last);
+                }
+            }
+            KeyCode::Tab if set + 1 < lengths.len() => (set, index) = (set + 1, 0),
+            KeyCode::BackTab if set > 0 => (set, index) = (set - 1, 0),
+            _ => {}
+        }
+        self.selected = (set, index);
+    }
+
+    /// Send the selected sticker to the open chat and hide the
+    /// `StickerPicker`.
+    fn send_selected(&mut self) {
+        let tg_context = self.app_context.tg_context();
+        let (set, index) = self.selected;
+        let Some(sticker_id) = tg_context
+            .sticker_sets()
+            .get(set)
+            .and_then(|set| set.stickers.get(index))
+            .map(|sticker| sticker.id)
+        else {
+            return;
+        };
+        if let Some(event_tx) = tg_context.event_tx().as_ref() {
+            event_tx
+                .send(Event::SendSticker(tg_context.open_chat_id(), sticker_id))
+                .unwrap();
+        }
+        self.hide();
+    }
+
+    /// Hide the `StickerPicker`.
+    fn hide(&self) {
+        if let Some(tx) = self.action_tx.as_ref() {
+            tx.send(Action::HideStickerPicker).unwrap();
+        }
+    }
+}
+
+/// Implement the `HandleFocus` trait for the `StickerPicker` struct.
+/// This trait allows the `StickerPicker` to be focused or unfocused.
+impl HandleFocus for StickerPicker {
+    /// Set the `focused` flag for the `StickerPicker`, and load the stickers
+    /// if they have not been loaded yet.
+    fn focus(&mut self) {
+        self.focused = true;
+        if let Some(event_tx) = self.app_context.tg_context().event_tx().as_ref() {
+            event_tx.send(Event::LoadStickerSets).unwrap();
+        }
+    }
+    /// Set the `focused` flag for the `StickerPicker`.
+    fn unfocus(&mut self) {
+        self.focused = false;
+    }
+}
+
+/// Implement the `Component` trait for the `StickerPicker` struct.
+impl Component for StickerPicker {
+    fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> io::Result<()> {
+        self.action_tx = Some(tx);
+        Ok(())
+    }
+
+    fn update(&mut self, action: Action) {
+        // The other actions are ignored, they are sent also for the keys of
+        // the grid.
+        if let Action::Key(key, _) = action {
+            match key {
+                KeyCode::Enter => self.send_selected(),
+                KeyCode::Esc => self.hide(),
+                key => self.move_selection(key),
+            }
+        }
+    }
+
+    /// Draw the `StickerPicker` in the middle of the area.
+    /// Each sticker set is shown as a title followed by the rows of the
+    /// emoji of its stickers.
+    fn draw(&mut self, frame: &mut ratatui::Frame<'_>, area: Rect) -> io::Result<()> {
+        let width = (MAX_STICKER_COLUMNS * STICKER_CELL_WIDTH + 2) as u16;
+        let [popup_area] = Layout::horizontal([Constraint::Length(width.min(area.width))])
+            .flex(Flex::Center)
+            .areas(area);
+        let [popup_area] = Layout::vertical([Constraint::Percentage(60)])
+            .flex(Flex::Center)
+            .areas(popup_area);
+        let block = Block::new()
+            .borders(Borders::ALL)
+            .border_style(self.app_context.style_border_component_focused())
+            .style(self.app_context.style_chat())
+            .title("Stickers");
+        let inner_area = block.inner(popup_area);
+        frame.render_widget(Clear, popup_area);
+        frame.render_widget(block, popup_area);
+        let [grid_area, help_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner_area);
+
+        self.columns = (grid_area.width as usize / STICKER_CELL_WIDTH).max(1);
+        let tg_context = self.app_context.tg_context();
+        let sticker_sets = tg_context.sticker_sets();
+        let mut lines = vec![];
+        let mut selected_lines = (0, 0);
+        let mut selected_emoji = String::new();
+        for (i, set) in sticker_sets.iter().enumerate() {
+            let title_line = lines.len();
+            lines.push(Line::from(Span::styled(
+                set.title.clone(),
+                self.app_context.style_chat_list_item_chat_name(),
+            )));
+            for (row, stickers) in set.stickers.chunks(self.columns).enumerate() {
+                let spans: Vec<Span> = stickers
+                    .iter()
+                    .enumerate()
+                    .map(|(column, sticker)| {
+                        let index = row * self.columns + column;
+                        let emoji = Span::raw(sticker.emoji.clone());
+                        let padding = STICKER_CELL_WIDTH.saturating_sub(emoji.width() + 1);
+                        let cell = format!(" {}{}", sticker.emoji, " ".repeat(padding));
+                        if (i, index) == self.selected {
+                            selected_lines =
+                                (if row == 0 { title_line } else { lines.len() }, lines.len());
+                            selected_emoji = sticker.emoji.clone();
+                            Span::styled(cell, self.app_context.style_chat_list_item_selected())
+                        } else {
+                            Span::raw(cell)
+                        }
+                    })
+                    .collect();
+                lines.push(Line::from(spans));
+            }
+        }
+        if lines.is_empty() {
+            lines.push(Line::from("Loading..."));
+        }
+
+        // The selected sticker is kept visible, together with the title of
+        // its set when it is in the first row.
+        let height = grid_area.height as usize;
+        let (first_line, selected_line) = selected_lines;
+        if first_line < self.scroll {
+            self.scroll = first_line;
+        } else if selected_line >= self.scroll + height {
+            self.scroll = selected_line + 1 - height;
+        }
+        self.scroll = self.scroll.min(lines.len().saturating_sub(1));
+        frame.render_widget(
+            Paragraph::new(lines).scroll((self.scroll as u16, 0)),
+            grid_area,
+        );
+
+        let help = Line::from(vec![
+            Span::raw(format!("{} ", selected_emoji)),
+            Span::styled("Enter", self.app_context.style_status_bar_press_key_key()),
+            Span::styled(
+                ": send, ",
+                self.app_context.style_status_bar_press_key_text(),
+            ),
+            Span::styled("Tab", self.app_context.style_status_bar_press_key_key()),
+            Span::styled(
+                ": next set, ",
+                self.app_context.style_status_bar_press_key_text(),
+            ),
+            Span::styled("Esc", self.app_context.style_status_bar_press_key_key()),
+            Span::styled(
+                ": close",
+                self.app_context.style_status_bar_press_key_text(),
+            ),
+        ]);
+        frame.render_widget(Paragraph::new(help), help_area);
+
+        Ok(())
+    }
+}
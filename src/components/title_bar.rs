@@ -3,6 +3,7 @@ use {
         action::Action,
         app_context::AppContext,
         components::component_traits::{Component, HandleFocus},
+        tg::tg_context::TgContext,
     },
     ratatui::{
         layout::{Alignment, Constraint, Direction, Layout, Rect},
@@ -117,7 +118,43 @@ impl Component for TitleBar {
             Span::styled("a", self.app_context.style_title_bar_title3()),
             Span::styled("m", self.app_context.style_title_bar_title1()),
         ])];
-        let block = Block::new().borders(Borders::ALL);
+        let mut block = Block::new().borders(Borders::ALL);
+        let unread_count = self
+            .app_context
+            .tg_context()
+            .unread_count(self.app_context.app_config().unread_count_include_muted);
+        if unread_count > 0 {
+            block = block.title_top(
+                Line::from(Span::styled(
+                    format!(" {} unread ", unread_count),
+                    self.app_context.style_title_bar_title1(),
+                ))
+                .right_aligned(),
+            );
+        }
+        let members_status = self.app_context.tg_context().open_chat_members_status();
+        if !members_status.is_empty() {
+            block = block.title_top(
+                Line::from(Span::styled(
+                    format!(" 👥 {} ", members_status),
+                    self.app_context.style_title_bar_title3(),
+                ))
+                .centered(),
+            );
+        }
+        let auto_delete_time = self.app_context.tg_context().open_chat_auto_delete_time();
+        if auto_delete_time > 0 {
+            block = block.title_top(
+                Line::from(Span::styled(
+                    format!(
+                        " ⏱ Auto-delete: {} ",
+                        TgContext::auto_delete_time_text(auto_delete_time)
+                    ),
+                    self.app_context.style_title_bar_title2(),
+                ))
+                .left_aligned(),
+            );
+        }
         let paragraph = Paragraph::new(text)
             .block(block.clone())
             .style(self.app_context.style_title_bar())
@@ -1,42 +1,161 @@
 use {
+    crate::app_error::AppError,
     crate::configs::{self, config_type::ConfigType},
-    crate::utils::{TGT, TGT_CONFIG_DIR},
+    crate::utils::{self, TGT, TGT_CONFIG_DIR},
     lazy_static::lazy_static,
     serde::de::DeserializeOwned,
-    std::path::PathBuf,
+    std::path::{Path, PathBuf},
 };
 
 lazy_static! {
-    static ref CONFIG_DIR_HIERARCHY: Vec<PathBuf> = {
-        let mut config_dirs = vec![];
+    static ref CONFIG_DIR_HIERARCHY: Vec<PathBuf> = config_dir_hierarchy();
+}
 
-        if let Ok(p) = std::env::var(TGT_CONFIG_DIR) {
-            let p = PathBuf::from(p);
-            if p.is_dir() {
-                config_dirs.push(p);
-            }
-            tracing::info!("Using {} for config", TGT_CONFIG_DIR);
-        }
+/// Get the user configuration directory of the system, e.g. `~/.config/tgt`.
+///
+/// # Returns
+/// The user configuration directory, `None` if it is not known.
+fn user_config_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|h| h.join(".config"))
+    } else {
+        dirs::config_dir()
+    }
+    .map(|p| p.join(TGT))
+}
 
-        if let Some(p) = if cfg!(target_os = "macos") {
-            dirs::home_dir().map(|h| h.join(".config"))
-        } else {
-            dirs::config_dir()
-        } {
-            let mut p = p;
-            p.push(TGT);
-            if p.is_dir() {
-                config_dirs.push(p.clone());
-            }
-            p.push("config");
-            if p.is_dir() {
-                config_dirs.push(p.clone());
-            }
+/// Get the directories where the custom configuration files are searched,
+/// in order of precedence. Only the existing directories are returned.
+///
+/// # Returns
+/// The configuration directories.
+pub fn config_dir_hierarchy() -> Vec<PathBuf> {
+    let mut config_dirs = vec![];
+
+    if utils::profile().is_some() {
+        if let Ok(p) = utils::tgt_profile_config_dir() {
             tracing::info!("Using {} for config", p.display());
+            config_dirs.push(p);
         }
+    }
 
-        config_dirs
+    if let Ok(p) = std::env::var(TGT_CONFIG_DIR) {
+        let p = PathBuf::from(p);
+        if p.is_dir() {
+            config_dirs.push(p);
+        }
+        tracing::info!("Using {} for config", TGT_CONFIG_DIR);
+    }
+
+    if let Some(mut p) = user_config_dir() {
+        if p.is_dir() {
+            config_dirs.push(p.clone());
+        }
+        p.push("config");
+        if p.is_dir() {
+            config_dirs.push(p.clone());
+        }
+        tracing::info!("Using {} for config", p.display());
+    }
+
+    config_dirs
+}
+
+/// Get the directory where a new custom configuration file is created: the
+/// configuration directory of the profile, `$TGT_CONFIG_DIR` or the
+/// `config` directory of the user configuration directory.
+///
+/// # Returns
+/// The directory of the new configuration files, `None` if it is not known.
+pub fn custom_config_dir() -> Option<PathBuf> {
+    if utils::profile().is_some() {
+        return utils::tgt_profile_config_dir().ok();
+    }
+    if let Ok(p) = std::env::var(TGT_CONFIG_DIR) {
+        return Some(PathBuf::from(p));
+    }
+    user_config_dir().map(|p| p.join("config"))
+}
+
+/// Get the path of a custom configuration file to write: the file of the
+/// configuration directories if it exists, otherwise the file in the
+/// directory returned by `custom_config_dir`.
+///
+/// # Arguments
+/// * `file_name` - The name of the configuration file, e.g. `keymap.toml`.
+///
+/// # Returns
+/// The path of the file, or an error if the configuration directory is not
+/// known.
+pub fn custom_config_path(file_name: &str) -> Result<PathBuf, AppError<()>> {
+    if let Some(path) = config_dir_hierarchy()
+        .into_iter()
+        .map(|dir| dir.join(file_name))
+        .find(|path| path.exists())
+    {
+        return Ok(path);
+    }
+    Ok(custom_config_dir()
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "The configuration directory is not known",
+            )
+        })?
+        .join(file_name))
+}
+
+/// Check whether a custom configuration file exists in the configuration
+/// directories, e.g. to know if the application runs for the first time.
+///
+/// # Returns
+/// `true` if at least a custom configuration file exists.
+pub fn custom_config_exists() -> bool {
+    config_dir_hierarchy().iter().any(|dir| {
+        ConfigType::enumerate()
+            .iter()
+            .any(|config_type| dir.join(config_type.as_default_filename()).exists())
+    })
+}
+
+/// Set some fields of a configuration file, creating it if it does not
+/// exist. The file is edited in place, so that its other fields, their order
+/// and the comments of the user are kept.
+///
+/// # Arguments
+/// * `path` - The path of the configuration file.
+/// * `fields` - The names and the values of the fields to set.
+///
+/// # Returns
+/// An error if the file can not be read, parsed or written.
+pub fn save_fields(path: &Path, fields: &[(&str, toml::Value)]) -> Result<(), AppError<()>> {
+    let mut document = if path.exists() {
+        std::fs::read_to_string(path)?
+            .parse::<toml_edit::DocumentMut>()
+            .map_err(std::io::Error::other)?
+    } else {
+        toml_edit::DocumentMut::new()
     };
+    for (name, value) in fields {
+        let value = value
+            .to_string()
+            .parse::<toml_edit::Value>()
+            .map_err(std::io::Error::other)?;
+        match document.get_mut(name).and_then(|item| item.as_value_mut()) {
+            // The decor keeps the comment at the end of the line.
+            Some(old) => {
+                let decor = old.decor().clone();
+                *old = value;
+                *old.decor_mut() = decor;
+            }
+            None => document[name] = toml_edit::value(value),
+        }
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, document.to_string())?;
+    Ok(())
 }
 
 /// A trait for configuration files.
@@ -69,6 +188,46 @@ pub trait ConfigFile: Sized + Default + Clone {
             )
         }
     }
+    /// Get the configuration of the specified type, like `get_config`, but
+    /// return an error instead of exiting if the configuration file can not
+    /// be parsed. It is used to reload the configuration at runtime.
+    ///
+    /// # Returns
+    /// The configuration of the specified type or an error.
+    fn try_get_config() -> Result<Self, AppError<()>> {
+        let file_name = Self::get_type().as_default_filename();
+        if Self::override_fields() {
+            let mut default = Self::default();
+            Ok(
+                default.merge(Self::try_deserialize_custom_config::<Self::Raw>(
+                    file_name.as_str(),
+                )?),
+            )
+        } else {
+            match Self::search_config_file(file_name.as_str()) {
+                Some(file_path) => {
+                    configs::deserialize_to_config_into::<Self::Raw, Self>(&file_path)
+                }
+                None => Ok(Self::default()),
+            }
+        }
+    }
+    /// Save some fields of the custom configuration file, e.g. the ones
+    /// chosen in the first run setup.
+    /// The file of the configuration directories is updated, or created in
+    /// the directory returned by `custom_config_dir` if it does not exist.
+    /// The changes are applied the next time the configuration is read.
+    ///
+    /// # Arguments
+    /// * `fields` - The names and the values of the fields to set.
+    ///
+    /// # Returns
+    /// The path of the saved file or an error.
+    fn save(fields: &[(&str, toml::Value)]) -> Result<PathBuf, AppError<()>> {
+        let path = custom_config_path(&Self::get_type().as_default_filename())?;
+        save_fields(&path, fields)?;
+        Ok(path)
+    }
     /// Search for a configuration file in the configuration directories.
     /// This function searches the configuration directories for the specified
     /// file name and returns the path to the first matching file. If no
@@ -101,6 +260,29 @@ pub trait ConfigFile: Sized + Default + Clone {
     /// The parsed configuration or `None` if the file is not found or cannot be
     /// parsed.
     fn deserialize_custom_config<R>(file_name: &str) -> Option<R>
+    where
+        R: DeserializeOwned,
+    {
+        match Self::try_deserialize_custom_config::<R>(file_name) {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Failed to parse {}: {}", file_name, e);
+                std::process::exit(1);
+            }
+        }
+    }
+    /// Deserialize a custom configuration file into a configuration struct.
+    /// Different from `deserialize_custom_config`, an error is returned if
+    /// the file cannot be parsed.
+    ///
+    /// # Arguments
+    /// * `file_name` - The name of the file (including the file extension) to
+    ///   search for in the configuration directories.
+    ///
+    /// # Returns
+    /// The parsed configuration, `None` if the file is not found, or an error
+    /// if the file cannot be parsed.
+    fn try_deserialize_custom_config<R>(file_name: &str) -> Result<Option<R>, AppError<()>>
     where
         R: DeserializeOwned,
     {
@@ -108,17 +290,16 @@ pub trait ConfigFile: Sized + Default + Clone {
             Some(file_path) => match configs::deserialize_to_config::<R>(&file_path) {
                 Ok(s) => {
                     tracing::info!("Loaded config from {}", file_path.display());
-                    Some(s)
+                    Ok(Some(s))
                 }
                 Err(e) => {
                     tracing::error!("Failed to parse {}: {}", file_name, e);
-                    eprintln!("Failed to parse {}: {}", file_name, e);
-                    std::process::exit(1);
+                    Err(e)
                 }
             },
             None => {
                 tracing::info!("No config file found for {}", file_name);
-                None
+                Ok(None)
             }
         }
     }
@@ -192,3 +373,49 @@ pub trait ConfigFile: Sized + Default + Clone {
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::configs::config_file::save_fields;
+
+    #[test]
+    fn test_save_fields() {
+        let dir = std::env::temp_dir().join(format!("tgt_save_fields_{}", std::process::id()));
+        let path = dir.join("app.toml");
+        save_fields(&path, &[("mouse_support", toml::Value::Boolean(false))]).unwrap();
+        save_fields(
+            &path,
+            &[(
+                "theme_filename",
+                toml::Value::String("first_theme.toml".to_string()),
+            )],
+        )
+        .unwrap();
+        let table = std::fs::read_to_string(&path)
+            .unwrap()
+            .parse::<toml::Table>()
+            .unwrap();
+        assert_eq!(table["mouse_support"].as_bool(), Some(false));
+        assert_eq!(table["theme_filename"].as_str(), Some("first_theme.toml"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_fields_keeps_comments() {
+        let dir =
+            std::env::temp_dir().join(format!("tgt_save_fields_comments_{}", std::process::id()));
+        let path = dir.join("app.toml");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            &path,
+            "# The status bar.\nshow_status_bar = true # shown\n# The title bar.\nshow_title_bar = true\n",
+        )
+        .unwrap();
+        save_fields(&path, &[("show_status_bar", toml::Value::Boolean(false))]).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# The status bar.\nshow_status_bar = false # shown\n# The title bar.\nshow_title_bar = true\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
 use {
     crate::{app_error::AppError, configs::raw::theme_raw::ThemeEntry, PALETTE_CONFIG},
     ratatui::style::{Color, Modifier, Style},
+    std::collections::HashMap,
 };
 #[derive(Clone, Debug)]
 /// `ThemeStyle` is a struct that represents a style in the theme config.
@@ -134,23 +135,42 @@ impl ThemeStyle {
     /// # Returns
     /// * `Result<Color, AppError>` - The converted `Color`.
     pub fn str_to_color_with_palette(s: &str) -> Result<Color, AppError<()>> {
+        Self::str_to_color_in_palette(s, &PALETTE_CONFIG.palette)
+    }
+    /// Convert a string to a `Color`, looking for it in the given palette
+    /// first, see `str_to_color_with_palette`.
+    ///
+    /// # Arguments
+    /// * `s` - The string to convert to a `Color`.
+    /// * `palette` - The palette.
+    ///
+    /// # Returns
+    /// * `Result<Color, AppError>` - The converted `Color`.
+    pub fn str_to_color_in_palette(
+        s: &str,
+        palette: &HashMap<String, Color>,
+    ) -> Result<Color, AppError<()>> {
         if s.is_empty() {
             return Err(AppError::InvalidColor(s.to_string()));
         }
-        if let Some(color) = PALETTE_CONFIG.palette.get(s) {
+        if let Some(color) = palette.get(s) {
             return Ok(*color);
         }
         Self::str_to_color(s)
     }
-}
-/// Implement the `From` trait for the `ThemeStyle` struct.
-/// It is used to convert a `ThemeEntry` to a `ThemeStyle`.
-/// The `ThemeEntry` is a struct that represents a style in the theme raw
-/// config.
-impl From<ThemeEntry> for ThemeStyle {
-    fn from(entry: ThemeEntry) -> Self {
+    /// Convert a `ThemeEntry` to a `ThemeStyle`, looking for its colors in
+    /// the given palette.
+    /// The colors that can not be parsed are left unset.
+    ///
+    /// # Arguments
+    /// * `entry` - The style in the theme raw config.
+    /// * `palette` - The palette.
+    ///
+    /// # Returns
+    /// * `ThemeStyle` - The converted `ThemeStyle`.
+    pub fn from_entry(entry: ThemeEntry, palette: &HashMap<String, Color>) -> Self {
         let fg = match entry.fg {
-            Some(fg) => match Self::str_to_color_with_palette(&fg) {
+            Some(fg) => match Self::str_to_color_in_palette(&fg, palette) {
                 Ok(color) => Some(color),
                 Err(e) => {
                     tracing::error!("Failed to parse foreground color: {}", e);
@@ -163,7 +183,7 @@ impl From<ThemeEntry> for ThemeStyle {
             }
         };
         let bg = match entry.bg {
-            Some(bg) => match Self::str_to_color_with_palette(&bg) {
+            Some(bg) => match Self::str_to_color_in_palette(&bg, palette) {
                 Ok(color) => Some(color),
                 Err(e) => {
                     tracing::error!("Failed to parse background color: {}", e);
@@ -191,6 +211,15 @@ impl From<ThemeEntry> for ThemeStyle {
         Self { fg, bg, modifier }
     }
 }
+/// Implement the `From` trait for the `ThemeStyle` struct.
+/// It is used to convert a `ThemeEntry` to a `ThemeStyle`.
+/// The `ThemeEntry` is a struct that represents a style in the theme raw
+/// config.
+impl From<ThemeEntry> for ThemeStyle {
+    fn from(entry: ThemeEntry) -> Self {
+        Self::from_entry(entry, &PALETTE_CONFIG.palette)
+    }
+}
 /// Implement the `Default` trait for the `ThemeStyle` struct.
 impl Default for ThemeStyle {
     fn default() -> Self {
@@ -219,6 +248,7 @@ mod tests {
     use {
         crate::configs::{config_theme::ThemeStyle, raw::theme_raw::ThemeEntry},
         ratatui::style::{Color, Modifier},
+        std::collections::HashMap,
     };
 
     #[test]
@@ -358,4 +388,20 @@ mod tests {
             Modifier::ITALIC | Modifier::BOLD | Modifier::UNDERLINED
         );
     }
+
+    #[test]
+    fn test_theme_style_from_entry() {
+        let palette = HashMap::from([("accent".to_string(), Color::Rgb(1, 2, 3))]);
+        let entry = ThemeEntry {
+            fg: Some("accent".to_string()),
+            bg: Some("not_a_color".to_string()),
+            italic: None,
+            bold: Some(true),
+            underline: None,
+        };
+        let style = ThemeStyle::from_entry(entry, &palette);
+        assert_eq!(style.fg, Some(Color::Rgb(1, 2, 3)));
+        assert_eq!(style.bg, None);
+        assert_eq!(style.modifier, Modifier::BOLD);
+    }
 }
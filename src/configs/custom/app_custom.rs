@@ -1,8 +1,9 @@
 use crate::{
     app_error::AppError,
+    component_name::ComponentName,
     configs::{self, config_file::ConfigFile, config_type::ConfigType, raw::app_raw::AppRaw},
 };
-use std::path::Path;
+use std::{collections::HashMap, path::Path};
 
 #[derive(Clone, Debug)]
 /// The application configuration.
@@ -25,6 +26,78 @@ pub struct AppConfig {
     pub take_api_id_from_telegram_config: bool,
     /// Take the API HASH from the Telegram configuration.
     pub take_api_hash_from_telegram_config: bool,
+    /// Include the sender name when copying a message.
+    pub copy_message_with_sender: bool,
+    /// The inactivity period, in seconds, after which the account goes offline.
+    pub away_after_seconds: u64,
+    /// Whether to scroll to the first unread message when a chat is opened.
+    /// If false, the chat is always opened at the last message.
+    pub jump_to_unread: bool,
+    /// The order of the chats in the chat list.
+    /// It is one of "activity", "unread_first" or "alphabetical".
+    pub chat_list_sort: String,
+    /// Whether to show a desktop notification for the messages received in the
+    /// chats that are not open.
+    pub desktop_notifications: bool,
+    /// Whether the unread messages of the muted chats are counted in the total
+    /// unread badge.
+    pub unread_count_include_muted: bool,
+    /// Whether to hide the header of the consecutive messages of the same sender.
+    pub compact_messages: bool,
+    /// The time window, in seconds, of the messages grouped in compact mode.
+    pub compact_messages_window_seconds: u64,
+    /// Whether a single click on a chat of the chat list opens it. If false, the
+    /// first click focuses the chat list and selects the chat, and a second click
+    /// on the same chat within `mouse_double_click_ms` opens it.
+    pub mouse_single_click_open: bool,
+    /// The maximum time, in milliseconds, between the two clicks of a double
+    /// click, see `mouse_single_click_open`.
+    pub mouse_double_click_ms: u64,
+    /// The width, in columns, under which the user interface is drawn as a small
+    /// area.
+    pub small_area_width: u16,
+    /// The minimum height, in rows, of the core window. The title bar and the
+    /// status bar are hidden when the terminal is not 5 rows higher than this.
+    pub small_area_height: u16,
+    /// Whether to show the numbers of the first 9 chats of the chat list, used to
+    /// open them with the `jump_to_chat_N` commands.
+    pub show_chat_list_numbers: bool,
+    /// The maximum width of the wrapped messages in the chat.
+    /// The messages are wrapped at half the width of the chat, or at this width
+    /// if it is smaller. 0 means no maximum.
+    pub max_wrap_width: u16,
+    /// The command used to play the voice notes, the path of the audio file is
+    /// appended to its arguments. It is empty if the voice notes can not be played.
+    pub voice_note_player: String,
+    /// Whether to ring the terminal bell when a message is received in a chat
+    /// that is not muted. It can be toggled at runtime.
+    pub bell_on_new_message: bool,
+    /// Whether to ring the terminal bell also for the messages received in the
+    /// open chat.
+    pub bell_in_open_chat: bool,
+    /// Whether the filter of the chat list is case sensitive.
+    pub chat_list_filter_case_sensitive: bool,
+    /// Whether to split the messages longer than the limit of Telegram into
+    /// multiple messages, instead of refusing to send them.
+    pub split_long_messages: bool,
+    /// The names shown instead of the Telegram names of some users, by user
+    /// id.
+    pub aliases: HashMap<i64, String>,
+    /// Whether the messages are marked as read when they are shown while scrolling
+    /// instead of when the chat is opened.
+    pub mark_read_on_scroll: bool,
+    /// The order of the components in the focus cycle of `focus_next` and
+    /// `focus_previous`.
+    pub focus_order: Vec<ComponentName>,
+    /// Whether to show the line with the hints of the key bindings.
+    pub show_hint_line: bool,
+    /// Whether to receive the messages with a single emoji as text instead of as animated emoji.
+    pub disable_animated_emoji: bool,
+    /// Whether to show the preview of the last message of the chats in the chat list.
+    pub show_chat_previews: bool,
+    /// The interval, in milliseconds, at which the user interface is redrawn when nothing changed.
+    /// The changes are redrawn at most `frame_rate` times per second.
+    pub idle_redraw_ms: u64,
 }
 /// The application configuration implementation.
 impl AppConfig {
@@ -37,6 +110,63 @@ impl AppConfig {
             &configs::custom::default_config_app_file_path()?,
         ))
     }
+    /// Parse the aliases of the users, whose keys are the user ids.
+    /// The aliases whose key is not a valid id are ignored.
+    ///
+    /// # Arguments
+    /// * `aliases` - The aliases of the raw configuration.
+    ///
+    /// # Returns
+    /// * `HashMap<i64, String>` - The aliases by user id.
+    fn parse_aliases(aliases: HashMap<String, String>) -> HashMap<i64, String> {
+        aliases
+            .into_iter()
+            .filter_map(|(user_id, alias)| match user_id.trim().parse() {
+                Ok(user_id) => Some((user_id, alias)),
+                Err(_) => {
+                    tracing::warn!("Invalid user id of the alias {}: {}", alias, user_id);
+                    None
+                }
+            })
+            .collect()
+    }
+    /// Parse the order of the components in the focus cycle.
+    /// The names that are not a component of the cycle, and the repeated
+    /// ones, are ignored. The default order is used if no component is
+    /// valid.
+    ///
+    /// # Arguments
+    /// * `focus_order` - The names of the components of the raw
+    ///   configuration.
+    ///
+    /// # Returns
+    /// * `Vec<ComponentName>` - The components in the order of the cycle.
+    fn parse_focus_order(focus_order: Vec<String>) -> Vec<ComponentName> {
+        let mut components = vec![];
+        for name in focus_order {
+            let component = match name.trim() {
+                "chat_list" => ComponentName::ChatList,
+                "chat" => ComponentName::Chat,
+                "prompt" => ComponentName::Prompt,
+                _ => {
+                    tracing::warn!("Invalid component in focus_order: {}", name);
+                    continue;
+                }
+            };
+            if !components.contains(&component) {
+                components.push(component);
+            }
+        }
+        if components.is_empty() {
+            tracing::warn!("No valid component in focus_order, the default order is used");
+            components = vec![
+                ComponentName::ChatList,
+                ComponentName::Chat,
+                ComponentName::Prompt,
+            ];
+        }
+        components
+    }
 }
 /// The implementation of the configuration file for the application.
 impl ConfigFile for AppConfig {
@@ -86,6 +216,86 @@ impl ConfigFile for AppConfig {
                 {
                     self.take_api_hash_from_telegram_config = take_api_hash_from_telegram_config;
                 }
+                if let Some(copy_message_with_sender) = other.copy_message_with_sender {
+                    self.copy_message_with_sender = copy_message_with_sender;
+                }
+                if let Some(away_after_seconds) = other.away_after_seconds {
+                    self.away_after_seconds = away_after_seconds;
+                }
+                if let Some(jump_to_unread) = other.jump_to_unread {
+                    self.jump_to_unread = jump_to_unread;
+                }
+                if let Some(chat_list_sort) = other.chat_list_sort {
+                    self.chat_list_sort = chat_list_sort;
+                }
+                if let Some(desktop_notifications) = other.desktop_notifications {
+                    self.desktop_notifications = desktop_notifications;
+                }
+                if let Some(unread_count_include_muted) = other.unread_count_include_muted {
+                    self.unread_count_include_muted = unread_count_include_muted;
+                }
+                if let Some(compact_messages) = other.compact_messages {
+                    self.compact_messages = compact_messages;
+                }
+                if let Some(compact_messages_window_seconds) = other.compact_messages_window_seconds
+                {
+                    self.compact_messages_window_seconds = compact_messages_window_seconds;
+                }
+                if let Some(mouse_single_click_open) = other.mouse_single_click_open {
+                    self.mouse_single_click_open = mouse_single_click_open;
+                }
+                if let Some(mouse_double_click_ms) = other.mouse_double_click_ms {
+                    self.mouse_double_click_ms = mouse_double_click_ms;
+                }
+                if let Some(small_area_width) = other.small_area_width {
+                    self.small_area_width = small_area_width;
+                }
+                if let Some(small_area_height) = other.small_area_height {
+                    self.small_area_height = small_area_height;
+                }
+                if let Some(show_chat_list_numbers) = other.show_chat_list_numbers {
+                    self.show_chat_list_numbers = show_chat_list_numbers;
+                }
+                if let Some(max_wrap_width) = other.max_wrap_width {
+                    self.max_wrap_width = max_wrap_width;
+                }
+                if let Some(voice_note_player) = other.voice_note_player {
+                    self.voice_note_player = voice_note_player;
+                }
+                if let Some(bell_on_new_message) = other.bell_on_new_message {
+                    self.bell_on_new_message = bell_on_new_message;
+                }
+                if let Some(bell_in_open_chat) = other.bell_in_open_chat {
+                    self.bell_in_open_chat = bell_in_open_chat;
+                }
+                if let Some(chat_list_filter_case_sensitive) = other.chat_list_filter_case_sensitive
+                {
+                    self.chat_list_filter_case_sensitive = chat_list_filter_case_sensitive;
+                }
+                if let Some(split_long_messages) = other.split_long_messages {
+                    self.split_long_messages = split_long_messages;
+                }
+                if let Some(aliases) = other.aliases {
+                    self.aliases = Self::parse_aliases(aliases);
+                }
+                if let Some(mark_read_on_scroll) = other.mark_read_on_scroll {
+                    self.mark_read_on_scroll = mark_read_on_scroll;
+                }
+                if let Some(focus_order) = other.focus_order {
+                    self.focus_order = Self::parse_focus_order(focus_order);
+                }
+                if let Some(show_hint_line) = other.show_hint_line {
+                    self.show_hint_line = show_hint_line;
+                }
+                if let Some(disable_animated_emoji) = other.disable_animated_emoji {
+                    self.disable_animated_emoji = disable_animated_emoji;
+                }
+                if let Some(show_chat_previews) = other.show_chat_previews {
+                    self.show_chat_previews = show_chat_previews;
+                }
+                if let Some(idle_redraw_ms) = other.idle_redraw_ms {
+                    self.idle_redraw_ms = idle_redraw_ms;
+                }
                 self.clone()
             }
         }
@@ -111,15 +321,43 @@ impl From<AppRaw> for AppConfig {
             theme_filename: raw.theme_filename.unwrap(),
             take_api_id_from_telegram_config: raw.take_api_id_from_telegram_config.unwrap(),
             take_api_hash_from_telegram_config: raw.take_api_hash_from_telegram_config.unwrap(),
+            copy_message_with_sender: raw.copy_message_with_sender.unwrap(),
+            away_after_seconds: raw.away_after_seconds.unwrap(),
+            jump_to_unread: raw.jump_to_unread.unwrap(),
+            chat_list_sort: raw.chat_list_sort.unwrap(),
+            desktop_notifications: raw.desktop_notifications.unwrap(),
+            unread_count_include_muted: raw.unread_count_include_muted.unwrap(),
+            compact_messages: raw.compact_messages.unwrap(),
+            compact_messages_window_seconds: raw.compact_messages_window_seconds.unwrap(),
+            mouse_single_click_open: raw.mouse_single_click_open.unwrap(),
+            mouse_double_click_ms: raw.mouse_double_click_ms.unwrap(),
+            small_area_width: raw.small_area_width.unwrap(),
+            small_area_height: raw.small_area_height.unwrap(),
+            show_chat_list_numbers: raw.show_chat_list_numbers.unwrap(),
+            max_wrap_width: raw.max_wrap_width.unwrap(),
+            voice_note_player: raw.voice_note_player.unwrap(),
+            bell_on_new_message: raw.bell_on_new_message.unwrap(),
+            bell_in_open_chat: raw.bell_in_open_chat.unwrap(),
+            chat_list_filter_case_sensitive: raw.chat_list_filter_case_sensitive.unwrap(),
+            split_long_messages: raw.split_long_messages.unwrap(),
+            aliases: Self::parse_aliases(raw.aliases.unwrap()),
+            mark_read_on_scroll: raw.mark_read_on_scroll.unwrap(),
+            focus_order: Self::parse_focus_order(raw.focus_order.unwrap()),
+            show_hint_line: raw.show_hint_line.unwrap(),
+            disable_animated_emoji: raw.disable_animated_emoji.unwrap(),
+            show_chat_previews: raw.show_chat_previews.unwrap(),
+            idle_redraw_ms: raw.idle_redraw_ms.unwrap(),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::configs::{
-        config_file::ConfigFile, custom::app_custom::AppConfig, raw::app_raw::AppRaw,
+    use crate::{
+        component_name::ComponentName,
+        configs::{config_file::ConfigFile, custom::app_custom::AppConfig, raw::app_raw::AppRaw},
     };
+    use std::collections::HashMap;
 
     #[test]
     fn test_app_config_default() {
@@ -133,6 +371,39 @@ mod tests {
         assert_eq!(app_config.theme_filename, "theme.toml");
         assert!(app_config.take_api_id_from_telegram_config);
         assert!(app_config.take_api_hash_from_telegram_config);
+        assert!(!app_config.copy_message_with_sender);
+        assert_eq!(app_config.away_after_seconds, 0);
+        assert!(app_config.jump_to_unread);
+        assert_eq!(app_config.chat_list_sort, "activity");
+        assert!(!app_config.desktop_notifications);
+        assert!(!app_config.unread_count_include_muted);
+        assert!(!app_config.compact_messages);
+        assert_eq!(app_config.compact_messages_window_seconds, 300);
+        assert!(!app_config.mouse_single_click_open);
+        assert_eq!(app_config.mouse_double_click_ms, 400);
+        assert_eq!(app_config.small_area_width, 100);
+        assert_eq!(app_config.small_area_height, 20);
+        assert!(!app_config.show_chat_list_numbers);
+        assert_eq!(app_config.max_wrap_width, 0);
+        assert_eq!(app_config.voice_note_player, "mpv --no-video".to_string());
+        assert!(!app_config.bell_on_new_message);
+        assert!(!app_config.bell_in_open_chat);
+        assert!(!app_config.chat_list_filter_case_sensitive);
+        assert!(!app_config.split_long_messages);
+        assert!(app_config.aliases.is_empty());
+        assert!(!app_config.mark_read_on_scroll);
+        assert_eq!(
+            app_config.focus_order,
+            vec![
+                ComponentName::ChatList,
+                ComponentName::Chat,
+                ComponentName::Prompt
+            ]
+        );
+        assert!(!app_config.show_hint_line);
+        assert!(app_config.disable_animated_emoji);
+        assert!(app_config.show_chat_previews);
+        assert_eq!(app_config.idle_redraw_ms, 1000);
     }
 
     #[test]
@@ -147,6 +418,36 @@ mod tests {
             theme_filename: Some("test".to_string()),
             take_api_id_from_telegram_config: Some(true),
             take_api_hash_from_telegram_config: Some(true),
+            copy_message_with_sender: Some(false),
+            away_after_seconds: Some(0),
+            jump_to_unread: Some(true),
+            chat_list_sort: Some("activity".to_string()),
+            desktop_notifications: Some(false),
+            unread_count_include_muted: Some(false),
+            compact_messages: Some(false),
+            compact_messages_window_seconds: Some(300),
+            mouse_single_click_open: Some(false),
+            mouse_double_click_ms: Some(400),
+            small_area_width: Some(100),
+            small_area_height: Some(20),
+            show_chat_list_numbers: Some(false),
+            max_wrap_width: Some(0),
+            voice_note_player: Some("mpv --no-video".to_string()),
+            bell_on_new_message: Some(false),
+            bell_in_open_chat: Some(false),
+            chat_list_filter_case_sensitive: Some(false),
+            split_long_messages: Some(false),
+            aliases: Some(HashMap::from([("42".to_string(), "Alice".to_string())])),
+            mark_read_on_scroll: Some(false),
+            focus_order: Some(vec![
+                "chat_list".to_string(),
+                "chat".to_string(),
+                "prompt".to_string(),
+            ]),
+            show_hint_line: Some(false),
+            disable_animated_emoji: Some(true),
+            show_chat_previews: Some(true),
+            idle_redraw_ms: Some(1000),
         };
         let app_config = AppConfig::from(app_raw);
         assert!(app_config.mouse_support);
@@ -156,6 +457,40 @@ mod tests {
         assert!(app_config.show_title_bar);
         assert!(app_config.theme_enable);
         assert_eq!(app_config.theme_filename, "test");
+        assert_eq!(
+            app_config.aliases,
+            HashMap::from([(42, "Alice".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_app_config_parse_focus_order() {
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        assert_eq!(
+            AppConfig::parse_focus_order(names(&["chat", "prompt", "chat", "title_bar"])),
+            vec![ComponentName::Chat, ComponentName::Prompt]
+        );
+        assert_eq!(
+            AppConfig::parse_focus_order(names(&["unknown"])),
+            vec![
+                ComponentName::ChatList,
+                ComponentName::Chat,
+                ComponentName::Prompt
+            ]
+        );
+    }
+
+    #[test]
+    fn test_app_config_parse_aliases() {
+        let aliases = HashMap::from([
+            ("42".to_string(), "Alice".to_string()),
+            ("-100".to_string(), "Group".to_string()),
+            ("bob".to_string(), "Bob".to_string()),
+        ]);
+        assert_eq!(
+            AppConfig::parse_aliases(aliases),
+            HashMap::from([(42, "Alice".to_string()), (-100, "Group".to_string())])
+        );
     }
 
     #[test]
@@ -170,6 +505,36 @@ mod tests {
             theme_filename: Some("test".to_string()),
             take_api_id_from_telegram_config: Some(true),
             take_api_hash_from_telegram_config: Some(true),
+            copy_message_with_sender: Some(false),
+            away_after_seconds: Some(0),
+            jump_to_unread: Some(true),
+            chat_list_sort: Some("activity".to_string()),
+            desktop_notifications: Some(false),
+            unread_count_include_muted: Some(false),
+            compact_messages: Some(false),
+            compact_messages_window_seconds: Some(300),
+            mouse_single_click_open: Some(false),
+            mouse_double_click_ms: Some(400),
+            small_area_width: Some(100),
+            small_area_height: Some(20),
+            show_chat_list_numbers: Some(false),
+            max_wrap_width: Some(0),
+            voice_note_player: Some("mpv --no-video".to_string()),
+            bell_on_new_message: Some(false),
+            bell_in_open_chat: Some(false),
+            chat_list_filter_case_sensitive: Some(false),
+            split_long_messages: Some(false),
+            aliases: Some(HashMap::new()),
+            mark_read_on_scroll: Some(false),
+            focus_order: Some(vec![
+                "chat_list".to_string(),
+                "chat".to_string(),
+                "prompt".to_string(),
+            ]),
+            show_hint_line: Some(false),
+            disable_animated_emoji: Some(true),
+            show_chat_previews: Some(true),
+            idle_redraw_ms: Some(1000),
         });
         let app_raw = AppRaw {
             mouse_support: Some(false),
@@ -181,6 +546,32 @@ mod tests {
             theme_filename: None,
             take_api_id_from_telegram_config: None,
             take_api_hash_from_telegram_config: None,
+            copy_message_with_sender: None,
+            away_after_seconds: None,
+            jump_to_unread: None,
+            chat_list_sort: None,
+            desktop_notifications: None,
+            unread_count_include_muted: None,
+            compact_messages: None,
+            compact_messages_window_seconds: None,
+            mouse_single_click_open: None,
+            mouse_double_click_ms: None,
+            small_area_width: None,
+            small_area_height: None,
+            show_chat_list_numbers: None,
+            max_wrap_width: None,
+            voice_note_player: None,
+            bell_on_new_message: None,
+            bell_in_open_chat: None,
+            chat_list_filter_case_sensitive: None,
+            split_long_messages: None,
+            aliases: None,
+            mark_read_on_scroll: None,
+            focus_order: None,
+            show_hint_line: None,
+            disable_animated_emoji: None,
+            show_chat_previews: None,
+            idle_redraw_ms: None,
         };
         app_config = app_config.merge(Some(app_raw));
         assert!(!app_config.mouse_support);
@@ -210,6 +601,32 @@ mod tests {
             theme_filename: None,
             take_api_id_from_telegram_config: None,
             take_api_hash_from_telegram_config: None,
+            copy_message_with_sender: None,
+            away_after_seconds: None,
+            jump_to_unread: None,
+            chat_list_sort: None,
+            desktop_notifications: None,
+            unread_count_include_muted: None,
+            compact_messages: None,
+            compact_messages_window_seconds: None,
+            mouse_single_click_open: None,
+            mouse_double_click_ms: None,
+            small_area_width: None,
+            small_area_height: None,
+            show_chat_list_numbers: None,
+            max_wrap_width: None,
+            voice_note_player: None,
+            bell_on_new_message: None,
+            bell_in_open_chat: None,
+            chat_list_filter_case_sensitive: None,
+            split_long_messages: None,
+            aliases: None,
+            mark_read_on_scroll: None,
+            focus_order: None,
+            show_hint_line: None,
+            disable_animated_emoji: None,
+            show_chat_previews: None,
+            idle_redraw_ms: None,
         };
         app_config = app_config.merge(Some(app_raw));
         assert!(app_config.mouse_support);
@@ -221,6 +638,39 @@ mod tests {
         assert_eq!(app_config.theme_filename, "theme.toml");
         assert!(app_config.take_api_id_from_telegram_config);
         assert!(app_config.take_api_hash_from_telegram_config);
+        assert!(!app_config.copy_message_with_sender);
+        assert_eq!(app_config.away_after_seconds, 0);
+        assert!(app_config.jump_to_unread);
+        assert_eq!(app_config.chat_list_sort, "activity");
+        assert!(!app_config.desktop_notifications);
+        assert!(!app_config.unread_count_include_muted);
+        assert!(!app_config.compact_messages);
+        assert_eq!(app_config.compact_messages_window_seconds, 300);
+        assert!(!app_config.mouse_single_click_open);
+        assert_eq!(app_config.mouse_double_click_ms, 400);
+        assert_eq!(app_config.small_area_width, 100);
+        assert_eq!(app_config.small_area_height, 20);
+        assert!(!app_config.show_chat_list_numbers);
+        assert_eq!(app_config.max_wrap_width, 0);
+        assert_eq!(app_config.voice_note_player, "mpv --no-video".to_string());
+        assert!(!app_config.bell_on_new_message);
+        assert!(!app_config.bell_in_open_chat);
+        assert!(!app_config.chat_list_filter_case_sensitive);
+        assert!(!app_config.split_long_messages);
+        assert!(app_config.aliases.is_empty());
+        assert!(!app_config.mark_read_on_scroll);
+        assert_eq!(
+            app_config.focus_order,
+            vec![
+                ComponentName::ChatList,
+                ComponentName::Chat,
+                ComponentName::Prompt
+            ]
+        );
+        assert!(!app_config.show_hint_line);
+        assert!(app_config.disable_animated_emoji);
+        assert!(app_config.show_chat_previews);
+        assert_eq!(app_config.idle_redraw_ms, 1000);
     }
 
     #[test]
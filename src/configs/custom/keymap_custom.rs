@@ -4,18 +4,32 @@ use crate::{
     component_name::ComponentName,
     configs::{
         self,
-        config_file::ConfigFile,
+        config_file::{self, ConfigFile},
         config_type::ConfigType,
-        raw::keymap_raw::{KeymapEntry, KeymapRaw},
+        raw::keymap_raw::{KeymapEntry, KeymapMode, KeymapRaw},
     },
     event::Event,
 };
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
-    path::Path,
+    collections::{hash_map::Entry, HashMap},
+    fmt::{self, Display, Formatter},
+    io,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
+/// The sections of the keymap configuration, in the order of `keymap.toml`.
+pub const KEYMAP_SECTIONS: [&str; 4] = ["core_window", "chat_list", "chat", "prompt"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+/// The format of the exported key bindings, see `KeymapConfig::export`.
+pub enum KeymapFormat {
+    /// The format of `keymap.toml`.
+    Toml,
+    /// A Markdown table for each section.
+    Markdown,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 /// The action binding.
 pub enum ActionBinding {
@@ -43,6 +57,24 @@ enum KeymapKind {
     Prompt,
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+/// A conflict in the keymaps: a key bound both in the `core_window` section
+/// and in the section of a component.
+/// The keys of the `core_window` section are handled first, so the binding
+/// of the component is never used.
+pub struct KeymapConflict {
+    /// The key bound in both sections.
+    pub key: Event,
+    /// The section of the component whose binding is shadowed, e.g. `chat`.
+    pub section: &'static str,
+}
+/// Implement the `Display` trait for the `KeymapConflict` struct.
+impl Display for KeymapConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (core_window and {})", self.key, self.section)
+    }
+}
+
 #[derive(Clone, Debug)]
 /// The keymap configuration.
 pub struct KeymapConfig {
@@ -67,6 +99,16 @@ impl KeymapConfig {
             &configs::custom::default_config_keymap_file_path()?,
         ))
     }
+    /// Get the default raw keymap configuration, i.e. the entries of the
+    /// default `keymap.toml` with their commands and descriptions.
+    ///
+    /// # Returns
+    /// The default raw keymap configuration.
+    pub fn default_raw() -> Result<KeymapRaw, AppError<()>> {
+        configs::deserialize_to_config::<KeymapRaw>(Path::new(
+            &configs::custom::default_config_keymap_file_path()?,
+        ))
+    }
     /// Get the keys associated with an action.
     ///
     /// # Arguments
@@ -92,6 +134,32 @@ impl KeymapConfig {
         }
         keys
     }
+    /// Check that all the keys and the commands of a raw keymap configuration
+    /// are valid, so that it can be merged without exiting.
+    ///
+    /// # Arguments
+    /// * `raw` - The raw keymap configuration.
+    ///
+    /// # Returns
+    /// An error with the first key or command that is not valid.
+    fn check_raw(raw: &KeymapRaw) -> Result<(), AppError<()>> {
+        for mode in [&raw.core_window, &raw.chat_list, &raw.chat, &raw.prompt]
+            .into_iter()
+            .flatten()
+        {
+            for keymap in &mode.keymap {
+                for key in &keymap.keys {
+                    if Event::from_str(key)? == Event::Unknown {
+                        return Err(AppError::InvalidEvent(key.clone()));
+                    }
+                }
+                if Action::from_str(&keymap.command)? == Action::Unknown {
+                    return Err(AppError::InvalidAction(keymap.command.clone()));
+                }
+            }
+        }
+        Ok(())
+    }
     /// Print the configuration file error.
     /// It is used to print the error when the configuration file is not
     /// correct. It prints the unrecognized settings.
@@ -268,35 +336,416 @@ impl KeymapConfig {
     }
 
     /// Check for duplicates in the keymaps.
-    /// It is used to check for duplicates in the keymaps. If a keymap entry is
-    /// present in multiple keymaps, it is considered a conflict.
+    /// It is used to check for duplicates in the keymaps. If a keymap entry of
+    /// a component is also present in the default keymap, it is considered a
+    /// conflict, because the default keymap is used in all the components.
+    /// The same key can be bound in different components.
     ///
     /// # Arguments
     /// * `default` - The default keymap.
     /// * `chat_list` - The chat list keymap.
     /// * `chat` - The chat keymap.
     /// * `prompt` - The prompt keymap.
+    ///
+    /// # Returns
+    /// The conflicts, sorted by section and key.
     fn check_duplicates(
         default: &HashMap<Event, ActionBinding>,
         chat_list: &HashMap<Event, ActionBinding>,
         chat: &HashMap<Event, ActionBinding>,
         prompt: &HashMap<Event, ActionBinding>,
-    ) {
-        let mut all: Vec<&Event> = vec![];
-        all.extend(default.keys());
-        all.extend(chat_list.keys());
-        all.extend(chat.keys());
-        all.extend(prompt.keys());
-
-        let mut duplicates = HashSet::new();
-        for k in all {
-            if !duplicates.insert(k) {
+    ) -> Vec<KeymapConflict> {
+        let mut conflicts = vec![];
+        for (section, map) in [("chat_list", chat_list), ("chat", chat), ("prompt", prompt)] {
+            let mut keys: Vec<&Event> = map.keys().filter(|k| default.contains_key(k)).collect();
+            keys.sort_by_key(|k| k.to_string());
+            for k in keys {
                 tracing::warn!(
-                    "Keymap entry {:?} is already present in another keymap",
+                    "Keymap entry {:?} of the {} section is already present in the core_window section",
                     k.to_string(),
+                    section
                 );
+                conflicts.push(KeymapConflict {
+                    key: k.clone(),
+                    section,
+                });
             }
         }
+        conflicts
+    }
+
+    /// Get the conflicts of the keymaps, i.e. the keys of the components that
+    /// are also bound in the `core_window` section and so never reach the
+    /// component.
+    ///
+    /// # Returns
+    /// The conflicts, empty if there are none.
+    pub fn conflicts(&self) -> Vec<KeymapConflict> {
+        Self::check_duplicates(&self.core_window, &self.chat_list, &self.chat, &self.prompt)
+    }
+
+    /// Get the keymap configuration of a section of `keymap.toml`.
+    ///
+    /// # Arguments
+    /// * `section` - The name of the section, one of `KEYMAP_SECTIONS`.
+    ///
+    /// # Returns
+    /// The keymap configuration of the section, `None` if the section does
+    /// not exist.
+    pub fn get_map_of_section(&self, section: &str) -> Option<&HashMap<Event, ActionBinding>> {
+        match section {
+            "core_window" => Some(&self.core_window),
+            "chat_list" => Some(&self.chat_list),
+            "chat" => Some(&self.chat),
+            "prompt" => Some(&self.prompt),
+            _ => None,
+        }
+    }
+
+    /// Get the warnings of binding a key to an action in a section, before
+    /// the binding is saved: the action bound to the key in the same section,
+    /// which would be replaced, and the new conflicts with the other
+    /// sections, see `conflicts`.
+    ///
+    /// # Arguments
+    /// * `section` - The name of the section, one of `KEYMAP_SECTIONS`.
+    /// * `key` - The key to bind.
+    /// * `action` - The action to bind.
+    ///
+    /// # Returns
+    /// The warnings, empty if the key can be bound safely.
+    pub fn rebind_warnings(&self, section: &str, key: &Event, action: &Action) -> Vec<String> {
+        let mut warnings = vec![];
+        let key_name = key.keymap_key().unwrap_or_else(|| key.to_string());
+        let mut candidate = self.clone();
+        let map = match section {
+            "core_window" => &mut candidate.core_window,
+            "chat_list" => &mut candidate.chat_list,
+            "chat" => &mut candidate.chat,
+            "prompt" => &mut candidate.prompt,
+            _ => return warnings,
+        };
+        let previous = map.insert(
+            key.clone(),
+            ActionBinding::Single {
+                action: action.clone(),
+                description: None,
+            },
+        );
+        match previous {
+            Some(ActionBinding::Single {
+                action: bound,
+                description,
+            }) if bound != *action => warnings.push(format!(
+                "{} is already bound in {}: {}",
+                key_name,
+                section,
+                description.unwrap_or_else(|| format!("{:?}", bound))
+            )),
+            Some(ActionBinding::Multiple(_)) => warnings.push(format!(
+                "{} starts a sequence of keys in {}",
+                key_name, section
+            )),
+            _ => {}
+        }
+        let conflicts = self.conflicts();
+        warnings.extend(
+            candidate
+                .conflicts()
+                .into_iter()
+                .filter(|conflict| !conflicts.contains(conflict))
+                .map(|conflict| format!("Conflict: {}", conflict)),
+        );
+        warnings
+    }
+
+    /// Save a binding in a section of the custom `keymap.toml`, replacing
+    /// the keys bound to the same command in that section.
+    /// The file is created if it does not exist, otherwise only the entry of
+    /// the command is edited, so that the other entries and the comments of
+    /// the user are kept.
+    ///
+    /// # Arguments
+    /// * `section` - The name of the section, one of `KEYMAP_SECTIONS`.
+    /// * `entry` - The keys, the command and the description to save.
+    ///
+    /// # Returns
+    /// The path of the saved file or an error.
+    pub fn save_binding(section: &str, entry: KeymapEntry) -> Result<PathBuf, AppError<()>> {
+        let path = config_file::custom_config_path(&Self::get_type().as_default_filename())?;
+        let mut document = if path.exists() {
+            std::fs::read_to_string(&path)?
+                .parse::<toml_edit::DocumentMut>()
+                .map_err(io::Error::other)?
+        } else {
+            toml_edit::DocumentMut::new()
+        };
+        Self::set_document_binding(&mut document, section, entry)?;
+        let content = document.to_string();
+        // The edited file must still be a valid keymap configuration.
+        toml::from_str::<KeymapRaw>(&content).map_err(io::Error::other)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, content)?;
+        Ok(path)
+    }
+
+    /// Set a binding in a section of a keymap document, replacing the entries
+    /// of the same command in that section.
+    /// The first entry of the command is replaced in place, keeping its
+    /// comments, and the other ones are removed. The binding is appended if
+    /// the command has no entry.
+    ///
+    /// # Arguments
+    /// * `document` - The keymap document.
+    /// * `section` - The name of the section, one of `KEYMAP_SECTIONS`.
+    /// * `entry` - The keys, the command and the description to set.
+    ///
+    /// # Returns
+    /// An error if the section does not exist or its keymap is not an array.
+    fn set_document_binding(
+        document: &mut toml_edit::DocumentMut,
+        section: &str,
+        entry: KeymapEntry,
+    ) -> Result<(), AppError<()>> {
+        let invalid =
+            |message: String| AppError::Io(io::Error::new(io::ErrorKind::InvalidInput, message));
+        if !KEYMAP_SECTIONS.contains(&section) {
+            return Err(invalid(format!("Unknown keymap section: {}", section)));
+        }
+        let mut binding = toml_edit::InlineTable::new();
+        binding.insert(
+            "keys",
+            toml_edit::Value::Array(entry.keys.iter().map(String::as_str).collect()),
+        );
+        binding.insert("command", entry.command.as_str().into());
+        if let Some(description) = &entry.description {
+            binding.insert("description", description.as_str().into());
+        }
+        let is_command = |command: Option<&toml_edit::Value>| {
+            command.and_then(|c| c.as_str()) == Some(entry.command.as_str())
+        };
+
+        let keymap = document
+            .entry(section)
+            .or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| invalid(format!("The keymap section {} is not a table", section)))?
+            .entry("keymap")
+            .or_insert(toml_edit::value(toml_edit::Array::new()));
+        match keymap {
+            toml_edit::Item::Value(toml_edit::Value::Array(array)) => {
+                let positions = array
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, v)| is_command(v.as_inline_table().and_then(|t| t.get("command"))))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+                match positions.split_first() {
+                    Some((first, others)) => {
+                        for i in others.iter().rev() {
+                            array.remove(*i);
+                        }
+                        array.replace(*first, binding);
+                    }
+                    None => {
+                        // The entries of a multiline array are on their own line.
+                        let multiline = array.iter().any(|v| {
+                            v.decor()
+                                .prefix()
+                                .and_then(|p| p.as_str())
+                                .is_some_and(|p| p.contains('\n'))
+                        });
+                        let mut value = toml_edit::Value::InlineTable(binding);
+                        if multiline {
+                            value.decor_mut().set_prefix("\n  ");
+                            array.push_formatted(value);
+                        } else {
+                            array.push(value);
+                        }
+                    }
+                }
+            }
+            toml_edit::Item::ArrayOfTables(tables) => {
+                let positions = tables
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, t)| is_command(t.get("command").and_then(|c| c.as_value())))
+                    .map(|(i, _)| i)
+                    .collect::<Vec<_>>();
+                let binding = binding.into_table();
+                match positions.split_first() {
+                    Some((first, others)) => {
+                        for i in others.iter().rev() {
+                            tables.remove(*i);
+                        }
+                        if let Some(table) = tables.get_mut(*first) {
+                            let decor = table.decor().clone();
+                            *table = binding;
+                            *table.decor_mut() = decor;
+                        }
+                    }
+                    None => tables.push(binding),
+                }
+            }
+            _ => {
+                return Err(invalid(format!(
+                    "The keymap of the section {} is not an array",
+                    section
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the names of the commands of the default and of the custom keymap
+    /// configurations, with their actions.
+    /// They are used to write the actions back as commands.
+    ///
+    /// # Returns
+    /// The actions with the names of their commands.
+    fn command_names() -> Result<Vec<(Action, String)>, AppError<()>> {
+        let custom = Self::try_deserialize_custom_config::<KeymapRaw>(
+            Self::get_type().as_default_filename().as_str(),
+        )?;
+        let mut names: Vec<(Action, String)> = vec![];
+        for raw in std::iter::once(Self::default_raw()?).chain(custom) {
+            for mode in [raw.core_window, raw.chat_list, raw.chat, raw.prompt]
+                .into_iter()
+                .flatten()
+            {
+                for entry in mode.keymap {
+                    if let Ok(action) = Action::from_str(&entry.command) {
+                        if !names.iter().any(|(a, _)| *a == action) {
+                            names.push((action, entry.command));
+                        }
+                    }
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    /// Convert the keymaps back to the raw keymap configuration, e.g. to see
+    /// the key bindings after merging the default and the custom ones.
+    /// The sequences of keys are written as a single entry, and the entries
+    /// are sorted by command and keys.
+    ///
+    /// # Arguments
+    /// * `command_names` - The actions with the names of their commands.
+    ///
+    /// # Returns
+    /// The raw keymap configuration.
+    pub fn to_raw(&self, command_names: &[(Action, String)]) -> KeymapRaw {
+        let mode = |map: &HashMap<Event, ActionBinding>| {
+            let mut keymap = vec![];
+            Self::map_to_entries(map, &mut vec![], command_names, &mut keymap);
+            keymap.sort_by(|a: &KeymapEntry, b| {
+                a.command.cmp(&b.command).then_with(|| a.keys.cmp(&b.keys))
+            });
+            Some(KeymapMode { keymap })
+        };
+        KeymapRaw {
+            core_window: mode(&self.core_window),
+            chat_list: mode(&self.chat_list),
+            chat: mode(&self.chat),
+            prompt: mode(&self.prompt),
+        }
+    }
+
+    /// Convert a keymap to the entries of a raw keymap configuration. It is
+    /// recursive, the keys of the multiple action bindings are prefixed with
+    /// `keys`.
+    ///
+    /// # Arguments
+    /// * `map` - A hashmap of event and action binding.
+    /// * `keys` - The keys pressed before the ones of `map`.
+    /// * `command_names` - The actions with the names of their commands.
+    /// * `entries` - The entries where the bindings are added.
+    fn map_to_entries(
+        map: &HashMap<Event, ActionBinding>,
+        keys: &mut Vec<String>,
+        command_names: &[(Action, String)],
+        entries: &mut Vec<KeymapEntry>,
+    ) {
+        for (event, binding) in map {
+            keys.push(event.keymap_key().unwrap_or_else(|| event.to_string()));
+            match binding {
+                ActionBinding::Single {
+                    action,
+                    description,
+                } => entries.push(KeymapEntry {
+                    keys: keys.clone(),
+                    command: command_names
+                        .iter()
+                        .find(|(a, _)| a == action)
+                        .map(|(_, name)| name.clone())
+                        .unwrap_or_else(|| format!("{:?}", action)),
+                    description: description.clone(),
+                }),
+                ActionBinding::Multiple(map) => {
+                    Self::map_to_entries(map, keys, command_names, entries)
+                }
+            }
+            keys.pop();
+        }
+    }
+
+    /// Format a raw keymap configuration as Markdown, with a table of the
+    /// keys, the commands and the descriptions for each section.
+    ///
+    /// # Arguments
+    /// * `raw` - The raw keymap configuration.
+    ///
+    /// # Returns
+    /// The Markdown text.
+    fn to_markdown(raw: &KeymapRaw) -> String {
+        let mut markdown = String::from("# Key bindings\n");
+        for (section, mode) in KEYMAP_SECTIONS.into_iter().zip([
+            &raw.core_window,
+            &raw.chat_list,
+            &raw.chat,
+            &raw.prompt,
+        ]) {
+            markdown.push_str(&format!(
+                "\n## {}\n\n| Keys | Command | Description |\n| --- | --- | --- |\n",
+                section
+            ));
+            for entry in mode.iter().flat_map(|mode| &mode.keymap) {
+                // The "|" separates the columns of the table.
+                markdown.push_str(&format!(
+                    "| `{}` | `{}` | {} |\n",
+                    entry.keys.join(" ").replace('|', "\\|"),
+                    entry.command,
+                    entry
+                        .description
+                        .as_deref()
+                        .unwrap_or_default()
+                        .replace('|', "\\|")
+                ));
+            }
+        }
+        markdown
+    }
+
+    /// Export the key bindings in use, i.e. the default ones merged with the
+    /// custom ones, to a file.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file to write.
+    /// * `format` - The format of the file.
+    ///
+    /// # Returns
+    /// An error if the file can not be written.
+    pub fn export(&self, path: &Path, format: KeymapFormat) -> Result<(), AppError<()>> {
+        let raw = self.to_raw(&Self::command_names()?);
+        let content = match format {
+            KeymapFormat::Toml => toml::to_string(&raw).map_err(io::Error::other)?,
+            KeymapFormat::Markdown => Self::to_markdown(&raw),
+        };
+        std::fs::write(path, content)?;
+        Ok(())
     }
 
     /// Get the keymap configuration of a component.
@@ -335,6 +784,16 @@ impl ConfigFile for KeymapConfig {
         true
     }
 
+    fn try_get_config() -> Result<Self, AppError<()>> {
+        let other = Self::try_deserialize_custom_config::<Self::Raw>(
+            Self::get_type().as_default_filename().as_str(),
+        )?;
+        if let Some(other) = other.as_ref() {
+            Self::check_raw(other)?;
+        }
+        Ok(Self::default().merge(other))
+    }
+
     fn merge(&mut self, other: Option<Self::Raw>) -> Self {
         match other {
             None => self.clone(),
@@ -427,7 +886,7 @@ mod tests {
             action::Action,
             configs::{
                 config_file::ConfigFile,
-                custom::keymap_custom::{ActionBinding, KeymapConfig},
+                custom::keymap_custom::{ActionBinding, KeymapConfig, KeymapConflict},
                 raw::keymap_raw::{KeymapEntry, KeymapMode, KeymapRaw},
             },
             event::Event,
@@ -435,12 +894,38 @@ mod tests {
         std::str::FromStr,
     };
 
+    /// Build a keymap entry without description.
+    fn entry(keys: &[&str], command: &str) -> KeymapEntry {
+        KeymapEntry {
+            keys: keys.iter().map(|k| k.to_string()).collect(),
+            command: command.to_string(),
+            description: None,
+        }
+    }
+
+    /// Build a raw keymap configuration from the entries of the
+    /// `core_window`, `chat_list`, `chat` and `prompt` sections.
+    fn raw(
+        core_window: Vec<KeymapEntry>,
+        chat_list: Vec<KeymapEntry>,
+        chat: Vec<KeymapEntry>,
+        prompt: Vec<KeymapEntry>,
+    ) -> KeymapRaw {
+        let mode = |keymap| Some(KeymapMode { keymap });
+        KeymapRaw {
+            core_window: mode(core_window),
+            chat_list: mode(chat_list),
+            chat: mode(chat),
+            prompt: mode(prompt),
+        }
+    }
+
     #[test]
     fn test_keymap_config_default() {
         let keymap_config = KeymapConfig::default();
-        assert_eq!(keymap_config.core_window.len(), 15);
-        assert_eq!(keymap_config.chat_list.len(), 5);
-        assert_eq!(keymap_config.chat.len(), 9);
+        assert_eq!(keymap_config.core_window.len(), 34);
+        assert_eq!(keymap_config.chat_list.len(), 15);
+        assert_eq!(keymap_config.chat.len(), 36);
         assert_eq!(keymap_config.prompt.len(), 0);
     }
 
@@ -540,9 +1025,9 @@ mod tests {
             prompt: Some(KeymapMode { keymap: vec![] }),
         };
         keymap_config = keymap_config.merge(Some(keymap_raw));
-        assert_eq!(keymap_config.core_window.len(), 15);
-        assert_eq!(keymap_config.chat_list.len(), 5);
-        assert_eq!(keymap_config.chat.len(), 9);
+        assert_eq!(keymap_config.core_window.len(), 34);
+        assert_eq!(keymap_config.chat_list.len(), 15);
+        assert_eq!(keymap_config.chat.len(), 36);
         assert_eq!(keymap_config.prompt.len(), 0);
     }
 
@@ -553,4 +1038,140 @@ mod tests {
             crate::configs::config_type::ConfigType::Keymap
         );
     }
+
+    #[test]
+    fn test_check_raw() {
+        let chat =
+            |keys: &[&str], command: &str| raw(vec![], vec![], vec![entry(keys, command)], vec![]);
+        assert!(KeymapConfig::check_raw(&chat(&["alt+q"], "quit")).is_ok());
+        assert!(KeymapConfig::check_raw(&chat(&["alt+q", "q"], "quit")).is_ok());
+        assert!(KeymapConfig::check_raw(&chat(&["not_a_key"], "quit")).is_err());
+        assert!(KeymapConfig::check_raw(&chat(&["alt+q"], "not_a_command")).is_err());
+    }
+
+    #[test]
+    fn test_keymap_conflicts() {
+        assert_eq!(KeymapConfig::default().conflicts(), vec![]);
+
+        let keymap_raw = raw(
+            vec![entry(&["q"], "quit")],
+            vec![entry(&["q"], "render")],
+            vec![entry(&["alt+q"], "render")],
+            vec![entry(&["alt+q"], "render")],
+        );
+        let conflicts = KeymapConfig::from(keymap_raw).conflicts();
+        assert_eq!(
+            conflicts,
+            vec![KeymapConflict {
+                key: Event::from_str("q").unwrap(),
+                section: "chat_list",
+            }]
+        );
+        assert_eq!(conflicts[0].to_string(), "q (core_window and chat_list)");
+    }
+
+    #[test]
+    fn test_rebind_warnings() {
+        let keymap_config = KeymapConfig::from(raw(
+            vec![entry(&["q"], "quit")],
+            vec![entry(&["j"], "chat_list_next")],
+            vec![],
+            vec![],
+        ));
+        let next = Action::from_str("chat_list_next").unwrap();
+        let key = |k: &str| Event::from_str(k).unwrap();
+        assert!(keymap_config
+            .rebind_warnings("chat_list", &key("alt+j"), &next)
+            .is_empty());
+        assert!(keymap_config
+            .rebind_warnings("chat_list", &key("j"), &next)
+            .is_empty());
+        assert_eq!(
+            keymap_config.rebind_warnings("chat_list", &key("q"), &next),
+            vec!["Conflict: q (core_window and chat_list)".to_string()]
+        );
+        let render = Action::from_str("render").unwrap();
+        assert_eq!(
+            keymap_config
+                .rebind_warnings("chat_list", &key("j"), &render)
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_set_document_binding() {
+        let mut document = "# The chat list.\n[chat_list]\nkeymap = [\n  # Select the next chat\n  { keys = [\"j\"], command = \"chat_list_next\", description = \"Select the next chat\"},\n  { keys = [\"down\"], command = \"chat_list_next\"},\n  # Select the previous chat\n  { keys = [\"k\"], command = \"chat_list_previous\"},\n]\n"
+            .parse::<toml_edit::DocumentMut>()
+            .unwrap();
+        KeymapConfig::set_document_binding(
+            &mut document,
+            "chat_list",
+            entry(&["alt+j"], "chat_list_next"),
+        )
+        .unwrap();
+        KeymapConfig::set_document_binding(&mut document, "chat", entry(&["r"], "reply_message"))
+            .unwrap();
+        assert!(KeymapConfig::set_document_binding(
+            &mut document,
+            "not_a_section",
+            entry(&["n"], "chat_list_next")
+        )
+        .is_err());
+
+        let content = document.to_string();
+        assert!(content.starts_with("# The chat list.\n[chat_list]\nkeymap = [\n  # Select the next chat\n  { keys = [\"alt+j\"], command = \"chat_list_next\" },\n  # Select the previous chat\n"));
+        let raw: KeymapRaw = toml::from_str(&content).unwrap();
+        assert!(raw.core_window.is_none());
+        let keymap = raw.chat_list.unwrap().keymap;
+        assert_eq!(keymap.len(), 2);
+        assert_eq!(keymap[0].keys, vec!["alt+j".to_string()]);
+        assert_eq!(keymap[1].command, "chat_list_previous");
+        assert_eq!(raw.chat.unwrap().keymap[0].command, "reply_message");
+        assert_eq!(
+            Event::from_str(&keymap[0].keys[0]).unwrap().keymap_key(),
+            Some("alt+j".to_string())
+        );
+    }
+
+    #[test]
+    fn test_to_raw() {
+        let described = |keys: &[&str], command: &str| KeymapEntry {
+            description: Some(format!("Run {}", command)),
+            ..entry(keys, command)
+        };
+        let keymap_raw = raw(
+            vec![
+                described(&["q"], "quit"),
+                described(&["alt+g", "1"], "render"),
+            ],
+            vec![described(&["ctrl+n"], "chat_list_next")],
+            vec![],
+            vec![],
+        );
+        let command_names: Vec<(Action, String)> = ["quit", "render", "chat_list_next"]
+            .into_iter()
+            .map(|c| (Action::from_str(c).unwrap(), c.to_string()))
+            .collect();
+        let keymap_config = KeymapConfig::from(keymap_raw);
+        let raw = keymap_config.to_raw(&command_names);
+        let core_window = &raw.core_window.as_ref().unwrap().keymap;
+        assert_eq!(core_window.len(), 2);
+        assert_eq!(core_window[0].keys, vec!["q".to_string()]);
+        assert_eq!(
+            core_window[1].keys,
+            vec!["alt+g".to_string(), "1".to_string()]
+        );
+        assert_eq!(core_window[1].command, "render");
+
+        let markdown = KeymapConfig::to_markdown(&raw);
+        assert!(markdown.contains("## chat_list"));
+        assert!(markdown.contains("| `ctrl+n` | `chat_list_next` | Run chat_list_next |"));
+
+        // The exported keymap is a valid keymap configuration.
+        let raw: KeymapRaw = toml::from_str(&toml::to_string(&raw).unwrap()).unwrap();
+        let exported = KeymapConfig::from(raw);
+        assert_eq!(exported.core_window, keymap_config.core_window);
+        assert_eq!(exported.chat_list, keymap_config.chat_list);
+    }
 }
@@ -30,6 +30,15 @@ pub struct LoggerConfig {
     /// * trace: log error, warning, info, debug, and trace
     /// * off: turn off logging
     pub log_level: String,
+    /// The format of the log.
+    /// The log format can be one of the following:
+    /// * plain: A human readable line for each event
+    /// * json: A JSON object for each event (JSON lines)
+    pub log_format: String,
+    /// The log level of specific modules, overriding `log_level`.
+    /// It is a comma separated list of `target=level` directives, e.g.
+    /// `tdlib_rs=warn,tgt::tg=debug`.
+    pub log_filters: String,
 }
 /// The logger configuration implementation.
 impl LoggerConfig {
@@ -78,6 +87,12 @@ impl ConfigFile for LoggerConfig {
                 if let Some(max_old_log_files) = other.max_old_log_files {
                     self.max_old_log_files = max_old_log_files;
                 }
+                if let Some(log_format) = other.log_format {
+                    self.log_format = log_format;
+                }
+                if let Some(log_filters) = other.log_filters {
+                    self.log_filters = log_filters;
+                }
                 self.clone()
             }
         }
@@ -93,7 +108,7 @@ impl Default for LoggerConfig {
 /// configuration.
 impl From<LoggerRaw> for LoggerConfig {
     fn from(raw: LoggerRaw) -> Self {
-        let log_dir = utils::tgt_dir()
+        let log_dir = utils::tgt_profile_dir()
             .unwrap()
             .join(raw.log_dir.unwrap())
             .to_string_lossy()
@@ -108,6 +123,8 @@ impl From<LoggerRaw> for LoggerConfig {
             rotation_frequency: raw.rotation_frequency.unwrap(),
             max_old_log_files: raw.max_old_log_files.unwrap(),
             log_level: raw.log_level.unwrap(),
+            log_format: raw.log_format.unwrap(),
+            log_filters: raw.log_filters.unwrap(),
         }
     }
 }
@@ -131,6 +148,8 @@ mod tests {
         );
         assert_eq!(logger_config.log_file, "tgt.log");
         assert_eq!(logger_config.log_level, "info");
+        assert_eq!(logger_config.log_format, "plain");
+        assert_eq!(logger_config.log_filters, "");
     }
 
     #[test]
@@ -141,6 +160,8 @@ mod tests {
             rotation_frequency: Some("hourly".to_string()),
             max_old_log_files: Some(3),
             log_level: Some("debug".to_string()),
+            log_format: Some("json".to_string()),
+            log_filters: Some("tdlib_rs=warn".to_string()),
         };
         let logger_config = LoggerConfig::from(logger_raw);
         assert_eq!(
@@ -155,6 +176,8 @@ mod tests {
         assert_eq!(logger_config.rotation_frequency, "hourly");
         assert_eq!(logger_config.max_old_log_files, 3);
         assert_eq!(logger_config.log_level, "debug");
+        assert_eq!(logger_config.log_format, "json");
+        assert_eq!(logger_config.log_filters, "tdlib_rs=warn");
     }
 
     #[test]
@@ -165,6 +188,8 @@ mod tests {
             rotation_frequency: Some("never".to_string()),
             max_old_log_files: Some(5),
             log_level: Some("info".to_string()),
+            log_format: Some("plain".to_string()),
+            log_filters: Some("".to_string()),
         });
         let logger_raw = LoggerRaw {
             log_dir: None,
@@ -172,6 +197,8 @@ mod tests {
             rotation_frequency: None,
             max_old_log_files: None,
             log_level: Some("debug".to_string()),
+            log_format: Some("json".to_string()),
+            log_filters: Some("tdlib_rs=warn".to_string()),
         };
         logger_config = logger_config.merge(Some(logger_raw));
         assert_eq!(
@@ -186,6 +213,8 @@ mod tests {
         assert_eq!(logger_config.rotation_frequency, "never");
         assert_eq!(logger_config.max_old_log_files, 5);
         assert_eq!(logger_config.log_level, "debug");
+        assert_eq!(logger_config.log_format, "json");
+        assert_eq!(logger_config.log_filters, "tdlib_rs=warn");
     }
 
     #[test]
@@ -202,6 +231,8 @@ mod tests {
             rotation_frequency: None,
             max_old_log_files: None,
             log_level: None,
+            log_format: None,
+            log_filters: None,
         };
         logger_config = logger_config.merge(Some(logger_raw));
         assert_eq!(
@@ -216,6 +247,8 @@ mod tests {
         assert_eq!(logger_config.rotation_frequency, "daily");
         assert_eq!(logger_config.max_old_log_files, 7);
         assert_eq!(logger_config.log_level, "info");
+        assert_eq!(logger_config.log_format, "plain");
+        assert_eq!(logger_config.log_filters, "");
     }
 
     #[test]
@@ -26,6 +26,29 @@ impl PaletteConfig {
             &configs::custom::default_config_palette_file_path()?,
         ))
     }
+    /// Load the palette configuration, merging the default palette with the
+    /// one in the given file, without exiting if the file can not be parsed
+    /// or contains an invalid color.
+    /// It is used to reload the palette at runtime.
+    ///
+    /// # Arguments
+    /// * `theme_filename` - The name of the file of the theme, that contains
+    ///   the palette.
+    ///
+    /// # Returns
+    /// * `Result<Self>` - The palette configuration or an error.
+    pub fn load(theme_filename: &str) -> Result<Self, AppError<()>> {
+        let mut palette_config = Self::default_result()?;
+        if let Some(palette) = Self::try_deserialize_custom_config::<PaletteRaw>(theme_filename)?
+            .and_then(|other| other.palette)
+        {
+            for (k, v) in palette {
+                let color = ThemeStyle::str_to_color(&v)?;
+                palette_config.palette.insert(k, color);
+            }
+        }
+        Ok(palette_config)
+    }
 }
 /// The implementation of the configuration file for the palette.
 impl ConfigFile for PaletteConfig {
@@ -56,6 +79,10 @@ impl ConfigFile for PaletteConfig {
         }
     }
 
+    fn try_get_config() -> Result<Self, AppError<()>> {
+        Self::load(&APP_CONFIG.theme_filename)
+    }
+
     fn merge(&mut self, other: Option<Self::Raw>) -> Self {
         match other {
             None => self.clone(),
@@ -116,7 +143,7 @@ mod tests {
     #[test]
     fn test_palette_config_default() {
         let palette_config = crate::configs::custom::palette_custom::PaletteConfig::default();
-        assert_eq!(palette_config.palette.len(), 16);
+        assert_eq!(palette_config.palette.len(), 28);
     }
 
     #[test]
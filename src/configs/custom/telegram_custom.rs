@@ -7,8 +7,12 @@ use crate::{
 };
 use std::path::Path;
 use std::path::PathBuf;
+use tdlib_rs::{
+    enums::ProxyType,
+    types::{ProxyTypeHttp, ProxyTypeMtproto, ProxyTypeSocks5},
+};
 
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 /// The telegram configuration.
 pub struct TelegramConfig {
     /// The API ID.
@@ -37,6 +41,45 @@ pub struct TelegramConfig {
     pub log_path: String,
     /// A flag that indicates if the log to stderr should be also redirected.
     pub redirect_stderr: bool,
+    /// The type of the proxy: "socks5", "http", "mtproto" or "none".
+    pub proxy_type: String,
+    /// The server of the proxy.
+    pub proxy_server: String,
+    /// The port of the proxy.
+    pub proxy_port: i32,
+    /// The username of the SOCKS5 and HTTP proxies.
+    pub proxy_username: String,
+    /// The password of the SOCKS5 and HTTP proxies.
+    pub proxy_password: String,
+    /// The secret of the MTProto proxy.
+    pub proxy_secret: String,
+}
+/// The debug representation of the telegram configuration.
+/// The password and the secret of the proxy are redacted, so that they are
+/// not written to the logs.
+impl std::fmt::Debug for TelegramConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let redact = |value: &str| if value.is_empty() { "" } else { "<redacted>" };
+        f.debug_struct("TelegramConfig")
+            .field("api_id", &self.api_id)
+            .field("api_hash", &self.api_hash)
+            .field("database_dir", &self.database_dir)
+            .field("use_file_database", &self.use_file_database)
+            .field("use_chat_info_database", &self.use_chat_info_database)
+            .field("use_message_database", &self.use_message_database)
+            .field("system_language_code", &self.system_language_code)
+            .field("device_model", &self.device_model)
+            .field("verbosity_level", &self.verbosity_level)
+            .field("log_path", &self.log_path)
+            .field("redirect_stderr", &self.redirect_stderr)
+            .field("proxy_type", &self.proxy_type)
+            .field("proxy_server", &self.proxy_server)
+            .field("proxy_port", &self.proxy_port)
+            .field("proxy_username", &self.proxy_username)
+            .field("proxy_password", &redact(&self.proxy_password))
+            .field("proxy_secret", &redact(&self.proxy_secret))
+            .finish()
+    }
 }
 /// The telegram configuration implementation.
 impl TelegramConfig {
@@ -107,6 +150,24 @@ impl ConfigFile for TelegramConfig {
                 if let Some(redirect_stderr) = _other.redirect_stderr {
                     self.redirect_stderr = redirect_stderr;
                 }
+                if let Some(proxy_type) = _other.proxy_type {
+                    self.proxy_type = proxy_type;
+                }
+                if let Some(proxy_server) = _other.proxy_server {
+                    self.proxy_server = proxy_server;
+                }
+                if let Some(proxy_port) = _other.proxy_port {
+                    self.proxy_port = proxy_port;
+                }
+                if let Some(proxy_username) = _other.proxy_username {
+                    self.proxy_username = proxy_username;
+                }
+                if let Some(proxy_password) = _other.proxy_password {
+                    self.proxy_password = proxy_password;
+                }
+                if let Some(proxy_secret) = _other.proxy_secret {
+                    self.proxy_secret = proxy_secret;
+                }
                 self.clone()
             }
         }
@@ -122,12 +183,12 @@ impl Default for TelegramConfig {
 /// configuration.
 impl From<TelegramRaw> for TelegramConfig {
     fn from(raw: TelegramRaw) -> Self {
-        let database_dir = utils::tgt_dir()
+        let database_dir = utils::tgt_profile_dir()
             .unwrap()
             .join(raw.database_dir.unwrap())
             .to_string_lossy()
             .to_string();
-        let log_path = utils::tgt_dir()
+        let log_path = utils::tgt_profile_dir()
             .unwrap()
             .join(raw.log_path.unwrap())
             .to_string_lossy()
@@ -152,9 +213,110 @@ impl From<TelegramRaw> for TelegramConfig {
             verbosity_level: raw.verbosity_level.unwrap(),
             log_path,
             redirect_stderr: raw.redirect_stderr.unwrap(),
+            proxy_type: raw.proxy_type.unwrap(),
+            proxy_server: raw.proxy_server.unwrap(),
+            proxy_port: raw.proxy_port.unwrap(),
+            proxy_username: raw.proxy_username.unwrap(),
+            proxy_password: raw.proxy_password.unwrap(),
+            proxy_secret: raw.proxy_secret.unwrap(),
         }
     }
 }
+/// The overrides of the telegram configuration.
+impl TelegramConfig {
+    /// Get the proxy of the configuration, validating its fields.
+    ///
+    /// # Returns
+    /// The server, the port and the type of the proxy, `None` if no proxy is
+    /// configured, or an error if the configuration of the proxy is not
+    /// valid.
+    pub fn proxy(&self) -> Result<Option<(String, i32, ProxyType)>, AppError<()>> {
+        let r#type = match self.proxy_type.to_lowercase().as_str() {
+            "" | "none" => return Ok(None),
+            "socks5" => ProxyType::Socks5(ProxyTypeSocks5 {
+                username: self.proxy_username.clone(),
+                password: self.proxy_password.clone(),
+            }),
+            "http" => ProxyType::Http(ProxyTypeHttp {
+                username: self.proxy_username.clone(),
+                password: self.proxy_password.clone(),
+                http_only: false,
+            }),
+            "mtproto" => {
+                if self.proxy_secret.is_empty() {
+                    return Err(AppError::InvalidProxy(
+                        "an MTProto proxy needs the proxy_secret".to_string(),
+                    ));
+                }
+                ProxyType::Mtproto(ProxyTypeMtproto {
+                    secret: self.proxy_secret.clone(),
+                })
+            }
+            proxy_type => {
+                return Err(AppError::InvalidProxy(format!(
+                    "unknown proxy_type \"{proxy_type}\", expected socks5, http, mtproto or none"
+                )))
+            }
+        };
+        if self.proxy_server.trim().is_empty() {
+            return Err(AppError::InvalidProxy(
+                "the proxy_server is empty".to_string(),
+            ));
+        }
+        if !(1..=65535).contains(&self.proxy_port) {
+            return Err(AppError::InvalidProxy(format!(
+                "the proxy_port {} is not between 1 and 65535",
+                self.proxy_port
+            )));
+        }
+        Ok(Some((
+            self.proxy_server.trim().to_string(),
+            self.proxy_port,
+            r#type,
+        )))
+    }
+
+    /// Override the directory of the database and the path of the log file,
+    /// e.g. with the ones given on the command line, so that several
+    /// instances of the application can use different accounts.
+    /// The missing directories are created.
+    ///
+    /// # Arguments
+    /// * `database_dir` - The directory where the database is stored.
+    /// * `log_path` - The path of the log file.
+    ///
+    /// # Returns
+    /// An error if a directory can not be created or if a path is not valid.
+    pub fn override_paths(
+        &mut self,
+        database_dir: Option<&str>,
+        log_path: Option<&str>,
+    ) -> Result<(), AppError<()>> {
+        if let Some(database_dir) = database_dir {
+            if Path::new(database_dir).is_file() {
+                return Err(AppError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("The database dir {database_dir} is a file"),
+                )));
+            }
+            std::fs::create_dir_all(database_dir)?;
+            self.database_dir = database_dir.to_string();
+        }
+        if let Some(log_path) = log_path {
+            if Path::new(log_path).is_dir() {
+                return Err(AppError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("The log path {log_path} is a directory"),
+                )));
+            }
+            if let Some(parent) = Path::new(log_path).parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            self.log_path = log_path.to_string();
+        }
+        Ok(())
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -165,6 +327,8 @@ mod tests {
         },
         utils,
     };
+    use std::path::Path;
+    use tdlib_rs::enums::ProxyType;
 
     #[test]
     fn test_telegram_config_default() {
@@ -187,6 +351,12 @@ mod tests {
             verbosity_level: Some(1),
             log_path: Some(".data/tdlib_rs/tdlib_rs.log".to_string()),
             redirect_stderr: Some(true),
+            proxy_type: Some("socks5".to_string()),
+            proxy_server: Some("127.0.0.1".to_string()),
+            proxy_port: Some(1080),
+            proxy_username: Some("username".to_string()),
+            proxy_password: Some("password".to_string()),
+            proxy_secret: Some(String::new()),
         };
         let telegram_config = TelegramConfig::from(telegram_raw);
         assert_eq!(telegram_config.api_id, "api_id");
@@ -214,6 +384,12 @@ mod tests {
                 .to_string()
         );
         assert!(telegram_config.redirect_stderr);
+        assert_eq!(telegram_config.proxy_type, "socks5");
+        assert_eq!(telegram_config.proxy_server, "127.0.0.1");
+        assert_eq!(telegram_config.proxy_port, 1080);
+        assert_eq!(telegram_config.proxy_username, "username");
+        assert_eq!(telegram_config.proxy_password, "password");
+        assert_eq!(telegram_config.proxy_secret, "");
     }
 
     #[test]
@@ -230,6 +406,12 @@ mod tests {
             verbosity_level: 1,
             log_path: ".data/tdlib_rs/tdlib_rs.log".to_string(),
             redirect_stderr: false,
+            proxy_type: String::new(),
+            proxy_server: String::new(),
+            proxy_port: 0,
+            proxy_username: String::new(),
+            proxy_password: String::new(),
+            proxy_secret: String::new(),
         };
         let telegram_raw = TelegramRaw {
             api_id: Some("api_id_2".to_string()),
@@ -243,6 +425,12 @@ mod tests {
             verbosity_level: Some(2),
             log_path: None,
             redirect_stderr: Some(true),
+            proxy_type: None,
+            proxy_server: None,
+            proxy_port: None,
+            proxy_username: None,
+            proxy_password: None,
+            proxy_secret: None,
         };
         let telegram_config = telegram_config.merge(Some(telegram_raw));
         assert_eq!(telegram_config.api_id, "api_id_2");
@@ -275,6 +463,12 @@ mod tests {
             verbosity_level: 1,
             log_path: ".data/tdlib_rs/tdlib_rs.log".to_string(),
             redirect_stderr: false,
+            proxy_type: String::new(),
+            proxy_server: String::new(),
+            proxy_port: 0,
+            proxy_username: String::new(),
+            proxy_password: String::new(),
+            proxy_secret: String::new(),
         };
         let telegram_config = telegram_config.merge(None);
         assert_eq!(telegram_config.api_id, "api_id");
@@ -304,6 +498,12 @@ mod tests {
             verbosity_level: 1,
             log_path: ".data/tdlib_rs/tdlib_rs.log".to_string(),
             redirect_stderr: false,
+            proxy_type: String::new(),
+            proxy_server: String::new(),
+            proxy_port: 0,
+            proxy_username: String::new(),
+            proxy_password: String::new(),
+            proxy_secret: String::new(),
         };
         let telegram_raw = TelegramRaw {
             api_id: Some("api_id_2".to_string()),
@@ -317,6 +517,12 @@ mod tests {
             verbosity_level: None,
             log_path: None,
             redirect_stderr: Some(true),
+            proxy_type: None,
+            proxy_server: None,
+            proxy_port: None,
+            proxy_username: None,
+            proxy_password: None,
+            proxy_secret: None,
         };
         let telegram_config = telegram_config.merge(Some(telegram_raw));
         assert_eq!(telegram_config.api_id, "api_id_2");
@@ -351,6 +557,12 @@ mod tests {
             verbosity_level: 1,
             log_path: ".data/tdlib_rs/tdlib_rs.log".to_string(),
             redirect_stderr: false,
+            proxy_type: String::new(),
+            proxy_server: String::new(),
+            proxy_port: 0,
+            proxy_username: String::new(),
+            proxy_password: String::new(),
+            proxy_secret: String::new(),
         };
         let telegram_raw = TelegramRaw {
             api_id: Some("api_id_2".to_string()),
@@ -364,6 +576,12 @@ mod tests {
             verbosity_level: Some(2),
             log_path: None,
             redirect_stderr: Some(true),
+            proxy_type: None,
+            proxy_server: None,
+            proxy_port: None,
+            proxy_username: None,
+            proxy_password: None,
+            proxy_secret: None,
         };
         let telegram_config = telegram_config.merge(Some(telegram_raw));
         assert_eq!(telegram_config.api_id, "api_id_2");
@@ -382,6 +600,71 @@ mod tests {
         assert!(telegram_config.redirect_stderr);
     }
 
+    #[test]
+    fn test_telegram_override_paths() {
+        let mut telegram_config = TelegramConfig::default();
+        let log_path = telegram_config.log_path.clone();
+        let dir = std::env::temp_dir().join("tgt_test_override_paths");
+        let database_dir = dir.join("tg").to_string_lossy().to_string();
+        telegram_config
+            .override_paths(Some(&database_dir), None)
+            .unwrap();
+        assert_eq!(telegram_config.database_dir, database_dir);
+        assert_eq!(telegram_config.log_path, log_path);
+        assert!(Path::new(&database_dir).is_dir());
+
+        let log_path = dir.join("logs/tdlib_rs.log").to_string_lossy().to_string();
+        telegram_config
+            .override_paths(None, Some(&log_path))
+            .unwrap();
+        assert_eq!(telegram_config.log_path, log_path);
+        assert!(Path::new(&log_path).parent().unwrap().is_dir());
+
+        // A directory can not be used as the log file.
+        assert!(telegram_config
+            .override_paths(None, Some(&database_dir))
+            .is_err());
+        assert_eq!(telegram_config.log_path, log_path);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_telegram_proxy() {
+        let mut telegram_config = TelegramConfig {
+            proxy_type: "none".to_string(),
+            ..Default::default()
+        };
+        assert!(telegram_config.proxy().unwrap().is_none());
+
+        telegram_config.proxy_type = "SOCKS5".to_string();
+        telegram_config.proxy_server = " 127.0.0.1 ".to_string();
+        telegram_config.proxy_port = 1080;
+        let (server, port, r#type) = telegram_config.proxy().unwrap().unwrap();
+        assert_eq!(server, "127.0.0.1");
+        assert_eq!(port, 1080);
+        assert!(matches!(r#type, ProxyType::Socks5(_)));
+
+        telegram_config.proxy_port = 0;
+        assert!(telegram_config.proxy().is_err());
+        telegram_config.proxy_port = 443;
+        telegram_config.proxy_type = "mtproto".to_string();
+        telegram_config.proxy_secret = String::new();
+        assert!(telegram_config.proxy().is_err());
+        telegram_config.proxy_secret = "secret".to_string();
+        assert!(matches!(
+            telegram_config.proxy(),
+            Ok(Some((_, 443, ProxyType::Mtproto(_))))
+        ));
+        telegram_config.proxy_type = "tor".to_string();
+        assert!(telegram_config.proxy().is_err());
+
+        telegram_config.proxy_password = "password".to_string();
+        let debug = format!("{:?}", telegram_config);
+        assert!(!debug.contains("\"secret\""));
+        assert!(!debug.contains("\"password\""));
+        assert!(debug.contains("proxy_secret: \"<redacted>\""));
+    }
+
     #[test]
     fn test_get_type() {
         assert_eq!(
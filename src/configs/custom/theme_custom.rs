@@ -2,11 +2,15 @@ use {
     crate::{
         app_error::AppError,
         configs::{
-            self, config_file::ConfigFile, config_theme::ThemeStyle, config_type::ConfigType,
-            raw::theme_raw::ThemeRaw,
+            self,
+            config_file::ConfigFile,
+            config_theme::ThemeStyle,
+            config_type::ConfigType,
+            raw::theme_raw::{ThemeEntry, ThemeRaw},
         },
-        APP_CONFIG,
+        APP_CONFIG, PALETTE_CONFIG,
     },
+    ratatui::style::Color,
     std::{collections::HashMap, path::Path},
 };
 
@@ -39,6 +43,114 @@ impl ThemeConfig {
             &configs::custom::default_config_theme_file_path()?,
         ))
     }
+    /// Load the theme configuration, merging the default theme with the one
+    /// in the given file, without exiting if the file can not be parsed.
+    /// It is used to reload the theme at runtime.
+    ///
+    /// # Arguments
+    /// * `theme_filename` - The name of the file of the theme.
+    /// * `palette` - The palette used to resolve the colors of the theme.
+    ///
+    /// # Returns
+    /// * `Result<Self>` - The theme configuration or an error.
+    pub fn load(
+        theme_filename: &str,
+        palette: &HashMap<String, Color>,
+    ) -> Result<Self, AppError<()>> {
+        let default = configs::deserialize_to_config::<ThemeRaw>(Path::new(
+            &configs::custom::default_config_theme_file_path()?,
+        ))?;
+        let mut theme_config = Self::from_raw(default, palette);
+        if let Some(other) = Self::try_deserialize_custom_config::<ThemeRaw>(theme_filename)? {
+            theme_config.merge_raw(other, palette);
+        }
+        Ok(theme_config)
+    }
+    /// Convert the raw theme configuration to the theme configuration.
+    ///
+    /// # Arguments
+    /// * `raw` - The raw theme configuration, with all the sections.
+    /// * `palette` - The palette used to resolve the colors of the theme.
+    ///
+    /// # Returns
+    /// * `Self` - The theme configuration.
+    pub fn from_raw(raw: ThemeRaw, palette: &HashMap<String, Color>) -> Self {
+        let styles = |entries: Option<HashMap<String, ThemeEntry>>| {
+            entries
+                .unwrap()
+                .into_iter()
+                .map(|(k, v)| (k, ThemeStyle::from_entry(v, palette)))
+                .collect()
+        };
+        Self {
+            common: styles(raw.common),
+            chat_list: styles(raw.chat_list),
+            chat: styles(raw.chat),
+            prompt: styles(raw.prompt),
+            status_bar: styles(raw.status_bar),
+            title_bar: styles(raw.title_bar),
+            reply_message: styles(raw.reply_message),
+        }
+    }
+    /// Override the styles of the theme configuration with the ones of a raw
+    /// theme configuration.
+    ///
+    /// # Arguments
+    /// * `other` - The raw theme configuration, its sections are optional.
+    /// * `palette` - The palette used to resolve the colors of the theme.
+    fn merge_raw(&mut self, other: ThemeRaw, palette: &HashMap<String, Color>) {
+        let merge = |styles: &mut HashMap<String, ThemeStyle>,
+                     entries: Option<HashMap<String, ThemeEntry>>| {
+            entries.into_iter().flatten().for_each(|(k, v)| {
+                styles.insert(k, ThemeStyle::from_entry(v, palette));
+            });
+        };
+        merge(&mut self.common, other.common);
+        merge(&mut self.chat_list, other.chat_list);
+        merge(&mut self.chat, other.chat);
+        merge(&mut self.prompt, other.prompt);
+        merge(&mut self.status_bar, other.status_bar);
+        merge(&mut self.title_bar, other.title_bar);
+        merge(&mut self.reply_message, other.reply_message);
+    }
+    /// Get the palette of the styles of the names of the senders in group
+    /// chats, that is the `message_sender_name_<n>` entries of the `chat`
+    /// section ordered by `n`.
+    ///
+    /// # Returns
+    /// * `Vec<&ThemeStyle>` - The styles of the palette.
+    pub fn sender_name_palette(&self) -> Vec<&ThemeStyle> {
+        let mut palette: Vec<(u32, &ThemeStyle)> = self
+            .chat
+            .iter()
+            .filter_map(|(k, v)| {
+                k.strip_prefix("message_sender_name_")?
+                    .parse()
+                    .ok()
+                    .map(|n| (n, v))
+            })
+            .collect();
+        palette.sort_by_key(|(n, _)| *n);
+        palette.into_iter().map(|(_, v)| v).collect()
+    }
+    /// Get the style of the name of a sender in group chats, chosen from the
+    /// palette by hashing the id of the sender, so that a sender always has
+    /// the same color.
+    ///
+    /// # Arguments
+    /// * `sender_id` - The id of the sender.
+    ///
+    /// # Returns
+    /// * `Option<&ThemeStyle>` - The style, `None` if the palette is empty.
+    pub fn sender_name_style(&self, sender_id: i64) -> Option<&ThemeStyle> {
+        let palette = self.sender_name_palette();
+        if palette.is_empty() {
+            return None;
+        }
+        // Fibonacci hashing spreads consecutive ids over the palette.
+        let hash = (sender_id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32;
+        Some(palette[(hash % palette.len() as u64) as usize])
+    }
 }
 /// The implementation of the configuration file for the theme.
 impl ConfigFile for ThemeConfig {
@@ -68,46 +180,16 @@ impl ConfigFile for ThemeConfig {
         }
     }
 
+    fn try_get_config() -> Result<Self, AppError<()>> {
+        Self::load(&APP_CONFIG.theme_filename, &PALETTE_CONFIG.palette)
+    }
+
     fn merge(&mut self, other: Option<Self::Raw>) -> Self {
         match other {
             None => self.clone(),
             Some(other) => {
                 tracing::info!("Merging theme config");
-                if let Some(common) = other.common {
-                    common.into_iter().for_each(|(k, v)| {
-                        self.common.insert(k, ThemeStyle::from(v));
-                    });
-                }
-                if let Some(chat_list) = other.chat_list {
-                    chat_list.into_iter().for_each(|(k, v)| {
-                        self.chat_list.insert(k, ThemeStyle::from(v));
-                    });
-                }
-                if let Some(chat) = other.chat {
-                    chat.into_iter().for_each(|(k, v)| {
-                        self.chat.insert(k, ThemeStyle::from(v));
-                    });
-                }
-                if let Some(prompt) = other.prompt {
-                    prompt.into_iter().for_each(|(k, v)| {
-                        self.prompt.insert(k, ThemeStyle::from(v));
-                    });
-                }
-                if let Some(status_bar) = other.status_bar {
-                    status_bar.into_iter().for_each(|(k, v)| {
-                        self.status_bar.insert(k, ThemeStyle::from(v));
-                    });
-                }
-                if let Some(title_bar) = other.title_bar {
-                    title_bar.into_iter().for_each(|(k, v)| {
-                        self.title_bar.insert(k, ThemeStyle::from(v));
-                    });
-                }
-                if let Some(reply_message) = other.reply_message {
-                    reply_message.into_iter().for_each(|(k, v)| {
-                        self.reply_message.insert(k, ThemeStyle::from(v));
-                    });
-                }
+                self.merge_raw(other, &PALETTE_CONFIG.palette);
                 self.clone()
             }
         }
@@ -122,58 +204,7 @@ impl Default for ThemeConfig {
 /// The conversion from the raw theme configuration to the theme configuration.
 impl From<ThemeRaw> for ThemeConfig {
     fn from(raw: ThemeRaw) -> Self {
-        let common = raw
-            .common
-            .unwrap()
-            .into_iter()
-            .map(|(k, v)| (k, ThemeStyle::from(v)))
-            .collect();
-        let chat_list = raw
-            .chat_list
-            .unwrap()
-            .into_iter()
-            .map(|(k, v)| (k, ThemeStyle::from(v)))
-            .collect();
-        let chat = raw
-            .chat
-            .unwrap()
-            .into_iter()
-            .map(|(k, v)| (k, ThemeStyle::from(v)))
-            .collect();
-        let prompt = raw
-            .prompt
-            .unwrap()
-            .into_iter()
-            .map(|(k, v)| (k, ThemeStyle::from(v)))
-            .collect();
-        let status_bar = raw
-            .status_bar
-            .unwrap()
-            .into_iter()
-            .map(|(k, v)| (k, ThemeStyle::from(v)))
-            .collect();
-        let title_bar = raw
-            .title_bar
-            .unwrap()
-            .into_iter()
-            .map(|(k, v)| (k, ThemeStyle::from(v)))
-            .collect();
-        let reply_message = raw
-            .reply_message
-            .unwrap()
-            .into_iter()
-            .map(|(k, v)| (k, ThemeStyle::from(v)))
-            .collect();
-
-        Self {
-            common,
-            chat_list,
-            chat,
-            prompt,
-            status_bar,
-            title_bar,
-            reply_message,
-        }
+        Self::from_raw(raw, &PALETTE_CONFIG.palette)
     }
 }
 
@@ -194,13 +225,52 @@ mod tests {
     fn test_theme_config_default() {
         let theme_config = crate::configs::custom::theme_custom::ThemeConfig::default();
         assert_eq!(theme_config.common.len(), 3);
-        assert_eq!(theme_config.chat_list.len(), 5);
-        assert_eq!(theme_config.chat.len(), 11);
-        assert_eq!(theme_config.prompt.len(), 4);
-        assert_eq!(theme_config.status_bar.len(), 9);
+        assert_eq!(theme_config.chat_list.len(), 6);
+        assert_eq!(theme_config.chat.len(), 27);
+        assert_eq!(theme_config.prompt.len(), 5);
+        assert_eq!(theme_config.status_bar.len(), 13);
         assert_eq!(theme_config.title_bar.len(), 4);
     }
 
+    #[test]
+    fn test_theme_config_default_message_styles() {
+        let theme_config = crate::configs::custom::theme_custom::ThemeConfig::default();
+        for key in [
+            "message_myself_name",
+            "message_myself_content",
+            "message_other_name",
+            "message_other_content",
+        ] {
+            assert!(theme_config.chat.contains_key(key), "missing {}", key);
+        }
+        assert_ne!(
+            theme_config.chat["message_myself_name"].fg,
+            theme_config.chat["message_other_name"].fg
+        );
+    }
+
+    #[test]
+    fn test_theme_config_sender_name_style() {
+        let mut theme_config = crate::configs::custom::theme_custom::ThemeConfig::default();
+        assert_eq!(theme_config.sender_name_palette().len(), 8);
+        for sender_id in [1, 42, 777000, -1001234567890] {
+            let style = theme_config.sender_name_style(sender_id).unwrap();
+            assert_eq!(
+                style.fg,
+                theme_config.sender_name_style(sender_id).unwrap().fg
+            );
+        }
+        let colors: std::collections::HashSet<_> = (0..100)
+            .map(|id| format!("{:?}", theme_config.sender_name_style(id).unwrap().fg))
+            .collect();
+        assert!(colors.len() > 1);
+
+        theme_config
+            .chat
+            .retain(|k, _| !k.starts_with("message_sender_name_"));
+        assert!(theme_config.sender_name_style(42).is_none());
+    }
+
     #[test]
     fn test_theme_config_from_raw_empty() {
         let theme_raw = ThemeRaw {
@@ -423,10 +493,10 @@ mod tests {
         };
         theme_config = theme_config.merge(Some(theme_raw));
         assert_eq!(theme_config.common.len(), 3);
-        assert_eq!(theme_config.chat_list.len(), 5);
-        assert_eq!(theme_config.chat.len(), 11);
-        assert_eq!(theme_config.prompt.len(), 4);
-        assert_eq!(theme_config.status_bar.len(), 9);
+        assert_eq!(theme_config.chat_list.len(), 6);
+        assert_eq!(theme_config.chat.len(), 27);
+        assert_eq!(theme_config.prompt.len(), 5);
+        assert_eq!(theme_config.status_bar.len(), 13);
         assert_eq!(theme_config.title_bar.len(), 4);
         assert_eq!(theme_config.reply_message.len(), 2);
     }
@@ -1,4 +1,5 @@
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Clone, Debug, Deserialize)]
 /// The raw application configuration.
@@ -26,4 +27,64 @@ pub struct AppRaw {
     /// A boolean flag that represents whether the API_HASH should be taken from
     /// the Telegram configuration or from environment variables `API_HASH`.
     pub take_api_hash_from_telegram_config: Option<bool>,
+    /// A boolean flag that represents whether the name of the sender should be
+    /// included when a message is copied to the clipboard or not.
+    pub copy_message_with_sender: Option<bool>,
+    /// The number of seconds of inactivity after which the account is set
+    /// offline. The value 0 disables the automatic away.
+    pub away_after_seconds: Option<u64>,
+    /// Whether to scroll to the first unread message when a chat is opened.
+    pub jump_to_unread: Option<bool>,
+    /// The order of the chats in the chat list.
+    pub chat_list_sort: Option<String>,
+    /// Whether to show a desktop notification for the messages received in the
+    /// chats that are not open.
+    pub desktop_notifications: Option<bool>,
+    /// Whether the unread messages of the muted chats are counted in the total
+    /// unread badge.
+    pub unread_count_include_muted: Option<bool>,
+    /// Whether to hide the header of the consecutive messages of the same sender.
+    pub compact_messages: Option<bool>,
+    /// The maximum number of seconds between two consecutive messages of the
+    /// same sender whose header is hidden in compact mode.
+    pub compact_messages_window_seconds: Option<u64>,
+    /// A boolean flag that represents whether a single click opens a chat.
+    pub mouse_single_click_open: Option<bool>,
+    /// The maximum time between two clicks of a double click.
+    pub mouse_double_click_ms: Option<u64>,
+    /// The width under which the small area layout is used.
+    pub small_area_width: Option<u16>,
+    /// The minimum height of the core window.
+    pub small_area_height: Option<u16>,
+    /// A boolean flag that represents whether the numbers of the first chats are shown.
+    pub show_chat_list_numbers: Option<bool>,
+    /// The maximum width of the wrapped messages in the chat, 0 for no maximum.
+    pub max_wrap_width: Option<u16>,
+    /// The command used to play the voice notes.
+    pub voice_note_player: Option<String>,
+    /// Ring the terminal bell when a message is received.
+    pub bell_on_new_message: Option<bool>,
+    /// Ring the terminal bell also for the open chat.
+    pub bell_in_open_chat: Option<bool>,
+    /// Whether the filter of the chat list is case sensitive.
+    pub chat_list_filter_case_sensitive: Option<bool>,
+    /// Whether to split the messages longer than the limit of Telegram into multiple messages.
+    pub split_long_messages: Option<bool>,
+    /// The names shown instead of the Telegram names of some users, by user
+    /// id.
+    pub aliases: Option<HashMap<String, String>>,
+    /// Whether the messages are marked as read when they are shown while scrolling
+    /// instead of when the chat is opened.
+    pub mark_read_on_scroll: Option<bool>,
+    /// The order of the components in the focus cycle of `focus_next` and
+    /// `focus_previous`.
+    pub focus_order: Option<Vec<String>>,
+    /// Whether to show the line with the hints of the key bindings.
+    pub show_hint_line: Option<bool>,
+    /// Whether to receive the messages with a single emoji as text instead of as animated emoji.
+    pub disable_animated_emoji: Option<bool>,
+    /// Whether to show the preview of the last message of the chats in the chat list.
+    pub show_chat_previews: Option<bool>,
+    /// The interval, in milliseconds, at which the user interface is redrawn when nothing changed.
+    pub idle_redraw_ms: Option<u64>,
 }
@@ -1,6 +1,6 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 /// The command keymap configuration.
 pub struct KeymapEntry {
     /// The key combination.
@@ -9,11 +9,12 @@ pub struct KeymapEntry {
     /// The command to execute.
     /// It must be a valid command.
     pub command: String, // Action
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The description of the command.
     pub description: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 /// The keymap configuration.
 pub struct KeymapMode {
     #[serde(default)]
@@ -21,15 +22,19 @@ pub struct KeymapMode {
     pub keymap: Vec<KeymapEntry>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 /// The raw keymap configuration.
 pub struct KeymapRaw {
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The keymap for the core window mode, they are used in all components.
     pub core_window: Option<KeymapMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The keymap for the chat list mode.
     pub chat_list: Option<KeymapMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The keymap for the chat mode.
     pub chat: Option<KeymapMode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     /// The keymap for the chat edit mode.
     pub prompt: Option<KeymapMode>,
 }
@@ -25,4 +25,13 @@ pub struct LoggerRaw {
     /// * trace: log error, warning, info, debug, and trace
     /// * off: turn off logging
     pub log_level: Option<String>,
+    /// The format of the log.
+    /// The log format can be one of the following:
+    /// * plain: A human readable line for each event
+    /// * json: A JSON object for each event (JSON lines)
+    pub log_format: Option<String>,
+    /// The log level of specific modules, overriding `log_level`.
+    /// It is a comma separated list of `target=level` directives, e.g.
+    /// `tdlib_rs=warn,tgt::tg=debug`.
+    pub log_filters: Option<String>,
 }
@@ -29,4 +29,16 @@ pub struct TelegramRaw {
     pub log_path: Option<String>,
     /// A flag that indicates if the log to stderr should be also redirected.
     pub redirect_stderr: Option<bool>,
+    /// The type of the proxy: "socks5", "http", "mtproto" or "none".
+    pub proxy_type: Option<String>,
+    /// The server of the proxy.
+    pub proxy_server: Option<String>,
+    /// The port of the proxy.
+    pub proxy_port: Option<i32>,
+    /// The username of the SOCKS5 and HTTP proxies.
+    pub proxy_username: Option<String>,
+    /// The password of the SOCKS5 and HTTP proxies.
+    pub proxy_password: Option<String>,
+    /// The secret of the MTProto proxy.
+    pub proxy_secret: Option<String>,
 }
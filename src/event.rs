@@ -1,5 +1,5 @@
 use crate::app_error::AppError;
-use crate::tg::td_enums::{TdChatList, TdMessageReplyToMessage};
+use crate::tg::td_enums::{TdChatList, TdFile, TdMessageReplyToMessage};
 use crossterm::event::{KeyCode, KeyModifiers, MouseEvent};
 use ratatui::layout::Rect;
 use std::fmt::{self, Display, Formatter};
@@ -40,6 +40,10 @@ pub enum Event {
     /// This event is used to reply to a message.
     /// The first parameter is the `message_id` and the second parameter is the `text`.
     ReplyMessage(i64, String),
+    /// QuoteReplyMessage event with a `String`.
+    /// This event is used to reply to a message quoting its text.
+    /// The first parameter is the `message_id` and the second parameter is the `text`.
+    QuoteReplyMessage(i64, String),
 
     /// GetMe event.
     GetMe,
@@ -53,15 +57,197 @@ pub enum Event {
     /// Send message edited event with a `i64` and a `String`.
     /// The first parameter is the `message_id` and the second parameter is the `text`.
     SendMessageEdited(i64, String),
-    /// Get chat history event.
-    GetChatHistory,
+    /// Send photo event with a `String`, a `String` and an optional
+    /// `TdMessageReplyToMessage`.
+    /// The first parameter is the path of the photo and the second one is the
+    /// caption.
+    SendPhoto(String, String, Option<TdMessageReplyToMessage>),
+    /// Get chat history event with a `i64`.
+    /// The parameter is the `chat_id` of the chat whose history is loaded.
+    GetChatHistory(i64),
     /// Delete messages event with a `Vec<i64>` and a `bool`.
     /// The first parameter is the `message_ids` and the second parameter is the `revoke`.
     /// If `revoke` is true, the message will be deleted for everyone.
     /// If `revoke` is false, the message will be deleted only for the current user.
     DeleteMessages(Vec<i64>, bool),
+    /// Forward messages event with a `i64`, a `i64` and a `Vec<i64>`.
+    /// The first parameter is the `chat_id` of the chat to forward the
+    /// messages to, the second parameter is the `from_chat_id` and the third
+    /// parameter is the `message_ids`.
+    ForwardMessages(i64, i64, Vec<i64>),
     /// View all messages event.
     ViewAllMessages,
+    /// View messages event with an `i64` and a `Vec<i64>`.
+    /// This event is used to mark as read the messages shown in the chat
+    /// window.
+    /// The first parameter is the `chat_id` and the second one is the list
+    /// of `message_id`.
+    ViewMessages(i64, Vec<i64>),
+    /// Open chat event with a `i64`.
+    /// This event is used to inform Telegram that a chat has been opened.
+    /// The parameter is the `chat_id`.
+    OpenChat(i64),
+    /// Set auto-delete time event with a `i64` and a `i32`.
+    /// This event is used to set the auto-delete timer of a chat.
+    /// The first parameter is the `chat_id` and the second one is the time
+    /// in seconds, 0 to disable it.
+    SetAutoDeleteTime(i64, i32),
+    /// Pin message event with two `i64` and a `bool`.
+    /// This event is used to pin a message of a chat.
+    /// The first parameter is the `chat_id`, the second one is the
+    /// `message_id` and the third one is whether to notify the members of the
+    /// group.
+    PinMessage(i64, i64, bool),
+    /// Unpin message event with two `i64`.
+    /// This event is used to unpin a message of a chat.
+    /// The first parameter is the `chat_id` and the second one is the
+    /// `message_id`.
+    UnpinMessage(i64, i64),
+    /// Get message viewers event with two `i64`.
+    /// This event is used to get the users that have read a message of a
+    /// group.
+    /// The first parameter is the `chat_id` and the second one is the
+    /// `message_id`.
+    GetMessageViewers(i64, i64),
+    /// Clear chat history event with a `i64` and two `bool`.
+    /// This event is used to delete all the messages of a chat.
+    /// The first parameter is the `chat_id`, the second one is whether to
+    /// remove the chat from the chat list too and the third one is whether to
+    /// delete the messages for the other users too.
+    ClearChatHistory(i64, bool, bool),
+    /// Toggle block user event with a `i64` and a `bool`.
+    /// This event is used to block a user, or to unblock them if they are
+    /// blocked.
+    /// The first parameter is the `user_id` and the second one is whether to
+    /// report the private chat with the user as spam when blocking them.
+    ToggleBlockUser(i64, bool),
+    /// Join chat event with a `i64`.
+    /// This event is used to join a chat.
+    /// The parameter is the `chat_id`.
+    JoinChat(i64),
+    /// Join chat by link event with a `String`.
+    /// This event is used to join a chat by the username of a public chat or
+    /// by an invite link.
+    /// The parameter is the username or the link.
+    JoinChatByLink(String),
+    /// Open by username event with a `String`.
+    /// This event is used to open a public chat by its username, or by a link
+    /// to it.
+    /// The parameter is the username or the link.
+    OpenByUsername(String),
+    /// Get message link event with two `i64`.
+    /// This event is used to copy the link of a message to the clipboard.
+    /// The first parameter is the `chat_id` and the second one is the
+    /// `message_id`.
+    GetMessageLink(i64, i64),
+    /// Load scheduled messages event with an `i64`.
+    /// This event is used to load the scheduled messages of a chat.
+    /// The parameter is the `chat_id`.
+    LoadScheduledMessages(i64),
+    /// Send scheduled message now event with two `i64`.
+    /// This event is used to send a scheduled message immediately.
+    /// The first parameter is the `chat_id` and the second one is the
+    /// `message_id`.
+    SendScheduledMessageNow(i64, i64),
+    /// Load chat members event with an `i64`.
+    /// This event is used to load the members of a group shown by the member
+    /// list.
+    /// The parameter is the `chat_id`.
+    LoadChatMembers(i64),
+    /// Open private chat event with an `i64`.
+    /// This event is used to open the private chat with a user.
+    /// The parameter is the `user_id`.
+    OpenPrivateChat(i64),
+    /// Load sticker sets event.
+    /// This event is used to load the favorite and the recent stickers and
+    /// the installed sticker sets shown by the sticker picker.
+    LoadStickerSets,
+    /// Send sticker event with two `i64`.
+    /// This event is used to send a sticker to a chat.
+    /// The first parameter is the `chat_id` and the second one is the id of
+    /// the sticker.
+    SendSticker(i64, i64),
+    /// Play voice note event with a `i64`.
+    /// This event is used to play the voice note of a message of the open
+    /// chat, or to stop it if it is being played.
+    /// The parameter is the `message_id`.
+    PlayVoiceNote(i64),
+    /// Open media event with a `i64`.
+    /// This event is used to open the media of a message of the open chat.
+    /// The parameter is the `message_id`.
+    OpenMedia(i64),
+    /// File progress event with a `TdFile`.
+    /// This event is used to update the progress of a download.
+    FileProgress(TdFile),
+    /// Save contact event with a `i64`.
+    /// This event is used to add the contact shared by a message of the open
+    /// chat to the contacts of the user.
+    /// The parameter is the `message_id`.
+    SaveContact(i64),
+    /// Data changed event.
+    /// This event is sent once for each batch of updates received from
+    /// TDLib, after they are applied, to redraw the user interface.
+    DataChanged,
+    /// Press inline button event with an `i64` and a `String`.
+    /// This event is used to press a callback button of the inline keyboard
+    /// of a message of the open chat.
+    /// The parameters are the `message_id` and the data of the button.
+    PressInlineButton(i64, String),
+    /// Cancel download event with an `i32`.
+    /// This event is used to cancel the download of a file.
+    /// The parameter is the `file_id`.
+    CancelDownload(i32),
+    /// Load sessions event.
+    /// This event is used to load the active sessions of the account shown
+    /// by the panel of the sessions.
+    LoadSessions,
+    /// Terminate session event with an `i64`.
+    /// This event is used to terminate another session of the account.
+    /// The parameter is the `session_id`.
+    TerminateSession(i64),
+    /// Leave chat event with a `i64`.
+    /// This event is used to leave a chat.
+    /// The parameter is the `chat_id`.
+    LeaveChat(i64),
+    /// Create secret chat event with a `i64`.
+    /// This event is used to start a secret chat with a user.
+    /// The parameter is the `user_id`.
+    CreateSecretChat(i64),
+    /// Notify event with a `String` and a `String`.
+    /// This event is used to notify a message received in a chat that is not
+    /// open.
+    /// The first parameter is the title of the chat and the second one is the
+    /// preview of the message.
+    Notify(String, String),
+    /// Send scheduled message event with an `i64`, a `String` and an `i32`.
+    /// This event is used to schedule a message.
+    /// The parameters are the `chat_id`, the text of the message and the
+    /// Unix time the message is sent at.
+    SendScheduledMessage(i64, String, i32),
+    /// Set chat theme event with an optional `String`.
+    /// This event is used to set the theme of the open chat.
+    /// The parameter is the name of the file of the theme, `None` to use the
+    /// default theme.
+    SetChatTheme(Option<String>),
+    /// Edit alias event with an `i64`.
+    /// This event is used to write the command that sets the alias of a user
+    /// in the prompt.
+    /// The parameter is the `user_id`.
+    EditAlias(i64),
+    /// Set alias event with an `i64` and an optional `String`.
+    /// This event is used to set the alias of a user.
+    /// The parameters are the `user_id` and the alias, `None` to remove it.
+    SetAlias(i64, Option<String>),
+    /// Bell event with a `bool`.
+    /// This event is used to ring the terminal bell for a message received in
+    /// a chat that is not muted.
+    /// The parameter is whether the message has been received in the open
+    /// chat.
+    Bell(bool),
+    /// ConnectionReady event.
+    /// This event is used to inform that the connection to Telegram has been
+    /// restored, so the requests that failed can be sent again.
+    ConnectionReady,
 }
 /// Implement the `Event` enum.
 impl Event {
@@ -115,6 +301,53 @@ impl Event {
             }
         }
     }
+    /// Get the key of a key event as it is written in the keymap
+    /// configuration, e.g. `alt+enter`. It is the inverse of `from_str`.
+    ///
+    /// # Returns
+    /// * `Option<String>` - The key, `None` if the event is not a key or the
+    ///   key can not be written in the keymap configuration.
+    pub fn keymap_key(&self) -> Option<String> {
+        let Event::Key(key, modifiers) = self else {
+            return None;
+        };
+        let key = match key {
+            KeyCode::Backspace => "backspace".to_string(),
+            KeyCode::Enter => "enter".to_string(),
+            KeyCode::Left => "left".to_string(),
+            KeyCode::Right => "right".to_string(),
+            KeyCode::Up => "up".to_string(),
+            KeyCode::Down => "down".to_string(),
+            KeyCode::Home => "home".to_string(),
+            KeyCode::End => "end".to_string(),
+            KeyCode::PageUp => "page_up".to_string(),
+            KeyCode::PageDown => "page_down".to_string(),
+            KeyCode::Tab => "tab".to_string(),
+            KeyCode::BackTab => "back_tab".to_string(),
+            KeyCode::Delete => "delete".to_string(),
+            KeyCode::Insert => "insert".to_string(),
+            KeyCode::Null => "null".to_string(),
+            KeyCode::Esc => "esc".to_string(),
+            KeyCode::F(n) if (1..=12).contains(n) => format!("f{}", n),
+            // The "+" separates the modifiers from the key.
+            KeyCode::Char(c) if c.is_ascii() && *c != '+' => c.to_string(),
+            _ => return None,
+        };
+        let mut keys: Vec<String> = [
+            (KeyModifiers::CONTROL, "ctrl"),
+            (KeyModifiers::ALT, "alt"),
+            (KeyModifiers::SHIFT, "shift"),
+            (KeyModifiers::SUPER, "super"),
+            (KeyModifiers::META, "meta"),
+            (KeyModifiers::HYPER, "hyper"),
+        ]
+        .into_iter()
+        .filter(|(modifier, _)| modifiers.contains(*modifier))
+        .map(|(_, name)| name.to_string())
+        .collect();
+        keys.push(key);
+        Some(keys.join("+"))
+    }
 }
 
 /// Implement the `FromStr` trait for `Event`.
@@ -187,21 +420,128 @@ impl Display for Event {
             Event::SendMessageEdited(message_id, s) => {
                 write!(f, "SendMessageEdited({}, {})", message_id, s)
             }
-            Event::GetChatHistory => {
-                write!(f, "GetChatHistory")
+            Event::SendPhoto(path, caption, reply_to) => {
+                write!(f, "SendPhoto({}, {}, {:?})", path, caption, reply_to)
+            }
+            Event::GetChatHistory(chat_id) => {
+                write!(f, "GetChatHistory({})", chat_id)
             }
             Event::DeleteMessages(message_ids, revoke) => {
                 write!(f, "DeleteMessages({:?}, {})", message_ids, revoke)
             }
+            Event::ForwardMessages(chat_id, from_chat_id, message_ids) => {
+                write!(
+                    f,
+                    "ForwardMessages({}, {}, {:?})",
+                    chat_id, from_chat_id, message_ids
+                )
+            }
             Event::EditMessage(message_id, text) => {
                 write!(f, "EditMessage({}, {})", message_id, text)
             }
             Event::ReplyMessage(message_id, text) => {
                 write!(f, "ReplyMessage({}, {})", message_id, text)
             }
+            Event::QuoteReplyMessage(message_id, text) => {
+                write!(f, "QuoteReplyMessage({}, {})", message_id, text)
+            }
             Event::ViewAllMessages => {
                 write!(f, "ViewAllMessages")
             }
+            Event::ViewMessages(chat_id, message_ids) => {
+                write!(f, "ViewMessages({}, {:?})", chat_id, message_ids)
+            }
+            Event::OpenChat(chat_id) => {
+                write!(f, "OpenChat({})", chat_id)
+            }
+            Event::SetAutoDeleteTime(chat_id, seconds) => {
+                write!(f, "SetAutoDeleteTime({}, {})", chat_id, seconds)
+            }
+            Event::PinMessage(chat_id, message_id, notify) => {
+                write!(f, "PinMessage({}, {}, {})", chat_id, message_id, notify)
+            }
+            Event::UnpinMessage(chat_id, message_id) => {
+                write!(f, "UnpinMessage({}, {})", chat_id, message_id)
+            }
+            Event::GetMessageViewers(chat_id, message_id) => {
+                write!(f, "GetMessageViewers({}, {})", chat_id, message_id)
+            }
+            Event::ClearChatHistory(chat_id, remove_from_chat_list, revoke) => {
+                write!(
+                    f,
+                    "ClearChatHistory({}, {}, {})",
+                    chat_id, remove_from_chat_list, revoke
+                )
+            }
+            Event::ToggleBlockUser(user_id, report_spam) => {
+                write!(f, "ToggleBlockUser({}, {})", user_id, report_spam)
+            }
+            Event::JoinChat(chat_id) => {
+                write!(f, "JoinChat({})", chat_id)
+            }
+            Event::JoinChatByLink(link) => {
+                write!(f, "JoinChatByLink({})", link)
+            }
+            Event::OpenByUsername(username) => {
+                write!(f, "OpenByUsername({})", username)
+            }
+            Event::GetMessageLink(chat_id, message_id) => {
+                write!(f, "GetMessageLink({}, {})", chat_id, message_id)
+            }
+            Event::LoadStickerSets => write!(f, "LoadStickerSets"),
+            Event::LoadChatMembers(chat_id) => write!(f, "LoadChatMembers({})", chat_id),
+            Event::OpenPrivateChat(user_id) => write!(f, "OpenPrivateChat({})", user_id),
+            Event::LoadScheduledMessages(chat_id) => {
+                write!(f, "LoadScheduledMessages({})", chat_id)
+            }
+            Event::SendScheduledMessageNow(chat_id, message_id) => {
+                write!(f, "SendScheduledMessageNow({}, {})", chat_id, message_id)
+            }
+            Event::PlayVoiceNote(message_id) => write!(f, "PlayVoiceNote({})", message_id),
+            Event::OpenMedia(message_id) => write!(f, "OpenMedia({})", message_id),
+            Event::FileProgress(file) => write!(
+                f,
+                "FileProgress({}, {}/{})",
+                file.id, file.downloaded_size, file.size
+            ),
+            Event::SaveContact(message_id) => write!(f, "SaveContact({})", message_id),
+            Event::DataChanged => write!(f, "DataChanged"),
+            Event::PressInlineButton(message_id, data) => {
+                write!(f, "PressInlineButton({}, {})", message_id, data)
+            }
+            Event::CancelDownload(file_id) => write!(f, "CancelDownload({})", file_id),
+            Event::LoadSessions => write!(f, "LoadSessions"),
+            Event::TerminateSession(session_id) => {
+                write!(f, "TerminateSession({})", session_id)
+            }
+            Event::SendSticker(chat_id, sticker_id) => {
+                write!(f, "SendSticker({}, {})", chat_id, sticker_id)
+            }
+            Event::LeaveChat(chat_id) => {
+                write!(f, "LeaveChat({})", chat_id)
+            }
+            Event::CreateSecretChat(user_id) => {
+                write!(f, "CreateSecretChat({})", user_id)
+            }
+            Event::Notify(title, preview) => {
+                write!(f, "Notify({}, {})", title, preview)
+            }
+            Event::Bell(in_open_chat) => write!(f, "Bell({})", in_open_chat),
+            Event::SendScheduledMessage(chat_id, message, send_date) => {
+                write!(
+                    f,
+                    "SendScheduledMessage({}, {}, {})",
+                    chat_id, message, send_date
+                )
+            }
+            Event::SetChatTheme(theme_filename) => {
+                write!(f, "SetChatTheme({:?})", theme_filename)
+            }
+            Event::EditAlias(user_id) => write!(f, "EditAlias({})", user_id),
+            Event::SetAlias(user_id, alias) => write!(f, "SetAlias({}, {:?})", user_id, alias),
+            Event::ConnectionReady => {
+                write!(f, "ConnectionReady")
+            }
         }
     }
 }
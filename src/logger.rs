@@ -3,8 +3,11 @@ use {
     std::fs,
     tracing_error::ErrorLayer,
     tracing_subscriber::{
-        filter::EnvFilter, prelude::__tracing_subscriber_SubscriberExt, registry::Registry,
-        util::SubscriberInitExt, Layer,
+        filter::{Directive, EnvFilter},
+        prelude::__tracing_subscriber_SubscriberExt,
+        registry::{LookupSpan, Registry},
+        util::SubscriberInitExt,
+        Layer,
     },
 };
 
@@ -22,6 +25,10 @@ pub struct Logger {
     max_old_log_files: usize,
     /// The log level.
     log_level: String,
+    /// Whether the log is written as JSON lines instead of plain text.
+    json_format: bool,
+    /// The directives that override the log level of specific modules.
+    log_filters: String,
 }
 
 impl Logger {
@@ -48,6 +55,7 @@ impl Logger {
     /// - target: true
     /// - ansi: false
     /// - writer: the log file
+    /// - format: plain or JSON lines, see `log_format`
     /// - filter: the `RUST_LOG` environment variable
     ///
     /// The error layer is initialized with the default settings.
@@ -55,13 +63,64 @@ impl Logger {
         self.set_rust_log_variable();
         let _ = self.delete_old_log_files();
 
+        // Parsing an EnvFilter from the default environment variable
+        // (RUST_LOG), with the per-module directives of the configuration
+        let (filter, invalid_directives) =
+            Self::filter(EnvFilter::from_default_env(), &self.log_filters);
+        let file_subscriber = self.file_layer(filter);
+
+        Registry::default()
+            .with(file_subscriber)
+            .with(ErrorLayer::default())
+            .init();
+
+        for (directive, e) in invalid_directives {
+            tracing::warn!("Ignoring the invalid log filter {}: {}", directive, e);
+        }
+    }
+    /// Add the per-module directives to a filter.
+    /// The invalid directives are skipped, so the modules they refer to keep
+    /// the base level.
+    ///
+    /// # Arguments
+    /// * `filter` - The base filter.
+    /// * `log_filters` - The comma separated list of directives.
+    ///
+    /// # Returns
+    /// * `(EnvFilter, Vec<(String, String)>)` - The filter and the invalid
+    ///   directives, with the reason why they are invalid.
+    fn filter(mut filter: EnvFilter, log_filters: &str) -> (EnvFilter, Vec<(String, String)>) {
+        let mut invalid_directives = Vec::new();
+        for directive in log_filters.split(',').map(str::trim) {
+            if directive.is_empty() {
+                continue;
+            }
+            match directive.parse::<Directive>() {
+                Ok(d) => filter = filter.add_directive(d),
+                Err(e) => invalid_directives.push((directive.to_string(), e.to_string())),
+            }
+        }
+        (filter, invalid_directives)
+    }
+    /// Create the layer that writes the log to the log file.
+    ///
+    /// # Arguments
+    /// * `filter` - The filter of the events to log.
+    ///
+    /// # Returns
+    /// * `Box<dyn Layer<S> + Send + Sync>` - The layer, that writes plain text
+    ///   or JSON lines depending on the log format.
+    fn file_layer<S>(&self, filter: EnvFilter) -> Box<dyn Layer<S> + Send + Sync>
+    where
+        S: tracing::Subscriber + for<'a> LookupSpan<'a>,
+    {
         let file_appender = tracing_appender::rolling::RollingFileAppender::new(
             self.rotation_frequency.clone(),
             self.log_dir.clone(),
             self.log_file.clone(),
         );
 
-        let file_subscriber = tracing_subscriber::fmt::layer()
+        let layer = tracing_subscriber::fmt::layer()
             .with_timer(tracing_subscriber::fmt::time::ChronoLocal::new(
                 "%Y-%m-%dT%H:%M:%S%.6fZ".to_string(),
             ))
@@ -69,15 +128,12 @@ impl Logger {
             .with_line_number(true)
             .with_target(true)
             .with_ansi(false)
-            .with_writer(file_appender)
-            // Parsing an EnvFilter from the default environment variable
-            // (RUST_LOG)
-            .with_filter(EnvFilter::from_default_env()); //tracing_subscriber::filter::LevelFilter::TRACE
-
-        Registry::default()
-            .with(file_subscriber)
-            .with(ErrorLayer::default())
-            .init();
+            .with_writer(file_appender);
+        if self.json_format {
+            layer.json().with_filter(filter).boxed()
+        } else {
+            layer.with_filter(filter).boxed()
+        }
     }
     /// Deletes old log files from the specified log folder.
     ///
@@ -136,6 +192,80 @@ impl From<LoggerConfig> for Logger {
             },
             max_old_log_files: config.max_old_log_files,
             log_level: config.log_level,
+            json_format: config.log_format == "json",
+            log_filters: config.log_filters,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::logger::Logger;
+    use std::fs;
+    use tracing_subscriber::{filter::EnvFilter, layer::SubscriberExt, registry::Registry};
+
+    /// Log a line with a logger that writes to a temporary directory, and
+    /// return the content of the log file.
+    fn log_line(json_format: bool) -> String {
+        let log_dir = std::env::temp_dir().join(format!(
+            "tgt-logger-test-{}-{}",
+            std::process::id(),
+            json_format
+        ));
+        let _ = fs::remove_dir_all(&log_dir);
+        fs::create_dir_all(&log_dir).unwrap();
+        let logger = Logger {
+            log_dir: log_dir.to_string_lossy().to_string(),
+            log_file: "tgt.log".to_string(),
+            rotation_frequency: tracing_appender::rolling::Rotation::NEVER,
+            max_old_log_files: 1,
+            log_level: "info".to_string(),
+            json_format,
+            log_filters: String::new(),
+        };
+        let subscriber = Registry::default().with(logger.file_layer(EnvFilter::new("info")));
+        tracing::subscriber::with_default(subscriber, || tracing::info!("logged line"));
+        let content = fs::read_to_string(log_dir.join("tgt.log")).unwrap();
+        let _ = fs::remove_dir_all(&log_dir);
+        content
+    }
+
+    #[test]
+    fn test_filter_directives() {
+        let (filter, invalid_directives) =
+            Logger::filter(EnvFilter::new("info"), "tdlib_rs=warn, tgt::tg=debug");
+        assert!(invalid_directives.is_empty());
+        let filter = filter.to_string();
+        assert!(filter.contains("tdlib_rs=warn"));
+        assert!(filter.contains("tgt::tg=debug"));
+        assert!(filter.contains("info"));
+    }
+
+    #[test]
+    fn test_filter_invalid_directive() {
+        let (filter, invalid_directives) =
+            Logger::filter(EnvFilter::new("info"), "tdlib_rs=loud,tgt=debug");
+        assert_eq!(invalid_directives.len(), 1);
+        assert_eq!(invalid_directives[0].0, "tdlib_rs=loud");
+        let filter = filter.to_string();
+        assert!(!filter.contains("tdlib_rs"));
+        assert!(filter.contains("tgt=debug"));
+    }
+
+    #[test]
+    fn test_plain_log_format() {
+        let content = log_line(false);
+        assert!(content.contains("INFO"));
+        assert!(content.contains("logged line"));
+        assert!(!content.starts_with('{'));
+    }
+
+    #[test]
+    fn test_json_log_format() {
+        let content = log_line(true);
+        let line = content.lines().next().unwrap();
+        assert!(line.starts_with('{') && line.ends_with('}'));
+        assert!(line.contains("\"message\":\"logged line\""));
+        assert!(line.contains("\"level\":\"INFO\""));
+    }
+}
@@ -1,6 +1,7 @@
 pub mod action;
 pub mod app_context;
 pub mod app_error;
+pub mod app_state;
 pub mod cli;
 pub mod component_name;
 pub mod event;
@@ -13,10 +14,12 @@ pub mod utils;
 pub mod components;
 pub mod configs;
 pub mod run;
+pub mod setup_wizard;
 pub mod tg;
 
 use crate::app_context::AppContext;
 use crate::app_error::AppError;
+use crate::app_state::AppState;
 use crate::configs::{
     config_file::ConfigFile,
     custom::{
@@ -32,6 +35,7 @@ use clap::Parser;
 use configs::custom::telegram_custom::TelegramConfig;
 use lazy_static::lazy_static;
 use std::panic::{set_hook, take_hook};
+use std::path::Path;
 use std::sync::Arc;
 
 lazy_static! {
@@ -51,6 +55,19 @@ lazy_static! {
 async fn tokio_main() -> Result<(), AppError<()>> {
     tracing::info!("Starting tokio main");
 
+    // The profile namespaces the directories of the configuration files, so
+    // it is selected before they are read.
+    let cli_args = cli::CliArgs::parse();
+    if let Some(profile) = cli_args.telegram_cli().profile() {
+        utils::set_profile(profile)?;
+    }
+
+    // The first time the application runs, i.e. when there are no custom
+    // configuration files, the setup wizard creates them.
+    if !cli_args.telegram_cli().is_command() && !configs::config_file::custom_config_exists() {
+        setup_wizard::run()?;
+    }
+
     // Initialize the lazy static variables
     // This is done to ensure that the configuration files are read only once
     // and the values are shared across the application.
@@ -61,7 +78,6 @@ async fn tokio_main() -> Result<(), AppError<()>> {
     lazy_static::initialize(&THEME_CONFIG);
     lazy_static::initialize(&TELEGRAM_CONFIG);
 
-    let cli_args = cli::CliArgs::parse();
     tracing::info!("Parsed CLI arguments: {:?}", cli_args);
 
     let logger = Logger::from_config(LOGGER_CONFIG.clone());
@@ -70,6 +86,11 @@ async fn tokio_main() -> Result<(), AppError<()>> {
 
     let keymap_config = KEYMAP_CONFIG.clone();
     tracing::info!("Keymap config: {:?}", keymap_config);
+    if let Some(path) = cli_args.telegram_cli().export_keymap() {
+        keymap_config.export(Path::new(path), cli_args.telegram_cli().keymap_format())?;
+        println!("Key bindings exported to {}", path);
+        return Ok(());
+    }
 
     let app_config = APP_CONFIG.clone();
     tracing::info!("App config: {:?}", app_config);
@@ -85,7 +106,22 @@ async fn tokio_main() -> Result<(), AppError<()>> {
     // This is used to disable the message database when running the application as a CLI.
     // This is done to avoid that deleting a message other application in
     // a chats causes the `--send-message` to resend the messages that were deleted.
-    telegram_config.use_message_database = std::env::args().count() <= 1;
+    telegram_config.use_message_database = !cli_args.telegram_cli().is_command();
+    telegram_config.override_paths(
+        cli_args.telegram_cli().data_dir(),
+        cli_args.telegram_cli().log_path(),
+    )?;
+    // The proxy is validated before starting, so that a mistake in the
+    // configuration is reported clearly.
+    telegram_config.proxy()?;
+    tracing::info!(
+        "Telegram database dir: {}, log path: {}",
+        telegram_config.database_dir,
+        telegram_config.log_path
+    );
+
+    let app_state = AppState::load();
+    tracing::info!("App state: {:?}", app_state);
 
     let tg_context = TgContext::default();
     tracing::info!("Telegram context: {:?}", tg_context);
@@ -95,6 +131,7 @@ async fn tokio_main() -> Result<(), AppError<()>> {
         theme_config,
         palette_config,
         telegram_config,
+        app_state,
         tg_context,
         cli_args,
     )?);
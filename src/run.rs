@@ -1,14 +1,125 @@
-use crate::component_name::ComponentName::Prompt;
+use crate::component_name::ComponentName::{self, Prompt};
+use crate::components::{
+    auth_window::AuthWindow,
+    component_traits::{Component, HandleFocus},
+};
 use crate::{
     action::Action, app_context::AppContext, app_error::AppError,
     configs::custom::keymap_custom::ActionBinding, event::Event, tg::tg_backend::TgBackend,
     tui::Tui, tui_backend::TuiBackend,
 };
 use ratatui::layout::Rect;
-use std::{collections::HashMap, io, sync::Arc, time::Instant};
-use tdlib_rs::enums::ChatList;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tdlib_rs::enums::{AuthorizationState, ChatList, ChatType};
 use tokio::sync::mpsc::UnboundedSender;
 
+/// The time during which the last opened chat is looked up in the chat list,
+/// before giving up on restoring it.
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `IdleTimer` keeps track of the last input of the user.
+/// It is used to set the account offline after a period of inactivity and
+/// back online on the next input.
+struct IdleTimer {
+    /// The instant of the last key or mouse input.
+    last_activity: Instant,
+    /// Indicates whether the account has been set offline because of the
+    /// inactivity.
+    is_away: bool,
+}
+/// Implementation of the `IdleTimer` struct.
+impl IdleTimer {
+    /// Create a new instance of the `IdleTimer` struct.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `IdleTimer` struct.
+    fn new() -> Self {
+        Self {
+            last_activity: Instant::now(),
+            is_away: false,
+        }
+    }
+    /// Record an input of the user.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the account was away and should go back online.
+    fn record_activity(&mut self) -> bool {
+        self.last_activity = Instant::now();
+        std::mem::take(&mut self.is_away)
+    }
+    /// Check whether the user has been inactive for longer than the threshold.
+    ///
+    /// # Arguments
+    /// * `threshold` - The inactivity period after which the user is away.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the user has just become away. It is true only once
+    ///   per period of inactivity.
+    fn check_away(&mut self, threshold: Duration) -> bool {
+        if self.is_away || self.last_activity.elapsed() < threshold {
+            return false;
+        }
+        self.is_away = true;
+        true
+    }
+}
+
+/// `RedrawLimiter` decides when the user interface is redrawn.
+/// The render ticks of the `TuiBackend` are sent `frame_rate` times per
+/// second, but the user interface is redrawn on a tick only if something
+/// changed since the last redraw, or if the idle interval has elapsed. The
+/// changes received between two ticks are drawn together.
+pub struct RedrawLimiter {
+    /// The instant of the last redraw.
+    last_draw: Instant,
+    /// The interval after which the user interface is redrawn even if nothing
+    /// changed, e.g. to update the time of the messages.
+    idle_interval: Duration,
+    /// Indicates whether something changed since the last redraw.
+    dirty: bool,
+}
+/// Implementation of the `RedrawLimiter` struct.
+impl RedrawLimiter {
+    /// Create a new instance of the `RedrawLimiter` struct.
+    /// The first tick always redraws the user interface.
+    ///
+    /// # Arguments
+    /// * `idle_interval` - The interval after which the user interface is
+    ///   redrawn even if nothing changed.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `RedrawLimiter` struct.
+    fn new(idle_interval: Duration) -> Self {
+        Self {
+            last_draw: Instant::now(),
+            idle_interval,
+            dirty: true,
+        }
+    }
+    /// Record that something changed since the last redraw.
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+    /// Check whether the user interface should be redrawn on a render tick,
+    /// and record the redraw if so.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the user interface should be redrawn.
+    fn should_draw(&mut self) -> bool {
+        if !self.dirty && self.last_draw.elapsed() < self.idle_interval {
+            return false;
+        }
+        self.dirty = false;
+        self.last_draw = Instant::now();
+        true
+    }
+}
+
 /// Run the main event loop for the application.
 /// This function will process events and actions for the tui and the backend.
 ///
@@ -33,7 +144,11 @@ pub async fn run_app(
 
     tg_backend.start();
     tg_backend.set_logging().await;
-    tg_backend.handle_authorization_state().await;
+    run_auth(Arc::clone(&app_context), tui_backend, tg_backend).await?;
+    if !tg_backend.have_authorization {
+        futures::join!(quit_cli(tg_backend));
+        return Ok(());
+    }
     tg_backend.use_quick_ack().await;
     tg_backend.get_me().await;
     tg_backend.load_chats(ChatList::Main, 30).await;
@@ -47,20 +162,51 @@ pub async fn run_app(
             futures::join!(log_out(tg_backend));
             return Ok(());
         }
+        HandleCliOutcome::Fail(error) => {
+            futures::join!(quit_cli(tg_backend));
+            return Err(error);
+        }
         HandleCliOutcome::Continue => {}
     }
 
+    // The last opened chat is restored once it is received with the chat
+    // list, that is loaded in the background.
+    let mut restore_pending = app_context.app_state().last_open_chat_id.is_some();
+    let restore_deadline = Instant::now() + RESTORE_TIMEOUT;
+
     tg_backend.online().await;
-    tg_backend.disable_animated_emoji(true).await;
+    let disable_animated_emoji = app_context.app_config().disable_animated_emoji;
+    tg_backend
+        .disable_animated_emoji(disable_animated_emoji)
+        .await;
 
     tui_backend.enter()?;
     tui.register_action_handler(app_context.action_tx().clone())?;
+    report_keymap_conflicts(&app_context);
+
+    let mut idle_timer = IdleTimer::new();
+    let mut redraw_limiter = RedrawLimiter::new(Duration::from_millis(
+        app_context.app_config().idle_redraw_ms,
+    ));
 
     // Main loop
     while tg_backend.have_authorization {
-        handle_tui_backend_events(Arc::clone(&app_context), tui, tui_backend).await?;
+        handle_tui_backend_events(Arc::clone(&app_context), tui, tui_backend, &mut idle_timer)
+            .await?;
+        check_idle(Arc::clone(&app_context), &mut idle_timer)?;
         handle_tg_backend_events(Arc::clone(&app_context), tg_backend).await?;
-        handle_app_actions(Arc::clone(&app_context), tui, tui_backend, tg_backend).await?;
+        if restore_pending {
+            let give_up = tg_backend.full_chats_list || Instant::now() >= restore_deadline;
+            restore_pending = !restore_app_state(Arc::clone(&app_context), give_up);
+        }
+        handle_app_actions(
+            Arc::clone(&app_context),
+            tui,
+            tui_backend,
+            tg_backend,
+            &mut redraw_limiter,
+        )
+        .await?;
 
         if app_context.quit_acquire() {
             quit_tui(tg_backend, tui_backend).await;
@@ -71,8 +217,146 @@ pub async fn run_app(
 
     Ok(())
 }
+/// Report the conflicts of the keymaps in the status bar, so that the user
+/// notices the keys of the components shadowed by the `core_window` ones,
+/// e.g. after editing `keymap.toml`.
+///
+/// # Arguments
+/// * `app_context` - A reference to the AppContext struct.
+///
+/// # Returns
+/// * `bool` - Whether there are conflicts.
+fn report_keymap_conflicts(app_context: &AppContext) -> bool {
+    let conflicts = app_context.keymap_config().conflicts();
+    if conflicts.is_empty() {
+        return false;
+    }
+    let conflicts = conflicts
+        .iter()
+        .map(|conflict| conflict.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    app_context.tg_context().set_error_message(format!(
+        "Keymap conflicts, the core_window keys win: {}",
+        conflicts
+    ));
+    true
+}
+#[allow(clippy::await_holding_lock)]
+/// Run the authorization process.
+/// If Telegram needs an input of the user, e.g. the phone number or the
+/// verification code, the user interface is entered and the `AuthWindow` is
+/// shown until the client is ready.
+///
+/// # Arguments
+/// * `app_context` - An Arc wrapped AppContext struct.
+/// * `tui_backend` - A mutable reference to the TuiBackend struct.
+/// * `tg_backend` - A mutable reference to the TgBackend struct.
+///
+/// # Returns
+/// * `Result<(), AppError>` - An Ok result or an error.
+async fn run_auth(
+    app_context: Arc<AppContext>,
+    tui_backend: &mut TuiBackend,
+    tg_backend: &mut TgBackend,
+) -> Result<(), AppError<Action>> {
+    let Some(mut state) = tg_backend.handle_authorization_state().await else {
+        return Ok(());
+    };
+
+    let mut auth_window =
+        AuthWindow::new(Arc::clone(&app_context)).with_name(ComponentName::Auth.to_string());
+    auth_window.register_action_handler(app_context.action_tx().clone())?;
+    auth_window.focus();
+    auth_window.set_authorization_state(&state);
+    if let Some(message) = tg_backend.take_setup_error() {
+        auth_window.set_setup_error(message);
+    }
+    tui_backend.enter()?;
+
+    loop {
+        tokio::select! {
+            Some(next_state) = tg_backend.auth_rx.recv() => {
+                // TDLib can not be used anymore once it is closed, e.g. when
+                // the database can not be opened.
+                let closed = matches!(next_state, AuthorizationState::Closed);
+                match tg_backend.process_authorization_state(next_state).await {
+                    Some(next_state) => {
+                        auth_window.set_authorization_state(&next_state);
+                        state = next_state;
+                    }
+                    None if tg_backend.have_authorization || closed => break,
+                    None => {}
+                }
+                if let Some(message) = tg_backend.take_setup_error() {
+                    auth_window.set_setup_error(message);
+                }
+            }
+            Some(event) = tui_backend.next() => match event {
+                Event::Render | Event::Resize(..) => app_context.action_tx().send(Action::Render)?,
+                Event::Key(key, modifiers) => app_context
+                    .action_tx()
+                    .send(Action::from_key_event(key, modifiers))?,
+                Event::Paste(text) => app_context.action_tx().send(Action::Paste(text))?,
+                _ => {}
+            },
+            else => break,
+        }
+
+        while let Ok(action) = app_context.action_rx().try_recv() {
+            match action {
+                Action::Render => {
+                    tui_backend.terminal.draw(|f| {
+                        auth_window.draw(f, f.area()).unwrap();
+                    })?;
+                }
+                Action::SubmitAuthorization(inputs) => {
+                    if let Err(e) = tg_backend.submit_authorization(&state, inputs).await {
+                        tracing::error!("Authorization error: {}", e.message);
+                        auth_window.set_error(Some(e.message));
+                    }
+                }
+                Action::Quit => {
+                    tui_backend.exit();
+                    return Ok(());
+                }
+                _ => auth_window.update(action),
+            }
+        }
+    }
+
+    tui_backend.exit();
+    Ok(())
+}
+/// Set the account offline if the user has been inactive for longer than
+/// `away_after_seconds`.
+/// Only an online account is set offline, so that the presence chosen by the
+/// user is not overridden when the user comes back.
+///
+/// # Arguments
+/// * `app_context` - An Arc wrapped AppContext struct.
+/// * `idle_timer` - A mutable reference to the IdleTimer struct.
+///
+/// # Returns
+/// * `Result<(), AppError>` - An Ok result or an error.
+fn check_idle(
+    app_context: Arc<AppContext>,
+    idle_timer: &mut IdleTimer,
+) -> Result<(), AppError<Action>> {
+    let away_after_seconds = app_context.app_config().away_after_seconds;
+    if away_after_seconds == 0 || !app_context.tg_context().is_online() {
+        return Ok(());
+    }
+    if idle_timer.check_away(Duration::from_secs(away_after_seconds)) {
+        tracing::info!("No input for {} seconds, going away", away_after_seconds);
+        app_context.action_tx().send(Action::SetOnline(false))?;
+    }
+    Ok(())
+}
 /// Handle incoming events from the Telegram backend and produce actions if
 /// necessary.
+/// All the pending events are handled, so that the events sent for each batch
+/// of updates received from TDLib do not pile up.
 ///
 /// # Arguments
 /// * `app_context` - An Arc wrapped AppContext struct.
@@ -84,7 +368,7 @@ async fn handle_tg_backend_events(
     app_context: Arc<AppContext>,
     tg_backend: &mut TgBackend,
 ) -> Result<(), AppError<Action>> {
-    if let Some(event) = tg_backend.next().await {
+    while let Some(event) = tg_backend.next().await {
         match event {
             Event::LoadChats(chat_list, limit) => {
                 app_context
@@ -96,19 +380,33 @@ async fn handle_tg_backend_events(
                     .action_tx()
                     .send(Action::SendMessage(message, reply_to))?;
             }
+            Event::SendPhoto(path, caption, reply_to) => {
+                app_context
+                    .action_tx()
+                    .send(Action::SendPhoto(path, caption, reply_to))?;
+            }
             Event::SendMessageEdited(message_id, message) => {
                 app_context
                     .action_tx()
                     .send(Action::SendMessageEdited(message_id, message))?;
             }
-            Event::GetChatHistory => {
-                app_context.action_tx().send(Action::GetChatHistory)?;
+            Event::GetChatHistory(chat_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::GetChatHistory(chat_id))?;
             }
             Event::DeleteMessages(message_ids, revoke) => {
                 app_context
                     .action_tx()
                     .send(Action::DeleteMessages(message_ids, revoke))?;
             }
+            Event::ForwardMessages(chat_id, from_chat_id, message_ids) => {
+                app_context.action_tx().send(Action::ForwardMessages(
+                    chat_id,
+                    from_chat_id,
+                    message_ids,
+                ))?;
+            }
             Event::EditMessage(message_id, message) => {
                 // It is important to focus the prompt before editing the message.
                 // Because the actions are sent to the focused component.
@@ -129,9 +427,187 @@ async fn handle_tg_backend_events(
                     .action_tx()
                     .send(Action::ReplyMessage(message_id, message))?;
             }
+            Event::QuoteReplyMessage(message_id, message) => {
+                app_context
+                    .action_tx()
+                    .send(Action::FocusComponent(Prompt))?;
+
+                app_context
+                    .action_tx()
+                    .send(Action::QuoteReplyMessage(message_id, message))?;
+            }
             Event::ViewAllMessages => {
                 app_context.action_tx().send(Action::ViewAllMessages)?;
             }
+            Event::ViewMessages(chat_id, message_ids) => {
+                app_context
+                    .action_tx()
+                    .send(Action::ViewMessages(chat_id, message_ids))?;
+            }
+            Event::OpenChat(chat_id) => {
+                app_context.action_tx().send(Action::OpenChat(chat_id))?;
+            }
+            Event::SetAutoDeleteTime(chat_id, seconds) => {
+                app_context
+                    .action_tx()
+                    .send(Action::SetAutoDeleteTime(chat_id, seconds))?;
+            }
+            Event::PinMessage(chat_id, message_id, notify) => {
+                app_context
+                    .action_tx()
+                    .send(Action::PinMessage(chat_id, message_id, notify))?;
+            }
+            Event::UnpinMessage(chat_id, message_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::UnpinMessage(chat_id, message_id))?;
+            }
+            Event::GetMessageViewers(chat_id, message_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::GetMessageViewers(chat_id, message_id))?;
+            }
+            Event::ClearChatHistory(chat_id, remove_from_chat_list, revoke) => {
+                app_context.action_tx().send(Action::ClearChatHistory(
+                    chat_id,
+                    remove_from_chat_list,
+                    revoke,
+                ))?;
+            }
+            Event::ToggleBlockUser(user_id, report_spam) => {
+                app_context
+                    .action_tx()
+                    .send(Action::ToggleBlockUser(user_id, report_spam))?;
+            }
+            Event::JoinChat(chat_id) => {
+                app_context.action_tx().send(Action::JoinChat(chat_id))?;
+            }
+            Event::JoinChatByLink(link) => {
+                app_context.action_tx().send(Action::JoinChatByLink(link))?;
+            }
+            Event::OpenByUsername(username) => {
+                app_context
+                    .action_tx()
+                    .send(Action::OpenByUsername(username))?;
+            }
+            Event::GetMessageLink(chat_id, message_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::GetMessageLink(chat_id, message_id))?;
+            }
+            Event::LoadStickerSets => {
+                app_context.action_tx().send(Action::LoadStickerSets)?;
+            }
+            Event::LoadChatMembers(chat_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::LoadChatMembers(chat_id))?;
+            }
+            Event::OpenPrivateChat(user_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::OpenPrivateChat(user_id))?;
+            }
+            Event::LoadScheduledMessages(chat_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::LoadScheduledMessages(chat_id))?;
+            }
+            Event::SendScheduledMessageNow(chat_id, message_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::SendScheduledMessageNow(chat_id, message_id))?;
+            }
+            Event::SendSticker(chat_id, sticker_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::SendSticker(chat_id, sticker_id))?;
+            }
+            Event::PlayVoiceNote(message_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::PlayVoiceNote(message_id))?;
+            }
+            Event::OpenMedia(message_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::OpenMedia(message_id))?;
+            }
+            Event::FileProgress(file) => {
+                app_context.action_tx().send(Action::FileProgress(file))?;
+            }
+            Event::SaveContact(message_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::SaveContact(message_id))?;
+            }
+            Event::DataChanged => app_context.action_tx().send(Action::DataChanged)?,
+            Event::PressInlineButton(message_id, data) => {
+                app_context
+                    .action_tx()
+                    .send(Action::PressInlineButton(message_id, data))?;
+            }
+            Event::CancelDownload(file_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::CancelDownload(file_id))?;
+            }
+            Event::LoadSessions => {
+                app_context.action_tx().send(Action::LoadSessions)?;
+            }
+            Event::TerminateSession(session_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::TerminateSession(session_id))?;
+            }
+            Event::LeaveChat(chat_id) => {
+                app_context.action_tx().send(Action::LeaveChat(chat_id))?;
+            }
+            Event::CreateSecretChat(user_id) => {
+                app_context
+                    .action_tx()
+                    .send(Action::CreateSecretChat(user_id))?;
+            }
+            Event::ConnectionReady => {
+                for action in tg_backend.take_pending_actions() {
+                    tracing::info!("Replaying the action {action:?}");
+                    app_context.action_tx().send(action)?;
+                }
+            }
+            Event::Notify(title, preview) => {
+                app_context
+                    .action_tx()
+                    .send(Action::Notify(title, preview))?;
+            }
+            Event::Bell(in_open_chat) => {
+                app_context.action_tx().send(Action::Bell(in_open_chat))?;
+            }
+            Event::SendScheduledMessage(chat_id, message, send_date) => {
+                app_context
+                    .action_tx()
+                    .send(Action::SendScheduledMessage(chat_id, message, send_date))?;
+            }
+            Event::SetChatTheme(theme_filename) => {
+                app_context
+                    .action_tx()
+                    .send(Action::SetChatTheme(theme_filename))?;
+            }
+            Event::EditAlias(user_id) => {
+                // The prompt is focused first, because the actions are sent to
+                // the focused component.
+                let alias = app_context.tg_context().alias(user_id).unwrap_or_default();
+                app_context
+                    .action_tx()
+                    .send(Action::FocusComponent(Prompt))?;
+                app_context
+                    .action_tx()
+                    .send(Action::EditAlias(user_id, alias))?;
+            }
+            Event::SetAlias(user_id, alias) => {
+                app_context
+                    .action_tx()
+                    .send(Action::SetAlias(user_id, alias))?;
+            }
             _ => {}
         }
     }
@@ -146,6 +622,7 @@ async fn handle_tg_backend_events(
 /// * `app_context` - An Arc wrapped AppContext struct.
 /// * `tui` - A mutable reference to the Tui struct.
 /// * `tui_backend` - A mutable reference to the TuiBackend struct.
+/// * `idle_timer` - A mutable reference to the IdleTimer struct.
 ///
 /// # Returns
 /// * `Result<(), AppError>` - An Ok result or an error.
@@ -153,8 +630,12 @@ async fn handle_tui_backend_events(
     app_context: Arc<AppContext>,
     tui: &mut Tui,
     tui_backend: &mut TuiBackend,
+    idle_timer: &mut IdleTimer,
 ) -> Result<(), AppError<Action>> {
     if let Some(event) = tui_backend.next().await {
+        if matches!(event, Event::Key(..) | Event::Mouse(_)) && idle_timer.record_activity() {
+            app_context.action_tx().send(Action::SetOnline(true))?;
+        }
         match event {
             Event::Render => app_context.action_tx().send(Action::Render)?,
             Event::Resize(width, height) => app_context
@@ -165,11 +646,13 @@ async fn handle_tui_backend_events(
                     .action_tx()
                     .send(Action::from_key_event(key, modifiers))?;
 
-                // Handle core_window key bindings.
+                // Handle core_window key bindings, unless the key is captured
+                // to be bound to an action.
                 if let Some(action_binding) = app_context
                     .keymap_config()
                     .core_window
                     .get(&Event::Key(key, modifiers))
+                    .filter(|_| !app_context.capturing_key())
                 {
                     match action_binding {
                         ActionBinding::Single { action, .. } => {
@@ -242,6 +725,7 @@ async fn consume_until_single_action(
 /// * `tui` - A mutable reference to the Tui struct.
 /// * `tui_backend` - A mutable reference to the TuiBackend struct.
 /// * `tg_backend` - A mutable reference to the TgBackend struct.
+/// * `redraw_limiter` - A mutable reference to the RedrawLimiter struct.
 ///
 /// # Returns
 /// * `Result<(), AppError>` - An Ok result or an error.
@@ -250,21 +734,29 @@ pub async fn handle_app_actions(
     tui: &mut Tui,
     tui_backend: &mut TuiBackend,
     tg_backend: &mut TgBackend,
+    redraw_limiter: &mut RedrawLimiter,
 ) -> Result<(), AppError<Action>> {
-    while let Ok(action) = app_context.action_rx().try_recv() {
+    // The pending actions are handled before redrawing, so that the user
+    // interface is redrawn once for all of them.
+    let mut render = false;
+    // The receiver must not be locked while the action is handled, because
+    // handling `LogOut` runs the authorization process, that receives actions
+    // too.
+    loop {
+        let Ok(action) = app_context.action_rx().try_recv() else {
+            break;
+        };
+        if action == Action::Render {
+            render = true;
+            continue;
+        }
+        redraw_limiter.mark_dirty();
         match action {
-            Action::Render => {
-                tui_backend.terminal.draw(|f| {
-                    tui.draw(f, f.area()).unwrap();
-                })?;
-            }
             Action::Resize(width, height) => {
                 tui_backend
                     .terminal
                     .resize(Rect::new(0, 0, width, height))?;
-                tui_backend.terminal.draw(|f| {
-                    tui.draw(f, f.area()).unwrap();
-                })?;
+                render = true;
             }
             Action::FocusLost => tui_backend.suspend()?,
             Action::FocusGained => tui_backend.resume()?,
@@ -283,14 +775,27 @@ pub async fn handle_app_actions(
                     )
                     .await;
             }
+            Action::SendPhoto(ref path, ref caption, ref reply_to) => {
+                tg_backend
+                    .send_photo(
+                        path.to_string(),
+                        caption.to_string(),
+                        app_context.tg_context().open_chat_id(),
+                        reply_to.clone(),
+                    )
+                    .await;
+            }
             Action::SendMessageEdited(message_id, ref message) => {
                 tg_backend
                     .send_message_edited(message_id, message.to_string())
                     .await;
             }
-            Action::GetChatHistory => {
+            Action::GetChatHistory(chat_id) => {
+                tg_backend.get_chat_history(chat_id).await;
+            }
+            Action::ForwardMessages(chat_id, from_chat_id, ref message_ids) => {
                 tg_backend
-                    .get_chat_history(app_context.tg_context().open_chat_id())
+                    .forward_messages(chat_id, from_chat_id, message_ids.to_vec())
                     .await;
             }
             Action::DeleteMessages(ref message_ids, revoke) => {
@@ -302,22 +807,313 @@ pub async fn handle_app_actions(
                     )
                     .await;
             }
-            Action::ReplyMessage(message_id, ref message) => {
+            Action::ReplyMessage(message_id, ref message)
+            | Action::QuoteReplyMessage(message_id, ref message) => {
                 app_context
                     .tg_context()
                     .set_reply_message(message_id, message.to_string());
             }
+            Action::CancelReply => {
+                app_context
+                    .tg_context()
+                    .set_reply_message(-1, String::new());
+            }
             Action::ViewAllMessages => {
                 tg_backend.view_all_messages().await;
             }
+            Action::ViewMessages(chat_id, ref message_ids) => {
+                tg_backend.view_messages(chat_id, message_ids.clone()).await;
+            }
+            Action::OpenChat(chat_id) => {
+                if let Err(e) = app_context.apply_chat_theme(chat_id) {
+                    tracing::error!("Failed to load the theme of the chat {}: {}", chat_id, e);
+                    app_context
+                        .tg_context()
+                        .set_error_message(format!("Failed to load the theme of the chat: {}", e));
+                }
+                tg_backend.open_chat(chat_id).await;
+            }
+            Action::SetAutoDeleteTime(chat_id, seconds) => {
+                tg_backend.set_auto_delete_time(chat_id, seconds).await;
+            }
+            Action::PinMessage(chat_id, message_id, notify) => {
+                tg_backend.pin_message(chat_id, message_id, notify).await;
+            }
+            Action::UnpinMessage(chat_id, message_id) => {
+                tg_backend.unpin_message(chat_id, message_id).await;
+            }
+            Action::GetMessageViewers(chat_id, message_id) => {
+                tg_backend.get_message_viewers(chat_id, message_id).await;
+            }
+            Action::ClearChatHistory(chat_id, remove_from_chat_list, revoke) => {
+                tg_backend
+                    .clear_chat_history(chat_id, remove_from_chat_list, revoke)
+                    .await;
+            }
+            Action::ToggleBlockUser(user_id, report_spam) => {
+                tg_backend.toggle_block_user(user_id, report_spam).await;
+            }
+            Action::JoinChat(chat_id) => {
+                tg_backend.join_chat(chat_id).await;
+            }
+            Action::SendScheduledMessage(chat_id, ref message, send_date) => {
+                tg_backend
+                    .send_scheduled_message(chat_id, message.to_string(), send_date)
+                    .await;
+            }
+            Action::JoinChatByLink(ref link) => {
+                tg_backend.join_chat_by_link(link).await;
+            }
+            Action::OpenByUsername(ref username) => {
+                tg_backend.open_by_username(username).await;
+            }
+            Action::GetMessageLink(chat_id, message_id) => {
+                tg_backend.get_message_link(chat_id, message_id).await;
+            }
+            Action::LoadStickerSets => {
+                tg_backend.load_sticker_sets().await;
+            }
+            Action::LoadChatMembers(chat_id) => {
+                tg_backend.load_chat_members(chat_id);
+            }
+            Action::OpenPrivateChat(user_id) => {
+                tg_backend.open_private_chat(user_id).await;
+            }
+            Action::LoadScheduledMessages(chat_id) => {
+                tg_backend.load_scheduled_messages(chat_id).await;
+            }
+            Action::SendScheduledMessageNow(chat_id, message_id) => {
+                tg_backend
+                    .send_scheduled_message_now(chat_id, message_id)
+                    .await;
+            }
+            Action::SendSticker(chat_id, sticker_id) => {
+                tg_backend.send_sticker(chat_id, sticker_id).await;
+            }
+            Action::PlayVoiceNote(message_id) => {
+                tg_backend.play_voice_note(message_id).await;
+            }
+            Action::OpenMedia(message_id) => {
+                tg_backend.open_media(message_id).await;
+            }
+            Action::PlayAudio(message_id, ref path) => {
+                tg_backend.play_audio(message_id, path);
+            }
+            Action::SaveContact(message_id) => {
+                tg_backend.save_contact(message_id).await;
+            }
+            Action::PressInlineButton(message_id, ref data) => {
+                tg_backend
+                    .press_inline_button(message_id, data.clone())
+                    .await;
+            }
+            Action::FileProgress(ref file) => {
+                app_context.tg_context().update_download(file.clone());
+            }
+            Action::CancelDownload(file_id) => {
+                tg_backend.cancel_download(file_id).await;
+            }
+            Action::LoadSessions => {
+                tg_backend.load_sessions().await;
+            }
+            Action::TerminateSession(session_id) => {
+                tg_backend.terminate_session(session_id).await;
+            }
+            Action::LeaveChat(chat_id) => {
+                tg_backend.leave_chat(chat_id).await;
+            }
+            Action::CreateSecretChat(user_id) => {
+                tg_backend.create_secret_chat(user_id).await;
+            }
+            Action::SetOnline(online) => {
+                if online {
+                    tg_backend.online().await;
+                } else {
+                    tg_backend.offline().await;
+                }
+            }
+            Action::ToggleOnline => {
+                let online = !app_context.tg_context().is_online();
+                app_context.action_tx().send(Action::SetOnline(online))?;
+            }
+            Action::Notify(ref title, ref preview)
+                if app_context.app_config().desktop_notifications
+                    && !app_context.do_not_disturb() =>
+            {
+                // Showing the notification can block, e.g. on D-Bus.
+                let (title, preview) = (title.clone(), preview.clone());
+                tokio::task::spawn_blocking(move || {
+                    if let Err(e) = notify_rust::Notification::new()
+                        .appname("tgt")
+                        .summary(&title)
+                        .body(&preview)
+                        .show()
+                    {
+                        tracing::error!("Failed to show the notification: {}", e);
+                    }
+                });
+            }
+            Action::ToggleDoNotDisturb => {
+                app_context.set_do_not_disturb(!app_context.do_not_disturb());
+            }
+            Action::TogglePrivacyMode => {
+                app_context.set_privacy_mode(!app_context.privacy_mode());
+            }
+            Action::SetChatTheme(ref theme_filename) => {
+                let chat_id = app_context.tg_context().open_chat_id();
+                if chat_id == 0 {
+                    app_context
+                        .tg_context()
+                        .set_error_message("Open a chat to set its theme");
+                } else {
+                    match app_context.set_chat_theme(chat_id, theme_filename.clone()) {
+                        Ok(_) => app_context
+                            .tg_context()
+                            .set_info_message(match theme_filename {
+                                Some(theme_filename) => {
+                                    format!("Chat theme set to {}", theme_filename)
+                                }
+                                None => "Chat theme reset to the default one".to_string(),
+                            }),
+                        Err(e) => {
+                            tracing::error!("Failed to set the theme of the chat: {}", e);
+                            app_context
+                                .tg_context()
+                                .set_error_message(format!("Failed to set the chat theme: {}", e));
+                        }
+                    }
+                }
+            }
+            Action::SetAlias(user_id, ref alias) => {
+                match app_context.set_alias(user_id, alias.clone()) {
+                    Ok(_) => app_context.tg_context().set_info_message(match alias {
+                        Some(alias) => format!("Alias of {} set to {}", user_id, alias),
+                        None => format!("Alias of {} removed", user_id),
+                    }),
+                    Err(e) => {
+                        tracing::error!("Failed to set the alias: {}", e);
+                        app_context
+                            .tg_context()
+                            .set_error_message(format!("Failed to set the alias: {}", e));
+                    }
+                }
+            }
+            Action::ReloadConfig => match app_context.reload_config() {
+                Ok(_) => {
+                    tracing::info!("Configuration reloaded");
+                    if !report_keymap_conflicts(&app_context) {
+                        app_context
+                            .tg_context()
+                            .set_info_message("Configuration reloaded");
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to reload the configuration: {}", e);
+                    app_context
+                        .tg_context()
+                        .set_error_message(format!("Failed to reload the configuration: {}", e));
+                }
+            },
+            Action::ToggleBell => {
+                let bell = !app_context.bell();
+                app_context.set_bell(bell);
+                app_context.tg_context().set_info_message(if bell {
+                    "Bell on new messages enabled"
+                } else {
+                    "Bell on new messages disabled"
+                });
+            }
+            Action::ToggleStatusBar => {
+                if let Err(e) = app_context.toggle_status_bar() {
+                    tracing::error!("Failed to save the configuration: {}", e);
+                }
+            }
+            Action::ToggleTitleBar => {
+                if let Err(e) = app_context.toggle_title_bar() {
+                    tracing::error!("Failed to save the configuration: {}", e);
+                }
+            }
+            Action::Bell(in_open_chat)
+                if app_context.bell()
+                    && !app_context.do_not_disturb()
+                    && (!in_open_chat || app_context.app_config().bell_in_open_chat) =>
+            {
+                // The bell does not move the cursor, so it does not break the
+                // drawing of the terminal.
+                let mut stdout = io::stdout();
+                if let Err(e) = stdout.write_all(b"\x07").and_then(|_| stdout.flush()) {
+                    tracing::error!("Failed to ring the bell: {}", e);
+                }
+            }
+            Action::LogOut => {
+                log_out_and_log_in(Arc::clone(&app_context), tui_backend, tg_backend).await?;
+            }
             _ => {}
         }
 
         tui.update(action.clone())
     }
+    if render && redraw_limiter.should_draw() {
+        app_context.tg_context().refresh_snapshot();
+        tui_backend.terminal.draw(|f| {
+            tui.draw(f, f.area()).unwrap();
+        })?;
+    }
     Ok(())
 }
 
+/// Restore the application state saved during the previous run.
+/// This function reopens the last opened chat once it is received with the
+/// chat list. It is called until the chat is received, the saved state is
+/// kept in the meantime because the chat list is loaded in the background.
+/// The restore is abandoned when the user opens a chat before.
+/// If the chat is still missing when giving up, it no longer exists and the
+/// saved state is cleared.
+///
+/// # Arguments
+/// * `app_context` - An Arc wrapped AppContext struct.
+/// * `give_up` - Whether the chat list has been loaded, or the chat has been
+///   looked up for too long.
+///
+/// # Returns
+/// * `bool` - Whether there is nothing left to restore.
+fn restore_app_state(app_context: Arc<AppContext>, give_up: bool) -> bool {
+    let Some(chat_id) = app_context.app_state().last_open_chat_id else {
+        return true;
+    };
+    let tg_context = app_context.tg_context();
+    if tg_context.open_chat_id() != 0 {
+        return true;
+    }
+
+    let user = match tg_context.chats().get(&chat_id) {
+        Some(chat) => match &chat.r#type {
+            ChatType::Private(p) => tg_context.users().get(&p.user_id).cloned(),
+            _ => None,
+        },
+        None if give_up => {
+            tracing::warn!("The last opened chat {} no longer exists", chat_id);
+            tg_context.set_error_message("The last opened chat no longer exists");
+            if let Err(e) = app_context.clear_last_open_chat() {
+                tracing::error!("Failed to save the app state: {}", e);
+            }
+            return true;
+        }
+        None => return false,
+    };
+
+    tracing::info!("Restoring the last opened chat {}", chat_id);
+    tg_context.set_open_chat_user(user);
+    tg_context.set_open_chat_id(chat_id);
+    tg_context.clear_open_chat_messages();
+    tg_context.set_from_message_id(0);
+    if let Some(event_tx) = tg_context.event_tx().as_ref() {
+        event_tx.send(Event::OpenChat(chat_id)).unwrap();
+        event_tx.send(Event::GetChatHistory(chat_id)).unwrap();
+    };
+    true
+}
+
 /// An enum to represent the outcome of the handle_cli function.
 enum HandleCliOutcome {
     /// The application should quit.
@@ -326,6 +1122,8 @@ enum HandleCliOutcome {
     Continue,
     /// The user should be logged out.
     Logout,
+    /// The command failed, the application should quit with an error.
+    Fail(AppError<Action>),
 }
 
 #[allow(clippy::await_holding_lock)]
@@ -340,6 +1138,17 @@ async fn handle_cli(app_context: Arc<AppContext>, tg_backend: &mut TgBackend) ->
     if app_context.cli_args().telegram_cli().logout() {
         return HandleCliOutcome::Logout;
     }
+    if app_context.cli_args().telegram_cli().list_chats() {
+        let limit = app_context.cli_args().telegram_cli().limit();
+        tg_backend.load_chats_up_to(limit).await;
+        // The chats are sent by TDLib as updates, that are applied in the
+        // background.
+        tg_backend.wait_for_updates().await;
+        return match list_chats(&app_context, limit) {
+            Ok(()) => HandleCliOutcome::Quit,
+            Err(e) => HandleCliOutcome::Fail(AppError::Io(e.into())),
+        };
+    }
     if let Some(chat) = app_context.cli_args().telegram_cli().send_message() {
         futures::join!(tg_backend.load_all_chats());
 
@@ -399,6 +1208,41 @@ async fn handle_cli(app_context: Arc<AppContext>, tg_backend: &mut TgBackend) ->
     HandleCliOutcome::Continue
 }
 
+/// Print the chats of the main list to the standard output, as JSON or one
+/// chat for each line.
+///
+/// # Arguments
+/// * `app_context` - The application context.
+/// * `limit` - The maximum number of chats to print.
+///
+/// # Returns
+/// * `Result<(), serde_json::Error>` - An error if the chats can not be
+///   serialized as JSON.
+fn list_chats(app_context: &AppContext, limit: Option<usize>) -> Result<(), serde_json::Error> {
+    let chats = app_context.tg_context().chat_summaries(limit);
+    if app_context.cli_args().telegram_cli().json() {
+        match serde_json::to_string_pretty(&chats) {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                tracing::error!("Failed to serialize the chats: {}", e);
+                eprintln!("Failed to serialize the chats: {}", e);
+                return Err(e);
+            }
+        }
+        return Ok(());
+    }
+    for chat in chats {
+        println!(
+            "{}\t{}\t{}\t{}",
+            chat.id,
+            chat.title,
+            chat.unread_count,
+            chat.last_message.unwrap_or_default().replace('\n', " ")
+        );
+    }
+    Ok(())
+}
+
 /// Quit the tui.
 ///
 /// # Arguments
@@ -406,10 +1250,8 @@ async fn handle_cli(app_context: Arc<AppContext>, tg_backend: &mut TgBackend) ->
 /// * `tui_backend` - A mutable reference to the TuiBackend struct.
 async fn quit_tui(tg_backend: &mut TgBackend, tui_backend: &mut TuiBackend) {
     futures::join!(tg_backend.offline());
-    tg_backend.have_authorization = false;
-    tg_backend.close().await;
     tui_backend.exit();
-    tg_backend.handle_authorization_state().await;
+    tg_backend.shutdown().await;
 
     // Clear the terminal and move the cursor to the top left corner
     io::Write::write_all(&mut io::stdout().lock(), b"\x1b[2J\x1b[1;1H").unwrap();
@@ -420,14 +1262,58 @@ async fn quit_tui(tg_backend: &mut TgBackend, tui_backend: &mut TuiBackend) {
 /// # Arguments
 /// * `tg_backend` - A mutable reference to the TgBackend struct.
 async fn quit_cli(tg_backend: &mut TgBackend) {
-    tg_backend.have_authorization = false;
-    tg_backend.close().await;
-    tg_backend.handle_authorization_state().await;
+    tg_backend.shutdown().await;
 
     // Clear the terminal and move the cursor to the top left corner
     io::Write::write_all(&mut io::stdout().lock(), b"\x1b[2J\x1b[1;1H").unwrap();
 }
 
+/// Logout the user from the Telegram backend and start a new session.
+/// The `AuthWindow` is shown to log in again, if the user quits instead the
+/// application quits.
+///
+/// # Arguments
+/// * `app_context` - An Arc wrapped AppContext struct.
+/// * `tui_backend` - A mutable reference to the TuiBackend struct.
+/// * `tg_backend` - A mutable reference to the TgBackend struct.
+///
+/// # Returns
+/// * `Result<(), AppError>` - An Ok result or an error.
+async fn log_out_and_log_in(
+    app_context: Arc<AppContext>,
+    tui_backend: &mut TuiBackend,
+    tg_backend: &mut TgBackend,
+) -> Result<(), AppError<Action>> {
+    tracing::info!("Logging out and starting a new session");
+    tui_backend.exit();
+    log_out(tg_backend).await;
+    app_context.tg_context().clear();
+    // The chat of the previous account must not be restored, also if the
+    // user quits before logging in again.
+    if let Err(e) = app_context.clear_last_open_chat() {
+        tracing::error!("Failed to save the app state: {}", e);
+    }
+
+    tg_backend.restart().await;
+    run_auth(Arc::clone(&app_context), tui_backend, tg_backend).await?;
+    if !tg_backend.have_authorization {
+        app_context.quit_store(true);
+        return Ok(());
+    }
+    tg_backend.use_quick_ack().await;
+    tg_backend.get_me().await;
+    tg_backend.load_chats(ChatList::Main, 30).await;
+    tg_backend.online().await;
+    let disable_animated_emoji = app_context.app_config().disable_animated_emoji;
+    tg_backend
+        .disable_animated_emoji(disable_animated_emoji)
+        .await;
+
+    tui_backend.enter()?;
+    app_context.action_tx().send(Action::UnfocusComponent)?;
+    Ok(())
+}
+
 /// Logout the user from the Telegram backend.
 ///
 /// # Arguments
@@ -435,7 +1321,41 @@ async fn quit_cli(tg_backend: &mut TgBackend) {
 async fn log_out(tg_backend: &mut TgBackend) {
     tg_backend.log_out().await;
     tg_backend.handle_authorization_state().await;
+    tg_backend.clear_database_dir();
 
     // Clear the terminal and move the cursor to the top left corner
     io::Write::write_all(&mut io::stdout().lock(), b"\x1b[2J\x1b[1;1H").unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::run::{IdleTimer, RedrawLimiter};
+    use std::time::Duration;
+
+    #[test]
+    fn test_redraw_limiter() {
+        let mut redraw_limiter = RedrawLimiter::new(Duration::from_secs(60));
+        // The first tick draws the user interface.
+        assert!(redraw_limiter.should_draw());
+        assert!(!redraw_limiter.should_draw());
+        redraw_limiter.mark_dirty();
+        assert!(redraw_limiter.should_draw());
+        assert!(!redraw_limiter.should_draw());
+        // Without changes, the user interface is redrawn after the idle
+        // interval.
+        let mut redraw_limiter = RedrawLimiter::new(Duration::ZERO);
+        assert!(redraw_limiter.should_draw());
+        assert!(redraw_limiter.should_draw());
+    }
+
+    #[test]
+    fn test_idle_timer() {
+        let mut idle_timer = IdleTimer::new();
+        assert!(!idle_timer.check_away(Duration::from_secs(60)));
+        assert!(idle_timer.check_away(Duration::ZERO));
+        // The user is away only once per period of inactivity.
+        assert!(!idle_timer.check_away(Duration::ZERO));
+        assert!(idle_timer.record_activity());
+        assert!(!idle_timer.record_activity());
+    }
+}
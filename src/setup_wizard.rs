@@ -0,0 +1,439 @@
+use crate::{
+    app_error::AppError,
+    configs::{
+        config_file::ConfigFile,
+        custom::{app_custom::AppConfig, telegram_custom::TelegramConfig},
+    },
+    utils::tgt_config_dir,
+};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use ratatui::{
+    layout::{Constraint, Flex, Layout},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+};
+
+/// The theme selected by default in the setup.
+const DEFAULT_THEME: &str = "theme.toml";
+/// The basic keybindings explained in the setup, with their description.
+const BASIC_KEYBINDINGS: &[(&str, &str)] = &[
+    (
+        "alt+1 / alt+2 / alt+3",
+        "focus the chat list, the chat or the prompt",
+    ),
+    ("tab / back_tab", "focus the next or the previous component"),
+    ("up / down, enter", "select and open a chat"),
+    ("r / e", "reply to or edit the selected message"),
+    ("esc", "unfocus the current component"),
+    ("q / ctrl+c", "quit"),
+];
+
+/// The steps of the `SetupWizard`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SetupStep {
+    /// The welcome message.
+    Welcome,
+    /// The API credentials.
+    Credentials,
+    /// The theme.
+    Theme,
+    /// The explanation of the basic keybindings.
+    Keybindings,
+}
+
+/// The outcome of a key pressed in the `SetupWizard`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupOutcome {
+    /// The setup continues.
+    Continue,
+    /// The setup is completed, the choices are saved.
+    Done,
+    /// The setup is skipped, the default configuration is used.
+    Skip,
+}
+
+/// `SetupWizard` guides the user through the configuration the first time
+/// the application runs: it collects the API credentials, lets the user pick
+/// a theme and explains the basic keybindings.
+/// The choices are saved in the custom `telegram.toml` and `app.toml`.
+pub struct SetupWizard {
+    /// The current step.
+    step: SetupStep,
+    /// The API id typed by the user, empty to use the default one.
+    api_id: String,
+    /// The API hash typed by the user, empty to use the default one.
+    api_hash: String,
+    /// The index of the selected credential field, 0 for the API id and 1
+    /// for the API hash.
+    selected_field: usize,
+    /// The themes of the configuration directory.
+    themes: Vec<String>,
+    /// The index of the selected theme.
+    selected_theme: usize,
+    /// The error of the last input, e.g. an invalid API id.
+    error: Option<String>,
+}
+
+impl SetupWizard {
+    /// Create a new instance of the `SetupWizard` struct.
+    ///
+    /// # Arguments
+    /// * `themes` - The file names of the themes the user can pick.
+    ///
+    /// # Returns
+    /// * `Self` - The new instance of the `SetupWizard` struct.
+    pub fn new(themes: Vec<String>) -> Self {
+        let selected_theme = themes
+            .iter()
+            .position(|theme| theme == DEFAULT_THEME)
+            .unwrap_or(0);
+        SetupWizard {
+            step: SetupStep::Welcome,
+            api_id: String::new(),
+            api_hash: String::new(),
+            selected_field: 0,
+            themes,
+            selected_theme,
+            error: None,
+        }
+    }
+
+    /// Get the file names of the themes in the configuration directory, i.e.
+    /// the files whose name ends with `theme.toml`.
+    ///
+    /// # Returns
+    /// * `Vec<String>` - The sorted file names of the themes.
+    pub fn available_themes() -> Vec<String> {
+        let mut themes: Vec<String> = tgt_config_dir()
+            .and_then(std::fs::read_dir)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.file_name().to_string_lossy().to_string())
+                    .filter(|name| name.ends_with(DEFAULT_THEME))
+                    .collect()
+            })
+            .unwrap_or_default();
+        themes.sort();
+        if themes.is_empty() {
+            themes.push(DEFAULT_THEME.to_string());
+        }
+        themes
+    }
+
+    /// Check the API credentials typed by the user. Both of them can be
+    /// empty, to use the default ones.
+    ///
+    /// # Returns
+    /// * `Result<(), String>` - An error explaining the invalid credential.
+    fn validate_credentials(&self) -> Result<(), String> {
+        let api_id = self.api_id.trim();
+        let api_hash = self.api_hash.trim();
+        if api_id.is_empty() && api_hash.is_empty() {
+            return Ok(());
+        }
+        if !api_id.parse::<i32>().is_ok_and(|api_id| api_id > 0) {
+            return Err(format!("The API id must be a positive number: {}", api_id));
+        }
+        if api_hash.is_empty() {
+            return Err("The API hash is empty".to_string());
+        }
+        Ok(())
+    }
+
+    /// Handle a key pressed by the user.
+    ///
+    /// # Arguments
+    /// * `key` - The code of the key.
+    ///
+    /// # Returns
+    /// * `SetupOutcome` - Whether the setup continues, is completed or is
+    ///   skipped.
+    pub fn handle_key(&mut self, key: KeyCode) -> SetupOutcome {
+        if key == KeyCode::Esc {
+            return SetupOutcome::Skip;
+        }
+        match self.step {
+            SetupStep::Welcome => {
+                if key == KeyCode::Enter {
+                    self.step = SetupStep::Credentials;
+                }
+            }
+            SetupStep::Credentials => {
+                let field = if self.selected_field == 0 {
+                    &mut self.api_id
+                } else {
+                    &mut self.api_hash
+                };
+                match key {
+                    KeyCode::Char(c) => field.push(c),
+                    KeyCode::Backspace => {
+                        field.pop();
+                    }
+                    KeyCode::Tab | KeyCode::Down | KeyCode::Up | KeyCode::BackTab => {
+                        self.selected_field = 1 - self.selected_field;
+                    }
+                    KeyCode::Enter if self.selected_field == 0 => self.selected_field = 1,
+                    KeyCode::Enter => match self.validate_credentials() {
+                        Ok(()) => {
+                            self.error = None;
+                            self.step = SetupStep::Theme;
+                        }
+                        Err(error) => self.error = Some(error),
+                    },
+                    _ => {}
+                }
+            }
+            SetupStep::Theme => match key {
+                KeyCode::Down | KeyCode::Char('j') => {
+                    self.selected_theme = (self.selected_theme + 1).min(self.themes.len() - 1);
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    self.selected_theme = self.selected_theme.saturating_sub(1);
+                }
+                KeyCode::Enter => self.step = SetupStep::Keybindings,
+                _ => {}
+            },
+            SetupStep::Keybindings => {
+                if key == KeyCode::Enter {
+                    return SetupOutcome::Done;
+                }
+            }
+        }
+        SetupOutcome::Continue
+    }
+
+    /// Save the choices of the user in the custom `telegram.toml` and
+    /// `app.toml`. The API credentials are saved only if they have been
+    /// typed.
+    ///
+    /// # Returns
+    /// * `Result<(), AppError>` - An Ok result or an error.
+    pub fn save(&self) -> Result<(), AppError<()>> {
+        let mut app_fields = vec![(
+            "theme_filename",
+            toml::Value::String(self.themes[self.selected_theme].clone()),
+        )];
+        if !self.api_id.trim().is_empty() {
+            TelegramConfig::save(&[
+                (
+                    "api_id",
+                    toml::Value::String(self.api_id.trim().to_string()),
+                ),
+                (
+                    "api_hash",
+                    toml::Value::String(self.api_hash.trim().to_string()),
+                ),
+            ])?;
+            app_fields.push((
+                "take_api_id_from_telegram_config",
+                toml::Value::Boolean(true),
+            ));
+            app_fields.push((
+                "take_api_hash_from_telegram_config",
+                toml::Value::Boolean(true),
+            ));
+        }
+        AppConfig::save(&app_fields)?;
+        Ok(())
+    }
+
+    /// Get the lines of the current step.
+    ///
+    /// # Returns
+    /// * `Vec<Line>` - The lines to display.
+    fn lines(&self) -> Vec<Line<'static>> {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let selected = Style::default().add_modifier(Modifier::REVERSED);
+        let mut lines = match self.step {
+            SetupStep::Welcome => vec![
+                Line::from(Span::styled("Welcome to tgt!", bold)),
+                Line::from(""),
+                Line::from("This setup configures tgt in a few steps: the API credentials,"),
+                Line::from("the theme and the basic keybindings."),
+                Line::from("Everything can be changed later in the configuration files."),
+            ],
+            SetupStep::Credentials => {
+                let mut lines = vec![
+                    Line::from(Span::styled("API credentials", bold)),
+                    Line::from(""),
+                    Line::from("Get your api_id and api_hash at https://my.telegram.org/apps."),
+                    Line::from("Leave them empty to use the default credentials of tgt."),
+                    Line::from(""),
+                ];
+                for (i, (label, value)) in [("API id", &self.api_id), ("API hash", &self.api_hash)]
+                    .into_iter()
+                    .enumerate()
+                {
+                    let style = if i == self.selected_field {
+                        selected
+                    } else {
+                        Style::default()
+                    };
+                    lines.push(Line::from(vec![
+                        Span::styled(format!("{}: ", label), style),
+                        Span::raw(value.clone()),
+                    ]));
+                }
+                lines
+            }
+            SetupStep::Theme => {
+                let mut lines = vec![Line::from(Span::styled("Theme", bold)), Line::from("")];
+                lines.extend(self.themes.iter().enumerate().map(|(i, theme)| {
+                    if i == self.selected_theme {
+                        Line::from(Span::styled(format!("> {}", theme), selected))
+                    } else {
+                        Line::from(format!("  {}", theme))
+                    }
+                }));
+                lines
+            }
+            SetupStep::Keybindings => {
+                let mut lines = vec![
+                    Line::from(Span::styled("Basic keybindings", bold)),
+                    Line::from(""),
+                ];
+                lines.extend(BASIC_KEYBINDINGS.iter().map(|(keys, description)| {
+                    Line::from(vec![
+                        Span::styled(format!("{:<24}", keys), bold),
+                        Span::raw(description.to_string()),
+                    ])
+                }));
+                lines.push(Line::from(""));
+                lines.push(Line::from(
+                    "All the keybindings are listed and can be changed in keymap.toml.",
+                ));
+                lines
+            }
+        };
+        lines.push(Line::from(""));
+        if let Some(error) = self.error.as_ref() {
+            lines.push(Line::from(Span::styled(error.clone(), bold)));
+        }
+        lines.push(Line::from(match self.step {
+            SetupStep::Credentials => "Enter: next, Tab: next field, Esc: skip the setup",
+            SetupStep::Theme => "Up/Down: select, Enter: next, Esc: skip the setup",
+            SetupStep::Keybindings => "Enter: finish, Esc: skip the setup",
+            SetupStep::Welcome => "Enter: start, Esc: skip the setup",
+        }));
+        lines
+    }
+}
+
+/// Run the `SetupWizard` in the terminal, then save the choices of the
+/// user. If the setup is skipped, an empty `app.toml` is saved, so that the
+/// default configuration is used and the setup is not shown again.
+///
+/// # Returns
+/// * `Result<(), AppError>` - An Ok result or an error.
+pub fn run() -> Result<(), AppError<()>> {
+    let mut wizard = SetupWizard::new(SetupWizard::available_themes());
+    let mut terminal = ratatui::init();
+    let outcome = loop {
+        let lines = wizard.lines();
+        if let Err(e) = terminal.draw(|frame| {
+            let [area] = Layout::horizontal([Constraint::Max(80)])
+                .flex(Flex::Center)
+                .areas(frame.area());
+            let [area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+                .flex(Flex::Center)
+                .areas(area);
+            let block = Block::new().borders(Borders::ALL).title("tgt setup");
+            frame.render_widget(
+                Paragraph::new(lines)
+                    .block(block)
+                    .wrap(Wrap { trim: false }),
+                area,
+            );
+        }) {
+            ratatui::restore();
+            return Err(e.into());
+        }
+        match event::read() {
+            Ok(Event::Key(KeyEvent {
+                code,
+                kind: KeyEventKind::Press,
+                ..
+            })) => match wizard.handle_key(code) {
+                SetupOutcome::Continue => {}
+                outcome => break outcome,
+            },
+            Ok(_) => {}
+            Err(e) => {
+                ratatui::restore();
+                return Err(e.into());
+            }
+        }
+    };
+    ratatui::restore();
+    match outcome {
+        SetupOutcome::Skip => {
+            tracing::info!("The first run setup has been skipped");
+            AppConfig::save(&[])?;
+        }
+        _ => wizard.save()?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::setup_wizard::{SetupOutcome, SetupStep, SetupWizard};
+    use crossterm::event::KeyCode;
+
+    fn type_text(wizard: &mut SetupWizard, text: &str) {
+        for c in text.chars() {
+            assert_eq!(wizard.handle_key(KeyCode::Char(c)), SetupOutcome::Continue);
+        }
+    }
+
+    #[test]
+    fn test_setup_wizard_steps() {
+        let mut wizard = SetupWizard::new(vec![
+            "first_theme.toml".to_string(),
+            "theme.toml".to_string(),
+        ]);
+        assert_eq!(wizard.selected_theme, 1);
+        wizard.handle_key(KeyCode::Enter);
+        assert_eq!(wizard.step, SetupStep::Credentials);
+
+        type_text(&mut wizard, "abc");
+        wizard.handle_key(KeyCode::Enter);
+        type_text(&mut wizard, "hash");
+        wizard.handle_key(KeyCode::Enter);
+        // The API id is not a number.
+        assert_eq!(wizard.step, SetupStep::Credentials);
+        assert!(wizard.error.is_some());
+
+        wizard.handle_key(KeyCode::Tab);
+        for _ in 0..3 {
+            wizard.handle_key(KeyCode::Backspace);
+        }
+        type_text(&mut wizard, "12345");
+        wizard.handle_key(KeyCode::Tab);
+        wizard.handle_key(KeyCode::Enter);
+        assert_eq!(wizard.step, SetupStep::Theme);
+        assert_eq!(wizard.api_id, "12345");
+        assert_eq!(wizard.api_hash, "hash");
+        assert!(wizard.error.is_none());
+
+        wizard.handle_key(KeyCode::Up);
+        wizard.handle_key(KeyCode::Up);
+        assert_eq!(wizard.selected_theme, 0);
+        wizard.handle_key(KeyCode::Enter);
+        assert_eq!(wizard.step, SetupStep::Keybindings);
+        assert_eq!(wizard.handle_key(KeyCode::Enter), SetupOutcome::Done);
+    }
+
+    #[test]
+    fn test_setup_wizard_skip() {
+        let mut wizard = SetupWizard::new(vec!["theme.toml".to_string()]);
+        wizard.handle_key(KeyCode::Enter);
+        // The default credentials are used when both are empty.
+        wizard.handle_key(KeyCode::Enter);
+        wizard.handle_key(KeyCode::Enter);
+        assert_eq!(wizard.step, SetupStep::Theme);
+        assert_eq!(wizard.handle_key(KeyCode::Esc), SetupOutcome::Skip);
+    }
+}
@@ -1,12 +1,122 @@
-use crate::app_context::AppContext;
+use crate::{
+    app_context::AppContext,
+    tg::tg_context::{TgContext, TgSnapshot},
+    utils::obscure_line,
+};
 use chrono::{DateTime, Local};
 use ratatui::style::{Modifier, Style};
 use ratatui::text::{Line, Span, Text};
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
 use std::time::{Duration, UNIX_EPOCH};
-use tdlib_rs::enums::{MessageContent, MessageReplyTo, MessageSender};
-use tdlib_rs::types::FormattedText;
+use tdlib_rs::enums::{MessageContent, MessageReplyTo, MessageSender, ReplyMarkup, TextEntityType};
+use tdlib_rs::types::{Contact, FormattedText, InlineKeyboardButton, TextEntity};
+use unicode_width::UnicodeWidthChar;
 
-use super::td_enums::{TdMessageReplyTo, TdMessageSender};
+use super::td_enums::{TdMessageOrigin, TdMessageReplyTo, TdMessageSender, TdServiceMessage};
+
+/// The lines of the content of a message, with the parts of them that are
+/// not displayed as the rest of the text.
+#[derive(Debug, Clone, Default)]
+struct ContentLines {
+    lines: Vec<Line<'static>>,
+    /// The spans of the code entities, as indices of line and span. Their
+    /// style depends on the theme, so it is applied when the message is
+    /// displayed, see `span_for_display`.
+    code_spans: HashSet<(usize, usize)>,
+    /// The indices of the lines of the language labels of the code blocks.
+    /// The labels are only displayed, so they are not part of the text of
+    /// the message.
+    language_labels: HashSet<usize>,
+}
+
+impl From<Vec<Line<'static>>> for ContentLines {
+    fn from(lines: Vec<Line<'static>>) -> Self {
+        Self {
+            lines,
+            ..Self::default()
+        }
+    }
+}
+
+/// The type of the content of a message.
+/// It is used to choose how the media of the message is opened.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MessageContentType {
+    /// A text message.
+    #[default]
+    Text,
+    /// A photo.
+    Photo,
+    /// A video.
+    Video,
+    /// An animation, e.g. a GIF.
+    Animation,
+    /// A video note, a round video message.
+    VideoNote,
+    /// A document.
+    Document,
+    /// An audio file.
+    Audio,
+    /// A voice note.
+    VoiceNote,
+    /// A contact card, it can be saved to the contacts.
+    Contact,
+    /// Any other content, without media that can be opened.
+    Other,
+}
+impl MessageContentType {
+    /// Get whether the content has a media that can be opened.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the content has a media.
+    pub fn has_media(&self) -> bool {
+        !matches!(self, Self::Text | Self::Contact | Self::Other)
+    }
+    /// Get whether the media of the content is played with the audio player
+    /// instead of being opened with the default application of the system.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the media is an audio.
+    pub fn is_audio(&self) -> bool {
+        matches!(self, Self::Audio | Self::VoiceNote)
+    }
+    /// Get the name of the type of the content, as shown in the messages to
+    /// the user.
+    ///
+    /// # Returns
+    /// * `&str` - The name of the type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Photo => "photo",
+            Self::Video => "video",
+            Self::Animation => "animation",
+            Self::VideoNote => "video note",
+            Self::Document => "document",
+            Self::Audio => "audio",
+            Self::VoiceNote => "voice note",
+            Self::Contact => "contact",
+            Self::Other => "content",
+        }
+    }
+}
+impl From<&MessageContent> for MessageContentType {
+    fn from(content: &MessageContent) -> Self {
+        match content {
+            MessageContent::MessageText(_) => Self::Text,
+            MessageContent::MessagePhoto(_) => Self::Photo,
+            MessageContent::MessageVideo(_) => Self::Video,
+            MessageContent::MessageAnimation(_) => Self::Animation,
+            MessageContent::MessageVideoNote(_) => Self::VideoNote,
+            MessageContent::MessageDocument(_) => Self::Document,
+            MessageContent::MessageAudio(_) => Self::Audio,
+            MessageContent::MessageVoiceNote(_) => Self::VoiceNote,
+            MessageContent::MessageContact(_) => Self::Contact,
+            _ => Self::Other,
+        }
+    }
+}
 
 #[derive(Debug, Default, Clone)]
 pub struct DateTimeEntry {
@@ -37,10 +147,29 @@ impl DateTimeEntry {
 pub struct MessageEntry {
     id: i64,
     sender_id: TdMessageSender,
-    message_content: Vec<Line<'static>>,
+    message_content: ContentLines,
     reply_to: Option<TdMessageReplyTo>,
+    /// The origin of the message, if it has been forwarded.
+    forward_origin: Option<TdMessageOrigin>,
     timestamp: DateTimeEntry,
     is_edited: bool,
+    /// Indicates whether the spoilers of the message are revealed or not.
+    spoilers_revealed: bool,
+    /// The text of the message, or the caption of a media message, with its
+    /// entities.
+    /// The lines of `message_content` are used to render the message, this
+    /// keeps the entities for the features that need them (e.g. the URLs).
+    formatted_text: FormattedText,
+    /// The type of the content of the message.
+    content_type: MessageContentType,
+    /// The action of the message, if it is a service message, e.g. a user
+    /// that joined the group.
+    service: Option<TdServiceMessage>,
+    /// The contact shared by the message, if any.
+    contact: Option<Contact>,
+    /// The rows of the buttons of the inline keyboard attached to the
+    /// message by a bot, empty if the message has no inline keyboard.
+    inline_keyboard: Vec<Vec<InlineKeyboardButton>>,
 }
 
 impl MessageEntry {
@@ -52,6 +181,19 @@ impl MessageEntry {
         &self.timestamp
     }
 
+    pub fn content_type(&self) -> MessageContentType {
+        self.content_type
+    }
+
+    /// Check whether the message is a service message, e.g. a user that
+    /// joined the group.
+    ///
+    /// # Returns
+    /// * `bool` - True if the message is a service message.
+    pub fn is_service(&self) -> bool {
+        self.service.is_some()
+    }
+
     pub fn sender_id(&self) -> i64 {
         match self.sender_id {
             TdMessageSender::User(user_id) => user_id,
@@ -59,38 +201,260 @@ impl MessageEntry {
         }
     }
 
+    /// Get the ids of the users and of the chats whose names are shown with
+    /// the message: the sender, the origin of a forwarded message and the
+    /// members added or removed by a service message.
+    ///
+    /// # Returns
+    /// * `Vec<i64>` - The ids.
+    pub fn named_ids(&self) -> Vec<i64> {
+        let mut ids = vec![self.sender_id()];
+        match &self.forward_origin {
+            Some(TdMessageOrigin::User(id))
+            | Some(TdMessageOrigin::Chat(id, _))
+            | Some(TdMessageOrigin::Channel(id, _)) => ids.push(*id),
+            Some(TdMessageOrigin::HiddenUser(_)) | None => {}
+        }
+        match &self.service {
+            Some(TdServiceMessage::AddMembers(user_ids)) => ids.extend(user_ids),
+            Some(TdServiceMessage::DeleteMember(user_id)) => ids.push(*user_id),
+            _ => {}
+        }
+        ids
+    }
+
+    /// Get the id of the user of the contact shared by the message.
+    ///
+    /// # Returns
+    /// * `Option<i64>` - The id of the user, `None` if the message is not a
+    ///   contact.
+    pub fn contact_user_id(&self) -> Option<i64> {
+        self.contact.as_ref().map(|contact| contact.user_id)
+    }
+
+    /// Get the URLs contained in the message, both the plain ones and the
+    /// ones behind a text link.
+    ///
+    /// The target of a `TextUrl` entity is its URL, while the target of a
+    /// `Url` entity is the text of the entity itself.
+    ///
+    /// # Returns
+    /// * `Vec<String>` - The URLs in order of appearance, without duplicates.
+    pub fn urls(&self) -> Vec<String> {
+        let mut urls: Vec<String> = Vec::new();
+        for e in self.entities() {
+            let url = match &e.r#type {
+                TextEntityType::TextUrl(text_url) => text_url.url.clone(),
+                TextEntityType::Url => self.entity_text(e),
+                _ => continue,
+            };
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+        urls
+    }
+
+    /// Get the buttons of the inline keyboard of the message, row by row.
+    ///
+    /// # Returns
+    /// * `Vec<InlineKeyboardButton>` - The buttons, empty if the message has
+    ///   no inline keyboard.
+    pub fn inline_buttons(&self) -> Vec<InlineKeyboardButton> {
+        self.inline_keyboard.iter().flatten().cloned().collect()
+    }
+
+    /// Set the inline keyboard of the message, e.g. when the bot edits it.
+    ///
+    /// # Arguments
+    /// * `reply_markup` - The new reply markup of the message, if any.
+    pub fn set_inline_keyboard(&mut self, reply_markup: Option<&ReplyMarkup>) {
+        self.inline_keyboard = Self::reply_markup_inline_keyboard(reply_markup);
+    }
+
+    /// Get the bot commands contained in the message, e.g. `/start`.
+    ///
+    /// # Returns
+    /// * `Vec<String>` - The commands in order of appearance, without
+    ///   duplicates.
+    pub fn bot_commands(&self) -> Vec<String> {
+        let mut commands: Vec<String> = Vec::new();
+        for e in self.entities() {
+            if e.r#type != TextEntityType::BotCommand {
+                continue;
+            }
+            let command = self.entity_text(e);
+            if !commands.contains(&command) {
+                commands.push(command);
+            }
+        }
+        commands
+    }
+
+    /// Get the text of the message, or the caption of a media message, with
+    /// its entities.
+    ///
+    /// # Returns
+    /// * `&FormattedText` - The formatted text, empty if the message has no
+    ///   text.
+    pub fn formatted_text(&self) -> &FormattedText {
+        &self.formatted_text
+    }
+
+    /// Get the entities of the text of the message (e.g. the links, the
+    /// mentions and the code blocks).
+    ///
+    /// # Returns
+    /// * `&[TextEntity]` - The entities, their offsets and lengths are
+    ///   expressed in UTF-16 code units of the formatted text.
+    pub fn entities(&self) -> &[TextEntity] {
+        &self.formatted_text().entities
+    }
+
+    /// Get the part of the text of the message covered by an entity.
+    ///
+    /// # Arguments
+    /// * `entity` - An entity of the message.
+    ///
+    /// # Returns
+    /// * `String` - The text of the entity.
+    pub fn entity_text(&self, entity: &TextEntity) -> String {
+        Self::formatted_text_entity_text(self.formatted_text(), entity)
+    }
+
     pub fn message_content_to_string(&self) -> String {
         self.message_content
+            .lines
             .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.message_content.language_labels.contains(i))
+            .map(|(_, l)| l)
             .map(|l| l.iter().map(|s| s.content.clone()).collect::<String>())
             .collect::<Vec<String>>()
             .join("\n")
     }
 
+    /// Get the text of the message as it is displayed, with the spoilers
+    /// replaced with blocks unless they are revealed.
+    /// It is used wherever the text leaves the message list, e.g. in the
+    /// notifications, the pinned banner and the copied text.
+    ///
+    /// # Returns
+    /// * `String` - The text of the message.
+    pub fn message_content_to_string_masked(&self) -> String {
+        self.message_content
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !self.message_content.language_labels.contains(i))
+            .map(|(_, l)| {
+                l.iter()
+                    .map(|s| self.span_for_display(s, false, Style::default()).content)
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
     pub fn set_message_content(&mut self, content: &MessageContent) {
         self.message_content = Self::message_content_lines(content);
+        self.formatted_text = Self::message_content_formatted_text(content);
+        self.service = TdServiceMessage::from_content(content);
+        self.contact = Self::message_content_contact(content);
     }
 
     pub fn set_is_edited(&mut self, is_edited: bool) {
         self.is_edited = is_edited;
     }
 
+    pub fn set_spoilers_revealed(&mut self, spoilers_revealed: bool) {
+        self.spoilers_revealed = spoilers_revealed;
+    }
+
+    /// Check whether the message continues the group of the previous
+    /// message, i.e. it is sent by the same sender within `window_seconds`.
+    /// The service messages are never grouped with the other messages, so
+    /// that the header of the message after them is shown.
+    ///
+    /// # Arguments
+    /// * `previous` - The message sent before this one.
+    /// * `window_seconds` - The maximum time between the two messages.
+    ///
+    /// # Returns
+    /// * `bool` - True if the two messages are grouped.
+    pub fn is_grouped_with(&self, previous: &MessageEntry, window_seconds: u64) -> bool {
+        let elapsed = i64::from(self.timestamp.timestamp) - i64::from(previous.timestamp.timestamp);
+        self.sender_id() == previous.sender_id()
+            && self.is_service() == previous.is_service()
+            && (0..=window_seconds as i64).contains(&elapsed)
+    }
+
+    /// Get the text of the message, styled with the theme.
+    /// The name and the content of the messages sent by the user are styled
+    /// with `message_myself_name` and `message_myself_content`, the ones of
+    /// the others with `message_other_name` and `message_other_content`.
+    ///
+    /// # Arguments
+    /// * `myself` - Whether the message is sent by the user.
+    /// * `app_context` - The application context.
+    /// * `is_unread` - Whether the message is unread, used only when `myself`
+    ///   is true.
+    /// * `wrap_width` - The width the content is wrapped at, -1 to not wrap.
+    /// * `show_header` - Whether to show the header, it is hidden for the
+    ///   grouped messages.
+    ///
+    /// # Returns
+    /// * `(Text, Range<usize>)` - The styled text of the message, and the
+    ///   range of its lines that show the content, without the header, the
+    ///   replied message and the buttons. The service messages are a single
+    ///   centered line, without header.
     pub fn get_text_styled(
         &self,
         myself: bool,
         app_context: &AppContext,
-        is_unread: bool, // When myself is false, is_unread is useless
-        name_style: Style,
-        content_style: Style,
+        is_unread: bool,
         wrap_width: i32,
-    ) -> Text {
-        let (message_reply_name, message_reply_content) = if myself {
+        show_header: bool,
+    ) -> (Text, Range<usize>) {
+        // The data of the open chat is read from the snapshot, without
+        // taking the locks of the backend.
+        let snapshot = app_context.tg_context().snapshot();
+        if let Some(service) = &self.service {
+            let text = Self::service_text(
+                service,
+                &self.sender_name(&snapshot),
+                self.sender_id(),
+                |user_id| {
+                    snapshot
+                        .names
+                        .get(&user_id)
+                        .cloned()
+                        .unwrap_or_else(|| "Unknown".to_string())
+                },
+            );
+            let mut line =
+                Line::from(Span::styled(text, app_context.style_chat_message_service())).centered();
+            if app_context.privacy_mode() {
+                obscure_line(&mut line);
+            }
+            return (Text::from(line), 0..1);
+        }
+        let (name_style, content_style, message_reply_name, message_reply_content) = if myself {
             (
+                app_context.style_chat_message_myself_name(),
+                app_context.style_chat_message_myself_content(),
                 app_context.style_chat_message_myself_reply_name(),
                 app_context.style_chat_message_myself_reply_content(),
             )
         } else {
+            // In group chats each sender has its own color.
             (
+                if snapshot.open_chat_is_group {
+                    app_context.style_chat_message_sender_name(self.sender_id())
+                } else {
+                    app_context.style_chat_message_other_name()
+                },
+                app_context.style_chat_message_other_content(),
                 app_context.style_chat_message_other_reply_name(),
                 app_context.style_chat_message_other_reply_content(),
             )
@@ -99,7 +463,11 @@ impl MessageEntry {
         let reply_text = match &self.reply_to {
             Some(reply_to) => match reply_to {
                 TdMessageReplyTo::Message(message) => {
-                    if app_context.tg_context().open_chat_id() == message.chat_id {
+                    if snapshot.open_chat_id == message.chat_id {
+                        let replied = snapshot
+                            .open_chat_messages
+                            .iter()
+                            .find(|m| m.id() == message.message_id);
                         let mut entry = Text::default();
                         entry.extend(vec![Line::from(vec![
                             Span::styled(
@@ -107,36 +475,28 @@ impl MessageEntry {
                                 app_context.style_chat_message_reply_text(),
                             ),
                             Span::styled(
-                                app_context
-                                    .tg_context()
-                                    .try_name_from_chats_or_users(
-                                        match app_context
-                                            .tg_context()
-                                            .open_chat_messages()
-                                            .iter()
-                                            .find(|m| m.id() == message.message_id)
-                                        {
-                                            Some(m) => m.sender_id(),
-                                            None => -1,
-                                        },
-                                    )
+                                replied
+                                    .and_then(|m| snapshot.names.get(&m.sender_id()))
+                                    .cloned()
                                     .unwrap_or_default(),
                                 message_reply_name,
                             ),
                         ])]);
-                        entry.extend(
-                            match app_context
-                                .tg_context()
-                                .open_chat_messages()
-                                .iter()
-                                .find(|m| m.id() == message.message_id)
-                            {
-                                Some(m) => {
-                                    m.get_lines_styled_with_style(message_reply_content, wrap_width)
-                                }
+                        // Only the quoted part of the replied message is shown,
+                        // if any.
+                        entry.extend(match &message.quote {
+                            Some(quote) => {
+                                Self::plain_lines_styled(quote, message_reply_content, wrap_width)
+                            }
+                            None => match replied {
+                                Some(m) => m.get_lines_styled_with_style(
+                                    message_reply_content,
+                                    app_context.style_code_block(),
+                                    wrap_width,
+                                ),
                                 None => vec![Line::from("")],
                             },
-                        );
+                        });
                         Some(entry)
                     } else {
                         None
@@ -154,21 +514,302 @@ impl MessageEntry {
             None => None,
         };
 
+        // The header is never obscured by the privacy mode, the body is.
         let mut entry = Text::default();
-        entry.extend(vec![Line::from(vec![
-            Span::styled(
-                match self.sender_id {
-                    TdMessageSender::User(user_id) => app_context
-                        .tg_context()
-                        .try_name_from_chats_or_users(user_id)
-                        .unwrap_or_default(),
-                    TdMessageSender::Chat(chat_id) => app_context
-                        .tg_context()
-                        .name_from_chats(chat_id)
-                        .unwrap_or_default(),
-                },
-                name_style,
+        if show_header {
+            entry.extend(self.header_lines(myself, app_context, &snapshot, is_unread, name_style));
+        }
+        let mut body = Text::default();
+        if let Some(origin) = &self.forward_origin {
+            body.extend(vec![Line::from(Span::styled(
+                format!(
+                    "Forwarded from {}",
+                    Self::forward_origin_name(origin, &snapshot)
+                ),
+                app_context.style_chat_message_forwarded(),
+            ))]);
+        }
+        body.extend(reply_text.unwrap_or_default());
+        let content_start = entry.lines.len() + body.lines.len();
+        // The username of a contact is known only if the user is loaded.
+        match self.contact.as_ref().zip(self.contact_username(&snapshot)) {
+            Some((contact, username)) => body.extend(vec![Line::from(Span::styled(
+                Self::contact_text(contact, Some(&username)),
+                content_style,
+            ))]),
+            None => body.extend(self.get_lines_styled_with_style(
+                content_style,
+                app_context.style_code_block(),
+                wrap_width,
+            )),
+        }
+        let content_end = entry.lines.len() + body.lines.len();
+        body.extend(
+            Self::inline_keyboard_text(&self.inline_keyboard)
+                .into_iter()
+                .map(|row| {
+                    Line::from(Span::styled(
+                        row,
+                        app_context.style_chat_message_inline_button(),
+                    ))
+                }),
+        );
+        if app_context.privacy_mode() {
+            body.lines.iter_mut().for_each(obscure_line);
+        }
+        entry.extend(body);
+        (entry, content_start..content_end)
+    }
+
+    /// Get the username of the user of the contact shared by the message.
+    ///
+    /// # Arguments
+    /// * `snapshot` - The snapshot of the open chat.
+    ///
+    /// # Returns
+    /// * `Option<String>` - The username, `None` if the message is not a
+    ///   contact, or the user has no username or is not known.
+    fn contact_username(&self, snapshot: &TgSnapshot) -> Option<String> {
+        snapshot
+            .contact_usernames
+            .get(&self.contact_user_id()?)
+            .cloned()
+    }
+
+    /// Get the description of a contact: its name, its phone number and its
+    /// username, if any.
+    ///
+    /// # Arguments
+    /// * `contact` - The contact.
+    /// * `username` - The username of the user of the contact, if known.
+    ///
+    /// # Returns
+    /// * `String` - The description of the contact.
+    fn contact_text(contact: &Contact, username: Option<&str>) -> String {
+        let mut text = format!(
+            "👤 Contact: {}",
+            format!("{} {}", contact.first_name, contact.last_name).trim()
+        );
+        if !contact.phone_number.is_empty() {
+            let plus = if contact.phone_number.starts_with('+') {
+                ""
+            } else {
+                "+"
+            };
+            text.push_str(&format!(", {}{}", plus, contact.phone_number));
+        }
+        if let Some(username) = username {
+            text.push_str(&format!(", @{}", username));
+        }
+        text
+    }
+
+    /// Get the contact shared by a message.
+    ///
+    /// # Arguments
+    /// * `content` - The content of the message.
+    ///
+    /// # Returns
+    /// * `Option<Contact>` - The contact, `None` if the message is not a
+    ///   contact.
+    fn message_content_contact(content: &MessageContent) -> Option<Contact> {
+        match content {
+            MessageContent::MessageContact(m) => Some(m.contact.clone()),
+            _ => None,
+        }
+    }
+
+    /// Get the rows of the buttons of an inline keyboard.
+    /// The other keyboards (e.g. the ones that replace the keyboard of the
+    /// user) are not shown under the message, so they are ignored.
+    ///
+    /// # Arguments
+    /// * `reply_markup` - The reply markup of the message, if any.
+    ///
+    /// # Returns
+    /// * `Vec<Vec<InlineKeyboardButton>>` - The rows of the buttons, empty if
+    ///   the reply markup is not an inline keyboard.
+    fn reply_markup_inline_keyboard(
+        reply_markup: Option<&ReplyMarkup>,
+    ) -> Vec<Vec<InlineKeyboardButton>> {
+        match reply_markup {
+            Some(ReplyMarkup::InlineKeyboard(keyboard)) => keyboard.rows.clone(),
+            _ => vec![],
+        }
+    }
+
+    /// Get the text of the rows of an inline keyboard, with each button
+    /// between brackets.
+    ///
+    /// # Arguments
+    /// * `rows` - The rows of the buttons.
+    ///
+    /// # Returns
+    /// * `Vec<String>` - The text of each row.
+    fn inline_keyboard_text(rows: &[Vec<InlineKeyboardButton>]) -> Vec<String> {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|button| format!("[ {} ]", button.text))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    /// Get the name of the origin of a forwarded message.
+    /// The name is resolved from the known chats and users, falling back to
+    /// the signature of the author or to the name stored in the message.
+    ///
+    /// # Arguments
+    /// * `origin` - The origin of the forwarded message.
+    /// * `snapshot` - The snapshot of the open chat, with the names.
+    ///
+    /// # Returns
+    /// * `String` - The name of the origin.
+    fn forward_origin_name(origin: &TdMessageOrigin, snapshot: &TgSnapshot) -> String {
+        let (name, signature) = match origin {
+            TdMessageOrigin::User(user_id) => (snapshot.names.get(user_id).cloned(), ""),
+            TdMessageOrigin::HiddenUser(name) => return name.clone(),
+            TdMessageOrigin::Chat(chat_id, signature)
+            | TdMessageOrigin::Channel(chat_id, signature) => {
+                (snapshot.names.get(chat_id).cloned(), signature.as_str())
+            }
+        };
+        match (name, signature.is_empty()) {
+            (Some(name), true) => name,
+            (Some(name), false) => format!("{} ({})", name, signature),
+            (None, false) => signature.to_string(),
+            (None, true) => "Unknown".to_string(),
+        }
+    }
+
+    /// Get the description of a service message, e.g. "Alice joined the
+    /// group".
+    ///
+    /// # Arguments
+    /// * `service` - The action of the service message.
+    /// * `sender` - The name of the sender of the message.
+    /// * `sender_id` - The id of the sender of the message, the members
+    ///   added or removed by themselves joined or left the group.
+    /// * `name_of` - A function that gets the name of a user from its id.
+    ///
+    /// # Returns
+    /// * `String` - The description of the service message.
+    fn service_text(
+        service: &TdServiceMessage,
+        sender: &str,
+        sender_id: i64,
+        name_of: impl Fn(i64) -> String,
+    ) -> String {
+        let action = match service {
+            TdServiceMessage::ChatCreated(title) => format!("created the group \"{}\"", title),
+            TdServiceMessage::AddMembers(user_ids) if *user_ids == [sender_id] => {
+                "joined the group".to_string()
+            }
+            TdServiceMessage::AddMembers(user_ids) => {
+                let names: Vec<String> = user_ids.iter().map(|id| name_of(*id)).collect();
+                format!("added {}", Self::join_names(&names))
+            }
+            TdServiceMessage::Joined => "joined the group".to_string(),
+            TdServiceMessage::DeleteMember(user_id) if *user_id == sender_id => {
+                "left the group".to_string()
+            }
+            TdServiceMessage::DeleteMember(user_id) => format!("removed {}", name_of(*user_id)),
+            TdServiceMessage::ChangeTitle(title) => {
+                format!("changed the group name to \"{}\"", title)
+            }
+            TdServiceMessage::ChangePhoto => "changed the group photo".to_string(),
+            TdServiceMessage::DeletePhoto => "removed the group photo".to_string(),
+            TdServiceMessage::PinMessage(_) => "pinned a message".to_string(),
+            TdServiceMessage::Upgraded => "upgraded the group to a supergroup".to_string(),
+            TdServiceMessage::ScreenshotTaken => "took a screenshot".to_string(),
+            TdServiceMessage::AutoDeleteTime(0) => "disabled the auto-delete timer".to_string(),
+            TdServiceMessage::AutoDeleteTime(seconds) => format!(
+                "set the auto-delete timer to {}",
+                TgContext::auto_delete_time_text(*seconds)
             ),
+            TdServiceMessage::ContactRegistered => "joined Telegram".to_string(),
+        };
+        format!("{} {}", sender, action)
+    }
+
+    /// Get the summary of a service message, without the names of the
+    /// users. It is the content of the message, e.g. in the preview of the
+    /// chat list.
+    ///
+    /// # Arguments
+    /// * `service` - The action of the service message.
+    ///
+    /// # Returns
+    /// * `String` - The summary of the service message.
+    fn service_summary(service: &TdServiceMessage) -> String {
+        match service {
+            TdServiceMessage::ChatCreated(title) => format!("👥 Group \"{}\" created", title),
+            TdServiceMessage::AddMembers(user_ids) if user_ids.len() == 1 => {
+                "👥 Member added".to_string()
+            }
+            TdServiceMessage::AddMembers(user_ids) => {
+                format!("👥 {} members added", user_ids.len())
+            }
+            TdServiceMessage::Joined => "👥 Joined the group".to_string(),
+            TdServiceMessage::DeleteMember(_) => "👥 Member left".to_string(),
+            TdServiceMessage::ChangeTitle(title) => format!("✏️ Name changed to \"{}\"", title),
+            TdServiceMessage::ChangePhoto => "🖼️ Photo changed".to_string(),
+            TdServiceMessage::DeletePhoto => "🖼️ Photo removed".to_string(),
+            TdServiceMessage::PinMessage(_) => "📌 Message pinned".to_string(),
+            TdServiceMessage::Upgraded => "👥 Upgraded to a supergroup".to_string(),
+            TdServiceMessage::ScreenshotTaken => "📸 Screenshot taken".to_string(),
+            TdServiceMessage::AutoDeleteTime(seconds) => format!(
+                "⏱️ Auto-delete timer {}",
+                TgContext::auto_delete_time_text(*seconds)
+            ),
+            TdServiceMessage::ContactRegistered => "👋 Joined Telegram".to_string(),
+        }
+    }
+
+    /// Join names in a list, e.g. "Alice, Bob and Carol".
+    ///
+    /// # Arguments
+    /// * `names` - The names.
+    ///
+    /// # Returns
+    /// * `String` - The joined names.
+    fn join_names(names: &[String]) -> String {
+        match names {
+            [] => String::new(),
+            [name] => name.clone(),
+            [rest @ .., last] => format!("{} and {}", rest.join(", "), last),
+        }
+    }
+
+    /// Get the name of the sender of the message.
+    ///
+    /// # Arguments
+    /// * `snapshot` - The snapshot of the open chat, with the names.
+    ///
+    /// # Returns
+    /// * `String` - The name of the sender, empty if it is unknown.
+    fn sender_name(&self, snapshot: &TgSnapshot) -> String {
+        snapshot
+            .names
+            .get(&self.sender_id())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Get the header of the message: the name of the sender, the edited
+    /// icon, the read state and the timestamp.
+    fn header_lines(
+        &self,
+        myself: bool,
+        app_context: &AppContext,
+        snapshot: &TgSnapshot,
+        is_unread: bool,
+        name_style: Style,
+    ) -> Vec<Line<'_>> {
+        vec![Line::from(vec![
+            Span::styled(self.sender_name(snapshot), name_style),
             Span::raw(" "),
             Span::raw(if self.is_edited { "✏️" } else { "" }),
             Span::raw(" "),
@@ -184,15 +825,12 @@ impl MessageEntry {
             }),
             Span::raw(" "),
             self.timestamp.get_span_styled(app_context),
-        ])]);
-        entry.extend(reply_text.unwrap_or_default());
-        entry.extend(self.get_lines_styled_with_style(content_style, wrap_width));
-        entry
+        ])]
     }
 
-    fn message_content_lines(content: &MessageContent) -> Vec<Line<'static>> {
-        match content {
-            MessageContent::MessageText(m) => Self::format_message_content(&m.text),
+    fn message_content_lines(content: &MessageContent) -> ContentLines {
+        let lines = match content {
+            MessageContent::MessageText(m) => return Self::format_message_content(&m.text),
             MessageContent::MessageAudio(_) => vec![Line::from("🎵 Audio")],
             MessageContent::MessagePhoto(_) => vec![Line::from("📷 Photo")],
             MessageContent::MessageSticker(_) => vec![Line::from("🎨 Sticker")],
@@ -200,10 +838,69 @@ impl MessageEntry {
             MessageContent::MessageAnimation(_) => vec![Line::from("🎞️ Animation")],
             MessageContent::MessageVoiceNote(_) => vec![Line::from("🎤 Voice Note")],
             MessageContent::MessageDocument(_) => vec![Line::from("📄 Document")],
-            _ => vec![Line::from("")],
+            MessageContent::MessageContact(m) => {
+                vec![Line::from(Self::contact_text(&m.contact, None))]
+            }
+            // The value is 0 until the dice stops rolling.
+            MessageContent::MessageDice(m) if m.value == 0 => {
+                vec![Line::from(format!("{} (rolling)", m.emoji))]
+            }
+            MessageContent::MessageDice(m) => {
+                vec![Line::from(format!("{} (value: {})", m.emoji, m.value))]
+            }
+            // The animated emoji are received only when
+            // `disable_animated_emoji` is false, otherwise they are text
+            // messages.
+            MessageContent::MessageAnimatedEmoji(m) => vec![Line::from(Span::styled(
+                m.emoji.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            ))],
+            content => match TdServiceMessage::from_content(content) {
+                Some(service) => vec![Line::from(Self::service_summary(&service))],
+                None => vec![Line::from("")],
+            },
+        };
+        ContentLines::from(lines)
+    }
+
+    /// Get the text of a message, or the caption of a media message.
+    ///
+    /// # Arguments
+    /// * `content` - The content of the message.
+    ///
+    /// # Returns
+    /// * `FormattedText` - The formatted text, empty if the message has no
+    ///   text.
+    fn message_content_formatted_text(content: &MessageContent) -> FormattedText {
+        match content {
+            MessageContent::MessageText(m) => m.text.clone(),
+            MessageContent::MessageAudio(m) => m.caption.clone(),
+            MessageContent::MessagePhoto(m) => m.caption.clone(),
+            MessageContent::MessageVideo(m) => m.caption.clone(),
+            MessageContent::MessageAnimation(m) => m.caption.clone(),
+            MessageContent::MessageVoiceNote(m) => m.caption.clone(),
+            MessageContent::MessageDocument(m) => m.caption.clone(),
+            _ => FormattedText::default(),
         }
     }
 
+    /// Get the part of a formatted text covered by one of its entities.
+    ///
+    /// # Arguments
+    /// * `message` - The formatted text.
+    /// * `entity` - The entity.
+    ///
+    /// # Returns
+    /// * `String` - The text of the entity.
+    fn formatted_text_entity_text(message: &FormattedText, entity: &TextEntity) -> String {
+        let start = Self::utf16_offset_to_char_index(&message.text, entity.offset as usize);
+        let end = Self::utf16_offset_to_char_index(
+            &message.text,
+            (entity.offset + entity.length) as usize,
+        );
+        message.text.chars().skip(start).take(end - start).collect()
+    }
+
     fn from_span_to_lines(span: Span) -> Vec<Line<'static>> {
         span.content
             .split('\n')
@@ -211,19 +908,6 @@ impl MessageEntry {
             .collect::<Vec<Line>>()
     }
 
-    fn from_spans_to_lines(spans: Vec<Span>) -> Vec<Line<'static>> {
-        let vec = spans
-            .iter()
-            .filter(|s| !s.content.is_empty())
-            .flat_map(|s| Self::from_span_to_lines(s.clone()))
-            .collect::<Vec<Line>>();
-        if vec.is_empty() {
-            vec![Line::from("")]
-        } else {
-            vec
-        }
-    }
-
     fn merge_two_style(a: Style, b: Style) -> Style {
         Style {
             fg: a.fg.or(b.fg),
@@ -234,17 +918,65 @@ impl MessageEntry {
         }
     }
 
+    /// Get the span as it should be displayed.
+    /// The code blocks are displayed with the code block style of the theme.
+    /// The spoilers are marked with the hidden modifier. If the spoilers are
+    /// not revealed, their text is replaced with blocks.
+    ///
+    /// # Arguments
+    /// * `span` - The span of the message content.
+    /// * `is_code` - Whether the span is part of a code block.
+    /// * `code_block_style` - The style of the code blocks.
+    ///
+    /// # Returns
+    /// * `Span<'static>` - The span to display.
+    fn span_for_display(
+        &self,
+        span: &Span<'static>,
+        is_code: bool,
+        code_block_style: Style,
+    ) -> Span<'static> {
+        let style = if is_code {
+            Self::merge_two_style(span.style, code_block_style)
+        } else {
+            span.style
+        };
+        if !style.add_modifier.contains(Modifier::HIDDEN) {
+            return Span::styled(span.content.clone(), style);
+        }
+        let style = style.remove_modifier(Modifier::HIDDEN);
+        if self.spoilers_revealed {
+            Span::styled(span.content.clone(), style)
+        } else {
+            Span::styled(
+                span.content
+                    .chars()
+                    .map(|c| if c.is_whitespace() { c } else { '█' })
+                    .collect::<String>(),
+                style,
+            )
+        }
+    }
+
     pub fn get_lines_styled_with_style(
         &self,
         content_style: Style,
+        code_block_style: Style,
         wrap_width: i32,
     ) -> Vec<Line<'static>> {
         if wrap_width == -1 {
             // No wrap
             self.message_content
+                .lines
                 .iter()
-                .map(|l| {
+                .enumerate()
+                .map(|(i, l)| {
                     l.iter()
+                        .enumerate()
+                        .map(|(j, s)| {
+                            let is_code = self.message_content.code_spans.contains(&(i, j));
+                            self.span_for_display(s, is_code, code_block_style)
+                        })
                         .map(|s| {
                             Span::styled(
                                 s.content.clone(),
@@ -256,159 +988,309 @@ impl MessageEntry {
                 .collect::<Vec<Line>>()
         } else {
             // Wrap the text
+            let wrap_width = wrap_width.max(1) as usize;
             let mut lines = Vec::new();
-            let mut current_line = Line::default();
-            let mut current_line_length = 0;
-            // for span in self.message_content.iter().flat_map(|l| l.iter()) {
-            for span in self.message_content.iter().flat_map(|l| l.iter()) {
-                for c in span.content.chars() {
-                    if c == ' ' && current_line_length >= wrap_width {
-                        lines.push(current_line);
-                        current_line = Line::default();
-                        current_line_length = 0;
-                    }
-                    current_line.spans.push(Span::styled(
-                        c.to_string(),
-                        Self::merge_two_style(span.style, content_style),
-                    ));
-                    current_line_length += 1;
-                }
-                lines.push(current_line);
-                current_line = Line::default();
-                current_line_length = 0;
+            for (i, line) in self.message_content.lines.iter().enumerate() {
+                let chars: Vec<(char, Style)> = line
+                    .iter()
+                    .enumerate()
+                    .map(|(j, s)| {
+                        let is_code = self.message_content.code_spans.contains(&(i, j));
+                        self.span_for_display(s, is_code, code_block_style)
+                    })
+                    .flat_map(|s| {
+                        let style = Self::merge_two_style(s.style, content_style);
+                        s.content
+                            .chars()
+                            .map(move |c| (c, style))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                lines.extend(
+                    Self::wrap_chars(&chars, wrap_width)
+                        .iter()
+                        .map(|chars| Self::line_of_chars(chars)),
+                );
             }
             lines
         }
     }
 
-    fn format_message_content(message: &FormattedText) -> Vec<Line<'static>> {
-        let text = &message.text;
-        let entities = &message.entities;
-
-        if entities.is_empty() {
-            return Self::from_span_to_lines(Span::raw(text));
-        }
-
-        let mut message_vec = Vec::new();
-        entities.iter().for_each(|e| {
-            let offset = e.offset as usize;
-            let length = e.length as usize;
-            message_vec.push(Span::raw(text.chars().take(offset).collect::<String>()));
-            match &e.r#type {
-                tdlib_rs::enums::TextEntityType::Italic => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::ITALIC),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::Bold => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::Underline => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::UNDERLINED),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::Strikethrough => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::CROSSED_OUT),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::Url => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::UNDERLINED),
-                    ));
+    /// Get the lines of a plain text, wrapped as the content of the messages.
+    ///
+    /// # Arguments
+    /// * `text` - The text.
+    /// * `style` - The style of the text.
+    /// * `wrap_width` - The width of the wrapped lines, -1 for no wrap.
+    ///
+    /// # Returns
+    /// * `Vec<Line<'static>>` - The lines of the text.
+    fn plain_lines_styled(text: &str, style: Style, wrap_width: i32) -> Vec<Line<'static>> {
+        text.lines()
+            .flat_map(|line| {
+                if wrap_width == -1 {
+                    return vec![Line::styled(line.to_string(), style)];
                 }
-                tdlib_rs::enums::TextEntityType::TextUrl(text_url) => {
-                    message_vec.push(Span::styled(
-                        text_url.url.clone(),
-                        Style::default().add_modifier(Modifier::UNDERLINED),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::EmailAddress => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::UNDERLINED),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::Mention => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::Hashtag => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::PhoneNumber => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::UNDERLINED),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::MentionName(mention_name) => {
-                    message_vec.push(Span::styled(
-                        // TODO: Fix from user_id to username
-                        mention_name.user_id.to_string(),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::Code => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::DIM),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::Pre => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::DIM),
-                    ));
-                }
-                tdlib_rs::enums::TextEntityType::PreCode(_pre_code) => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::DIM),
-                    ));
+                let chars: Vec<(char, Style)> = line.chars().map(|c| (c, style)).collect();
+                Self::wrap_chars(&chars, wrap_width.max(1) as usize)
+                    .iter()
+                    .map(|chars| Self::line_of_chars(chars))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Wrap a line of characters at the spaces, so that the wrapped lines are
+    /// not wider than the given width.
+    /// The width of the characters is the number of columns they take in the
+    /// terminal, e.g. 2 for the CJK characters and most emojis.
+    /// The words wider than the width are broken at the width, and the spaces
+    /// where the line is broken are removed.
+    ///
+    /// # Arguments
+    /// * `chars` - The characters of the line, with their style.
+    /// * `wrap_width` - The maximum width of the wrapped lines, at least 1.
+    ///
+    /// # Returns
+    /// * `Vec<&[(char, Style)]>` - The wrapped lines, a single empty line if
+    ///   the line is empty.
+    fn wrap_chars(chars: &[(char, Style)], wrap_width: usize) -> Vec<&[(char, Style)]> {
+        let mut lines = Vec::new();
+        let mut start = 0;
+        loop {
+            // The end of the longest line that fits, at least one character.
+            let mut end = start;
+            let mut width = 0;
+            while end < chars.len() {
+                width += chars[end].0.width().unwrap_or(0);
+                if width > wrap_width && end > start {
+                    break;
                 }
-                tdlib_rs::enums::TextEntityType::Cashtag => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::BOLD),
-                    ));
+                end += 1;
+            }
+            if end == chars.len() {
+                break;
+            }
+            // Break at the last space that fits, or in the middle of the word
+            // if there is none.
+            match chars[start..=end].iter().rposition(|(c, _)| *c == ' ') {
+                Some(space) if space > 0 => {
+                    lines.push(&chars[start..start + space]);
+                    start += space + 1;
                 }
-                tdlib_rs::enums::TextEntityType::BankCardNumber => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::UNDERLINED),
-                    ));
+                _ => {
+                    lines.push(&chars[start..end]);
+                    start = end;
                 }
-                tdlib_rs::enums::TextEntityType::BlockQuote => {
-                    message_vec.push(Span::styled(
-                        text.chars().skip(offset).take(length).collect::<String>(),
-                        Style::default().add_modifier(Modifier::DIM),
-                    ));
+            }
+        }
+        if start < chars.len() || lines.is_empty() {
+            lines.push(&chars[start..]);
+        }
+        lines
+    }
+
+    /// Build a line from characters, merging the consecutive characters with
+    /// the same style into a single span.
+    ///
+    /// # Arguments
+    /// * `chars` - The characters of the line, with their style.
+    ///
+    /// # Returns
+    /// * `Line<'static>` - The line.
+    fn line_of_chars(chars: &[(char, Style)]) -> Line<'static> {
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        let mut content = String::new();
+        let mut current_style = None;
+        for (c, style) in chars {
+            if current_style.is_some_and(|s| s != *style) {
+                spans.push(Span::styled(
+                    std::mem::take(&mut content),
+                    current_style.unwrap(),
+                ));
+            }
+            current_style = Some(*style);
+            content.push(*c);
+        }
+        if let Some(style) = current_style {
+            spans.push(Span::styled(content, style));
+        }
+        Line::from(spans)
+    }
+
+    /// Convert an offset expressed in UTF-16 code units, as the offsets of
+    /// the Telegram text entities, to the index of the corresponding char.
+    /// Characters outside the Basic Multilingual Plane (e.g. most emoji) take
+    /// two UTF-16 code units but only one char.
+    ///
+    /// # Arguments
+    /// * `text` - The text to which the offset refers.
+    /// * `utf16_offset` - The offset in UTF-16 code units.
+    ///
+    /// # Returns
+    /// * `usize` - The index of the char at the given offset.
+    fn utf16_offset_to_char_index(text: &str, utf16_offset: usize) -> usize {
+        let mut utf16_index = 0;
+        for (char_index, c) in text.chars().enumerate() {
+            if utf16_index >= utf16_offset {
+                return char_index;
+            }
+            utf16_index += c.len_utf16();
+        }
+        text.chars().count()
+    }
+
+    /// Get the style of a text entity.
+    ///
+    /// # Arguments
+    /// * `entity_type` - The type of the text entity.
+    ///
+    /// # Returns
+    /// * `Style` - The style used to render the text of the entity.
+    fn entity_style(entity_type: &TextEntityType) -> Style {
+        match entity_type {
+            TextEntityType::Italic => Style::default().add_modifier(Modifier::ITALIC),
+            TextEntityType::Bold
+            | TextEntityType::Mention
+            | TextEntityType::MentionName(_)
+            | TextEntityType::Hashtag
+            | TextEntityType::Cashtag => Style::default().add_modifier(Modifier::BOLD),
+            // The bot commands can be sent back to the bot, like a link.
+            TextEntityType::BotCommand => {
+                Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+            }
+            TextEntityType::Underline
+            | TextEntityType::Url
+            | TextEntityType::TextUrl(_)
+            | TextEntityType::EmailAddress
+            | TextEntityType::PhoneNumber
+            | TextEntityType::BankCardNumber => Style::default().add_modifier(Modifier::UNDERLINED),
+            TextEntityType::Strikethrough => Style::default().add_modifier(Modifier::CROSSED_OUT),
+            // The code block style depends on the theme, so it is applied
+            // when the message is displayed, see `span_for_display`.
+            TextEntityType::Code | TextEntityType::Pre | TextEntityType::PreCode(_) => {
+                Style::default()
+            }
+            TextEntityType::BlockQuote => Style::default().add_modifier(Modifier::DIM),
+            // The hidden modifier is used only to mark the spoilers, see
+            // `span_for_display`.
+            TextEntityType::Spoiler => Style::default().add_modifier(Modifier::HIDDEN),
+            TextEntityType::MediaTimestamp(_) | TextEntityType::CustomEmoji(_) => Style::default(),
+        }
+    }
+
+    /// Format the text of a message applying the styles of its entities.
+    /// The entities can be nested (e.g. an italic text inside a bold one), so
+    /// the style of each character is computed folding the styles of all the
+    /// entities that contain it. Then, the consecutive characters with the
+    /// same style are grouped in a single span.
+    /// The language of a `PreCode` entity, if any, is displayed in its own
+    /// line before the code.
+    ///
+    /// # Arguments
+    /// * `message` - The formatted text of the message.
+    ///
+    /// # Returns
+    /// * `ContentLines` - The styled lines of the message, with their code
+    ///   blocks and language labels.
+    fn format_message_content(message: &FormattedText) -> ContentLines {
+        let text = &message.text;
+        let entities = &message.entities;
+
+        if entities.is_empty() {
+            return ContentLines::from(Self::from_span_to_lines(Span::raw(text)));
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        let mut styles = vec![Style::default(); chars.len()];
+        let mut is_code = vec![false; chars.len()];
+        let mut language_labels = HashMap::new();
+        for e in entities {
+            // The offset and the length of the entities are expressed in
+            // UTF-16 code units, so they are converted to char indices.
+            let start = Self::utf16_offset_to_char_index(text, e.offset as usize);
+            let end = Self::utf16_offset_to_char_index(text, (e.offset + e.length) as usize);
+            let entity_style = Self::entity_style(&e.r#type);
+            if let TextEntityType::PreCode(pre_code) = &e.r#type {
+                if !pre_code.language.is_empty() {
+                    language_labels.insert(start, pre_code.language.clone());
                 }
-                tdlib_rs::enums::TextEntityType::Spoiler => {}
-                tdlib_rs::enums::TextEntityType::MediaTimestamp(_) => {}
-                tdlib_rs::enums::TextEntityType::CustomEmoji(_) => {}
-                tdlib_rs::enums::TextEntityType::BotCommand => {}
             }
-            message_vec.push(Span::raw(
-                text.chars().skip(offset + length).collect::<String>(),
-            ));
-        });
+            for style in styles[start..end].iter_mut() {
+                *style = Self::merge_two_style(*style, entity_style);
+            }
+            if matches!(
+                e.r#type,
+                TextEntityType::Code | TextEntityType::Pre | TextEntityType::PreCode(_)
+            ) {
+                is_code[start..end].fill(true);
+            }
+        }
 
-        Self::from_spans_to_lines(message_vec)
+        let mut content = ContentLines::default();
+        let mut current_line = Line::default();
+        let mut current_text = String::new();
+        let mut current_style = (Style::default(), false);
+        // Push the text collected so far as a span of the current line.
+        let push_span = |content: &mut ContentLines,
+                         line: &mut Line<'static>,
+                         text: &mut String,
+                         (style, is_code): (Style, bool)| {
+            if text.is_empty() {
+                return;
+            }
+            if is_code {
+                content
+                    .code_spans
+                    .insert((content.lines.len(), line.spans.len()));
+            }
+            line.spans.push(Span::styled(std::mem::take(text), style));
+        };
+        for (i, (c, style)) in chars
+            .into_iter()
+            .zip(styles.into_iter().zip(is_code))
+            .enumerate()
+        {
+            if let Some(language) = language_labels.remove(&i) {
+                push_span(
+                    &mut content,
+                    &mut current_line,
+                    &mut current_text,
+                    current_style,
+                );
+                if !current_line.spans.is_empty() {
+                    content.lines.push(std::mem::take(&mut current_line));
+                }
+                content.language_labels.insert(content.lines.len());
+                content.code_spans.insert((content.lines.len(), 0));
+                content.lines.push(Line::from(Span::styled(
+                    format!(" {} ", language),
+                    style.0.add_modifier(Modifier::ITALIC),
+                )));
+            }
+            if c == '\n' || style != current_style {
+                push_span(
+                    &mut content,
+                    &mut current_line,
+                    &mut current_text,
+                    current_style,
+                );
+                current_style = style;
+            }
+            if c == '\n' {
+                content.lines.push(std::mem::take(&mut current_line));
+            } else {
+                current_text.push(c);
+            }
+        }
+        push_span(
+            &mut content,
+            &mut current_line,
+            &mut current_text,
+            current_style,
+        );
+        content.lines.push(current_line);
+        content
     }
 }
 impl From<&tdlib_rs::types::Message> for MessageEntry {
@@ -420,6 +1302,10 @@ impl From<&tdlib_rs::types::Message> for MessageEntry {
                 MessageSender::Chat(chat) => TdMessageSender::Chat(chat.chat_id),
             },
             message_content: Self::message_content_lines(&message.content),
+            forward_origin: message
+                .forward_info
+                .as_ref()
+                .map(|forward_info| TdMessageOrigin::from(&forward_info.origin)),
             reply_to: match &message.reply_to {
                 Some(reply) => match reply {
                     MessageReplyTo::Message(message) => {
@@ -433,6 +1319,510 @@ impl From<&tdlib_rs::types::Message> for MessageEntry {
                 timestamp: message.date,
             },
             is_edited: message.edit_date != 0,
+            spoilers_revealed: false,
+            formatted_text: Self::message_content_formatted_text(&message.content),
+            content_type: MessageContentType::from(&message.content),
+            service: TdServiceMessage::from_content(&message.content),
+            contact: Self::message_content_contact(&message.content),
+            inline_keyboard: Self::reply_markup_inline_keyboard(message.reply_markup.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+impl MessageEntry {
+    /// Create a text message entry sent by a user. It is used only in tests,
+    /// because building a `tdlib_rs::types::Message` requires too many fields.
+    pub fn from_text(id: i64, user_id: i64, text: &FormattedText) -> Self {
+        Self {
+            id,
+            sender_id: TdMessageSender::User(user_id),
+            message_content: Self::format_message_content(text),
+            reply_to: None,
+            forward_origin: None,
+            timestamp: DateTimeEntry::default(),
+            is_edited: false,
+            spoilers_revealed: false,
+            formatted_text: text.clone(),
+            content_type: MessageContentType::Text,
+            service: None,
+            contact: None,
+            inline_keyboard: vec![],
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::tg::{
+        message_entry::{DateTimeEntry, MessageEntry},
+        td_enums::TdServiceMessage,
+    };
+    use ratatui::style::{Color, Modifier, Style};
+    use tdlib_rs::{
+        enums::{InlineKeyboardButtonType, MessageContent, TextEntityType},
+        types::{
+            Contact, FormattedText, InlineKeyboardButton, InlineKeyboardButtonTypeCallback,
+            InlineKeyboardButtonTypeUrl, MessageDice, TextEntity, TextEntityTypePreCode,
+            TextEntityTypeTextUrl,
+        },
+    };
+
+    fn spans_of(lines: &[ratatui::text::Line]) -> Vec<(String, Modifier)> {
+        lines
+            .iter()
+            .flat_map(|l| l.spans.iter())
+            .map(|s| (s.content.to_string(), s.style.add_modifier))
+            .collect()
+    }
+
+    #[test]
+    fn test_utf16_offset_to_char_index() {
+        let text = "😀 bold";
+        assert_eq!(MessageEntry::utf16_offset_to_char_index(text, 0), 0);
+        assert_eq!(MessageEntry::utf16_offset_to_char_index(text, 2), 1);
+        assert_eq!(MessageEntry::utf16_offset_to_char_index(text, 3), 2);
+        assert_eq!(MessageEntry::utf16_offset_to_char_index(text, 7), 6);
+        assert_eq!(MessageEntry::utf16_offset_to_char_index(text, 100), 6);
+    }
+
+    #[test]
+    fn test_format_message_content_bold_after_emoji() {
+        // "😀" is 2 UTF-16 code units, so "bold" starts at offset 3
+        let formatted_text = FormattedText {
+            text: "😀 bold".to_string(),
+            entities: vec![TextEntity {
+                offset: 3,
+                length: 4,
+                r#type: TextEntityType::Bold,
+            }],
+        };
+        let lines = MessageEntry::format_message_content(&formatted_text).lines;
+        let bold_spans: Vec<&str> = lines
+            .iter()
+            .flat_map(|l| l.spans.iter())
+            .filter(|s| s.style.add_modifier.contains(Modifier::BOLD))
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(bold_spans, vec!["bold"]);
+        let text: String = lines
+            .iter()
+            .flat_map(|l| l.spans.iter())
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(text, "😀 bold");
+    }
+
+    #[test]
+    fn test_format_message_content_nested_entities() {
+        let formatted_text = FormattedText {
+            text: "bold italic bold".to_string(),
+            entities: vec![
+                TextEntity {
+                    offset: 0,
+                    length: 16,
+                    r#type: TextEntityType::Bold,
+                },
+                TextEntity {
+                    offset: 5,
+                    length: 6,
+                    r#type: TextEntityType::Italic,
+                },
+            ],
+        };
+        let lines = MessageEntry::format_message_content(&formatted_text).lines;
+        assert_eq!(lines.len(), 1);
+        assert_eq!(
+            spans_of(&lines),
+            vec![
+                ("bold ".to_string(), Modifier::BOLD),
+                ("italic".to_string(), Modifier::BOLD | Modifier::ITALIC),
+                (" bold".to_string(), Modifier::BOLD),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_message_content_multiline_entity() {
+        let formatted_text = FormattedText {
+            text: "first\nsecond line".to_string(),
+            entities: vec![TextEntity {
+                offset: 3,
+                length: 6,
+                r#type: TextEntityType::Bold,
+            }],
+        };
+        let lines = MessageEntry::format_message_content(&formatted_text).lines;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            spans_of(&lines[..1]),
+            vec![
+                ("fir".to_string(), Modifier::empty()),
+                ("st".to_string(), Modifier::BOLD),
+            ]
+        );
+        assert_eq!(
+            spans_of(&lines[1..]),
+            vec![
+                ("sec".to_string(), Modifier::BOLD),
+                ("ond line".to_string(), Modifier::empty()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spoiler_hidden_until_revealed() {
+        let formatted_text = FormattedText {
+            text: "the end is near".to_string(),
+            entities: vec![TextEntity {
+                offset: 4,
+                length: 3,
+                r#type: TextEntityType::Spoiler,
+            }],
+        };
+        let mut message = MessageEntry::from_text(1, 1, &formatted_text);
+        let text_of = |lines: Vec<ratatui::text::Line>| -> String {
+            lines
+                .iter()
+                .flat_map(|l| l.spans.iter())
+                .map(|s| s.content.to_string())
+                .collect()
+        };
+        let lines = message.get_lines_styled_with_style(Style::default(), Style::default(), -1);
+        assert_eq!(text_of(lines), "the ███ is near");
+
+        assert_eq!(
+            message.message_content_to_string_masked(),
+            "the ███ is near"
+        );
+
+        message.set_spoilers_revealed(true);
+        assert_eq!(
+            message.message_content_to_string_masked(),
+            "the end is near"
+        );
+        let lines = message.get_lines_styled_with_style(Style::default(), Style::default(), -1);
+        assert!(lines
+            .iter()
+            .flat_map(|l| l.spans.iter())
+            .all(|s| !s.style.add_modifier.contains(Modifier::HIDDEN)));
+        assert_eq!(text_of(lines), "the end is near");
+    }
+
+    #[test]
+    fn test_code_block_style() {
+        let formatted_text = FormattedText {
+            text: "run\nfn main() {}".to_string(),
+            entities: vec![TextEntity {
+                offset: 4,
+                length: 12,
+                r#type: TextEntityType::PreCode(TextEntityTypePreCode {
+                    language: "rust".to_string(),
+                }),
+            }],
+        };
+        let message = MessageEntry::from_text(1, 1, &formatted_text);
+        let code_block_style = Style::default().bg(Color::DarkGray);
+        let lines = message.get_lines_styled_with_style(
+            Style::default().fg(Color::White),
+            code_block_style,
+            -1,
+        );
+        let contents: Vec<Vec<String>> = lines
+            .iter()
+            .map(|l| l.spans.iter().map(|s| s.content.to_string()).collect())
+            .collect();
+        assert_eq!(
+            contents,
+            vec![
+                vec!["run".to_string()],
+                vec![" rust ".to_string()],
+                vec!["fn main() {}".to_string()],
+            ]
+        );
+        let code = &lines[2].spans[0];
+        assert_eq!(code.style.bg, Some(Color::DarkGray));
+        assert_eq!(code.style.fg, Some(Color::White));
+        assert_eq!(lines[0].spans[0].style.bg, None);
+        assert_eq!(lines[1].spans[0].style.bg, Some(Color::DarkGray));
+        assert_eq!(message.message_content_to_string(), "run\nfn main() {}");
+    }
+
+    #[test]
+    fn test_urls() {
+        let formatted_text = FormattedText {
+            text: "😀 https://example.com and docs".to_string(),
+            entities: vec![
+                TextEntity {
+                    offset: 3,
+                    length: 19,
+                    r#type: TextEntityType::Url,
+                },
+                TextEntity {
+                    offset: 27,
+                    length: 4,
+                    r#type: TextEntityType::TextUrl(TextEntityTypeTextUrl {
+                        url: "https://docs.rs".to_string(),
+                    }),
+                },
+            ],
+        };
+        let message = MessageEntry::from_text(1, 1, &formatted_text);
+        assert_eq!(
+            message.urls(),
+            vec![
+                "https://example.com".to_string(),
+                "https://docs.rs".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bot_commands() {
+        let formatted_text = FormattedText {
+            text: "/start to begin, /help or /start@bot".to_string(),
+            entities: vec![
+                TextEntity {
+                    offset: 0,
+                    length: 6,
+                    r#type: TextEntityType::BotCommand,
+                },
+                TextEntity {
+                    offset: 17,
+                    length: 5,
+                    r#type: TextEntityType::BotCommand,
+                },
+                TextEntity {
+                    offset: 26,
+                    length: 10,
+                    r#type: TextEntityType::BotCommand,
+                },
+                TextEntity {
+                    offset: 17,
+                    length: 5,
+                    r#type: TextEntityType::BotCommand,
+                },
+            ],
+        };
+        let message = MessageEntry::from_text(1, 1, &formatted_text);
+        assert_eq!(
+            message.bot_commands(),
+            vec![
+                "/start".to_string(),
+                "/help".to_string(),
+                "/start@bot".to_string()
+            ]
+        );
+        // The text of the commands is kept.
+        assert_eq!(
+            message.message_content_to_string(),
+            "/start to begin, /help or /start@bot"
+        );
+    }
+
+    #[test]
+    fn test_entities() {
+        let formatted_text = FormattedText {
+            text: "😀 see @tgt and `code`".to_string(),
+            entities: vec![
+                TextEntity {
+                    offset: 7,
+                    length: 4,
+                    r#type: TextEntityType::Mention,
+                },
+                TextEntity {
+                    offset: 16,
+                    length: 6,
+                    r#type: TextEntityType::Code,
+                },
+            ],
+        };
+        let message = MessageEntry::from_text(1, 1, &formatted_text);
+        assert_eq!(message.formatted_text(), &formatted_text);
+        assert_eq!(message.entities().len(), 2);
+        assert_eq!(message.entities()[0].r#type, TextEntityType::Mention);
+        assert_eq!(message.entity_text(&message.entities()[0]), "@tgt");
+        assert_eq!(message.entity_text(&message.entities()[1]), "`code`");
+    }
+
+    #[test]
+    fn test_is_grouped_with() {
+        let text = FormattedText {
+            text: "text".to_string(),
+            entities: vec![],
+        };
+        let mut previous = MessageEntry::from_text(1, 10, &text);
+        previous.timestamp = DateTimeEntry { timestamp: 1000 };
+        let mut message = MessageEntry::from_text(2, 10, &text);
+        message.timestamp = DateTimeEntry { timestamp: 1200 };
+        assert!(message.is_grouped_with(&previous, 300));
+        assert!(!message.is_grouped_with(&previous, 100));
+        let mut other = MessageEntry::from_text(3, 20, &text);
+        other.timestamp = DateTimeEntry { timestamp: 1200 };
+        assert!(!other.is_grouped_with(&previous, 300));
+    }
+
+    #[test]
+    fn test_wrap_long_word() {
+        let formatted_text = FormattedText {
+            text: "a".repeat(200),
+            entities: vec![],
+        };
+        let message = MessageEntry::from_text(1, 1, &formatted_text);
+        let lines = message.get_lines_styled_with_style(Style::default(), Style::default(), 30);
+        let widths: Vec<usize> = lines.iter().map(|l| l.width()).collect();
+        assert_eq!(widths, vec![30, 30, 30, 30, 30, 30, 20]);
+    }
+
+    #[test]
+    fn test_wrap_wide_chars() {
+        let formatted_text = FormattedText {
+            text: "日本語のテキスト 🦀🦀🦀".to_string(),
+            entities: vec![],
+        };
+        let message = MessageEntry::from_text(1, 1, &formatted_text);
+        let lines = message.get_lines_styled_with_style(Style::default(), Style::default(), 7);
+        let contents: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(contents, vec!["日本語", "のテキ", "スト", "🦀🦀🦀"]);
+        assert!(lines.iter().all(|l| l.width() <= 7));
+    }
+
+    #[test]
+    fn test_wrap_multi_line() {
+        let formatted_text = FormattedText {
+            text: "the quick brown fox\n\njumps over".to_string(),
+            entities: vec![TextEntity {
+                offset: 4,
+                length: 5,
+                r#type: TextEntityType::Bold,
+            }],
+        };
+        let message = MessageEntry::from_text(1, 1, &formatted_text);
+        let lines = message.get_lines_styled_with_style(Style::default(), Style::default(), 10);
+        let contents: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(contents, vec!["the quick", "brown fox", "", "jumps over"]);
+        // The bold word is a single span, not split across lines.
+        assert_eq!(lines[0].spans.len(), 2);
+        assert!(lines[0].spans[1]
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_plain_lines_styled() {
+        let style = Style::default().add_modifier(Modifier::ITALIC);
+        let lines = MessageEntry::plain_lines_styled("quoted text\nsecond", style, 6);
+        let contents: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        assert_eq!(contents, vec!["quoted", "text", "second"]);
+        assert!(lines.iter().all(|l| l.spans[0].style == style));
+    }
+
+    #[test]
+    fn test_service_text() {
+        let name_of = |user_id: i64| match user_id {
+            1 => "Alice".to_string(),
+            2 => "Bob".to_string(),
+            _ => "Carol".to_string(),
+        };
+        let text = |service, sender, sender_id| {
+            MessageEntry::service_text(&service, sender, sender_id, name_of)
+        };
+        assert_eq!(
+            text(TdServiceMessage::AddMembers(vec![1]), "Alice", 1),
+            "Alice joined the group"
+        );
+        assert_eq!(
+            text(TdServiceMessage::AddMembers(vec![1, 2, 3]), "Dave", 4),
+            "Dave added Alice, Bob and Carol"
+        );
+        // Another user with the same name.
+        assert_eq!(
+            text(TdServiceMessage::AddMembers(vec![1]), "Alice", 5),
+            "Alice added Alice"
+        );
+        assert_eq!(
+            text(TdServiceMessage::DeleteMember(2), "Bob", 2),
+            "Bob left the group"
+        );
+        assert_eq!(
+            text(TdServiceMessage::DeleteMember(2), "Alice", 1),
+            "Alice removed Bob"
+        );
+        assert_eq!(
+            text(TdServiceMessage::DeleteMember(2), "Bob", 6),
+            "Bob removed Bob"
+        );
+        assert_eq!(
+            text(TdServiceMessage::AutoDeleteTime(86400), "Bob", 2),
+            "Bob set the auto-delete timer to 1 day"
+        );
+    }
+
+    #[test]
+    fn test_contact_text() {
+        let contact = Contact {
+            phone_number: "391234567".to_string(),
+            first_name: "Alice".to_string(),
+            last_name: String::new(),
+            vcard: String::new(),
+            user_id: 1,
+        };
+        assert_eq!(
+            MessageEntry::contact_text(&contact, None),
+            "👤 Contact: Alice, +391234567"
+        );
+        assert_eq!(
+            MessageEntry::contact_text(&contact, Some("alice")),
+            "👤 Contact: Alice, +391234567, @alice"
+        );
+    }
+
+    #[test]
+    fn test_inline_keyboard_text() {
+        let button = |text: &str, r#type| InlineKeyboardButton {
+            text: text.to_string(),
+            r#type,
+        };
+        let callback = || {
+            InlineKeyboardButtonType::Callback(InlineKeyboardButtonTypeCallback {
+                data: String::new(),
+            })
+        };
+        let rows = vec![
+            vec![button("Yes", callback()), button("No", callback())],
+            vec![button(
+                "Docs",
+                InlineKeyboardButtonType::Url(InlineKeyboardButtonTypeUrl {
+                    url: "https://docs.rs".to_string(),
+                }),
+            )],
+        ];
+        assert_eq!(
+            MessageEntry::inline_keyboard_text(&rows),
+            vec!["[ Yes ] [ No ]".to_string(), "[ Docs ]".to_string()]
+        );
+        assert!(MessageEntry::inline_keyboard_text(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_dice_lines() {
+        let dice = |value| {
+            MessageContent::MessageDice(MessageDice {
+                initial_state: None,
+                final_state: None,
+                emoji: "🎲".to_string(),
+                value,
+                success_animation_frame_number: 0,
+            })
+        };
+        assert_eq!(
+            MessageEntry::message_content_lines(&dice(4)).lines[0].to_string(),
+            "🎲 (value: 4)"
+        );
+        assert_eq!(
+            MessageEntry::message_content_lines(&dice(0)).lines[0].to_string(),
+            "🎲 (rolling)"
+        );
+    }
+}
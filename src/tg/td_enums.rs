@@ -1,6 +1,9 @@
 use std::hash::Hash;
 
-use tdlib_rs::{enums::ChatList, types::ChatListFolder};
+use tdlib_rs::{
+    enums::{ChatList, MessageContent, MessageOrigin},
+    types::ChatListFolder,
+};
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum TdMessageSender {
@@ -15,6 +18,105 @@ pub enum TdChatList {
     Folder(i32),
 }
 
+/// The origin of a forwarded message.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TdMessageOrigin {
+    /// The message was sent by the user with the given id.
+    User(i64),
+    /// The message was sent by a user hidden by their privacy settings, only
+    /// their name is known.
+    HiddenUser(String),
+    /// The message was sent on behalf of the chat with the given id, with the
+    /// signature of the author.
+    Chat(i64, String),
+    /// The message was a post of the channel with the given id, with the
+    /// signature of the author.
+    Channel(i64, String),
+}
+
+impl From<&MessageOrigin> for TdMessageOrigin {
+    fn from(origin: &MessageOrigin) -> Self {
+        match origin {
+            MessageOrigin::User(user) => TdMessageOrigin::User(user.sender_user_id),
+            MessageOrigin::HiddenUser(user) => {
+                TdMessageOrigin::HiddenUser(user.sender_name.clone())
+            }
+            MessageOrigin::Chat(chat) => {
+                TdMessageOrigin::Chat(chat.sender_chat_id, chat.author_signature.clone())
+            }
+            MessageOrigin::Channel(channel) => {
+                TdMessageOrigin::Channel(channel.chat_id, channel.author_signature.clone())
+            }
+        }
+    }
+}
+
+/// The action described by a service message, e.g. a user that joined a
+/// group. The user performing the action is the sender of the message.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TdServiceMessage {
+    /// A group was created with the given title.
+    ChatCreated(String),
+    /// The users with the given ids were added to the group.
+    AddMembers(Vec<i64>),
+    /// The sender joined the group by an invite link or by a request.
+    Joined,
+    /// The user with the given id left the group or was removed from it.
+    DeleteMember(i64),
+    /// The title of the chat was changed to the given one.
+    ChangeTitle(String),
+    /// The photo of the chat was changed.
+    ChangePhoto,
+    /// The photo of the chat was removed.
+    DeletePhoto,
+    /// The message with the given id was pinned.
+    PinMessage(i64),
+    /// The group was upgraded to a supergroup.
+    Upgraded,
+    /// The sender took a screenshot of the chat.
+    ScreenshotTaken,
+    /// The auto-delete time of the chat was set to the given seconds, 0 if
+    /// it was disabled.
+    AutoDeleteTime(i32),
+    /// The sender joined Telegram.
+    ContactRegistered,
+}
+
+impl TdServiceMessage {
+    /// Get the service action of the content of a message.
+    ///
+    /// # Arguments
+    /// * `content` - The content of the message.
+    ///
+    /// # Returns
+    /// * `Option<TdServiceMessage>` - The service action, `None` if the
+    ///   message is not a service message or its action is not supported.
+    pub fn from_content(content: &MessageContent) -> Option<Self> {
+        Some(match content {
+            MessageContent::MessageBasicGroupChatCreate(m) => Self::ChatCreated(m.title.clone()),
+            MessageContent::MessageSupergroupChatCreate(m) => Self::ChatCreated(m.title.clone()),
+            MessageContent::MessageChatAddMembers(m) => Self::AddMembers(m.member_user_ids.clone()),
+            MessageContent::MessageChatJoinByLink | MessageContent::MessageChatJoinByRequest => {
+                Self::Joined
+            }
+            MessageContent::MessageChatDeleteMember(m) => Self::DeleteMember(m.user_id),
+            MessageContent::MessageChatChangeTitle(m) => Self::ChangeTitle(m.title.clone()),
+            MessageContent::MessageChatChangePhoto(_) => Self::ChangePhoto,
+            MessageContent::MessageChatDeletePhoto => Self::DeletePhoto,
+            MessageContent::MessagePinMessage(m) => Self::PinMessage(m.message_id),
+            MessageContent::MessageChatUpgradeTo(_) | MessageContent::MessageChatUpgradeFrom(_) => {
+                Self::Upgraded
+            }
+            MessageContent::MessageScreenshotTaken => Self::ScreenshotTaken,
+            MessageContent::MessageChatSetMessageAutoDeleteTime(m) => {
+                Self::AutoDeleteTime(m.message_auto_delete_time)
+            }
+            MessageContent::MessageContactRegistered => Self::ContactRegistered,
+            _ => return None,
+        })
+    }
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum TdMessageReplyTo {
     Message(TdMessageReplyToMessage),
@@ -27,6 +129,8 @@ pub struct TdMessageReplyToMessage {
     pub chat_id: i64,
     /// The identifier of the replied message
     pub message_id: i64,
+    /// The text of the quoted part of the replied message, if any; ignored for outgoing replies
+    pub quote: Option<String>,
 }
 
 impl From<&TdMessageReplyToMessage> for tdlib_rs::types::InputMessageReplyToMessage {
@@ -44,6 +148,10 @@ impl From<&tdlib_rs::types::MessageReplyToMessage> for TdMessageReplyToMessage {
         TdMessageReplyToMessage {
             chat_id: reply_to_message.chat_id,
             message_id: reply_to_message.message_id,
+            quote: reply_to_message
+                .quote
+                .as_ref()
+                .map(|quote| quote.text.text.clone()),
         }
     }
 }
@@ -74,6 +182,40 @@ impl From<&tdlib_rs::types::MessageReplyToStory> for TdMessageReplyToStory {
     }
 }
 
+/// The state of a file downloaded by the user.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TdFile {
+    /// Unique file identifier
+    pub id: i32,
+    /// File size, in bytes; the expected size if the exact size is unknown, 0 if both are unknown
+    pub size: i64,
+    /// Size of the downloaded part of the file, in bytes
+    pub downloaded_size: i64,
+    /// True, if the file is being downloaded
+    pub is_downloading_active: bool,
+    /// True, if the file has been downloaded
+    pub is_downloading_completed: bool,
+    /// Local path to the file; empty until the file is downloaded
+    pub path: String,
+}
+
+impl From<&tdlib_rs::types::File> for TdFile {
+    fn from(file: &tdlib_rs::types::File) -> Self {
+        TdFile {
+            id: file.id,
+            size: if file.size != 0 {
+                file.size
+            } else {
+                file.expected_size
+            },
+            downloaded_size: file.local.downloaded_size,
+            is_downloading_active: file.local.is_downloading_active,
+            is_downloading_completed: file.local.is_downloading_completed,
+            path: file.local.path.clone(),
+        }
+    }
+}
+
 impl From<ChatList> for TdChatList {
     fn from(chat_list: ChatList) -> Self {
         match chat_list {
@@ -1,20 +1,205 @@
 use crate::action::Action;
 use crate::event::Event;
 use crate::{app_context::AppContext, tg::ordered_chat::OrderedChat};
-use std::collections::{BTreeSet, VecDeque};
+use arboard::Clipboard;
+use chrono::{DateTime, Local};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, MutexGuard};
+use std::time::Duration;
 use tdlib_rs::enums::{
-    self, AuthorizationState, ChatList, InputMessageContent, InputMessageReplyTo, LogStream,
-    Messages, OptionValue, Update, User,
+    self, AuthorizationState, BlockList, CallbackQueryPayload, ChatList, ChatType, InputFile,
+    InputMessageContent, InputMessageReplyTo, LogStream, MessageContent, MessageSchedulingState,
+    MessageSender, Messages, OptionValue, ReportReason, SearchMessagesFilter, StickerType,
+    SupergroupMembersFilter, TextEntityType, Update, User,
 };
 use tdlib_rs::functions;
-use tdlib_rs::types::{Chat, ChatPosition, InputMessageText, LogStreamFile, OptionValueBoolean};
+use tdlib_rs::types::{
+    CallbackQueryPayloadData, Chat, ChatListFolder, ChatPosition, Error, File, FormattedText,
+    InputFileLocal, InputFileRemote, InputMessagePhoto, InputMessageSticker, InputMessageText,
+    LogStreamFile, MessageSchedulingStateSendAtDate, MessageSendOptions, MessageSenderUser,
+    OptionValueBoolean, TextEntity,
+};
+use tokio::process::Command;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
 use tokio::task::JoinHandle;
 
-use super::message_entry::MessageEntry;
-use super::td_enums::TdMessageReplyToMessage;
+use super::message_entry::{MessageContentType, MessageEntry};
+use super::td_enums::{TdFile, TdMessageReplyToMessage};
+use super::tg_context::{
+    ChatLink, ScheduledMessageEntry, StickerSetEntry, TgContext, MAX_MESSAGE_LENGTH,
+};
+
+/// The number of times a request that failed because of the network is sent
+/// again before giving up.
+const RETRY_ATTEMPTS: u32 = 3;
+/// The delay before the first retry of a request, doubled at each attempt.
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+/// The maximum delay between two retries of a request.
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(8);
+/// The maximum time to wait for TDLib to close, see `shutdown`.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+/// The files of a TDLib database, see `clear_database_dir`.
+const TDLIB_DATABASE_FILES: [&str; 8] = [
+    "td.binlog",
+    "td_test.binlog",
+    "db.sqlite",
+    "db.sqlite-shm",
+    "db.sqlite-wal",
+    "db_test.sqlite",
+    "db_test.sqlite-shm",
+    "db_test.sqlite-wal",
+];
+/// The subdirectories where TDLib stores the downloaded files, when no files
+/// directory is set, see `clear_database_dir`.
+const TDLIB_DATABASE_DIRS: [&str; 18] = [
+    "animations",
+    "documents",
+    "music",
+    "paid",
+    "passport",
+    "photos",
+    "profile_photos",
+    "secret",
+    "secret_stickers",
+    "secret_thumbnails",
+    "stickers",
+    "stories",
+    "temp",
+    "thumbnails",
+    "video_notes",
+    "videos",
+    "voice",
+    "wallpapers",
+];
+/// The number of batches of updates after which an update waiting for an
+/// unknown chat or user is dropped, see `UpdateQueue`.
+const MAX_UPDATE_RETRIES: usize = 50;
+/// The number of members of a supergroup loaded with each request, the limit
+/// of Telegram.
+const SUPERGROUP_MEMBERS_PAGE_SIZE: i32 = 200;
+
+/// A message of the channel of the updates received from TDLib.
+/// Almost all the messages are updates, so they are not boxed.
+#[allow(clippy::large_enum_variant)]
+enum UpdateMessage {
+    /// An update received from TDLib.
+    Update(Update),
+    /// A request to be notified once the updates received before it are
+    /// applied, see `wait_for_updates`.
+    Flush(oneshot::Sender<()>),
+}
+
+/// A chat or a user that an update refers to, see `UpdateQueue`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum UpdateDependency {
+    /// A chat, by id.
+    Chat(i64),
+    /// A user, by id.
+    User(i64),
+}
+
+/// The queue of the updates received from TDLib.
+/// An update that refers to a chat or a user that is not known yet waits
+/// for it, with the other updates of the same chat or user, and they are
+/// applied in the order they were received as soon as it is inserted. They
+/// are dropped after `MAX_UPDATE_RETRIES` batches, so that they can not wait
+/// forever.
+#[derive(Default)]
+struct UpdateQueue {
+    /// The updates of the current batch.
+    updates: VecDeque<Update>,
+    /// The updates waiting for a chat or a user, in the order they were
+    /// received, with the batch in which they started waiting.
+    pending: HashMap<UpdateDependency, Vec<(Update, usize)>>,
+    /// The number of the current batch.
+    batch: usize,
+    /// The requests to be notified once the current batch is applied.
+    flushes: Vec<oneshot::Sender<()>>,
+}
+
+impl UpdateQueue {
+    /// Add an update received from TDLib to the current batch.
+    ///
+    /// # Arguments
+    /// * `update` - The update to add.
+    fn push(&mut self, update: Update) {
+        self.updates.push_back(update);
+    }
+
+    /// Add a message of the channel of the updates to the current batch.
+    ///
+    /// # Arguments
+    /// * `message` - The message to add.
+    fn push_message(&mut self, message: UpdateMessage) {
+        match message {
+            UpdateMessage::Update(update) => self.push(update),
+            UpdateMessage::Flush(done_tx) => self.flushes.push(done_tx),
+        }
+    }
+
+    /// Notify the requests waiting for the current batch to be applied.
+    fn notify_flushes(&mut self) {
+        for done_tx in self.flushes.drain(..) {
+            let _ = done_tx.send(());
+        }
+    }
+
+    /// Start a batch: the updates that have been waiting for too many
+    /// batches are dropped.
+    fn start_batch(&mut self) {
+        self.batch += 1;
+        let batch = self.batch;
+        self.pending.retain(|dependency, updates| {
+            let expired = updates
+                .first()
+                .is_some_and(|(_, since)| batch - since > MAX_UPDATE_RETRIES);
+            if expired {
+                tracing::warn!(
+                    "Dropping {} updates waiting for {dependency:?}",
+                    updates.len()
+                );
+            }
+            !expired
+        });
+    }
+
+    /// Take the next update of the batch.
+    ///
+    /// # Returns
+    /// The update, `None` when the batch is applied.
+    fn pop(&mut self) -> Option<Update> {
+        self.updates.pop_front()
+    }
+
+    /// Make an update that can not be applied yet wait for the chat or the
+    /// user it refers to.
+    ///
+    /// # Arguments
+    /// * `dependency` - The chat or the user the update refers to.
+    /// * `update` - The update to defer.
+    fn defer(&mut self, dependency: UpdateDependency, update: Update) {
+        self.pending
+            .entry(dependency)
+            .or_default()
+            .push((update, self.batch));
+    }
+
+    /// Apply the updates waiting for a chat or a user, that has just been
+    /// inserted, before the following updates of the batch.
+    ///
+    /// # Arguments
+    /// * `dependency` - The inserted chat or user.
+    fn resolve(&mut self, dependency: UpdateDependency) {
+        if let Some(updates) = self.pending.remove(&dependency) {
+            for (update, _) in updates.into_iter().rev() {
+                self.updates.push_front(update);
+            }
+        }
+    }
+}
 
 pub struct TgBackend {
     pub handle_updates: JoinHandle<()>,
@@ -26,7 +211,26 @@ pub struct TgBackend {
     pub have_authorization: bool,
     pub can_quit: Arc<AtomicBool>,
     pub app_context: Arc<AppContext>,
-    full_chats_list: bool,
+    /// The sender of the channel of the updates, used to wait for the
+    /// updates received so far to be applied, see `wait_for_updates`.
+    update_tx: Option<UnboundedSender<UpdateMessage>>,
+    /// Indicates whether all the chats of the main list have been loaded.
+    pub full_chats_list: bool,
+    /// The chat that Telegram has been informed to be opened, see `open_chat`.
+    opened_chat_id: Option<i64>,
+    /// The actions that failed because of the network, replayed when the
+    /// connection is ready again, see `take_pending_actions`.
+    pending_actions: VecDeque<Action>,
+    /// The actions that are being retried because of the network, with the
+    /// number of retries, see `retry_action`.
+    retry_attempts: Vec<(Action, u32)>,
+    /// The sender used to stop the voice note being played, see
+    /// `play_voice_note`.
+    stop_voice_note: Option<oneshot::Sender<()>>,
+    /// The message explaining how to set the API credentials, when they are
+    /// missing or invalid, or why the TDLib parameters were not set, see
+    /// `take_setup_error`.
+    setup_error: Option<Vec<String>>,
 }
 
 impl TgBackend {
@@ -38,7 +242,13 @@ impl TgBackend {
         let client_id = tdlib_rs::create_client();
         let have_authorization = false;
         let can_quit = Arc::new(AtomicBool::new(false));
+        let update_tx = None;
         let full_chats_list = false;
+        let opened_chat_id = None;
+        let pending_actions = VecDeque::new();
+        let retry_attempts = vec![];
+        let stop_voice_note = None;
+        let setup_error = None;
         app_context.tg_context().set_event_tx(event_tx.clone());
         tracing::info!("Created TDLib client with client_id: {}", client_id);
 
@@ -52,10 +262,255 @@ impl TgBackend {
             have_authorization,
             can_quit,
             app_context,
+            update_tx,
             full_chats_list,
+            opened_chat_id,
+            pending_actions,
+            retry_attempts,
+            stop_voice_note,
+            setup_error,
         })
     }
 
+    /// Parse the API credentials used to connect to Telegram.
+    ///
+    /// # Arguments
+    /// * `api_id` - The API id, `None` if it is not set.
+    /// * `api_hash` - The API hash, `None` if it is not set.
+    /// * `from_config` - Whether the credentials are taken from the telegram
+    ///   configuration instead of the environment.
+    ///
+    /// # Returns
+    /// * `Result<(i32, String), Vec<String>>` - The API id and the API hash,
+    ///   or the lines of a message explaining how to set them.
+    fn parse_api_credentials(
+        api_id: Option<&str>,
+        api_hash: Option<&str>,
+        from_config: (bool, bool),
+    ) -> Result<(i32, String), Vec<String>> {
+        let id_source = if from_config.0 {
+            "api_id in telegram.toml"
+        } else {
+            "the API_ID environment variable"
+        };
+        let hash_source = if from_config.1 {
+            "api_hash in telegram.toml"
+        } else {
+            "the API_HASH environment variable"
+        };
+        let mut problems = vec![];
+        let api_id = match api_id.map(str::trim) {
+            None | Some("") => {
+                problems.push(format!("- {} is not set", id_source));
+                None
+            }
+            Some(api_id) => match api_id.parse::<i32>() {
+                Ok(api_id) if api_id > 0 => Some(api_id),
+                _ => {
+                    problems.push(format!("- {} is not a valid id: {}", id_source, api_id));
+                    None
+                }
+            },
+        };
+        let api_hash = match api_hash.map(str::trim) {
+            None | Some("") => {
+                problems.push(format!("- {} is not set", hash_source));
+                None
+            }
+            Some(api_hash) => Some(api_hash.to_string()),
+        };
+        if let (Some(api_id), Some(api_hash)) = (api_id, api_hash) {
+            return Ok((api_id, api_hash));
+        }
+        let mut message = vec!["The Telegram API credentials are missing or invalid:".to_string()];
+        message.extend(problems);
+        message.extend([
+            String::new(),
+            "Get your api_id and api_hash at https://my.telegram.org/apps, then either:"
+                .to_string(),
+            "- set the API_ID and API_HASH environment variables before starting tgt, or"
+                .to_string(),
+            "- set api_id and api_hash in telegram.toml, with".to_string(),
+            "  take_api_id_from_telegram_config = true and".to_string(),
+            "  take_api_hash_from_telegram_config = true in app.toml.".to_string(),
+        ]);
+        Err(message)
+    }
+
+    /// Take the message explaining how to set the API credentials, if they
+    /// are missing or invalid.
+    ///
+    /// # Returns
+    /// * `Option<Vec<String>>` - The lines of the message, `None` if the
+    ///   credentials are valid.
+    pub fn take_setup_error(&mut self) -> Option<Vec<String>> {
+        self.setup_error.take()
+    }
+
+    /// Check whether a request failed because of the network, and so it can
+    /// be sent again later.
+    ///
+    /// # Arguments
+    /// * `error` - The error returned by TDLib.
+    ///
+    /// # Returns
+    /// * `bool` - `true` if the error is caused by the network.
+    pub fn is_network_error(error: &Error) -> bool {
+        error.code >= 500 || error.message.to_uppercase().contains("NETWORK")
+    }
+
+    /// Get the delay to wait before a retry of a request.
+    ///
+    /// # Arguments
+    /// * `attempt` - The number of the retry, starting from 0.
+    ///
+    /// # Returns
+    /// * `Duration` - The delay, doubled at each attempt up to
+    ///   `RETRY_MAX_DELAY`.
+    pub fn retry_delay(attempt: u32) -> Duration {
+        RETRY_BASE_DELAY
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(RETRY_MAX_DELAY)
+    }
+
+    /// Split the text of a message into parts that are not longer than a
+    /// maximum length, counted in UTF-16 code units like Telegram does.
+    /// Each part ends after the last newline that fits in it, otherwise after
+    /// the last space, otherwise it is cut at the maximum length.
+    ///
+    /// # Arguments
+    /// * `text` - The text of the message.
+    /// * `max_length` - The maximum length of a part.
+    ///
+    /// # Returns
+    /// * `Vec<&str>` - The parts of the text, at least one.
+    pub fn split_message(text: &str, max_length: usize) -> Vec<&str> {
+        let mut parts = vec![];
+        let mut rest = text;
+        while rest.encode_utf16().count() > max_length {
+            // The byte index of the first character that does not fit.
+            let mut length = 0;
+            let mut limit = 0;
+            for (i, c) in rest.char_indices() {
+                if length + c.len_utf16() > max_length {
+                    limit = i;
+                    break;
+                }
+                length += c.len_utf16();
+            }
+            if limit == 0 {
+                // Not even a character fits, the maximum length is too small.
+                break;
+            }
+            let head = &rest[..limit];
+            let end = head
+                .rfind('\n')
+                .or_else(|| head.rfind(' '))
+                .map_or(limit, |i| i + 1);
+            parts.push(&rest[..end]);
+            rest = &rest[end..];
+        }
+        if !rest.is_empty() || parts.is_empty() {
+            parts.push(rest);
+        }
+        parts
+    }
+
+    /// Get the entities of a part of a text, with the offsets relative to the
+    /// part. The entities that cross the boundaries of the part are cut.
+    ///
+    /// # Arguments
+    /// * `entities` - The entities of the whole text.
+    /// * `offset` - The offset of the part in the text, in UTF-16 code units.
+    /// * `length` - The length of the part, in UTF-16 code units.
+    ///
+    /// # Returns
+    /// * `Vec<TextEntity>` - The entities of the part.
+    pub fn part_entities(entities: &[TextEntity], offset: i32, length: i32) -> Vec<TextEntity> {
+        entities
+            .iter()
+            .filter_map(|entity| {
+                let start = entity.offset.max(offset);
+                let end = (entity.offset + entity.length).min(offset + length);
+                (start < end).then(|| TextEntity {
+                    offset: start - offset,
+                    length: end - start,
+                    r#type: entity.r#type.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Queue an action that failed because of the network, so that it is
+    /// replayed when the connection is ready again.
+    ///
+    /// # Arguments
+    /// * `action` - The action to replay.
+    fn queue_action(&mut self, action: Action) {
+        if !self.pending_actions.contains(&action) {
+            tracing::warn!("Queueing the action {action:?} until the connection is ready");
+            self.pending_actions.push_back(action);
+        }
+    }
+
+    /// Send again an action that failed because of the network, after a delay
+    /// that grows at each retry. The action is sent by a task, so that the
+    /// action loop is not blocked while waiting. When the connection is not
+    /// ready, or after `RETRY_ATTEMPTS` retries, the action is queued until
+    /// the connection is ready again.
+    ///
+    /// # Arguments
+    /// * `action` - The action to retry.
+    fn retry_action(&mut self, action: Action) {
+        let attempt = match self.retry_attempts.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, attempt)) => {
+                *attempt += 1;
+                *attempt
+            }
+            None => {
+                self.retry_attempts.push((action.clone(), 0));
+                0
+            }
+        };
+        if attempt >= RETRY_ATTEMPTS || !self.app_context.tg_context().is_connection_ready() {
+            self.reset_retries(&action);
+            self.queue_action(action);
+            return;
+        }
+        tracing::warn!("Retrying the action {action:?}");
+        let action_tx = self.app_context.action_tx().clone();
+        let delay = Self::retry_delay(attempt);
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            let _ = action_tx.send(action);
+        });
+    }
+
+    /// Forget the retries of an action, e.g. because it succeeded.
+    ///
+    /// # Arguments
+    /// * `action` - The action.
+    fn reset_retries(&mut self, action: &Action) {
+        self.retry_attempts.retain(|(a, _)| a != action);
+    }
+
+    /// Take the actions that failed because of the network, in the order in
+    /// which they failed. The history of a chat that is not open anymore is
+    /// not loaded again.
+    ///
+    /// # Returns
+    /// * `Vec<Action>` - The actions to replay.
+    pub fn take_pending_actions(&mut self) -> Vec<Action> {
+        let open_chat_id = self.app_context.tg_context().open_chat_id();
+        self.pending_actions
+            .drain(..)
+            .filter(|action| match action {
+                Action::GetChatHistory(chat_id) => *chat_id == open_chat_id,
+                _ => true,
+            })
+            .collect()
+    }
+
     pub async fn get_me(&mut self) {
         match functions::get_me(self.client_id).await {
             Ok(User::User(me)) => {
@@ -65,20 +520,99 @@ impl TgBackend {
         }
     }
 
+    /// Load the chats of a chat list.
+    /// The request is sent again with a backoff if it fails because of the
+    /// network, and then queued until the connection is ready, see
+    /// `retry_action`.
+    ///
+    /// # Arguments
+    /// * `chat_list` - The chat list to load.
+    /// * `limit` - The number of chats to load.
     pub async fn load_chats(&mut self, chat_list: ChatList, limit: i32) {
-        if self.full_chats_list {
-            return;
+        let action = Action::LoadChats(chat_list.clone().into(), limit);
+        match self.try_load_chats(chat_list, limit).await {
+            Ok(()) => self.reset_retries(&action),
+            Err(e) => {
+                tracing::warn!("Failed to load chats: {e:?}");
+                self.retry_action(action);
+            }
+        }
+    }
+
+    /// Send a request to load the chats of a chat list.
+    ///
+    /// # Arguments
+    /// * `chat_list` - The chat list to load.
+    /// * `limit` - The number of chats to load.
+    ///
+    /// # Returns
+    /// * `Result<(), Error>` - The error if the request failed because of the
+    ///   network.
+    async fn try_load_chats(&mut self, chat_list: ChatList, limit: i32) -> Result<(), Error> {
+        // The chats of a folder are loaded every time the folder is selected,
+        // only the main chat list is marked as fully loaded.
+        let is_main = chat_list == ChatList::Main;
+        if self.full_chats_list && is_main {
+            return Ok(());
+        }
+        match functions::load_chats(Some(chat_list), limit, self.client_id).await {
+            Ok(()) => Ok(()),
+            Err(e) if Self::is_network_error(&e) => Err(e),
+            Err(e) => {
+                // TDLib returns an error when all the chats are loaded
+                tracing::error!("Failed to load chats: {e:?}");
+                if is_main {
+                    self.full_chats_list = true;
+                }
+                Ok(())
+            }
         }
+    }
 
-        if let Err(e) = functions::load_chats(Some(chat_list), limit, self.client_id).await {
-            tracing::error!("Failed to load chats: {e:?}");
-            self.full_chats_list = true;
+    /// Load a page of the chats of the main list from the command line.
+    /// There is no action loop to retry the request, so it is sent again
+    /// with a backoff here if it fails because of the network.
+    ///
+    /// # Returns
+    /// * `bool` - `false` if the chats can not be loaded.
+    async fn load_main_chats_blocking(&mut self) -> bool {
+        let mut attempt = 0;
+        loop {
+            match self.try_load_chats(ChatList::Main, 50).await {
+                Ok(()) => return true,
+                Err(e) if attempt < RETRY_ATTEMPTS => {
+                    tracing::warn!("Failed to load chats, retrying: {e:?}");
+                    tokio::time::sleep(Self::retry_delay(attempt)).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to load chats: {e:?}");
+                    return false;
+                }
+            }
         }
     }
 
     pub async fn load_all_chats(&mut self) {
         while !self.full_chats_list {
-            self.load_chats(ChatList::Main, 50).await;
+            if !self.load_main_chats_blocking().await {
+                break;
+            }
+        }
+    }
+
+    /// Load the chats of the main list until `limit` chats are loaded, or
+    /// all of them if `limit` is `None`.
+    ///
+    /// # Arguments
+    /// * `limit` - The number of chats to load.
+    pub async fn load_chats_up_to(&mut self, limit: Option<usize>) {
+        while !self.full_chats_list
+            && limit.is_none_or(|limit| self.app_context.tg_context().chats_index().len() < limit)
+        {
+            if !self.load_main_chats_blocking().await {
+                break;
+            }
         }
     }
 
@@ -102,6 +636,412 @@ impl TgBackend {
         }
     }
 
+    /// Close the TDLib client and wait until it is closed, so that its state
+    /// is flushed to the database before the application exits.
+    /// The wait ends after `SHUTDOWN_TIMEOUT` if TDLib does not answer.
+    pub async fn shutdown(&mut self) {
+        self.have_authorization = false;
+        self.close().await;
+        let closed = async {
+            while !self.can_quit.load(Ordering::Acquire) {
+                let Some(state) = self.auth_rx.recv().await else {
+                    break;
+                };
+                self.process_authorization_state(state).await;
+            }
+        };
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, closed)
+            .await
+            .is_err()
+        {
+            tracing::warn!(
+                "TDLib client not closed after {} seconds, quitting anyway",
+                SHUTDOWN_TIMEOUT.as_secs()
+            );
+        }
+    }
+
+    /// Load the pinned messages of a chat.
+    /// If they can not be searched, only the most recent pinned message is
+    /// loaded.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    pub async fn load_pinned_messages(&self, chat_id: i64) {
+        let pinned_messages = match functions::search_chat_messages(
+            chat_id,
+            String::new(),
+            None,
+            0,
+            0,
+            50,
+            Some(SearchMessagesFilter::Pinned),
+            0,
+            0,
+            self.client_id,
+        )
+        .await
+        {
+            Ok(enums::FoundChatMessages::FoundChatMessages(found)) => {
+                found.messages.iter().map(MessageEntry::from).collect()
+            }
+            Err(e) => {
+                tracing::warn!("Failed to search the pinned messages of {chat_id}: {e:?}");
+                match functions::get_chat_pinned_message(chat_id, self.client_id).await {
+                    Ok(enums::Message::Message(message)) => vec![MessageEntry::from(&message)],
+                    // There is no pinned message
+                    Err(_) => vec![],
+                }
+            }
+        };
+        self.app_context
+            .tg_context()
+            .pinned_messages()
+            .insert(chat_id, pinned_messages);
+    }
+
+    /// Pin a message of a chat, and show it in the pinned message banner.
+    /// An error is shown if the user can not pin messages in the chat.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `message_id` - The id of the message.
+    /// * `notify` - Whether to notify the members of the group.
+    pub async fn pin_message(&self, chat_id: i64, message_id: i64, notify: bool) {
+        let tg_context = self.app_context.tg_context();
+        if !tg_context.can_pin_messages(chat_id) {
+            tg_context.set_error_message("You can not pin messages in this chat");
+            return;
+        }
+        match functions::pin_chat_message(chat_id, message_id, !notify, false, self.client_id).await
+        {
+            Ok(_) => tg_context.set_message_pinned(chat_id, message_id, true),
+            Err(e) => {
+                tracing::error!("Failed to pin the message {message_id} of {chat_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to pin the message: {}", e.message));
+            }
+        }
+    }
+
+    /// Unpin a message of a chat.
+    /// An error is shown if the user can not unpin messages in the chat.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `message_id` - The id of the message.
+    pub async fn unpin_message(&self, chat_id: i64, message_id: i64) {
+        let tg_context = self.app_context.tg_context();
+        if !tg_context.can_pin_messages(chat_id) {
+            tg_context.set_error_message("You can not unpin messages in this chat");
+            return;
+        }
+        match functions::unpin_chat_message(chat_id, message_id, self.client_id).await {
+            Ok(_) => tg_context.set_message_pinned(chat_id, message_id, false),
+            Err(e) => {
+                tracing::error!("Failed to unpin the message {message_id} of {chat_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to unpin the message: {}", e.message));
+            }
+        }
+    }
+
+    /// Get the users that have read a message of a group.
+    /// Nothing is requested if they have been got recently, see
+    /// `MESSAGE_VIEWERS_CACHE_DURATION`.
+    /// The viewers are not available in large groups, and for old messages.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `message_id` - The id of the message.
+    pub async fn get_message_viewers(&self, chat_id: i64, message_id: i64) {
+        let tg_context = self.app_context.tg_context();
+        if tg_context.message_viewers(chat_id, message_id).is_some() {
+            return;
+        }
+        let viewers =
+            match functions::get_message_viewers(chat_id, message_id, self.client_id).await {
+                Ok(enums::MessageViewers::MessageViewers(viewers)) => {
+                    Some(viewers.viewers.iter().map(|v| v.user_id).collect())
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to get the viewers of {message_id} in {chat_id}: {e:?}");
+                    None
+                }
+            };
+        tg_context.set_message_viewers(chat_id, message_id, viewers);
+    }
+
+    /// Delete all the messages of a chat.
+    /// The messages of the chat are removed from the chat window if it is
+    /// open.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `remove_from_chat_list` - Whether to remove the chat from the chat
+    ///   list too.
+    /// * `revoke` - Whether to delete the messages for the other users too.
+    pub async fn clear_chat_history(
+        &self,
+        chat_id: i64,
+        remove_from_chat_list: bool,
+        revoke: bool,
+    ) {
+        let tg_context = self.app_context.tg_context();
+        match functions::delete_chat_history(chat_id, remove_from_chat_list, revoke, self.client_id)
+            .await
+        {
+            Ok(_) => {
+                tg_context.pinned_messages().remove(&chat_id);
+                if tg_context.open_chat_id() == chat_id {
+                    tg_context.clear_open_chat_messages();
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to clear the history of {chat_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to clear the history: {}", e.message));
+            }
+        }
+    }
+
+    /// Block a user, or unblock them if they are blocked.
+    /// The block state is updated immediately, without waiting for the update
+    /// from Telegram.
+    ///
+    /// # Arguments
+    /// * `user_id` - The id of the user, it is also the id of the private
+    ///   chat with the user.
+    /// * `report_spam` - Whether to report the private chat with the user as
+    ///   spam when blocking them.
+    pub async fn toggle_block_user(&self, user_id: i64, report_spam: bool) {
+        let tg_context = self.app_context.tg_context();
+        let block_list = if tg_context.is_chat_blocked(user_id) {
+            None
+        } else {
+            Some(BlockList::Main)
+        };
+        if let Err(e) = functions::set_message_sender_block_list(
+            MessageSender::User(MessageSenderUser { user_id }),
+            block_list.clone(),
+            self.client_id,
+        )
+        .await
+        {
+            tracing::error!("Failed to change the block state of {user_id}: {e:?}");
+            tg_context.set_error_message(format!("Failed to block the user: {}", e.message));
+            return;
+        }
+        let blocked = block_list.is_some();
+        if let Some(chat) = tg_context.chats().get_mut(&user_id) {
+            chat.block_list = block_list;
+        }
+        if blocked && report_spam {
+            if let Err(e) = functions::report_chat(
+                user_id,
+                vec![],
+                ReportReason::Spam,
+                String::new(),
+                self.client_id,
+            )
+            .await
+            {
+                tracing::error!("Failed to report the chat {user_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to report the chat: {}", e.message));
+            }
+        }
+    }
+
+    /// Join a chat, and open it once it is shown in the chat list.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    pub async fn join_chat(&self, chat_id: i64) {
+        let tg_context = self.app_context.tg_context();
+        match functions::join_chat(chat_id, self.client_id).await {
+            Ok(_) => self
+                .app_context
+                .action_tx()
+                .send(Action::JumpToChat(chat_id))
+                .unwrap(),
+            Err(e) => {
+                tracing::error!("Failed to join the chat {chat_id}: {e:?}");
+                tg_context.set_error_message(Self::join_error_message(&e));
+            }
+        }
+    }
+
+    /// Join a chat by the username of a public chat or by an invite link, and
+    /// open it once it is shown in the chat list.
+    ///
+    /// # Arguments
+    /// * `link` - The username or the link, see `ChatLink::parse`.
+    pub async fn join_chat_by_link(&self, link: &str) {
+        let tg_context = self.app_context.tg_context();
+        let chat = match ChatLink::parse(link) {
+            Some(ChatLink::Username(username)) => {
+                match functions::search_public_chat(username.clone(), self.client_id).await {
+                    Ok(enums::Chat::Chat(chat)) => chat,
+                    Err(e) => {
+                        tracing::error!("Failed to find the chat {username}: {e:?}");
+                        tg_context.set_error_message(format!("There is no chat @{username}"));
+                        return;
+                    }
+                }
+            }
+            Some(ChatLink::InviteLink(invite_link)) => {
+                match functions::join_chat_by_invite_link(invite_link, self.client_id).await {
+                    Ok(enums::Chat::Chat(chat)) => {
+                        self.app_context
+                            .action_tx()
+                            .send(Action::JumpToChat(chat.id))
+                            .unwrap();
+                        return;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to join the chat {link}: {e:?}");
+                        tg_context.set_error_message(Self::join_error_message(&e));
+                        return;
+                    }
+                }
+            }
+            None => {
+                tg_context.set_error_message(format!("Invalid username or link: {link}"));
+                return;
+            }
+        };
+        self.join_chat(chat.id).await;
+    }
+
+    /// Open a public chat by its username, or by a link to it.
+    /// The chat is opened even if the user is not a member of it.
+    ///
+    /// # Arguments
+    /// * `username` - The username or the link, see `ChatLink::parse`.
+    pub async fn open_by_username(&self, username: &str) {
+        let tg_context = self.app_context.tg_context();
+        let username = match ChatLink::parse(username) {
+            Some(ChatLink::Username(username)) => username,
+            Some(ChatLink::InviteLink(_)) => {
+                tg_context.set_error_message("Invite links can be opened with :join");
+                return;
+            }
+            None => {
+                tg_context.set_error_message(format!("Invalid username or link: {username}"));
+                return;
+            }
+        };
+        match functions::search_public_chat(username.clone(), self.client_id).await {
+            Ok(enums::Chat::Chat(chat)) => self
+                .app_context
+                .action_tx()
+                .send(Action::JumpToChat(chat.id))
+                .unwrap(),
+            Err(e) => {
+                tracing::error!("Failed to find the chat {username}: {e:?}");
+                tg_context.set_error_message(format!("There is no chat @{username}"));
+            }
+        }
+    }
+
+    /// Copy the link of a message to the clipboard.
+    /// The links are available only for the messages of the supergroups and
+    /// of the channels.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `message_id` - The id of the message.
+    pub async fn get_message_link(&self, chat_id: i64, message_id: i64) {
+        let tg_context = self.app_context.tg_context();
+        let link =
+            match functions::get_message_link(chat_id, message_id, 0, false, false, self.client_id)
+                .await
+            {
+                Ok(enums::MessageLink::MessageLink(link)) => link,
+                Err(e) => {
+                    tracing::warn!("Failed to get the link of {message_id} in {chat_id}: {e:?}");
+                    tg_context.set_error_message(
+                        "Message links are available only in supergroups and channels",
+                    );
+                    return;
+                }
+            };
+        match Clipboard::new().and_then(|mut clipboard| clipboard.set_text(link.link.clone())) {
+            Ok(_) if link.is_public => {
+                tg_context.set_info_message(format!("Copied {}", link.link));
+            }
+            Ok(_) => tg_context.set_info_message(format!(
+                "Copied {}, it works only for the members of the chat",
+                link.link
+            )),
+            Err(e) => {
+                tracing::error!("Failed to copy the link {}: {}", link.link, e);
+                tg_context.set_error_message(format!("Failed to copy the link: {}", e));
+            }
+        }
+    }
+
+    /// Get the message shown to the user when joining a chat fails.
+    ///
+    /// # Arguments
+    /// * `error` - The error returned by Telegram.
+    ///
+    /// # Returns
+    /// * `String` - The message.
+    fn join_error_message(error: &Error) -> String {
+        match error.message.as_str() {
+            "USER_ALREADY_PARTICIPANT" => "You are already a member of this chat".to_string(),
+            "INVITE_HASH_EXPIRED" | "INVITE_HASH_INVALID" => {
+                "The invite link is expired or invalid".to_string()
+            }
+            "CHANNELS_TOO_MUCH" => "You have joined too many chats".to_string(),
+            message => format!("Failed to join the chat: {}", message),
+        }
+    }
+
+    /// Leave a chat.
+    /// The chat is removed from the chat list by the updates of its position.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    pub async fn leave_chat(&self, chat_id: i64) {
+        if let Err(e) = functions::leave_chat(chat_id, self.client_id).await {
+            tracing::error!("Failed to leave the chat {chat_id}: {e:?}");
+            self.app_context
+                .tg_context()
+                .set_error_message(format!("Failed to leave the chat: {}", e.message));
+        }
+    }
+
+    /// Set the time after which the new messages of a chat are deleted.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `seconds` - The time in seconds, 0 to disable the timer.
+    pub async fn set_auto_delete_time(&self, chat_id: i64, seconds: i32) {
+        if let Err(e) =
+            functions::set_chat_message_auto_delete_time(chat_id, seconds, self.client_id).await
+        {
+            tracing::error!("Failed to set the auto-delete time of {chat_id}: {e:?}");
+        }
+    }
+
+    /// Start a secret chat with a user, and select it in the chat list.
+    /// The chat is usable once the other user accepts it, see
+    /// `SecretChatState`.
+    ///
+    /// # Arguments
+    /// * `user_id` - The id of the user.
+    pub async fn create_secret_chat(&self, user_id: i64) {
+        match functions::create_new_secret_chat(user_id, self.client_id).await {
+            Ok(enums::Chat::Chat(chat)) => {
+                tracing::info!("Created the secret chat {}", chat.id);
+                self.app_context
+                    .action_tx()
+                    .send(Action::JumpToChat(chat.id))
+                    .unwrap();
+            }
+            Err(e) => tracing::error!("Failed to create a secret chat with {user_id}: {e:?}"),
+        }
+    }
+
     pub async fn view_all_messages(&self) {
         if let Err(e) = functions::view_messages(
             self.app_context.tg_context().open_chat_id(),
@@ -116,6 +1056,76 @@ impl TgBackend {
         }
     }
 
+    /// Mark some messages of a chat as read.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `message_ids` - The ids of the messages.
+    pub async fn view_messages(&self, chat_id: i64, message_ids: Vec<i64>) {
+        if let Err(e) =
+            functions::view_messages(chat_id, message_ids, None, true, self.client_id).await
+        {
+            tracing::error!("Failed to view the messages of {chat_id}: {e:?}");
+        }
+    }
+
+    /// Inform Telegram that a chat has been opened, and close the chat
+    /// opened before.
+    /// Telegram sends some updates, e.g. the number of online members, only
+    /// for the opened chats.
+    /// The full information of a supergroup is loaded if it is not cached yet.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the opened chat.
+    pub async fn open_chat(&mut self, chat_id: i64) {
+        if let Some(opened_chat_id) = self.opened_chat_id.take() {
+            if let Err(e) = functions::close_chat(opened_chat_id, self.client_id).await {
+                tracing::error!("Failed to close the chat {opened_chat_id}: {e:?}");
+            }
+        }
+        match functions::open_chat(chat_id, self.client_id).await {
+            Ok(_) => self.opened_chat_id = Some(chat_id),
+            Err(e) => tracing::error!("Failed to open the chat {chat_id}: {e:?}"),
+        }
+        if !self
+            .app_context
+            .tg_context()
+            .pinned_messages()
+            .contains_key(&chat_id)
+        {
+            self.load_pinned_messages(chat_id).await;
+        }
+
+        let chat_type = self
+            .app_context
+            .tg_context()
+            .chats()
+            .get(&chat_id)
+            .map(|chat| chat.r#type.clone());
+        if let Some(ChatType::Supergroup(supergroup)) = chat_type {
+            let supergroup_id = supergroup.supergroup_id;
+            if self
+                .app_context
+                .tg_context()
+                .supergroups_full_info()
+                .contains_key(&supergroup_id)
+            {
+                return;
+            }
+            match functions::get_supergroup_full_info(supergroup_id, self.client_id).await {
+                Ok(enums::SupergroupFullInfo::SupergroupFullInfo(full_info)) => {
+                    self.app_context
+                        .tg_context()
+                        .supergroups_full_info()
+                        .insert(supergroup_id, full_info);
+                }
+                Err(e) => {
+                    tracing::error!("Failed to get the supergroup full info {supergroup_id}: {e:?}")
+                }
+            }
+        }
+    }
+
     #[allow(clippy::await_holding_lock)]
     // By default telegram send us only one message the first time
     pub async fn prepare_to_get_chat_history(&mut self, chat_id: i64) {
@@ -127,6 +1137,11 @@ impl TgBackend {
 
     #[allow(clippy::await_holding_lock)]
     pub async fn get_chat_history(&mut self, chat_id: i64) {
+        if chat_id != self.app_context.tg_context().open_chat_id() {
+            tracing::info!("Skipping the history of the chat {chat_id}, it is not open anymore");
+            self.reset_retries(&Action::GetChatHistory(chat_id));
+            return;
+        }
         let start_open_chat_messages_len = self.app_context.tg_context().open_chat_messages().len();
         let mut mut_open_chat_messages_len =
             self.app_context.tg_context().open_chat_messages().len();
@@ -145,6 +1160,7 @@ impl TgBackend {
             .await
             {
                 Ok(Messages::Messages(messages)) => {
+                    self.reset_retries(&Action::GetChatHistory(chat_id));
                     if messages.messages.is_empty() {
                         tracing::info!("No more messages to get");
                         break;
@@ -152,11 +1168,13 @@ impl TgBackend {
 
                     let message_flatten = messages.messages.into_iter().flatten();
                     for message in message_flatten.clone() {
-                        self.app_context
+                        if self
+                            .app_context
                             .tg_context()
-                            .open_chat_messages()
-                            .push(MessageEntry::from(&message));
-                        mut_open_chat_messages_len += 1;
+                            .push_open_chat_message(MessageEntry::from(&message))
+                        {
+                            mut_open_chat_messages_len += 1;
+                        }
                     }
                     if let Some(message) = message_flatten.last() {
                         self.app_context
@@ -164,21 +1182,38 @@ impl TgBackend {
                             .set_from_message_id(message.id);
                     }
                 }
+                Err(e) if Self::is_network_error(&e) => {
+                    tracing::warn!("Failed to get chat history: {e:?}");
+                    self.retry_action(Action::GetChatHistory(chat_id));
+                    break;
+                }
                 Err(e) => {
                     tracing::error!("Failed to get chat history: {e:?}");
                     break;
                 }
             }
         }
+        self.app_context
+            .tg_context()
+            .set_is_loading_chat_history(false);
     }
 
-    #[allow(clippy::await_holding_lock)]
-    pub async fn send_message(
-        &mut self,
-        message: String,
-        chat_id: i64,
-        reply_to: Option<TdMessageReplyToMessage>,
-    ) -> Result<tdlib_rs::types::Message, tdlib_rs::types::Error> {
+    /// Take the reply that is being sent, and hide it from the chat window.
+    ///
+    /// # Returns
+    /// * `Vec<TextEntity>` - The entities of the text of the reply, i.e. the
+    ///   quote of a quote reply sent as a block quote.
+    fn take_reply_entities(&mut self) -> Vec<TextEntity> {
+        let quote_length = self.app_context.tg_context().reply_quote_length();
+        let entities = if quote_length > 0 {
+            vec![TextEntity {
+                offset: 0,
+                length: quote_length as i32,
+                r#type: TextEntityType::BlockQuote,
+            }]
+        } else {
+            vec![]
+        };
         self.app_context
             .tg_context()
             .set_reply_message(-1, "".to_string());
@@ -187,22 +1222,790 @@ impl TgBackend {
             .action_tx()
             .send(Action::HideChatWindowReply)
             .unwrap();
+        entities
+    }
+
+    /// Send a text message.
+    /// If `split_long_messages` is enabled, a message longer than the limit
+    /// of Telegram is sent as multiple messages, in order. Only the first one
+    /// replies to the message, and the entities are split among them.
+    ///
+    /// # Arguments
+    /// * `message` - The text of the message.
+    /// * `chat_id` - The chat to send the message to.
+    /// * `reply_to` - The message the message replies to, if any.
+    ///
+    /// # Returns
+    /// * `Result<Message, Error>` - The last sent message, or the error of
+    ///   the first message that could not be sent.
+    pub async fn send_message(
+        &mut self,
+        message: String,
+        chat_id: i64,
+        reply_to: Option<TdMessageReplyToMessage>,
+    ) -> Result<tdlib_rs::types::Message, tdlib_rs::types::Error> {
+        let entities = self.take_reply_entities();
+        let mut reply_to: Option<InputMessageReplyTo> =
+            reply_to.map(|reply_to| InputMessageReplyTo::Message((&reply_to).into()));
+        let parts = if self.app_context.app_config().split_long_messages {
+            Self::split_message(&message, MAX_MESSAGE_LENGTH)
+        } else {
+            vec![message.as_str()]
+        };
+        let mut offset = 0;
+        let mut sent = None;
+        for part in parts {
+            let length = part.encode_utf16().count() as i32;
+            let text = InputMessageContent::InputMessageText(InputMessageText {
+                text: tdlib_rs::types::FormattedText {
+                    text: part.to_string(),
+                    entities: Self::part_entities(&entities, offset, length),
+                },
+                link_preview_options: None,
+                clear_draft: true,
+            });
+            match functions::send_message(chat_id, 0, reply_to.take(), None, text, self.client_id)
+                .await
+            {
+                Ok(tdlib_rs::enums::Message::Message(message)) => sent = Some(message),
+                Err(e) => {
+                    tracing::error!("Failed to send message: {e:?}");
+                    return Err(e);
+                }
+            }
+            offset += length;
+        }
+        Ok(sent.expect("A message has at least one part"))
+    }
 
+    /// Schedule a text message.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The chat to send the message to.
+    /// * `message` - The text of the message.
+    /// * `send_date` - The Unix time the message is sent at.
+    pub async fn send_scheduled_message(&mut self, chat_id: i64, message: String, send_date: i32) {
         let text = InputMessageContent::InputMessageText(InputMessageText {
-            text: tdlib_rs::types::FormattedText {
+            text: FormattedText {
                 text: message,
-                entities: vec![], // TODO: Add entities
+                entities: vec![],
             },
             link_preview_options: None,
             clear_draft: true,
         });
+        let options = MessageSendOptions {
+            scheduling_state: Some(MessageSchedulingState::SendAtDate(
+                MessageSchedulingStateSendAtDate { send_date },
+            )),
+            ..Default::default()
+        };
+        match functions::send_message(chat_id, 0, None, Some(options), text, self.client_id).await {
+            Ok(_) => {
+                let date = DateTime::from_timestamp(i64::from(send_date), 0)
+                    .map(|date| {
+                        date.with_timezone(&Local)
+                            .format("%Y-%m-%d %H:%M")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+                self.app_context
+                    .tg_context()
+                    .set_info_message(format!("Message scheduled for {}", date));
+            }
+            Err(e) => {
+                tracing::error!("Failed to schedule the message: {e:?}");
+                self.app_context
+                    .tg_context()
+                    .set_error_message(format!("Failed to schedule the message: {}", e.message));
+            }
+        }
+    }
+
+    /// Load the members of a group, shown by the member list.
+    /// The members of a supergroup are loaded page by page, the most recent
+    /// first, while all the members of a basic group are loaded at once.
+    /// They are loaded in the background, so that the other actions are not
+    /// delayed by a large group, and the pages of a loading are discarded
+    /// once the member list is opened again.
+    /// The result of the loading is set in the context, so that the member
+    /// list shows the error instead of waiting for the members.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat of the group.
+    pub fn load_chat_members(&self, chat_id: i64) {
+        let tg_context = self.app_context.tg_context();
+        let chat_type = tg_context
+            .chats()
+            .get(&chat_id)
+            .map(|chat| chat.r#type.clone());
+        if !matches!(
+            chat_type,
+            Some(ChatType::Supergroup(_) | ChatType::BasicGroup(_))
+        ) {
+            tg_context.set_info_message("Only groups have a member list");
+            return;
+        }
+        let generation = tg_context.chat_members_generation();
+        let client_id = self.client_id;
+        tokio::spawn(async move {
+            let result = match chat_type {
+                Some(ChatType::Supergroup(supergroup)) => {
+                    Self::load_supergroup_members(
+                        &tg_context,
+                        client_id,
+                        generation,
+                        supergroup.supergroup_id,
+                    )
+                    .await
+                }
+                Some(ChatType::BasicGroup(basic_group)) => {
+                    functions::get_basic_group_full_info(basic_group.basic_group_id, client_id)
+                        .await
+                        .map(|enums::BasicGroupFullInfo::BasicGroupFullInfo(info)| {
+                            tg_context.add_chat_members(generation, info.members);
+                        })
+                }
+                _ => return,
+            };
+            if generation != tg_context.chat_members_generation() {
+                return;
+            }
+            if let Err(e) = &result {
+                tracing::error!("Failed to get the members of {chat_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to get the members: {}", e.message));
+            }
+            tg_context.set_chat_members_loaded(generation, result.map_err(|e| e.message));
+        });
+    }
+
+    /// Load the members of a supergroup with pages of
+    /// `SUPERGROUP_MEMBERS_PAGE_SIZE` members, adding each page to the
+    /// member list as soon as it is received.
+    /// The loading stops once the member list is opened again.
+    ///
+    /// # Arguments
+    /// * `tg_context` - The Telegram context.
+    /// * `client_id` - The id of the TDLib client.
+    /// * `generation` - The number of the loading, see
+    ///   `TgContext::reset_chat_members`.
+    /// * `supergroup_id` - The id of the supergroup.
+    ///
+    /// # Returns
+    /// * `Result<(), Error>` - An error if a page can not be loaded.
+    async fn load_supergroup_members(
+        tg_context: &TgContext,
+        client_id: i32,
+        generation: u64,
+        supergroup_id: i64,
+    ) -> Result<(), Error> {
+        let mut offset = 0;
+        loop {
+            let enums::ChatMembers::ChatMembers(page) = functions::get_supergroup_members(
+                supergroup_id,
+                Some(SupergroupMembersFilter::Recent),
+                offset,
+                SUPERGROUP_MEMBERS_PAGE_SIZE,
+                client_id,
+            )
+            .await?;
+            let received = page.members.len() as i32;
+            offset += received;
+            if !tg_context.add_chat_members(generation, page.members) {
+                return Ok(());
+            }
+            if received < SUPERGROUP_MEMBERS_PAGE_SIZE || offset >= page.total_count {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Open the private chat with a user, creating it if needed.
+    ///
+    /// # Arguments
+    /// * `user_id` - The id of the user.
+    pub async fn open_private_chat(&self, user_id: i64) {
+        match functions::create_private_chat(user_id, false, self.client_id).await {
+            Ok(enums::Chat::Chat(chat)) => self
+                .app_context
+                .action_tx()
+                .send(Action::JumpToChat(chat.id))
+                .unwrap(),
+            Err(e) => {
+                tracing::error!("Failed to open the chat with {user_id}: {e:?}");
+                self.app_context
+                    .tg_context()
+                    .set_error_message(format!("Failed to open the chat: {}", e.message));
+            }
+        }
+    }
+
+    /// Load the scheduled messages of a chat, shown by the panel of the
+    /// scheduled messages.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    pub async fn load_scheduled_messages(&self, chat_id: i64) {
+        match functions::get_chat_scheduled_messages(chat_id, self.client_id).await {
+            Ok(Messages::Messages(messages)) => {
+                *self.app_context.tg_context().scheduled_messages() = messages
+                    .messages
+                    .into_iter()
+                    .flatten()
+                    .map(|message| ScheduledMessageEntry {
+                        send_date: match &message.scheduling_state {
+                            Some(MessageSchedulingState::SendAtDate(state)) => state.send_date,
+                            _ => 0,
+                        },
+                        message: MessageEntry::from(&message),
+                    })
+                    .collect();
+            }
+            Err(e) => tracing::error!("Failed to get the scheduled messages: {e:?}"),
+        }
+    }
+
+    /// Send a scheduled message immediately, removing its scheduling state.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `message_id` - The id of the scheduled message.
+    pub async fn send_scheduled_message_now(&self, chat_id: i64, message_id: i64) {
+        match functions::edit_message_scheduling_state(chat_id, message_id, None, self.client_id)
+            .await
+        {
+            Ok(_) => self
+                .app_context
+                .tg_context()
+                .set_info_message("Scheduled message sent"),
+            Err(e) => {
+                tracing::error!("Failed to send the scheduled message: {e:?}");
+                self.app_context.tg_context().set_error_message(format!(
+                    "Failed to send the scheduled message: {}",
+                    e.message
+                ));
+            }
+        }
+    }
+
+    /// Send a photo stored in a local file.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the photo.
+    /// * `caption` - The caption of the photo, it can be empty.
+    /// * `chat_id` - The chat to send the photo to.
+    /// * `reply_to` - The message the photo replies to, if any.
+    pub async fn send_photo(
+        &mut self,
+        path: String,
+        caption: String,
+        chat_id: i64,
+        reply_to: Option<TdMessageReplyToMessage>,
+    ) {
+        let entities = self.take_reply_entities();
+        let photo = InputMessageContent::InputMessagePhoto(InputMessagePhoto {
+            photo: InputFile::Local(InputFileLocal { path: path.clone() }),
+            thumbnail: None,
+            added_sticker_file_ids: vec![],
+            width: 0,
+            height: 0,
+            caption: Some(FormattedText {
+                text: caption,
+                entities,
+            }),
+            self_destruct_type: None,
+            has_spoiler: false,
+        });
         let reply_to: Option<InputMessageReplyTo> =
             reply_to.map(|reply_to| InputMessageReplyTo::Message((&reply_to).into()));
-        match functions::send_message(chat_id, 0, reply_to, None, text, self.client_id).await {
-            Ok(tdlib_rs::enums::Message::Message(message)) => Ok(message),
+        // The photo is a temporary file, deleted once it is uploaded.
+        self.app_context.tg_context().start_temporary_file();
+        let message_id = match functions::send_message(
+            chat_id,
+            0,
+            reply_to,
+            None,
+            photo,
+            self.client_id,
+        )
+        .await
+        {
+            Ok(enums::Message::Message(message)) => {
+                tracing::info!("Photo sent");
+                Some(message.id)
+            }
             Err(e) => {
-                tracing::error!("Failed to send message: {e:?}");
-                Err(e)
+                tracing::error!("Failed to send photo: {e:?}");
+                None
+            }
+        };
+        self.app_context
+            .tg_context()
+            .add_temporary_file(message_id, path);
+    }
+
+    /// Play the voice note of a message of the open chat with the command set
+    /// in `voice_note_player`, downloading it first if needed.
+    /// The voice note being played, if any, is stopped, and nothing else is
+    /// played if it is the voice note of the same message.
+    /// The voice note is played once downloaded, without waiting for the
+    /// download.
+    ///
+    /// # Arguments
+    /// * `message_id` - The id of the message.
+    pub async fn play_voice_note(&mut self, message_id: i64) {
+        if self.stop_playing() == message_id {
+            return;
+        }
+        let tg_context = self.app_context.tg_context();
+        let chat_id = tg_context.open_chat_id();
+        let voice = match functions::get_message(chat_id, message_id, self.client_id).await {
+            Ok(enums::Message::Message(message)) => match message.content {
+                MessageContent::MessageVoiceNote(content) => content.voice_note.voice,
+                _ => {
+                    tg_context.set_error_message("The selected message is not a voice note");
+                    return;
+                }
+            },
+            Err(e) => {
+                tracing::error!("Failed to get the message {message_id} of {chat_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to get the message: {}", e.message));
+                return;
+            }
+        };
+        let client_id = self.client_id;
+        let action_tx = self.app_context.action_tx().clone();
+        tokio::spawn(async move {
+            if let Some(path) =
+                Self::download_media(&tg_context, client_id, voice, "voice note").await
+            {
+                let _ = action_tx.send(Action::PlayAudio(message_id, path));
+            }
+        });
+    }
+
+    /// Add the contact shared by a message of the open chat to the contacts
+    /// of the user. The phone number of the user is not shared with the
+    /// contact.
+    ///
+    /// # Arguments
+    /// * `message_id` - The id of the message.
+    pub async fn save_contact(&self, message_id: i64) {
+        let tg_context = self.app_context.tg_context();
+        let chat_id = tg_context.open_chat_id();
+        let contact = match functions::get_message(chat_id, message_id, self.client_id).await {
+            Ok(enums::Message::Message(message)) => match message.content {
+                MessageContent::MessageContact(content) => content.contact,
+                _ => return,
+            },
+            Err(e) => {
+                tracing::error!("Failed to get the message {message_id} of {chat_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to get the message: {}", e.message));
+                return;
+            }
+        };
+        let name = format!("{} {}", contact.first_name, contact.last_name)
+            .trim()
+            .to_string();
+        match functions::add_contact(contact, false, self.client_id).await {
+            Ok(()) => tg_context.set_info_message(format!("{} saved to the contacts", name)),
+            Err(e) => {
+                tracing::error!("Failed to save the contact of {message_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to save the contact: {}", e.message));
+            }
+        }
+    }
+
+    /// Press a callback button of the inline keyboard of a message of the
+    /// open chat, sending its data to the bot.
+    /// The answer of the bot, if any, is shown as an info message, and its
+    /// URL is opened with the default browser of the system.
+    ///
+    /// # Arguments
+    /// * `message_id` - The id of the message.
+    /// * `data` - The data of the button.
+    pub async fn press_inline_button(&self, message_id: i64, data: String) {
+        let tg_context = self.app_context.tg_context();
+        let chat_id = tg_context.open_chat_id();
+        let payload = CallbackQueryPayload::Data(CallbackQueryPayloadData { data });
+        match functions::get_callback_query_answer(chat_id, message_id, payload, self.client_id)
+            .await
+        {
+            Ok(enums::CallbackQueryAnswer::CallbackQueryAnswer(answer)) => {
+                if !answer.text.is_empty() {
+                    tg_context.set_info_message(answer.text);
+                }
+                if !answer.url.is_empty() {
+                    if let Err(e) = open::that_detached(&answer.url) {
+                        tracing::error!("Failed to open the URL {}: {}", answer.url, e);
+                    }
+                }
+            }
+            Err(e) => {
+                tracing::error!("Failed to press the button of {message_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to press the button: {}", e.message));
+            }
+        }
+    }
+
+    /// Open the media of a message of the open chat, downloading it first if
+    /// needed.
+    /// The voice notes and the audio files are played with the command set
+    /// in `voice_note_player`, like `play_voice_note` does; the photos, the
+    /// videos and the documents are opened with the default application of
+    /// the system.
+    /// The media are downloaded without waiting for the download, so that
+    /// several files can be downloaded at the same time, and they are played
+    /// or opened once downloaded.
+    ///
+    /// # Arguments
+    /// * `message_id` - The id of the message.
+    pub async fn open_media(&mut self, message_id: i64) {
+        let tg_context = self.app_context.tg_context();
+        let chat_id = tg_context.open_chat_id();
+        let content = match functions::get_message(chat_id, message_id, self.client_id).await {
+            Ok(enums::Message::Message(message)) => message.content,
+            Err(e) => {
+                tracing::error!("Failed to get the message {message_id} of {chat_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to get the message: {}", e.message));
+                return;
+            }
+        };
+        let content_type = MessageContentType::from(&content);
+        let (file, file_name) = match content {
+            // The biggest size of the photo is opened.
+            MessageContent::MessagePhoto(content) => (
+                content
+                    .photo
+                    .sizes
+                    .into_iter()
+                    .max_by_key(|size| size.width * size.height)
+                    .map(|size| size.photo),
+                String::new(),
+            ),
+            MessageContent::MessageVideo(content) => {
+                (Some(content.video.video), content.video.file_name)
+            }
+            MessageContent::MessageAnimation(content) => (
+                Some(content.animation.animation),
+                content.animation.file_name,
+            ),
+            MessageContent::MessageVideoNote(content) => {
+                (Some(content.video_note.video), String::new())
+            }
+            MessageContent::MessageDocument(content) => {
+                (Some(content.document.document), content.document.file_name)
+            }
+            MessageContent::MessageAudio(content) => {
+                (Some(content.audio.audio), content.audio.file_name)
+            }
+            MessageContent::MessageVoiceNote(content) => {
+                (Some(content.voice_note.voice), String::new())
+            }
+            _ => (None, String::new()),
+        };
+        let Some(file) = file else {
+            tg_context.set_error_message("The selected message has no media to open");
+            return;
+        };
+        let name = if file_name.is_empty() {
+            content_type.name().to_string()
+        } else {
+            file_name
+        };
+        if content_type.is_audio() && self.stop_playing() == message_id {
+            return;
+        }
+        let client_id = self.client_id;
+        let action_tx = self.app_context.action_tx().clone();
+        tokio::spawn(async move {
+            let Some(path) = Self::download_media(&tg_context, client_id, file, &name).await else {
+                return;
+            };
+            // The audio is played by the backend, which keeps the player to
+            // stop it.
+            if content_type.is_audio() {
+                let _ = action_tx.send(Action::PlayAudio(message_id, path));
+                return;
+            }
+            match open::that_detached(&path) {
+                Ok(()) => tg_context.set_info_message(format!("Opened {}", name)),
+                Err(e) => {
+                    tracing::error!("Failed to open {path}: {e}");
+                    tg_context.set_error_message(format!("Failed to open {}: {}", name, e));
+                }
+            }
+        });
+    }
+
+    /// Download a file of a media, unless it has already been downloaded.
+    /// The file is added to the downloads, and the errors are shown to the
+    /// user, unless the download has been canceled.
+    ///
+    /// # Arguments
+    /// * `tg_context` - The Telegram context.
+    /// * `client_id` - The id of the TDLib client.
+    /// * `file` - The file to download.
+    /// * `name` - The name of the file, used in the downloads and in the
+    ///   messages to the user.
+    ///
+    /// # Returns
+    /// * `Option<String>` - The local path of the file, `None` if it could
+    ///   not be downloaded.
+    async fn download_media(
+        tg_context: &TgContext,
+        client_id: i32,
+        file: File,
+        name: &str,
+    ) -> Option<String> {
+        if file.local.is_downloading_completed {
+            return Some(file.local.path);
+        }
+        tg_context.add_download(name, TdFile::from(&file));
+        tg_context.set_info_message(format!("Downloading {}...", name));
+        match functions::download_file(file.id, 1, 0, 0, true, client_id).await {
+            Ok(enums::File::File(file)) if file.local.is_downloading_completed => {
+                tg_context.update_download(TdFile::from(&file));
+                Some(file.local.path)
+            }
+            _ if !tg_context.is_download(file.id) => None,
+            Ok(_) => {
+                tg_context.set_error_message(format!("Failed to download {}", name));
+                None
+            }
+            Err(e) => {
+                tracing::error!("Failed to download {name} {}: {e:?}", file.id);
+                tg_context.set_error_message(format!("Failed to download {}: {}", name, e.message));
+                None
+            }
+        }
+    }
+
+    /// Cancel the download of a file and remove it from the downloads.
+    ///
+    /// # Arguments
+    /// * `file_id` - The id of the file.
+    pub async fn cancel_download(&self, file_id: i32) {
+        let tg_context = self.app_context.tg_context();
+        // The file is removed first, so that the failure of the download is
+        // not reported.
+        tg_context.remove_download(file_id);
+        match functions::cancel_download_file(file_id, false, self.client_id).await {
+            Ok(()) => tg_context.set_info_message("Download canceled"),
+            Err(e) => {
+                tracing::error!("Failed to cancel the download of {file_id}: {e:?}");
+                tg_context
+                    .set_error_message(format!("Failed to cancel the download: {}", e.message));
+            }
+        }
+    }
+
+    /// Load the active sessions of the account, shown by the panel of the
+    /// sessions. The current session is the first one.
+    pub async fn load_sessions(&self) {
+        match functions::get_active_sessions(self.client_id).await {
+            Ok(tdlib_rs::enums::Sessions::Sessions(sessions)) => {
+                let mut sessions = sessions.sessions;
+                sessions.sort_by_key(|session| (!session.is_current, -session.last_active_date));
+                *self.app_context.tg_context().sessions() = sessions;
+            }
+            Err(e) => {
+                tracing::error!("Failed to get the active sessions: {e:?}");
+                self.app_context
+                    .tg_context()
+                    .set_error_message(format!("Failed to get the sessions: {}", e.message));
+            }
+        }
+    }
+
+    /// Terminate another session of the account and load the active sessions
+    /// again.
+    ///
+    /// # Arguments
+    /// * `session_id` - The id of the session.
+    pub async fn terminate_session(&self, session_id: i64) {
+        match functions::terminate_session(session_id, self.client_id).await {
+            Ok(()) => self
+                .app_context
+                .tg_context()
+                .set_info_message("Session terminated"),
+            Err(e) => {
+                tracing::error!("Failed to terminate the session {session_id}: {e:?}");
+                self.app_context
+                    .tg_context()
+                    .set_error_message(format!("Failed to terminate the session: {}", e.message));
+            }
+        }
+        self.load_sessions().await;
+    }
+
+    /// Stop the voice note being played, if any.
+    ///
+    /// # Returns
+    /// * `i64` - The id of the message of the stopped voice note, `0` if
+    ///   nothing was played.
+    fn stop_playing(&mut self) -> i64 {
+        let tg_context = self.app_context.tg_context();
+        let playing = tg_context.playing_voice_note();
+        if let Some(stop_tx) = self.stop_voice_note.take() {
+            let _ = stop_tx.send(());
+            tg_context.set_playing_voice_note(0);
+        }
+        playing
+    }
+
+    /// Play an audio file of a message with the command set in
+    /// `voice_note_player`, until it ends or it is stopped by
+    /// `stop_playing`.
+    /// The audio being played, if any, is stopped first, e.g. if another one
+    /// was played while the file was downloaded.
+    ///
+    /// # Arguments
+    /// * `message_id` - The id of the message.
+    /// * `path` - The local path of the audio file.
+    pub fn play_audio(&mut self, message_id: i64, path: &str) {
+        self.stop_playing();
+        let tg_context = self.app_context.tg_context();
+        let player = self.app_context.app_config().voice_note_player.clone();
+        let Some(mut command) = Self::player_command(&player, path) else {
+            tg_context
+                .set_error_message("Set `voice_note_player` in app.toml to play the voice notes");
+            return;
+        };
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                tracing::error!("Failed to run the voice note player `{player}`: {e}");
+                tg_context.set_error_message(match e.kind() {
+                    std::io::ErrorKind::NotFound => format!(
+                        "The voice note player `{}` was not found",
+                        player.split_whitespace().next().unwrap_or_default()
+                    ),
+                    _ => format!("Failed to run the voice note player: {}", e),
+                });
+                return;
+            }
+        };
+        tg_context.set_playing_voice_note(message_id);
+        let (stop_tx, stop_rx) = oneshot::channel();
+        self.stop_voice_note = Some(stop_tx);
+        tokio::spawn(async move {
+            let stopped = tokio::select! {
+                _ = child.wait() => false,
+                _ = stop_rx => true,
+            };
+            if stopped {
+                if let Err(e) = child.kill().await {
+                    tracing::warn!("Failed to stop the voice note player: {e}");
+                }
+            }
+            if tg_context.playing_voice_note() == message_id {
+                tg_context.set_playing_voice_note(0);
+            }
+        });
+    }
+
+    /// Build the command that plays an audio file.
+    /// The output of the player is discarded, so that it does not mess up
+    /// the terminal, and the player is stopped when the application exits.
+    ///
+    /// # Arguments
+    /// * `player` - The player and its arguments, separated by whitespace.
+    /// * `path` - The path of the audio file, appended to the arguments.
+    ///
+    /// # Returns
+    /// * `Option<Command>` - The command, `None` if `player` is empty.
+    fn player_command(player: &str, path: &str) -> Option<Command> {
+        let mut parts = player.split_whitespace();
+        let mut command = Command::new(parts.next()?);
+        command
+            .args(parts)
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true);
+        Some(command)
+    }
+
+    /// Load the stickers shown by the sticker picker: the favorite and the
+    /// recent stickers first, then the installed sticker sets.
+    /// Nothing is requested if they have already been loaded, they are
+    /// loaded again when they change.
+    pub async fn load_sticker_sets(&self) {
+        let tg_context = self.app_context.tg_context();
+        if !tg_context.sticker_sets().is_empty() {
+            return;
+        }
+        let mut sticker_sets = vec![];
+        match functions::get_favorite_stickers(self.client_id).await {
+            Ok(enums::Stickers::Stickers(stickers)) if !stickers.stickers.is_empty() => {
+                sticker_sets.push(StickerSetEntry {
+                    title: "Favorites".to_string(),
+                    stickers: stickers.stickers,
+                });
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to get the favorite stickers: {e:?}"),
+        }
+        match functions::get_recent_stickers(false, self.client_id).await {
+            Ok(enums::Stickers::Stickers(stickers)) if !stickers.stickers.is_empty() => {
+                sticker_sets.push(StickerSetEntry {
+                    title: "Recent".to_string(),
+                    stickers: stickers.stickers,
+                });
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to get the recent stickers: {e:?}"),
+        }
+        let installed =
+            match functions::get_installed_sticker_sets(StickerType::Regular, self.client_id).await
+            {
+                Ok(enums::StickerSets::StickerSets(sets)) => sets.sets,
+                Err(e) => {
+                    tracing::error!("Failed to get the installed sticker sets: {e:?}");
+                    vec![]
+                }
+            };
+        for set_info in installed {
+            match functions::get_sticker_set(set_info.id, self.client_id).await {
+                Ok(enums::StickerSet::StickerSet(set)) => sticker_sets.push(StickerSetEntry {
+                    title: set.title,
+                    stickers: set.stickers,
+                }),
+                Err(e) => tracing::warn!("Failed to get the sticker set {}: {e:?}", set_info.id),
+            }
+        }
+        if sticker_sets.is_empty() {
+            tg_context.set_info_message("There are no stickers to send");
+        }
+        *tg_context.sticker_sets() = sticker_sets;
+    }
+
+    /// Send a sticker loaded by `load_sticker_sets`.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The chat to send the sticker to.
+    /// * `sticker_id` - The id of the sticker.
+    pub async fn send_sticker(&self, chat_id: i64, sticker_id: i64) {
+        let tg_context = self.app_context.tg_context();
+        let Some(sticker) = tg_context.sticker(sticker_id) else {
+            tracing::warn!("The sticker {sticker_id} has not been loaded");
+            return;
+        };
+        let content = InputMessageContent::InputMessageSticker(InputMessageSticker {
+            sticker: InputFile::Remote(InputFileRemote {
+                id: sticker.sticker.remote.id,
+            }),
+            thumbnail: None,
+            width: sticker.width,
+            height: sticker.height,
+            emoji: sticker.emoji,
+        });
+        match functions::send_message(chat_id, 0, None, None, content, self.client_id).await {
+            Ok(_) => tracing::info!("Sticker sent"),
+            Err(e) => {
+                tracing::error!("Failed to send the sticker {sticker_id}: {e:?}");
+                tg_context.set_error_message(format!("Failed to send the sticker: {}", e.message));
             }
         }
     }
@@ -236,6 +2039,38 @@ impl TgBackend {
         }
     }
 
+    /// Forward messages to a chat.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The chat to forward the messages to.
+    /// * `from_chat_id` - The chat the messages belong to.
+    /// * `message_ids` - The messages to forward.
+    pub async fn forward_messages(
+        &self,
+        chat_id: i64,
+        from_chat_id: i64,
+        mut message_ids: Vec<i64>,
+    ) {
+        // The message identifiers must be in a strictly increasing order.
+        message_ids.sort_unstable();
+        message_ids.dedup();
+        match functions::forward_messages(
+            chat_id,
+            0,
+            from_chat_id,
+            message_ids,
+            None,
+            false,
+            false,
+            self.client_id,
+        )
+        .await
+        {
+            Ok(_) => tracing::info!("Messages forwarded"),
+            Err(e) => tracing::error!("Failed to forward messages: {e:?}"),
+        }
+    }
+
     pub async fn use_quick_ack(&self) {
         match functions::set_option(
             String::from("use_quick_ack"),
@@ -264,13 +2099,19 @@ impl TgBackend {
 
     pub async fn online(&mut self) {
         match self.set_online(true).await {
-            Ok(_) => tracing::info!("Went online"),
+            Ok(_) => {
+                tracing::info!("Went online");
+                self.app_context.tg_context().set_is_online(true);
+            }
             Err(error) => tracing::error!("Error going online: {error:?}"),
         }
     }
     pub async fn offline(&mut self) {
         match self.set_online(false).await {
-            Ok(_) => tracing::info!("Went offline"),
+            Ok(_) => {
+                tracing::info!("Went offline");
+                self.app_context.tg_context().set_is_online(false);
+            }
             Err(error) => tracing::error!("Error going offline: {error:?}"),
         }
     }
@@ -301,190 +2142,434 @@ impl TgBackend {
         }
     }
 
-    #[allow(clippy::await_holding_lock)]
-    pub async fn handle_authorization_state(&mut self) {
+    /// Delete the local data of the session, i.e. the TDLib database in the
+    /// `database_dir`.
+    /// It must be called after the client is closed, so that a new session
+    /// starts clean.
+    pub fn clear_database_dir(&self) {
+        let database_dir = self.app_context.telegram_config().database_dir.clone();
+        match Self::clear_tdlib_database(std::path::Path::new(&database_dir)) {
+            Ok(()) => tracing::info!("Cleared the database dir {}", database_dir),
+            Err(error) => {
+                tracing::error!("Error clearing the database dir {}: {error}", database_dir)
+            }
+        }
+    }
+
+    /// Delete the files and the subdirectories of a TDLib database.
+    /// Only the known ones are deleted, so that the other files of the
+    /// directory are kept if it is shared, e.g. by a wrong `database_dir`.
+    ///
+    /// # Arguments
+    /// * `dir` - The directory of the database.
+    ///
+    /// # Returns
+    /// * `Result<(), String>` - An error if the directory does not look like
+    ///   a TDLib database or if some files can not be deleted.
+    fn clear_tdlib_database(dir: &std::path::Path) -> Result<(), String> {
+        // The binlog is created by TDLib as soon as the database is opened.
+        if !["td.binlog", "td_test.binlog"]
+            .iter()
+            .any(|file| dir.join(file).is_file())
+        {
+            return Err("it does not contain a TDLib database".to_string());
+        }
+        let mut errors = Vec::new();
+        for file in TDLIB_DATABASE_FILES {
+            let path = dir.join(file);
+            if path.is_file() {
+                if let Err(error) = std::fs::remove_file(&path) {
+                    errors.push(format!("{}: {error}", path.display()));
+                }
+            }
+        }
+        for subdir in TDLIB_DATABASE_DIRS {
+            let path = dir.join(subdir);
+            if path.is_dir() {
+                if let Err(error) = std::fs::remove_dir_all(&path) {
+                    errors.push(format!("{}: {error}", path.display()));
+                }
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join(", "))
+        }
+    }
+
+    /// Create a new TDLib client to start a new session, after the previous
+    /// client has been closed.
+    pub async fn restart(&mut self) {
+        // Wait for the previous client to stop handling updates, TDLib does
+        // not allow to receive the updates concurrently.
+        self.update_tx = None;
+        if let Err(error) = (&mut self.handle_updates).await {
+            tracing::error!("Error handling updates: {error:?}");
+        }
+        self.client_id = tdlib_rs::create_client();
+        self.have_authorization = false;
+        self.can_quit.store(false, Ordering::Release);
+        self.full_chats_list = false;
+        self.opened_chat_id = None;
+        tracing::info!("Created TDLib client with client_id: {}", self.client_id);
+        self.start();
+        self.set_logging().await;
+    }
+
+    /// Handle the authorization states until an input of the user is needed
+    /// or the authorization process ends.
+    ///
+    /// # Returns
+    /// * `Option<AuthorizationState>` - The authorization state that needs an
+    ///   input of the user, or `None` if the client is ready or closed.
+    pub async fn handle_authorization_state(&mut self) -> Option<AuthorizationState> {
         tracing::info!("Handling authorization state");
-        let telegram_config = self.app_context.telegram_config();
-        let api_id: i32 = {
-            if !self
-                .app_context
-                .app_config()
-                .take_api_id_from_telegram_config
-            {
-                // `env!("API_ID").parse().unwrap()` generates a compile time error
-                if let Ok(api_id) = std::env::var("API_ID") {
-                    api_id.parse().unwrap()
+        while let Some(state) = self.auth_rx.recv().await {
+            let done = matches!(
+                state,
+                AuthorizationState::Ready | AuthorizationState::Closed
+            );
+            if let Some(state) = self.process_authorization_state(state).await {
+                return Some(state);
+            }
+            // TDLib keeps waiting for the parameters when the credentials are
+            // not valid, so the user is asked to set them.
+            if self.setup_error.is_some() {
+                return Some(AuthorizationState::WaitTdlibParameters);
+            }
+            if done {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Process an authorization state.
+    /// The states that do not need an input of the user are handled directly.
+    ///
+    /// # Arguments
+    /// * `state` - The authorization state to process.
+    ///
+    /// # Returns
+    /// * `Option<AuthorizationState>` - The state if it needs an input of the
+    ///   user, see `submit_authorization`.
+    pub async fn process_authorization_state(
+        &mut self,
+        state: AuthorizationState,
+    ) -> Option<AuthorizationState> {
+        match state {
+            AuthorizationState::WaitTdlibParameters => {
+                self.apply_proxy().await;
+                self.set_tdlib_parameters().await;
+                None
+            }
+            AuthorizationState::WaitPhoneNumber
+            | AuthorizationState::WaitOtherDeviceConfirmation(_)
+            | AuthorizationState::WaitEmailAddress(_)
+            | AuthorizationState::WaitEmailCode(_)
+            | AuthorizationState::WaitCode(_)
+            | AuthorizationState::WaitRegistration(_)
+            | AuthorizationState::WaitPassword(_) => Some(state),
+            AuthorizationState::Ready => {
+                // Maybe block all until this state is reached
+                self.have_authorization = true;
+                None
+            }
+            AuthorizationState::LoggingOut => {
+                self.have_authorization = false;
+                tracing::info!("Logging out");
+                None
+            }
+            AuthorizationState::Closing => {
+                self.have_authorization = false;
+                tracing::info!("Closing");
+                None
+            }
+            AuthorizationState::Closed => {
+                tracing::info!("Closed");
+                self.can_quit.store(true, Ordering::Release);
+                None
+            }
+        }
+    }
+
+    /// Submit the input of the user for an authorization state.
+    ///
+    /// # Arguments
+    /// * `state` - The authorization state that requested the input.
+    /// * `inputs` - The values entered by the user, one for each input field.
+    ///
+    /// # Returns
+    /// * `Result<(), tdlib_rs::types::Error>` - An Ok result or the error
+    ///   returned by Telegram, e.g. for a wrong code.
+    pub async fn submit_authorization(
+        &self,
+        state: &AuthorizationState,
+        inputs: Vec<String>,
+    ) -> Result<(), tdlib_rs::types::Error> {
+        let mut inputs = inputs.into_iter();
+        let mut input = || inputs.next().unwrap_or_default();
+        match state {
+            AuthorizationState::WaitPhoneNumber => {
+                let phone_number = input();
+                if phone_number.is_empty() {
+                    functions::request_qr_code_authentication(vec![], self.client_id).await
                 } else {
-                    tracing::error!("API_ID not found in environment");
-                    "-1".parse().unwrap() // This will throw the tdlib-rs error message
+                    functions::set_authentication_phone_number(phone_number, None, self.client_id)
+                        .await
+                }
+            }
+            AuthorizationState::WaitEmailAddress(_x) => {
+                functions::set_authentication_email_address(input(), self.client_id).await
+            }
+            AuthorizationState::WaitEmailCode(_x) => functions::check_authentication_email_code(
+                enums::EmailAddressAuthentication::Code(
+                    tdlib_rs::types::EmailAddressAuthenticationCode { code: input() },
+                ),
+                self.client_id,
+            )
+            .await
+            .map(|_| ()),
+            AuthorizationState::WaitCode(_x) => {
+                // x contains info about verification code
+                functions::check_authentication_code(input(), self.client_id).await
+            }
+            AuthorizationState::WaitRegistration(_x) => {
+                // x useless but contains the TOS if we want to show it
+                let first_name = input();
+                let last_name = input();
+                functions::register_user(first_name, last_name, false, self.client_id).await
+            }
+            AuthorizationState::WaitPassword(_x) => {
+                functions::check_authentication_password(input(), self.client_id).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the proxy of the telegram configuration.
+    /// TDLib keeps the proxies in its database, so the proxy is added only
+    /// if it is not there yet, then it is enabled. The proxies are disabled
+    /// if no proxy is configured.
+    /// The proxy is pinged in the background, to report clearly when it is
+    /// not reachable.
+    async fn apply_proxy(&self) {
+        let tg_context = self.app_context.tg_context();
+        let (proxy, proxy_type) = {
+            let telegram_config = self.app_context.telegram_config();
+            (
+                telegram_config.proxy(),
+                telegram_config.proxy_type.to_lowercase(),
+            )
+        };
+        let (server, port, r#type) = match proxy {
+            Ok(Some(proxy)) => proxy,
+            Ok(None) => {
+                if let Err(e) = functions::disable_proxy(self.client_id).await {
+                    tracing::error!("Failed to disable the proxy: {e:?}");
                 }
-            } else {
-                telegram_config.api_id.parse().unwrap()
+                *tg_context.proxy() = None;
+                return;
+            }
+            Err(e) => {
+                tracing::error!("{e}");
+                tg_context.set_error_message(e.to_string());
+                return;
+            }
+        };
+        let description = format!("{} {}:{}", proxy_type, server, port);
+
+        let existing = match functions::get_proxies(self.client_id).await {
+            Ok(enums::Proxies::Proxies(proxies)) => proxies.proxies.into_iter().find(|proxy| {
+                proxy.server == server && proxy.port == port && proxy.r#type == r#type
+            }),
+            Err(e) => {
+                tracing::error!("Failed to get the proxies: {e:?}");
+                None
             }
         };
-        let api_hash: String = {
-            if !self
-                .app_context
-                .app_config()
-                .take_api_hash_from_telegram_config
-            {
-                // `env!("API_HASH").into()` generates a compile time error
-                if let Ok(api_hash) = std::env::var("API_HASH") {
-                    api_hash
-                } else {
-                    tracing::error!("API_HASH not found in environment");
-                    "".into() // This will throw the tdlib-rs error message
-                }
-            } else {
-                telegram_config.api_hash.clone()
+        let proxy_id = match existing {
+            Some(proxy) if proxy.is_enabled => Ok(proxy.id),
+            Some(proxy) => functions::enable_proxy(proxy.id, self.client_id)
+                .await
+                .map(|()| proxy.id),
+            None => functions::add_proxy(server, port, true, r#type, self.client_id)
+                .await
+                .map(|enums::Proxy::Proxy(proxy)| proxy.id),
+        };
+        let proxy_id = match proxy_id {
+            Ok(proxy_id) => proxy_id,
+            Err(e) => {
+                tracing::error!("Failed to enable the proxy {description}: {e:?}");
+                tg_context.set_error_message(format!(
+                    "Failed to enable the proxy {description}: {}",
+                    e.message
+                ));
+                return;
+            }
+        };
+        tracing::info!("Using the proxy {description}");
+        *tg_context.proxy() = Some(description.clone());
+
+        let app_context = Arc::clone(&self.app_context);
+        let client_id = self.client_id;
+        tokio::spawn(async move {
+            if let Err(e) = functions::ping_proxy(proxy_id, client_id).await {
+                tracing::error!("The proxy {description} is not reachable: {e:?}");
+                app_context.tg_context().set_error_message(format!(
+                    "The proxy {description} is not reachable: {}",
+                    e.message
+                ));
+            }
+        });
+    }
+
+    /// Set the parameters of TDLib, taken from the telegram configuration.
+    #[allow(clippy::await_holding_lock)]
+    async fn set_tdlib_parameters(&mut self) {
+        // The configuration is cloned, so that its lock is not held while
+        // waiting for TDLib.
+        let telegram_config = self.app_context.telegram_config().clone();
+        let from_config = {
+            let app_config = self.app_context.app_config();
+            (
+                app_config.take_api_id_from_telegram_config,
+                app_config.take_api_hash_from_telegram_config,
+            )
+        };
+        // `env!("API_ID")` generates a compile time error, so the credentials
+        // are read at runtime.
+        let api_id = if from_config.0 {
+            Some(telegram_config.api_id.clone())
+        } else {
+            std::env::var("API_ID").ok()
+        };
+        let api_hash = if from_config.1 {
+            Some(telegram_config.api_hash.clone())
+        } else {
+            std::env::var("API_HASH").ok()
+        };
+        let (api_id, api_hash) = match Self::parse_api_credentials(
+            api_id.as_deref(),
+            api_hash.as_deref(),
+            from_config,
+        ) {
+            Ok(credentials) => credentials,
+            Err(message) => {
+                tracing::error!("{}", message.join("\n"));
+                self.setup_error = Some(message);
+                return;
             }
         };
-        let database_dir = telegram_config.database_dir.clone();
-        let use_file_database = telegram_config.use_file_database;
-        let use_chat_info_database = telegram_config.use_chat_info_database;
-        let use_message_database = telegram_config.use_message_database;
-        let system_language_code = telegram_config.system_language_code.clone();
-        let device_model = telegram_config.device_model.clone();
-
-        while let Some(state) = self.auth_rx.recv().await {
-            match state {
-                AuthorizationState::WaitTdlibParameters => {
-                    let response = functions::set_tdlib_parameters(
-                        false,
-                        database_dir.clone(),
-                        String::new(),
-                        String::new(),
-                        use_file_database,
-                        use_chat_info_database,
-                        use_message_database,
-                        false,
-                        api_id,
-                        api_hash.clone(),
-                        system_language_code.clone(),
-                        device_model.clone(),
-                        String::new(),
-                        env!("CARGO_PKG_VERSION").into(),
-                        self.client_id,
-                    )
-                    .await;
+        let response = functions::set_tdlib_parameters(
+            false,
+            telegram_config.database_dir.clone(),
+            String::new(),
+            String::new(),
+            telegram_config.use_file_database,
+            telegram_config.use_chat_info_database,
+            telegram_config.use_message_database,
+            false,
+            api_id,
+            api_hash,
+            telegram_config.system_language_code.clone(),
+            telegram_config.device_model.clone(),
+            String::new(),
+            env!("CARGO_PKG_VERSION").into(),
+            self.client_id,
+        )
+        .await;
 
-                    if let Err(error) = response {
-                        println!("{}", error.message);
-                    }
-                }
-                AuthorizationState::WaitPhoneNumber => loop {
-                    let phone_number =
-                        ask_user("Enter your phone number (include the country calling code):");
-                    let response = functions::set_authentication_phone_number(
-                        phone_number,
-                        None,
-                        self.client_id,
-                    )
-                    .await;
-                    match response {
-                        Ok(_) => break,
-                        Err(e) => println!("{}", e.message),
-                    }
-                },
-                AuthorizationState::WaitOtherDeviceConfirmation(x) => {
-                    println!(
-                        "Please confirm this login link on another device: {}",
-                        x.link
-                    );
-                }
-                AuthorizationState::WaitEmailAddress(_x) => {
-                    let email_address = ask_user("Please enter email address: ");
-                    let response =
-                        functions::set_authentication_email_address(email_address, self.client_id)
-                            .await;
-                    match response {
-                        Ok(_) => break,
-                        Err(e) => println!("{}", e.message),
-                    }
-                }
-                AuthorizationState::WaitEmailCode(_x) => {
-                    let code = ask_user("Please enter email authentication code: ");
-                    let response = functions::check_authentication_email_code(
-                        enums::EmailAddressAuthentication::Code(
-                            tdlib_rs::types::EmailAddressAuthenticationCode { code },
-                        ),
-                        self.client_id,
-                    )
-                    .await;
-                    match response {
-                        Ok(_) => break,
-                        Err(e) => println!("{}", e.message),
-                    }
-                }
-                AuthorizationState::WaitCode(_x) => loop {
-                    // x contains info about verification code
-                    let code = ask_user("Enter the verification code:");
-                    let response = functions::check_authentication_code(code, self.client_id).await;
-                    match response {
-                        Ok(_) => break,
-                        Err(e) => println!("{}", e.message),
-                    }
-                },
-                AuthorizationState::WaitRegistration(_x) => {
-                    // x useless but contains the TOS if we want to show it
-                    let first_name = ask_user("Please enter your first name: ");
-                    let last_name = ask_user("Please enter your last name: ");
-                    functions::register_user(first_name, last_name, false, self.client_id)
-                        .await
-                        .unwrap();
-                }
-                AuthorizationState::WaitPassword(_x) => {
-                    let password = ask_user("Please enter password: ");
-                    functions::check_authentication_password(password, self.client_id)
-                        .await
-                        .unwrap();
-                }
-                AuthorizationState::Ready => {
-                    // Maybe block all until this state is reached
-                    self.have_authorization = true;
-                    break;
-                }
-                AuthorizationState::LoggingOut => {
-                    self.have_authorization = false;
-                    tracing::info!("Logging out");
-                }
-                AuthorizationState::Closing => {
-                    self.have_authorization = false;
-                    tracing::info!("Closing");
-                }
-                AuthorizationState::Closed => {
-                    tracing::info!("Closed");
-                    self.can_quit.store(true, Ordering::Release);
-                    break;
-                }
-            }
+        if let Err(error) = response {
+            tracing::error!("Failed to set the TDLib parameters: {}", error.message);
+            self.setup_error = Some(vec![
+                "Failed to set the TDLib parameters.".to_string(),
+                error.message,
+            ]);
         }
     }
 
     fn set_chat_positions(
         mut chats_index: MutexGuard<'_, BTreeSet<OrderedChat>>,
+        mut chat_folders_index: MutexGuard<'_, HashMap<i32, BTreeSet<OrderedChat>>>,
         chat: &mut Chat,
         positions: Vec<ChatPosition>,
     ) {
-        for position in &chat.positions {
-            if let enums::ChatList::Main = position.list {
-                let is_removed = chats_index.remove(&OrderedChat {
+        Self::update_chats_index(
+            &mut chats_index,
+            &enums::ChatList::Main,
+            chat.id,
+            &chat.positions,
+            &positions,
+        );
+        // The chat is moved in the index of each folder it leaves or enters.
+        let chat_folder_ids: BTreeSet<i32> = chat
+            .positions
+            .iter()
+            .chain(positions.iter())
+            .filter_map(|position| match position.list {
+                enums::ChatList::Folder(ref folder) => Some(folder.chat_folder_id),
+                _ => None,
+            })
+            .collect();
+        for chat_folder_id in chat_folder_ids {
+            Self::update_chats_index(
+                chat_folders_index.entry(chat_folder_id).or_default(),
+                &enums::ChatList::Folder(ChatListFolder { chat_folder_id }),
+                chat.id,
+                &chat.positions,
+                &positions,
+            );
+        }
+        chat.positions = positions;
+    }
+
+    /// Move a chat in the index of a chat list from its old positions to the
+    /// new ones.
+    /// A chat has at most a position in a chat list, so all its entries are
+    /// removed before inserting the new one. The updates received out of
+    /// order can remove a position that is not in the index: it is logged,
+    /// and the stale entry of the chat is removed anyway, so that the chat is
+    /// not shown twice.
+    ///
+    /// # Arguments
+    /// * `chats_index` - The index of the chat list.
+    /// * `list` - The chat list of the index, the positions in the other
+    ///   lists are ignored.
+    /// * `chat_id` - The id of the chat.
+    /// * `old_positions` - The positions of the chat to remove.
+    /// * `new_positions` - The positions of the chat to insert.
+    fn update_chats_index(
+        chats_index: &mut BTreeSet<OrderedChat>,
+        list: &enums::ChatList,
+        chat_id: i64,
+        old_positions: &[ChatPosition],
+        new_positions: &[ChatPosition],
+    ) {
+        for position in old_positions {
+            if position.list == *list {
+                let ordered_chat = OrderedChat {
                     position: position.clone(),
-                    chat_id: chat.id,
-                });
-                assert!(is_removed);
+                    chat_id,
+                };
+                if !chats_index.contains(&ordered_chat) {
+                    tracing::warn!(
+                        "The position {} of the chat {} is not in the chat list",
+                        position.order,
+                        chat_id
+                    );
+                }
             }
         }
+        chats_index.retain(|ordered_chat| ordered_chat.chat_id != chat_id);
 
-        chat.positions = positions;
-
-        for position in &chat.positions {
-            if let enums::ChatList::Main = position.list {
-                let is_inserted = chats_index.insert(OrderedChat {
+        for position in new_positions {
+            if position.list == *list {
+                chats_index.insert(OrderedChat {
                     position: position.clone(),
-                    chat_id: chat.id,
+                    chat_id,
                 });
-                assert!(is_inserted);
             }
         }
     }
@@ -524,377 +2609,859 @@ impl TgBackend {
         let auth_tx = self.auth_tx.clone();
         let can_quit = self.can_quit.clone();
         let tg_context = self.app_context.tg_context();
+        let (update_tx, update_rx) = tokio::sync::mpsc::unbounded_channel::<UpdateMessage>();
+        self.update_tx = Some(update_tx.clone());
 
         self.handle_updates = tokio::spawn(async move {
             tracing::info!("Starting handling updates from TDLib");
-            while !can_quit.load(Ordering::Acquire) {
-                let mut update_dequeue: VecDeque<Update> = VecDeque::new();
-                if let Some((update, _client_id)) = tdlib_rs::receive() {
-                    update_dequeue.push_back(update);
-                    let update = update_dequeue.pop_front().unwrap();
-                    match update.clone() {
-                        Update::MessageSendSucceeded(update_message) => {
-                            tracing::info!("Message sent: {:?}", update_message);
-                        }
-                        Update::MessageSendAcknowledged(update_message) => {
-                            tg_context.set_last_acknowledged_message_id(update_message.message_id);
-                        }
-                        Update::AuthorizationState(update) => {
-                            auth_tx.send(update.authorization_state).unwrap();
-                        }
-                        Update::User(update_user) => {
-                            tg_context
-                                .users()
-                                .insert(update_user.user.id, update_user.user);
-                        }
-                        Update::UserStatus(update_user) => {
-                            match tg_context.users().get_mut(&update_user.user_id) {
-                                Some(user) => {
-                                    user.status = update_user.status;
-                                }
-                                None => update_dequeue.push_back(update),
-                            }
+            // `tdlib_rs::receive` blocks until an update is received, so the
+            // updates are received in their own thread.
+            tokio::task::spawn_blocking(move || {
+                while !can_quit.load(Ordering::Acquire) {
+                    if let Some((update, _client_id)) = tdlib_rs::receive() {
+                        if update_tx.send(UpdateMessage::Update(update)).is_err() {
+                            break;
                         }
-                        Update::BasicGroup(update_basic_group) => {
-                            tg_context.basic_groups().insert(
-                                update_basic_group.basic_group.id,
-                                update_basic_group.basic_group,
+                    }
+                }
+            });
+            Self::handle_update_messages(update_rx, auth_tx, tg_context).await;
+        });
+    }
+
+    /// Apply the updates received from TDLib, in order, and notify the user
+    /// interface after each batch.
+    /// It returns once the client is closed, i.e. once the `Closed`
+    /// authorization state is applied, or once the updates are no longer
+    /// sent.
+    ///
+    /// # Arguments
+    /// * `update_rx` - The receiver of the updates.
+    /// * `auth_tx` - The sender of the authorization states.
+    /// * `tg_context` - The context updated by the updates.
+    async fn handle_update_messages(
+        mut update_rx: UnboundedReceiver<UpdateMessage>,
+        auth_tx: UnboundedSender<AuthorizationState>,
+        tg_context: Arc<TgContext>,
+    ) {
+        let mut update_queue = UpdateQueue::default();
+        let mut closed = false;
+        while let Some(message) = update_rx.recv().await {
+            // All the updates immediately available are applied in
+            // order, then the user interface is notified once.
+            update_queue.push_message(message);
+            while let Ok(message) = update_rx.try_recv() {
+                update_queue.push_message(message);
+            }
+            update_queue.start_batch();
+            while let Some(update) = update_queue.pop() {
+                match update.clone() {
+                    Update::MessageSendSucceeded(update_message) => {
+                        tracing::info!("Message sent: {:?}", update_message);
+                        tg_context.remove_temporary_file(update_message.old_message_id);
+                        if tg_context.open_chat_id() == update_message.message.chat_id {
+                            tg_context.replace_open_chat_message(
+                                update_message.old_message_id,
+                                MessageEntry::from(&update_message.message),
                             );
                         }
-                        Update::Supergroup(update_supergroup) => {
-                            tg_context.supergroups().insert(
-                                update_supergroup.supergroup.id,
-                                update_supergroup.supergroup,
-                            );
+                    }
+                    Update::MessageSendFailed(update_message) => {
+                        tracing::error!("Message not sent: {:?}", update_message.error);
+                        tg_context.remove_temporary_file(update_message.old_message_id);
+                    }
+                    Update::MessageSendAcknowledged(update_message) => {
+                        tg_context.set_last_acknowledged_message_id(update_message.message_id);
+                    }
+                    Update::AuthorizationState(update) => {
+                        closed |= matches!(update.authorization_state, AuthorizationState::Closed);
+                        auth_tx.send(update.authorization_state).unwrap();
+                    }
+                    Update::User(update_user) => {
+                        let user_id = update_user.user.id;
+                        tg_context.users().insert(user_id, update_user.user);
+                        update_queue.resolve(UpdateDependency::User(user_id));
+                    }
+                    Update::UserStatus(update_user) => {
+                        match tg_context.users().get_mut(&update_user.user_id) {
+                            Some(user) => {
+                                user.status = update_user.status;
+                            }
+                            None => update_queue
+                                .defer(UpdateDependency::User(update_user.user_id), update),
                         }
-                        Update::SecretChat(update_secret_chat) => {
-                            tg_context.secret_chats().insert(
-                                update_secret_chat.secret_chat.id,
-                                update_secret_chat.secret_chat,
+                    }
+                    Update::BasicGroup(update_basic_group) => {
+                        tg_context.basic_groups().insert(
+                            update_basic_group.basic_group.id,
+                            update_basic_group.basic_group,
+                        );
+                    }
+                    Update::Supergroup(update_supergroup) => {
+                        tg_context.supergroups().insert(
+                            update_supergroup.supergroup.id,
+                            update_supergroup.supergroup,
+                        );
+                    }
+                    Update::SecretChat(update_secret_chat) => {
+                        let secret_chat = update_secret_chat.secret_chat;
+                        let previous = tg_context
+                            .secret_chats()
+                            .insert(secret_chat.id, secret_chat.clone());
+                        if previous.is_none_or(|previous| previous.state != secret_chat.state) {
+                            tracing::info!(
+                                "Secret chat {} is {}",
+                                secret_chat.id,
+                                TgContext::secret_chat_state_text(&secret_chat.state)
                             );
                         }
-                        Update::NewChat(update_new_chat) => {
-                            let mut chat = update_new_chat.chat;
-                            tg_context.chats().insert(chat.id, chat.clone());
-                            let positions = chat.positions;
-                            chat.positions = Vec::new();
-                            Self::set_chat_positions(
-                                tg_context.chats_index(),
-                                &mut chat,
-                                positions,
-                            );
+                    }
+                    Update::NewChat(update_new_chat) => {
+                        let mut chat = update_new_chat.chat;
+                        tg_context.add_unread_count(chat.unread_count, tg_context.is_muted(&chat));
+                        tg_context.chats().insert(chat.id, chat.clone());
+                        let positions = chat.positions;
+                        chat.positions = Vec::new();
+                        Self::set_chat_positions(
+                            tg_context.chats_index(),
+                            tg_context.chat_folders_index(),
+                            &mut chat,
+                            positions,
+                        );
+                        update_queue.resolve(UpdateDependency::Chat(chat.id));
+                    }
+                    Update::ChatTitle(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => chat.title = update_chat.title,
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatTitle(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => chat.title = update_chat.title,
-                                None => update_dequeue.push_back(update),
-                            }
+                    }
+                    Update::ChatPhoto(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => chat.photo = update_chat.photo,
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatPhoto(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => chat.photo = update_chat.photo,
-                                None => update_dequeue.push_back(update),
-                            }
+                    }
+                    Update::ChatPermissions(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => chat.permissions = update_chat.permissions,
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatPermissions(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => chat.permissions = update_chat.permissions,
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatLastMessage(update_chat) => {
+                        // This update is received also when a message is edited
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.last_message = update_chat.last_message;
+
+                                Self::set_chat_positions(
+                                    tg_context.chats_index(),
+                                    tg_context.chat_folders_index(),
+                                    chat,
+                                    update_chat.positions,
+                                );
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatLastMessage(update_chat) => {
-                            // This update is received also when a message is edited
+                    }
+                    Update::ChatPosition(update_chat) => {
+                        // The positions in the folders are used to filter the chat list
+                        if let enums::ChatList::Main | enums::ChatList::Folder(_) =
+                            update_chat.position.list
+                        {
                             match tg_context.chats().get_mut(&update_chat.chat_id) {
                                 Some(chat) => {
-                                    chat.last_message = update_chat.last_message;
+                                    let mut i = 0;
+
+                                    for p in &chat.positions {
+                                        if p.list == update_chat.position.list {
+                                            break;
+                                        }
+                                        i += 1;
+                                    }
+                                    let mut new_position: Vec<ChatPosition> = Vec::new();
+                                    let mut pos = 0;
+                                    if update_chat.position.order != 0 {
+                                        new_position.insert(pos, update_chat.position);
+                                        pos += 1;
+                                    }
+                                    for j in 0..chat.positions.len() {
+                                        if j != i {
+                                            new_position.insert(pos, chat.positions[j].clone());
+                                            pos += 1;
+                                        }
+                                    }
+                                    assert!(pos == new_position.len());
 
                                     Self::set_chat_positions(
                                         tg_context.chats_index(),
+                                        tg_context.chat_folders_index(),
                                         chat,
-                                        update_chat.positions,
+                                        new_position,
                                     );
                                 }
-                                None => update_dequeue.push_back(update),
+                                None => update_queue
+                                    .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                             }
                         }
-                        Update::ChatPosition(update_chat) => {
-                            if let enums::ChatList::Main = update_chat.position.list {
-                                match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                    Some(chat) => {
-                                        let mut i = 0;
-
-                                        for p in &chat.positions {
-                                            if let enums::ChatList::Main = p.list {
-                                                break;
-                                            }
-                                            i += 1;
-                                        }
-                                        let mut new_position: Vec<ChatPosition> = Vec::new();
-                                        let mut pos = 0;
-                                        if update_chat.position.order != 0 {
-                                            new_position.insert(pos, update_chat.position);
-                                            pos += 1;
-                                        }
-                                        for j in 0..chat.positions.len() {
-                                            if j != i {
-                                                new_position.insert(pos, chat.positions[j].clone());
-                                                pos += 1;
-                                            }
-                                        }
-                                        assert!(pos == new_position.len());
-
-                                        Self::set_chat_positions(
-                                            tg_context.chats_index(),
-                                            chat,
-                                            new_position,
-                                        );
-                                    }
-                                    None => update_dequeue.push_back(update),
-                                }
+                    }
+                    Update::ChatReadInbox(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.last_read_inbox_message_id =
+                                    update_chat.last_read_inbox_message_id;
+                                // The unread count changes with this update both when
+                                // the messages are read and when new ones arrive
+                                tg_context.add_unread_count(
+                                    update_chat.unread_count - chat.unread_count,
+                                    tg_context.is_muted(chat),
+                                );
+                                chat.unread_count = update_chat.unread_count;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatReadInbox(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.last_read_inbox_message_id =
-                                        update_chat.last_read_inbox_message_id;
-                                    chat.unread_count = update_chat.unread_count;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatReadOutbox(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.last_read_outbox_message_id =
+                                    update_chat.last_read_outbox_message_id;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatReadOutbox(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.last_read_outbox_message_id =
-                                        update_chat.last_read_outbox_message_id;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatActionBar(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.action_bar = update_chat.action_bar;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatActionBar(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.action_bar = update_chat.action_bar;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatAvailableReactions(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.available_reactions = update_chat.available_reactions;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatAvailableReactions(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.available_reactions = update_chat.available_reactions;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatUnreadMentionCount(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.unread_mention_count = update_chat.unread_mention_count;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatUnreadMentionCount(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.unread_mention_count = update_chat.unread_mention_count;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::MessageMentionRead(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.unread_mention_count = update_chat.unread_mention_count;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::MessageMentionRead(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.unread_mention_count = update_chat.unread_mention_count;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatReplyMarkup(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.reply_markup_message_id = update_chat.reply_markup_message_id;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatReplyMarkup(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.reply_markup_message_id =
-                                        update_chat.reply_markup_message_id;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatDraftMessage(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.draft_message = update_chat.draft_message;
+                                Self::set_chat_positions(
+                                    tg_context.chats_index(),
+                                    tg_context.chat_folders_index(),
+                                    chat,
+                                    update_chat.positions,
+                                );
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatDraftMessage(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.draft_message = update_chat.draft_message;
-                                    Self::set_chat_positions(
-                                        tg_context.chats_index(),
-                                        chat,
-                                        update_chat.positions,
-                                    );
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatMessageSender(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.message_sender_id = update_chat.message_sender_id;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatMessageSender(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.message_sender_id = update_chat.message_sender_id;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatMessageAutoDeleteTime(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.message_auto_delete_time =
+                                    update_chat.message_auto_delete_time;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatMessageAutoDeleteTime(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.message_auto_delete_time =
-                                        update_chat.message_auto_delete_time;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatNotificationSettings(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                // Move the unread messages of the chat between the
+                                // muted and the unmuted ones
+                                tg_context.add_unread_count(
+                                    -chat.unread_count,
+                                    tg_context.is_muted(chat),
+                                );
+                                chat.notification_settings = update_chat.notification_settings;
+                                tg_context
+                                    .add_unread_count(chat.unread_count, tg_context.is_muted(chat));
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatNotificationSettings(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.notification_settings = update_chat.notification_settings;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ScopeNotificationSettings(update_scope) => {
+                        tg_context.set_scope_mute_for(
+                            &update_scope.scope,
+                            update_scope.notification_settings.mute_for,
+                        );
+                    }
+                    Update::ChatPendingJoinRequests(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.pending_join_requests = update_chat.pending_join_requests;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatPendingJoinRequests(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.pending_join_requests = update_chat.pending_join_requests;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatBackground(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.background = update_chat.background;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatBackground(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.background = update_chat.background;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatTheme(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.theme_name = update_chat.theme_name;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatTheme(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.theme_name = update_chat.theme_name;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatUnreadReactionCount(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.unread_reaction_count = update_chat.unread_reaction_count;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatUnreadReactionCount(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.unread_reaction_count = update_chat.unread_reaction_count;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatDefaultDisableNotification(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.default_disable_notification =
+                                    update_chat.default_disable_notification;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatDefaultDisableNotification(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.default_disable_notification =
-                                        update_chat.default_disable_notification;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatIsMarkedAsUnread(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.is_marked_as_unread = update_chat.is_marked_as_unread;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatIsMarkedAsUnread(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.is_marked_as_unread = update_chat.is_marked_as_unread;
-                                }
-                                None => update_dequeue.push_back(update),
-                            }
+                    }
+                    Update::ChatBlockList(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => chat.block_list = update_chat.block_list,
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatBlockList(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => chat.block_list = update_chat.block_list,
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::ChatHasScheduledMessages(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.has_scheduled_messages = update_chat.has_scheduled_messages;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::ChatHasScheduledMessages(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.has_scheduled_messages =
-                                        update_chat.has_scheduled_messages;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::MessageUnreadReactions(update_chat) => {
+                        match tg_context.chats().get_mut(&update_chat.chat_id) {
+                            Some(chat) => {
+                                chat.unread_mention_count = update_chat.unread_reaction_count;
                             }
+                            None => update_queue
+                                .defer(UpdateDependency::Chat(update_chat.chat_id), update),
                         }
-                        Update::MessageUnreadReactions(update_chat) => {
-                            match tg_context.chats().get_mut(&update_chat.chat_id) {
-                                Some(chat) => {
-                                    chat.unread_mention_count = update_chat.unread_reaction_count;
-                                }
-                                None => update_dequeue.push_back(update),
+                    }
+                    Update::UserFullInfo(update_user_full_info) => {
+                        tg_context.users_full_info().insert(
+                            update_user_full_info.user_id,
+                            update_user_full_info.user_full_info,
+                        );
+                    }
+                    Update::BasicGroupFullInfo(update_basic_group_full_info) => {
+                        tg_context.basic_groups_full_info().insert(
+                            update_basic_group_full_info.basic_group_id,
+                            update_basic_group_full_info.basic_group_full_info,
+                        );
+                    }
+                    Update::ConnectionState(update_connection_state) => {
+                        tracing::info!("Connection state: {:?}", update_connection_state.state);
+                        let was_ready = tg_context.is_connection_ready();
+                        *tg_context.connection_state() = Some(update_connection_state.state);
+                        if !was_ready && tg_context.is_connection_ready() {
+                            if let Some(event_tx) = tg_context.event_tx().as_ref() {
+                                event_tx.send(Event::ConnectionReady).unwrap();
                             }
                         }
-                        Update::UserFullInfo(update_user_full_info) => {
-                            tg_context.users_full_info().insert(
-                                update_user_full_info.user_id,
-                                update_user_full_info.user_full_info,
-                            );
+                    }
+                    Update::ChatFolders(update_chat_folders) => {
+                        *tg_context.chat_folders() = update_chat_folders.chat_folders;
+                    }
+                    Update::ChatOnlineMemberCount(update_online_member_count) => {
+                        tg_context.chats_online_member_count().insert(
+                            update_online_member_count.chat_id,
+                            update_online_member_count.online_member_count,
+                        );
+                    }
+                    Update::SupergroupFullInfo(update_supergroup_full_info) => {
+                        tg_context.supergroups_full_info().insert(
+                            update_supergroup_full_info.supergroup_id,
+                            update_supergroup_full_info.supergroup_full_info,
+                        );
+                    }
+                    Update::NewMessage(update_new_message) => {
+                        // New message update only the opened chat in order to have
+                        // in real time the message displayed
+                        let message = update_new_message.message;
+                        let chat_id = message.chat_id;
+                        let in_open_chat = tg_context.open_chat_id() == chat_id;
+                        if !message.is_outgoing && !tg_context.is_chat_muted(chat_id) {
+                            if let Some(event_tx) = tg_context.event_tx().as_ref() {
+                                event_tx.send(Event::Bell(in_open_chat)).unwrap();
+                            }
                         }
-                        Update::BasicGroupFullInfo(update_basic_group_full_info) => {
-                            tg_context.basic_groups_full_info().insert(
-                                update_basic_group_full_info.basic_group_id,
-                                update_basic_group_full_info.basic_group_full_info,
-                            );
+                        if in_open_chat {
+                            tg_context.insert_open_chat_message(MessageEntry::from(&message));
+                        } else if !message.is_outgoing && !tg_context.is_chat_muted(chat_id) {
+                            // Notify the messages received in the other chats
+                            let title = tg_context.name_from_chats(chat_id).unwrap_or_default();
+                            let preview =
+                                MessageEntry::from(&message).message_content_to_string_masked();
+                            if let Some(event_tx) = tg_context.event_tx().as_ref() {
+                                event_tx.send(Event::Notify(title, preview)).unwrap();
+                            }
                         }
-                        Update::SupergroupFullInfo(update_supergroup_full_info) => {
-                            tg_context.supergroups_full_info().insert(
-                                update_supergroup_full_info.supergroup_id,
-                                update_supergroup_full_info.supergroup_full_info,
-                            );
+                    }
+                    // The bots edit the inline keyboards, e.g. to show
+                    // the next page of a menu.
+                    Update::MessageEdited(update_message_edited)
+                        if tg_context.open_chat_id() == update_message_edited.chat_id =>
+                    {
+                        if let Some(message) = tg_context
+                            .open_chat_messages()
+                            .iter_mut()
+                            .find(|m| m.id() == update_message_edited.message_id)
+                        {
+                            message
+                                .set_inline_keyboard(update_message_edited.reply_markup.as_ref());
                         }
-                        Update::NewMessage(update_new_message) => {
-                            // New message update only the opened chat in order to have
-                            // in real time the message displayed
-                            let message = update_new_message.message;
-                            let chat_id = message.chat_id;
-                            if tg_context.open_chat_id() == chat_id {
-                                tg_context
-                                    .open_chat_messages()
-                                    .insert(0, MessageEntry::from(&message));
-                            }
+                    }
+                    Update::MessageEdited(_) => {}
+                    // Only the progress of the downloads started by the
+                    // user is shown.
+                    Update::File(update_file) if tg_context.is_download(update_file.file.id) => {
+                        if let Some(event_tx) = tg_context.event_tx().as_ref() {
+                            event_tx
+                                .send(Event::FileProgress(TdFile::from(&update_file.file)))
+                                .unwrap();
                         }
-                        Update::MessageEdited(_) => {}
-                        Update::MessageContent(message) => {
-                            if tg_context.open_chat_id() == message.chat_id {
-                                for m in tg_context.open_chat_messages().iter_mut() {
-                                    if m.id() == message.message_id {
-                                        m.set_message_content(&message.new_content);
-                                        m.set_is_edited(true);
-                                    }
+                    }
+                    Update::InstalledStickerSets(_)
+                    | Update::RecentStickers(_)
+                    | Update::FavoriteStickers(_) => {
+                        // They are loaded again when the sticker picker
+                        // is shown.
+                        tg_context.sticker_sets().clear();
+                    }
+                    Update::MessageIsPinned(update_pinned) => {
+                        tg_context.set_message_pinned(
+                            update_pinned.chat_id,
+                            update_pinned.message_id,
+                            update_pinned.is_pinned,
+                        );
+                    }
+                    Update::MessageContent(message) => {
+                        if tg_context.open_chat_id() == message.chat_id {
+                            for m in tg_context.open_chat_messages().iter_mut() {
+                                if m.id() == message.message_id {
+                                    m.set_message_content(&message.new_content);
+                                    m.set_is_edited(true);
                                 }
                             }
                         }
-                        Update::DeleteMessages(update_delete_messages) => {
-                            if tg_context.open_chat_id() == update_delete_messages.chat_id {
-                                let mut i = 0;
-                                while i < tg_context.open_chat_messages().len() {
-                                    if update_delete_messages
-                                        .message_ids
-                                        .contains(&tg_context.open_chat_messages()[i].id())
-                                    {
-                                        tg_context.open_chat_messages().remove(i);
-                                    } else {
-                                        i += 1;
-                                    }
+                    }
+                    Update::DeleteMessages(update_delete_messages) => {
+                        if tg_context.open_chat_id() == update_delete_messages.chat_id {
+                            let mut i = 0;
+                            while i < tg_context.open_chat_messages().len() {
+                                if update_delete_messages
+                                    .message_ids
+                                    .contains(&tg_context.open_chat_messages()[i].id())
+                                {
+                                    tg_context.open_chat_messages().remove(i);
+                                } else {
+                                    i += 1;
                                 }
                             }
                         }
-                        // Update::Option(option) => {
-                        //     tracing::info!("{:?}", option);
-                        // }
-                        // _ => eprintln!("[HANDLE UPDATE]: {update:?}"),
-                        _ => {
-                            // tracing::info!("Unhandled update: {:?}", update);
-                        }
+                    }
+                    // Update::Option(option) => {
+                    //     tracing::info!("{:?}", option);
+                    // }
+                    // _ => eprintln!("[HANDLE UPDATE]: {update:?}"),
+                    _ => {
+                        // tracing::info!("Unhandled update: {:?}", update);
                     }
                 }
             }
-        });
+            update_queue.notify_flushes();
+            if let Some(event_tx) = tg_context.event_tx().as_ref() {
+                event_tx.send(Event::DataChanged).unwrap();
+            }
+            // The sender of the updates is kept by the `TgBackend`, so
+            // the channel is not closed with the client.
+            if closed {
+                break;
+            }
+        }
+    }
+
+    /// Wait until the updates received so far are applied, e.g. the chats
+    /// that TDLib sends before answering to `load_chats`.
+    pub async fn wait_for_updates(&self) {
+        let Some(update_tx) = self.update_tx.as_ref() else {
+            return;
+        };
+        let (done_tx, done_rx) = oneshot::channel();
+        if update_tx.send(UpdateMessage::Flush(done_tx)).is_ok() {
+            let _ = done_rx.await;
+        }
     }
 }
 
-fn ask_user(string: &str) -> String {
-    println!("{}", string);
-    let mut input = String::new();
-    std::io::stdin().read_line(&mut input).unwrap();
-    input.trim().to_string()
+#[cfg(test)]
+mod tests {
+    use crate::tg::{
+        ordered_chat::OrderedChat,
+        tg_backend::{TgBackend, UpdateDependency, UpdateMessage, UpdateQueue, MAX_UPDATE_RETRIES},
+        tg_context::TgContext,
+    };
+    use std::collections::BTreeSet;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tdlib_rs::enums::{AuthorizationState, ChatList, TextEntityType, Update};
+    use tdlib_rs::types::{
+        ChatListFolder, ChatPosition, Error, TextEntity, UpdateAuthorizationState, UpdateChatTitle,
+    };
+
+    fn chat_title(chat_id: i64, title: &str) -> Update {
+        Update::ChatTitle(UpdateChatTitle {
+            chat_id,
+            title: title.to_string(),
+        })
+    }
+
+    fn title(update: &Update) -> (i64, &str) {
+        match update {
+            Update::ChatTitle(update) => (update.chat_id, update.title.as_str()),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_update_queue_resolve() {
+        let mut update_queue = UpdateQueue::default();
+        update_queue.push(chat_title(1, "old"));
+        update_queue.start_batch();
+        let update = update_queue.pop().unwrap();
+        update_queue.defer(UpdateDependency::Chat(1), update);
+        assert!(update_queue.pop().is_none());
+
+        // The newer update of the chat waits too, and both are applied in
+        // order as soon as the chat is inserted, before the following
+        // updates of the batch.
+        update_queue.push(chat_title(1, "new"));
+        update_queue.push(chat_title(2, "other"));
+        update_queue.start_batch();
+        let update = update_queue.pop().unwrap();
+        update_queue.defer(UpdateDependency::Chat(1), update);
+        update_queue.resolve(UpdateDependency::Chat(1));
+        update_queue.resolve(UpdateDependency::User(1));
+        let titles = std::iter::from_fn(|| update_queue.pop())
+            .map(|update| title(&update).1.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(titles, vec!["old", "new", "other"]);
+        assert!(update_queue.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_handle_update_messages_until_closed() {
+        let (update_tx, update_rx) = tokio::sync::mpsc::unbounded_channel();
+        let (auth_tx, mut auth_rx) = tokio::sync::mpsc::unbounded_channel();
+        let handle_updates = tokio::spawn(TgBackend::handle_update_messages(
+            update_rx,
+            auth_tx,
+            Arc::new(TgContext::default()),
+        ));
+        let authorization_state = |authorization_state| {
+            UpdateMessage::Update(Update::AuthorizationState(UpdateAuthorizationState {
+                authorization_state,
+            }))
+        };
+        update_tx
+            .send(UpdateMessage::Update(chat_title(1, "title")))
+            .unwrap();
+        update_tx
+            .send(authorization_state(AuthorizationState::Closing))
+            .unwrap();
+        update_tx
+            .send(authorization_state(AuthorizationState::Closed))
+            .unwrap();
+        // The updates stop being handled once the client is closed, even if
+        // the sender of the updates is still alive, as the one of the
+        // `TgBackend`.
+        tokio::time::timeout(Duration::from_secs(1), handle_updates)
+            .await
+            .expect("the updates are still handled after the client is closed")
+            .unwrap();
+        assert_eq!(auth_rx.recv().await, Some(AuthorizationState::Closing));
+        assert_eq!(auth_rx.recv().await, Some(AuthorizationState::Closed));
+        drop(update_tx);
+    }
+
+    #[test]
+    fn test_update_queue_drop() {
+        let mut update_queue = UpdateQueue::default();
+        update_queue.push(chat_title(1, "title"));
+        update_queue.start_batch();
+        let update = update_queue.pop().unwrap();
+        assert_eq!(title(&update), (1, "title"));
+        update_queue.defer(UpdateDependency::Chat(1), update);
+        for _ in 0..MAX_UPDATE_RETRIES {
+            update_queue.start_batch();
+        }
+        assert_eq!(update_queue.pending.len(), 1);
+        // An update whose chat never arrives is dropped instead of waiting
+        // forever.
+        update_queue.start_batch();
+        assert!(update_queue.pending.is_empty());
+    }
+
+    #[test]
+    fn test_parse_api_credentials() {
+        assert_eq!(
+            TgBackend::parse_api_credentials(Some("94575"), Some(" hash "), (false, false)),
+            Ok((94575, "hash".to_string()))
+        );
+        let message =
+            TgBackend::parse_api_credentials(None, Some("hash"), (false, false)).unwrap_err();
+        assert_eq!(message[1], "- the API_ID environment variable is not set");
+        assert!(message
+            .iter()
+            .any(|line| line.contains("https://my.telegram.org/apps")));
+        let message =
+            TgBackend::parse_api_credentials(Some("abc"), Some(""), (true, true)).unwrap_err();
+        assert_eq!(
+            message[1],
+            "- api_id in telegram.toml is not a valid id: abc"
+        );
+        assert_eq!(message[2], "- api_hash in telegram.toml is not set");
+    }
+
+    #[test]
+    fn test_update_chats_index() {
+        let position = |order| ChatPosition {
+            list: ChatList::Main,
+            order,
+            is_pinned: false,
+            source: None,
+        };
+        let mut chats_index = BTreeSet::new();
+        TgBackend::update_chats_index(&mut chats_index, &ChatList::Main, 1, &[], &[position(10)]);
+        TgBackend::update_chats_index(&mut chats_index, &ChatList::Main, 2, &[], &[position(20)]);
+        // A position that is not in the index is removed, and a position
+        // that is already in it is inserted, without panicking nor leaving a
+        // stale entry of the chat.
+        TgBackend::update_chats_index(
+            &mut chats_index,
+            &ChatList::Main,
+            1,
+            &[position(5)],
+            &[position(20)],
+        );
+        TgBackend::update_chats_index(
+            &mut chats_index,
+            &ChatList::Main,
+            2,
+            &[position(20)],
+            &[position(20)],
+        );
+        let chat_ids: Vec<i64> = chats_index.iter().map(|chat| chat.chat_id).collect();
+        assert_eq!(chat_ids, vec![2, 1]);
+        assert!(chats_index.contains(&OrderedChat {
+            chat_id: 1,
+            position: position(20),
+        }));
+        TgBackend::update_chats_index(
+            &mut chats_index,
+            &ChatList::Main,
+            1,
+            &[position(10), position(20)],
+            &[],
+        );
+        let chat_ids: Vec<i64> = chats_index.iter().map(|chat| chat.chat_id).collect();
+        assert_eq!(chat_ids, vec![2]);
+    }
+
+    #[test]
+    fn test_update_chats_index_of_a_folder() {
+        let folder = ChatList::Folder(ChatListFolder { chat_folder_id: 1 });
+        let position = |list: &ChatList, order| ChatPosition {
+            list: list.clone(),
+            order,
+            is_pinned: false,
+            source: None,
+        };
+        let mut chats_index = BTreeSet::new();
+        // The chat 2 is only in the folder, e.g. because it is archived.
+        TgBackend::update_chats_index(
+            &mut chats_index,
+            &folder,
+            1,
+            &[],
+            &[position(&ChatList::Main, 30), position(&folder, 10)],
+        );
+        TgBackend::update_chats_index(&mut chats_index, &folder, 2, &[], &[position(&folder, 20)]);
+        let chat_ids: Vec<i64> = chats_index.iter().map(|chat| chat.chat_id).collect();
+        assert_eq!(chat_ids, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_is_network_error() {
+        let error = |code: i32, message: &str| Error {
+            code,
+            message: message.to_string(),
+        };
+        assert!(TgBackend::is_network_error(&error(500, "Request aborted")));
+        assert!(TgBackend::is_network_error(&error(
+            400,
+            "NETWORK_MIGRATE_2"
+        )));
+        assert!(!TgBackend::is_network_error(&error(404, "Not Found")));
+    }
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(TgBackend::split_message("hello", 10), vec!["hello"]);
+        assert_eq!(TgBackend::split_message("", 10), vec![""]);
+        assert_eq!(
+            TgBackend::split_message("one two\nthree four", 12),
+            vec!["one two\n", "three four"]
+        );
+        assert_eq!(
+            TgBackend::split_message("one two three", 10),
+            vec!["one two ", "three"]
+        );
+        assert_eq!(
+            TgBackend::split_message("abcdefghij", 4),
+            vec!["abcd", "efgh", "ij"]
+        );
+        // The emoji counts as two code units and it is not cut.
+        assert_eq!(TgBackend::split_message("abc😀", 4), vec!["abc", "😀"]);
+    }
+
+    #[test]
+    fn test_part_entities() {
+        let entity = |offset, length| TextEntity {
+            offset,
+            length,
+            r#type: TextEntityType::Bold,
+        };
+        let entities = vec![entity(0, 3), entity(5, 10)];
+        assert_eq!(
+            TgBackend::part_entities(&entities, 0, 8),
+            vec![entity(0, 3), entity(5, 3)]
+        );
+        assert_eq!(
+            TgBackend::part_entities(&entities, 8, 8),
+            vec![entity(0, 7)]
+        );
+        assert_eq!(TgBackend::part_entities(&entities, 15, 5), vec![]);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(TgBackend::retry_delay(0), Duration::from_millis(500));
+        assert_eq!(TgBackend::retry_delay(1), Duration::from_secs(1));
+        assert_eq!(TgBackend::retry_delay(2), Duration::from_secs(2));
+        assert_eq!(TgBackend::retry_delay(10), Duration::from_secs(8));
+        assert_eq!(TgBackend::retry_delay(u32::MAX), Duration::from_secs(8));
+    }
+
+    #[test]
+    fn test_player_command() {
+        assert!(TgBackend::player_command("", "voice.oga").is_none());
+        assert!(TgBackend::player_command("  ", "voice.oga").is_none());
+        let command = TgBackend::player_command("ffplay -nodisp  -autoexit", "voice.oga").unwrap();
+        let command = command.as_std();
+        assert_eq!(command.get_program(), "ffplay");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            vec!["-nodisp", "-autoexit", "voice.oga"]
+        );
+    }
+
+    #[test]
+    fn test_clear_tdlib_database() {
+        let dir = std::env::temp_dir().join(format!("tgt_clear_database_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("photos")).unwrap();
+        std::fs::write(dir.join("notes.txt"), "keep").unwrap();
+        assert!(TgBackend::clear_tdlib_database(&dir).is_err());
+        assert!(dir.join("photos").is_dir());
+
+        std::fs::write(dir.join("td.binlog"), "").unwrap();
+        std::fs::write(dir.join("db.sqlite"), "").unwrap();
+        assert!(TgBackend::clear_tdlib_database(&dir).is_ok());
+        assert!(!dir.join("td.binlog").exists());
+        assert!(!dir.join("db.sqlite").exists());
+        assert!(!dir.join("photos").exists());
+        assert!(dir.join("notes.txt").is_file());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
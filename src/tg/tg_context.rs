@@ -1,25 +1,266 @@
 use super::message_entry::MessageEntry;
-use crate::tg::message_entry::DateTimeEntry;
+use crate::tg::td_enums::TdFile;
 use crate::{
     app_error::AppError, components::chat_list_window::ChatListEntry, event::Event,
     tg::ordered_chat::OrderedChat,
 };
-use std::sync::atomic::{AtomicI64, Ordering};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicI64, AtomicU64, Ordering};
 use std::{
-    collections::{BTreeSet, HashMap},
-    sync::{Mutex, MutexGuard},
+    collections::{hash_map::Entry, BTreeSet, HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
 };
 use tdlib_rs::{
-    enums::ChatType,
+    enums::{
+        BlockList, ChatMemberStatus, ChatType, ConnectionState, NotificationSettingsScope,
+        SecretChatState, UserStatus, UserType,
+    },
     types::{
-        BasicGroup, BasicGroupFullInfo, Chat, SecretChat, Supergroup, SupergroupFullInfo, User,
-        UserFullInfo,
+        BasicGroup, BasicGroupFullInfo, Chat, ChatFolderInfo, ChatMember, ChatNotificationSettings,
+        SecretChat, Session, Sticker, Supergroup, SupergroupFullInfo, User, UserFullInfo,
     },
 };
 use tokio::sync::mpsc::UnboundedSender;
 
+/// The maximum number of chats remembered as recently opened.
+pub const MAX_RECENT_CHATS: usize = 10;
+/// How long a status message, e.g. an error, is shown to the user.
+pub const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(5);
+/// How long the viewers of a message are kept before being requested again.
+pub const MESSAGE_VIEWERS_CACHE_DURATION: Duration = Duration::from_secs(30);
+/// The maximum length of the text of a message accepted by Telegram, in
+/// UTF-16 code units.
+pub const MAX_MESSAGE_LENGTH: usize = 4096;
+/// The maximum length of the caption of a media message accepted by
+/// Telegram, in UTF-16 code units.
+pub const MAX_CAPTION_LENGTH: usize = 1024;
+
+/// The ids of the users that have read a message, `None` if they are not
+/// available, with the time they were got, by chat id and message id.
+type MessageViewersCache = HashMap<(i64, i64), (Option<Vec<i64>>, Instant)>;
+
+/// A group of stickers shown by the sticker picker, e.g. an installed
+/// sticker set or the recently sent stickers.
+#[derive(Debug, Clone)]
+pub struct StickerSetEntry {
+    /// The title of the group.
+    pub title: String,
+    /// The stickers of the group.
+    pub stickers: Vec<Sticker>,
+}
+
+/// A message of the open chat scheduled to be sent, shown by the panel of
+/// the scheduled messages.
+#[derive(Debug, Clone)]
+pub struct ScheduledMessageEntry {
+    /// The Unix time the message is sent at, 0 if it is sent when the other
+    /// user is online.
+    pub send_date: i32,
+    /// The message.
+    pub message: MessageEntry,
+}
+
+/// A file downloaded by the user, shown by the panel of the downloads.
+#[derive(Debug, Clone)]
+pub struct DownloadEntry {
+    /// The name of the file, or the type of the media if it has no name.
+    pub name: String,
+    /// The state of the download.
+    pub file: TdFile,
+}
+
+/// A reference to a chat entered by the user.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ChatLink {
+    /// The username of a public chat, without the `@`.
+    Username(String),
+    /// An invite link to a chat.
+    InviteLink(String),
+}
+impl ChatLink {
+    /// Parse a reference to a chat.
+    /// It can be a username, with or without the `@`, a link to a public chat
+    /// such as `https://t.me/username`, or an invite link such as
+    /// `https://t.me/+hash` or `https://t.me/joinchat/hash`.
+    ///
+    /// # Arguments
+    /// * `link` - The reference to the chat.
+    ///
+    /// # Returns
+    /// * `Option<ChatLink>` - The reference, `None` if it is not valid.
+    pub fn parse(link: &str) -> Option<Self> {
+        let link = link.trim();
+        let without_scheme = link
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_start_matches("www.");
+        let username = match without_scheme
+            .strip_prefix("t.me/")
+            .or_else(|| without_scheme.strip_prefix("telegram.me/"))
+        {
+            Some(path) => {
+                let path = path.split(['?', '#']).next().unwrap_or_default();
+                if path.starts_with('+') || path.starts_with("joinchat/") {
+                    return Some(ChatLink::InviteLink(format!("https://t.me/{}", path)));
+                }
+                path.split('/').next().unwrap_or_default()
+            }
+            None => link.strip_prefix('@').unwrap_or(link),
+        };
+        let is_valid = !username.is_empty()
+            && username
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        is_valid.then(|| ChatLink::Username(username.to_string()))
+    }
+}
+
+/// The time, in seconds, for which the chats of each notification scope are
+/// muted, used by the chats that follow the default of their scope.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ScopeMuteFor {
+    /// The mute time of the private and secret chats.
+    pub private_chats: i32,
+    /// The mute time of the basic groups and supergroups.
+    pub group_chats: i32,
+    /// The mute time of the channels.
+    pub channel_chats: i32,
+}
+
+impl ScopeMuteFor {
+    /// Get the mute time of a scope.
+    ///
+    /// # Arguments
+    /// * `scope` - The notification scope.
+    ///
+    /// # Returns
+    /// * `i32` - The mute time of the scope.
+    fn get(&self, scope: &NotificationSettingsScope) -> i32 {
+        match scope {
+            NotificationSettingsScope::PrivateChats => self.private_chats,
+            NotificationSettingsScope::GroupChats => self.group_chats,
+            NotificationSettingsScope::ChannelChats => self.channel_chats,
+        }
+    }
+
+    /// Get the mutable mute time of a scope.
+    ///
+    /// # Arguments
+    /// * `scope` - The notification scope.
+    ///
+    /// # Returns
+    /// * `&mut i32` - The mute time of the scope.
+    fn get_mut(&mut self, scope: &NotificationSettingsScope) -> &mut i32 {
+        match scope {
+            NotificationSettingsScope::PrivateChats => &mut self.private_chats,
+            NotificationSettingsScope::GroupChats => &mut self.group_chats,
+            NotificationSettingsScope::ChannelChats => &mut self.channel_chats,
+        }
+    }
+
+    /// Get the scope of the notification settings of a chat.
+    ///
+    /// # Arguments
+    /// * `chat_type` - The type of the chat.
+    ///
+    /// # Returns
+    /// * `NotificationSettingsScope` - The scope of the chat.
+    fn scope_of(chat_type: &ChatType) -> NotificationSettingsScope {
+        match chat_type {
+            ChatType::Private(_) | ChatType::Secret(_) => NotificationSettingsScope::PrivateChats,
+            ChatType::Supergroup(supergroup) if supergroup.is_channel => {
+                NotificationSettingsScope::ChannelChats
+            }
+            ChatType::BasicGroup(_) | ChatType::Supergroup(_) => {
+                NotificationSettingsScope::GroupChats
+            }
+        }
+    }
+
+    /// Check whether a chat is muted, with the mute time of its scope when
+    /// it follows the default.
+    ///
+    /// # Arguments
+    /// * `chat_type` - The type of the chat.
+    /// * `settings` - The notification settings of the chat.
+    ///
+    /// # Returns
+    /// * `bool` - True if the chat is muted.
+    pub fn is_muted(&self, chat_type: &ChatType, settings: &ChatNotificationSettings) -> bool {
+        let mute_for = if settings.use_default_mute_for {
+            self.get(&Self::scope_of(chat_type))
+        } else {
+            settings.mute_for
+        };
+        mute_for > 0
+    }
+}
+
+/// The temporary files of the messages being sent, e.g. the images pasted
+/// from the clipboard.
+/// The temporary id of a message is known only once `send_message` returns,
+/// possibly after the message is already sent, so the ids of the messages
+/// sent while a file is being sent are kept, to delete the file at once.
+#[derive(Debug, Default)]
+struct TemporaryFiles {
+    /// The paths of the files, by temporary message id.
+    paths: HashMap<i64, String>,
+    /// The number of files whose message is being sent.
+    sending: usize,
+    /// The temporary ids of the messages sent while `sending` is not 0.
+    sent: HashSet<i64>,
+}
+
+/// Delete a temporary file.
+///
+/// # Arguments
+/// * `path` - The path of the file.
+fn delete_temporary_file(path: &str) {
+    if let Err(e) = std::fs::remove_file(path) {
+        tracing::error!("Failed to delete the temporary file {}: {}", path, e);
+    }
+}
+
+/// A summary of a chat, used to list the chats from the command line.
+#[derive(Debug, Serialize)]
+pub struct ChatSummary {
+    /// The id of the chat.
+    pub id: i64,
+    /// The title of the chat.
+    pub title: String,
+    /// The number of unread messages of the chat.
+    pub unread_count: i32,
+    /// The text of the last message of the chat, if any.
+    pub last_message: Option<String>,
+}
+
+/// An immutable copy of the data of the open chat drawn by the user
+/// interface.
+/// It is refreshed before each redraw, so that the draw code reads it without
+/// taking the locks that the backend holds while applying the updates.
+#[derive(Debug, Default)]
+pub struct TgSnapshot {
+    /// The id of the open chat, 0 if no chat is open.
+    pub open_chat_id: i64,
+    /// The messages of the open chat.
+    pub open_chat_messages: Vec<MessageEntry>,
+    /// Whether the open chat is a group, whose senders have their own color.
+    pub open_chat_is_group: bool,
+    /// The names of the users and of the chats shown with the messages of
+    /// the open chat, e.g. their senders, by id, see
+    /// `MessageEntry::named_ids`.
+    pub names: HashMap<i64, String>,
+    /// The usernames of the users of the contacts shared by the messages of
+    /// the open chat, by user id.
+    pub contact_usernames: HashMap<i64, String>,
+}
+
 #[derive(Debug, Default)]
 pub struct TgContext {
+    /// The data drawn by the user interface, see `TgSnapshot`.
+    snapshot: Mutex<Arc<TgSnapshot>>,
     users: Mutex<HashMap<i64, User>>,
     basic_groups: Mutex<HashMap<i64, BasicGroup>>,
     supergroups: Mutex<HashMap<i64, Supergroup>>,
@@ -28,28 +269,102 @@ pub struct TgContext {
 
     // Ordered
     chats_index: Mutex<BTreeSet<OrderedChat>>,
+    /// The index of the chat list of each chat folder, by chat folder id.
+    chat_folders_index: Mutex<HashMap<i32, BTreeSet<OrderedChat>>>,
 
     users_full_info: Mutex<HashMap<i64, UserFullInfo>>,
     basic_groups_full_info: Mutex<HashMap<i64, BasicGroupFullInfo>>,
     supergroups_full_info: Mutex<HashMap<i64, SupergroupFullInfo>>,
+    /// The chat folders of the user, in the order shown by Telegram.
+    chat_folders: Mutex<Vec<ChatFolderInfo>>,
+    /// The pinned messages of the opened chats, the most recent first, by
+    /// chat id.
+    pinned_messages: Mutex<HashMap<i64, Vec<MessageEntry>>>,
+    /// The viewers of the messages got recently.
+    message_viewers: Mutex<MessageViewersCache>,
+    /// The favorite and the recent stickers followed by the installed
+    /// sticker sets, empty until they are loaded.
+    sticker_sets: Mutex<Vec<StickerSetEntry>>,
+    /// The scheduled messages of the open chat, in the order they are sent,
+    /// empty until they are loaded.
+    scheduled_messages: Mutex<Vec<ScheduledMessageEntry>>,
+    /// The members of the group shown by the member list, empty until they
+    /// are loaded.
+    chat_members: Mutex<Vec<ChatMember>>,
+    /// The result of the loading of the members of the group, `None` while
+    /// they are loaded, the error if they can not be loaded.
+    chat_members_loaded: Mutex<Option<Result<(), String>>>,
+    /// The mute time of the notification scopes, see `is_muted`.
+    scope_mute_for: Mutex<ScopeMuteFor>,
+    /// The number of the current loading of the members, the pages of a
+    /// previous loading still running are discarded.
+    chat_members_generation: AtomicU64,
+    /// The files downloaded by the user, in the order they were started.
+    downloads: Mutex<Vec<DownloadEntry>>,
+    /// The active sessions of the account shown by the panel of the
+    /// sessions, empty until they are loaded.
+    sessions: Mutex<Vec<Session>>,
+    /// The names shown instead of the Telegram names of some users, by user
+    /// id.
+    aliases: Mutex<HashMap<i64, String>>,
+    /// The number of online members of the opened chats, by chat id.
+    chats_online_member_count: Mutex<HashMap<i64, i32>>,
+    /// The state of the connection to the Telegram servers, `None` until
+    /// the first update is received.
+    connection_state: Mutex<Option<ConnectionState>>,
+    /// The proxy used to connect to the Telegram servers, e.g. "socks5
+    /// 127.0.0.1:1080", `None` if the connection is direct.
+    proxy: Mutex<Option<String>>,
+    /// The last status message to show to the user, whether it is an error
+    /// and the time it was set.
+    status_message: Mutex<Option<(String, bool, Instant)>>,
+    /// The total number of unread messages of all the chats.
+    /// It is kept up to date with the updates of the chats, so that it is not
+    /// computed on every frame.
+    unread_count: AtomicI32,
+    /// The number of unread messages of the muted chats, included in
+    /// `unread_count`.
+    unread_muted_count: AtomicI32,
 
     event_tx: Mutex<Option<UnboundedSender<Event>>>,
     me: AtomicI64,
+    /// Indicates whether the presence of the current user is online or not.
+    is_online: AtomicBool,
     open_chat_id: AtomicI64,
+    /// The last read message of the open chat when it was opened, if it had
+    /// unread messages. It is taken before the messages are marked as read,
+    /// so that the chat window shows where the unread messages start.
+    open_chat_last_read_message_id: Mutex<Option<i64>>,
+    /// The ids of the recently opened chats, the most recent first.
+    recent_chats: Mutex<Vec<i64>>,
     // This is the chat messages that are currently being displayed
     // in the chat window.
     open_chat_messages: Mutex<Vec<MessageEntry>>,
     open_chat_user: Mutex<Option<User>>,
 
     last_acknowledged_message_id: AtomicI64,
+    /// The id of the message whose voice note is being played, 0 if none.
+    playing_voice_note: AtomicI64,
 
     /// The message id from which to start loading the chat history.
     from_message_id: AtomicI64,
+    /// Indicates whether a request for the chat history is pending.
+    /// It is used to avoid sending overlapping requests while scrolling.
+    is_loading_chat_history: AtomicBool,
 
     /// reply message id
     reply_message_id: AtomicI64,
     /// reply message text
     reply_message_text: Mutex<String>,
+    /// The length, in UTF-16 code units, of the quote at the beginning of the
+    /// reply. It is 0 when the reply does not quote the replied message.
+    reply_quote_length: AtomicI64,
+    /// The messages to forward to the next opened chat, with the id of the
+    /// chat they belong to.
+    forward_messages: Mutex<Option<(i64, Vec<i64>)>>,
+    /// The temporary files of the messages being sent. They are deleted once
+    /// the messages are sent, see `remove_temporary_file`.
+    temporary_files: Mutex<TemporaryFiles>,
 }
 
 impl TgContext {
@@ -71,6 +386,9 @@ impl TgContext {
     pub fn chats_index(&self) -> MutexGuard<'_, BTreeSet<OrderedChat>> {
         self.chats_index.lock().unwrap()
     }
+    pub fn chat_folders_index(&self) -> MutexGuard<'_, HashMap<i32, BTreeSet<OrderedChat>>> {
+        self.chat_folders_index.lock().unwrap()
+    }
     pub fn users_full_info(&self) -> MutexGuard<'_, HashMap<i64, UserFullInfo>> {
         self.users_full_info.lock().unwrap()
     }
@@ -80,6 +398,229 @@ impl TgContext {
     pub fn supergroups_full_info(&self) -> MutexGuard<'_, HashMap<i64, SupergroupFullInfo>> {
         self.supergroups_full_info.lock().unwrap()
     }
+    pub fn chat_folders(&self) -> MutexGuard<'_, Vec<ChatFolderInfo>> {
+        self.chat_folders.lock().unwrap()
+    }
+    pub fn pinned_messages(&self) -> MutexGuard<'_, HashMap<i64, Vec<MessageEntry>>> {
+        self.pinned_messages.lock().unwrap()
+    }
+    pub fn sticker_sets(&self) -> MutexGuard<'_, Vec<StickerSetEntry>> {
+        self.sticker_sets.lock().unwrap()
+    }
+    pub fn scheduled_messages(&self) -> MutexGuard<'_, Vec<ScheduledMessageEntry>> {
+        self.scheduled_messages.lock().unwrap()
+    }
+    pub fn chat_members(&self) -> MutexGuard<'_, Vec<ChatMember>> {
+        self.chat_members.lock().unwrap()
+    }
+    pub fn chat_members_loaded(&self) -> MutexGuard<'_, Option<Result<(), String>>> {
+        self.chat_members_loaded.lock().unwrap()
+    }
+    pub fn downloads(&self) -> MutexGuard<'_, Vec<DownloadEntry>> {
+        self.downloads.lock().unwrap()
+    }
+    pub fn sessions(&self) -> MutexGuard<'_, Vec<Session>> {
+        self.sessions.lock().unwrap()
+    }
+    /// Add a file to the downloads, or update it if it is already there.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the file.
+    /// * `file` - The state of the file.
+    pub fn add_download(&self, name: impl Into<String>, file: TdFile) {
+        let name = name.into();
+        let mut downloads = self.downloads();
+        match downloads.iter_mut().find(|entry| entry.file.id == file.id) {
+            Some(entry) => *entry = DownloadEntry { name, file },
+            None => downloads.push(DownloadEntry { name, file }),
+        }
+    }
+    /// Update the state of a file of the downloads.
+    /// The files that are not in the downloads are ignored.
+    ///
+    /// # Arguments
+    /// * `file` - The new state of the file.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the file is in the downloads.
+    pub fn update_download(&self, file: TdFile) -> bool {
+        match self
+            .downloads()
+            .iter_mut()
+            .find(|entry| entry.file.id == file.id)
+        {
+            Some(entry) => {
+                entry.file = file;
+                true
+            }
+            None => false,
+        }
+    }
+    /// Get whether a file is in the downloads.
+    ///
+    /// # Arguments
+    /// * `file_id` - The id of the file.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the file is in the downloads.
+    pub fn is_download(&self, file_id: i32) -> bool {
+        self.downloads()
+            .iter()
+            .any(|entry| entry.file.id == file_id)
+    }
+    /// Remove a file from the downloads.
+    ///
+    /// # Arguments
+    /// * `file_id` - The id of the file.
+    pub fn remove_download(&self, file_id: i32) {
+        self.downloads().retain(|entry| entry.file.id != file_id);
+    }
+    /// Set the names shown instead of the Telegram names of some users.
+    ///
+    /// # Arguments
+    /// * `aliases` - The aliases by user id.
+    pub fn set_aliases(&self, aliases: HashMap<i64, String>) {
+        *self.aliases.lock().unwrap() = aliases;
+    }
+    /// Get the alias of a user.
+    ///
+    /// # Arguments
+    /// * `user_id` - The id of the user.
+    ///
+    /// # Returns
+    /// * `Option<String>` - The alias, `None` if the user has no alias.
+    pub fn alias(&self, user_id: i64) -> Option<String> {
+        self.aliases.lock().unwrap().get(&user_id).cloned()
+    }
+    /// Get a sticker shown by the sticker picker.
+    ///
+    /// # Arguments
+    /// * `sticker_id` - The id of the sticker.
+    ///
+    /// # Returns
+    /// * `Option<Sticker>` - The sticker, `None` if it has not been loaded.
+    pub fn sticker(&self, sticker_id: i64) -> Option<Sticker> {
+        self.sticker_sets()
+            .iter()
+            .flat_map(|set| set.stickers.iter())
+            .find(|sticker| sticker.id == sticker_id)
+            .cloned()
+    }
+    /// Get the users that have read a message.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `message_id` - The id of the message.
+    ///
+    /// # Returns
+    /// * `Option<Option<Vec<i64>>>` - The ids of the users, `Some(None)` if
+    ///   they are not available, `None` if they have not been got in the last
+    ///   `MESSAGE_VIEWERS_CACHE_DURATION`.
+    pub fn message_viewers(&self, chat_id: i64, message_id: i64) -> Option<Option<Vec<i64>>> {
+        self.message_viewers
+            .lock()
+            .unwrap()
+            .get(&(chat_id, message_id))
+            .filter(|(_, time)| time.elapsed() < MESSAGE_VIEWERS_CACHE_DURATION)
+            .map(|(viewers, _)| viewers.clone())
+    }
+    /// Set the users that have read a message.
+    /// The expired entries are removed.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `message_id` - The id of the message.
+    /// * `viewers` - The ids of the users, `None` if they are not available.
+    pub fn set_message_viewers(&self, chat_id: i64, message_id: i64, viewers: Option<Vec<i64>>) {
+        let mut message_viewers = self.message_viewers.lock().unwrap();
+        message_viewers.retain(|_, (_, time)| time.elapsed() < MESSAGE_VIEWERS_CACHE_DURATION);
+        message_viewers.insert((chat_id, message_id), (viewers, Instant::now()));
+    }
+    /// Check whether the user can pin and unpin the messages of a chat.
+    /// In channels, the messages can be pinned by the administrators that
+    /// can edit them.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the user can pin messages in the chat.
+    pub fn can_pin_messages(&self, chat_id: i64) -> bool {
+        let Some((chat_type, can_pin_messages)) = self
+            .chats()
+            .get(&chat_id)
+            .map(|chat| (chat.r#type.clone(), chat.permissions.can_pin_messages))
+        else {
+            return false;
+        };
+        let (status, is_channel) = match chat_type {
+            ChatType::Private(_) => return true,
+            ChatType::Secret(_) => return false,
+            ChatType::BasicGroup(basic_group) => {
+                match self.basic_groups().get(&basic_group.basic_group_id) {
+                    Some(basic_group) => (basic_group.status.clone(), false),
+                    None => return false,
+                }
+            }
+            ChatType::Supergroup(supergroup) => {
+                match self.supergroups().get(&supergroup.supergroup_id) {
+                    Some(supergroup) => (supergroup.status.clone(), supergroup.is_channel),
+                    None => return false,
+                }
+            }
+        };
+        match status {
+            ChatMemberStatus::Creator(_) => true,
+            ChatMemberStatus::Administrator(administrator) if is_channel => {
+                administrator.rights.can_edit_messages
+            }
+            ChatMemberStatus::Administrator(administrator) => administrator.rights.can_pin_messages,
+            ChatMemberStatus::Member => !is_channel && can_pin_messages,
+            ChatMemberStatus::Restricted(restricted) => {
+                restricted.is_member && restricted.permissions.can_pin_messages
+            }
+            _ => false,
+        }
+    }
+    /// Update the pinned messages of a chat after a message has been pinned
+    /// or unpinned.
+    /// A pinned message is added only if it is loaded in the open chat,
+    /// otherwise the pinned messages of the chat are loaded again the next
+    /// time it is opened.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    /// * `message_id` - The id of the message.
+    /// * `is_pinned` - Whether the message has been pinned or unpinned.
+    pub fn set_message_pinned(&self, chat_id: i64, message_id: i64, is_pinned: bool) {
+        let message = if is_pinned && self.open_chat_id() == chat_id {
+            self.open_chat_messages()
+                .iter()
+                .find(|message| message.id() == message_id)
+                .cloned()
+        } else {
+            None
+        };
+        let mut pinned_messages = self.pinned_messages();
+        match (is_pinned, message) {
+            (true, Some(message)) => {
+                let pinned = pinned_messages.entry(chat_id).or_default();
+                pinned.retain(|m| m.id() != message_id);
+                pinned.insert(0, message);
+            }
+            (true, None) => {
+                pinned_messages.remove(&chat_id);
+            }
+            (false, _) => {
+                if let Some(pinned) = pinned_messages.get_mut(&chat_id) {
+                    pinned.retain(|m| m.id() != message_id);
+                }
+            }
+        }
+    }
+    pub fn chats_online_member_count(&self) -> MutexGuard<'_, HashMap<i64, i32>> {
+        self.chats_online_member_count.lock().unwrap()
+    }
     pub fn open_chat_id(&self) -> i64 {
         self.open_chat_id.load(Ordering::Relaxed)
     }
@@ -92,15 +633,114 @@ impl TgContext {
     pub fn me(&self) -> i64 {
         self.me.load(Ordering::Relaxed)
     }
+    pub fn is_online(&self) -> bool {
+        self.is_online.load(Ordering::Relaxed)
+    }
+    pub fn connection_state(&self) -> MutexGuard<'_, Option<ConnectionState>> {
+        self.connection_state.lock().unwrap()
+    }
+    pub fn proxy(&self) -> MutexGuard<'_, Option<String>> {
+        self.proxy.lock().unwrap()
+    }
+    /// Forget the members of the group shown by the member list, before
+    /// loading the ones of the open group. The pages of a loading still
+    /// running are discarded from now on.
+    pub fn reset_chat_members(&self) {
+        {
+            let mut chat_members = self.chat_members();
+            chat_members.clear();
+            self.chat_members_generation.fetch_add(1, Ordering::Relaxed);
+        }
+        *self.chat_members_loaded() = None;
+    }
+    /// Get the number of the current loading of the members, see
+    /// `reset_chat_members`.
+    ///
+    /// # Returns
+    /// * `u64` - The number of the loading.
+    pub fn chat_members_generation(&self) -> u64 {
+        self.chat_members_generation.load(Ordering::Relaxed)
+    }
+    /// Add a page of members to the member list, unless it belongs to a
+    /// previous loading.
+    ///
+    /// # Arguments
+    /// * `generation` - The number of the loading of the page.
+    /// * `members` - The members of the page.
+    ///
+    /// # Returns
+    /// * `bool` - `false` if the loading is stale and should be stopped.
+    pub fn add_chat_members(&self, generation: u64, members: Vec<ChatMember>) -> bool {
+        // The generation is changed with the lock of the members taken.
+        let mut chat_members = self.chat_members();
+        if generation != self.chat_members_generation() {
+            return false;
+        }
+        chat_members.extend(members);
+        true
+    }
+    /// Set the result of the loading of the members, unless it belongs to a
+    /// previous loading.
+    ///
+    /// # Arguments
+    /// * `generation` - The number of the loading.
+    /// * `result` - The result of the loading.
+    pub fn set_chat_members_loaded(&self, generation: u64, result: Result<(), String>) {
+        let mut chat_members_loaded = self.chat_members_loaded();
+        if generation == self.chat_members_generation() {
+            *chat_members_loaded = Some(result);
+        }
+    }
+    /// Set the error to show to the user.
+    /// It is shown for `STATUS_MESSAGE_DURATION`.
+    ///
+    /// # Arguments
+    /// * `message` - The description of the error.
+    pub fn set_error_message(&self, message: impl Into<String>) {
+        *self.status_message.lock().unwrap() = Some((message.into(), true, Instant::now()));
+    }
+    /// Set the information to show to the user, e.g. the confirmation of an
+    /// action.
+    /// It is shown for `STATUS_MESSAGE_DURATION`.
+    ///
+    /// # Arguments
+    /// * `message` - The information.
+    pub fn set_info_message(&self, message: impl Into<String>) {
+        *self.status_message.lock().unwrap() = Some((message.into(), false, Instant::now()));
+    }
+    /// Get the status message to show to the user.
+    ///
+    /// # Returns
+    /// * `Option<(String, bool)>` - The message and whether it is an error,
+    ///   `None` if there is no message or it is older than
+    ///   `STATUS_MESSAGE_DURATION`.
+    pub fn status_message(&self) -> Option<(String, bool)> {
+        self.status_message
+            .lock()
+            .unwrap()
+            .as_ref()
+            .filter(|(_, _, time)| time.elapsed() < STATUS_MESSAGE_DURATION)
+            .map(|(message, is_error, _)| (message.clone(), *is_error))
+    }
+    /// Check whether there is a working connection to the Telegram servers.
+    pub fn is_connection_ready(&self) -> bool {
+        matches!(*self.connection_state(), Some(ConnectionState::Ready))
+    }
     pub fn from_message_id(&self) -> i64 {
         self.from_message_id.load(Ordering::Relaxed)
     }
+    pub fn is_loading_chat_history(&self) -> bool {
+        self.is_loading_chat_history.load(Ordering::Relaxed)
+    }
     pub fn open_chat_user(&self) -> MutexGuard<'_, Option<User>> {
         self.open_chat_user.lock().unwrap()
     }
     pub fn last_acknowledged_message_id(&self) -> i64 {
         self.last_acknowledged_message_id.load(Ordering::Relaxed)
     }
+    pub fn playing_voice_note(&self) -> i64 {
+        self.playing_voice_note.load(Ordering::Relaxed)
+    }
 
     pub fn set_open_chat_user(&self, user: Option<User>) {
         *self.open_chat_user() = user;
@@ -108,6 +748,160 @@ impl TgContext {
 
     pub fn set_open_chat_id(&self, chat_id: i64) {
         self.open_chat_id.store(chat_id, Ordering::Relaxed);
+        *self.open_chat_last_read_message_id.lock().unwrap() = self
+            .chats()
+            .get(&chat_id)
+            .filter(|chat| chat.unread_count > 0)
+            .map(|chat| chat.last_read_inbox_message_id);
+        if chat_id != 0 {
+            let mut recent_chats = self.recent_chats.lock().unwrap();
+            recent_chats.retain(|id| *id != chat_id);
+            recent_chats.insert(0, chat_id);
+            recent_chats.truncate(MAX_RECENT_CHATS);
+        }
+    }
+
+    /// Get the last read message of the open chat when it was opened.
+    ///
+    /// # Returns
+    /// * `Option<i64>` - The id of the message, `None` if the chat had no
+    ///   unread messages.
+    pub fn open_chat_last_read_message_id(&self) -> Option<i64> {
+        *self.open_chat_last_read_message_id.lock().unwrap()
+    }
+
+    /// Get the recently opened chats.
+    ///
+    /// # Returns
+    /// * `Vec<i64>` - The ids of the chats, the most recently opened first.
+    pub fn recent_chats(&self) -> Vec<i64> {
+        self.recent_chats.lock().unwrap().clone()
+    }
+
+    /// Clear the data of the current session, e.g. after a log out.
+    /// The event sender is kept, so that a new session can use it.
+    pub fn clear(&self) {
+        self.users().clear();
+        self.basic_groups().clear();
+        self.supergroups().clear();
+        self.secret_chats().clear();
+        self.chats().clear();
+        self.chats_index().clear();
+        self.chat_folders_index().clear();
+        self.users_full_info().clear();
+        self.basic_groups_full_info().clear();
+        self.supergroups_full_info().clear();
+        self.chat_folders().clear();
+        self.chats_online_member_count().clear();
+        self.pinned_messages().clear();
+        self.message_viewers.lock().unwrap().clear();
+        self.sticker_sets().clear();
+        self.scheduled_messages().clear();
+        self.reset_chat_members();
+        self.downloads().clear();
+        self.sessions().clear();
+        *self.connection_state() = None;
+        *self.status_message.lock().unwrap() = None;
+        self.unread_count.store(0, Ordering::Relaxed);
+        self.unread_muted_count.store(0, Ordering::Relaxed);
+        self.me.store(0, Ordering::Relaxed);
+        self.is_online.store(false, Ordering::Relaxed);
+        self.set_open_chat_id(0);
+        self.recent_chats.lock().unwrap().clear();
+        self.clear_open_chat_messages();
+        self.set_open_chat_user(None);
+        self.set_last_acknowledged_message_id(0);
+        self.set_playing_voice_note(0);
+        self.set_from_message_id(0);
+        self.set_is_loading_chat_history(false);
+        self.set_reply_message(0, String::new());
+        *self.forward_messages.lock().unwrap() = None;
+        let mut temporary_files = self.temporary_files.lock().unwrap();
+        for path in temporary_files.paths.values() {
+            delete_temporary_file(path);
+        }
+        *temporary_files = TemporaryFiles::default();
+    }
+
+    /// Add the change of the unread messages of a chat to the total number of
+    /// unread messages.
+    ///
+    /// # Arguments
+    /// * `delta` - The change of the number of unread messages of the chat.
+    /// * `muted` - Whether the chat is muted.
+    pub fn add_unread_count(&self, delta: i32, muted: bool) {
+        self.unread_count.fetch_add(delta, Ordering::Relaxed);
+        if muted {
+            self.unread_muted_count.fetch_add(delta, Ordering::Relaxed);
+        }
+    }
+
+    /// Get the total number of unread messages.
+    ///
+    /// # Arguments
+    /// * `include_muted` - Whether the unread messages of the muted chats are
+    ///   counted.
+    ///
+    /// # Returns
+    /// * `i32` - The total number of unread messages.
+    pub fn unread_count(&self, include_muted: bool) -> i32 {
+        let unread_count = self.unread_count.load(Ordering::Relaxed);
+        if include_muted {
+            unread_count
+        } else {
+            unread_count - self.unread_muted_count.load(Ordering::Relaxed)
+        }
+    }
+
+    /// Get the last snapshot of the data drawn by the user interface.
+    /// The lock is held only to clone the `Arc` of the snapshot.
+    ///
+    /// # Returns
+    /// * `Arc<TgSnapshot>` - The snapshot.
+    pub fn snapshot(&self) -> Arc<TgSnapshot> {
+        Arc::clone(&self.snapshot.lock().unwrap())
+    }
+
+    /// Refresh the snapshot of the data drawn by the user interface, taking
+    /// each lock once.
+    pub fn refresh_snapshot(&self) {
+        let open_chat_id = self.open_chat_id();
+        let open_chat_messages = self.open_chat_messages().clone();
+        let open_chat_is_group =
+            self.chats()
+                .get(&open_chat_id)
+                .is_some_and(|chat| match &chat.r#type {
+                    ChatType::BasicGroup(_) => true,
+                    ChatType::Supergroup(supergroup) => !supergroup.is_channel,
+                    _ => false,
+                });
+        let mut names = HashMap::new();
+        let mut contact_usernames = HashMap::new();
+        for message in open_chat_messages.iter() {
+            for id in message.named_ids() {
+                if let Entry::Vacant(entry) = names.entry(id) {
+                    if let Some(name) = self.try_name_from_chats_or_users(id) {
+                        entry.insert(name);
+                    }
+                }
+            }
+            if let Some(user_id) = message.contact_user_id() {
+                let username = self
+                    .users()
+                    .get(&user_id)
+                    .and_then(|user| user.usernames.as_ref()?.active_usernames.first().cloned());
+                if let Some(username) = username {
+                    contact_usernames.insert(user_id, username);
+                }
+            }
+        }
+        *self.snapshot.lock().unwrap() = Arc::new(TgSnapshot {
+            open_chat_id,
+            open_chat_messages,
+            open_chat_is_group,
+            names,
+            contact_usernames,
+        });
     }
 
     pub fn clear_open_chat_messages(&self) {
@@ -119,15 +913,72 @@ impl TgContext {
             .store(from_message_id, Ordering::Relaxed);
     }
 
+    pub fn set_is_loading_chat_history(&self, is_loading: bool) {
+        self.is_loading_chat_history
+            .store(is_loading, Ordering::Relaxed);
+    }
+
     pub fn set_me(&self, me: i64) {
         self.me.store(me, Ordering::Relaxed);
     }
 
+    pub fn set_is_online(&self, is_online: bool) {
+        self.is_online.store(is_online, Ordering::Relaxed);
+    }
+
     pub fn set_last_acknowledged_message_id(&self, message_id: i64) {
         self.last_acknowledged_message_id
             .store(message_id, Ordering::Relaxed);
     }
 
+    /// Start sending a temporary file, before its message is sent, see
+    /// `add_temporary_file`.
+    pub fn start_temporary_file(&self) {
+        self.temporary_files.lock().unwrap().sending += 1;
+    }
+
+    /// Keep a temporary file until the message that is sending it is sent.
+    /// The file is deleted at once if the message is already sent, or if it
+    /// failed to be sent.
+    ///
+    /// # Arguments
+    /// * `message_id` - The temporary id of the message being sent, `None`
+    ///   if it failed to be sent.
+    /// * `path` - The path of the file.
+    pub fn add_temporary_file(&self, message_id: Option<i64>, path: String) {
+        let mut temporary_files = self.temporary_files.lock().unwrap();
+        temporary_files.sending = temporary_files.sending.saturating_sub(1);
+        match message_id {
+            Some(message_id) if !temporary_files.sent.remove(&message_id) => {
+                temporary_files.paths.insert(message_id, path);
+            }
+            _ => delete_temporary_file(&path),
+        }
+        if temporary_files.sending == 0 {
+            temporary_files.sent.clear();
+        }
+    }
+
+    /// Delete the temporary file of a message, once it is sent or failed to
+    /// be sent.
+    ///
+    /// # Arguments
+    /// * `message_id` - The temporary id of the message.
+    pub fn remove_temporary_file(&self, message_id: i64) {
+        let mut temporary_files = self.temporary_files.lock().unwrap();
+        match temporary_files.paths.remove(&message_id) {
+            Some(path) => delete_temporary_file(&path),
+            None if temporary_files.sending > 0 => {
+                temporary_files.sent.insert(message_id);
+            }
+            None => {}
+        }
+    }
+
+    pub fn set_playing_voice_note(&self, message_id: i64) {
+        self.playing_voice_note.store(message_id, Ordering::Relaxed);
+    }
+
     pub fn set_event_tx(&self, event_tx: UnboundedSender<Event>) {
         *self.event_tx() = Some(event_tx);
     }
@@ -136,6 +987,78 @@ impl TgContext {
     pub fn set_reply_message(&self, message_id: i64, text: String) {
         self.reply_message_id.store(message_id, Ordering::Relaxed);
         *self.reply_message_text.lock().unwrap() = text;
+        self.reply_quote_length.store(0, Ordering::Relaxed);
+    }
+
+    /// Set the length of the quote at the beginning of the reply that is
+    /// being sent.
+    ///
+    /// # Arguments
+    /// * `length` - The length of the quote in UTF-16 code units.
+    pub fn set_reply_quote_length(&self, length: i64) {
+        self.reply_quote_length.store(length, Ordering::Relaxed);
+    }
+
+    /// Insert a message at the beginning of the open chat messages, where the
+    /// newest messages are stored.
+    /// The message is not inserted if a message with the same id is already
+    /// present, e.g. because it has been received both as a new message and
+    /// while loading the chat history.
+    ///
+    /// # Arguments
+    /// * `message` - The message to insert.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the message has been inserted or not.
+    pub fn insert_open_chat_message(&self, message: MessageEntry) -> bool {
+        let mut open_chat_messages = self.open_chat_messages();
+        if open_chat_messages.iter().any(|m| m.id() == message.id()) {
+            return false;
+        }
+        open_chat_messages.insert(0, message);
+        true
+    }
+
+    /// Append a message at the end of the open chat messages, where the
+    /// oldest messages are stored.
+    /// The message is not appended if a message with the same id is already
+    /// present.
+    ///
+    /// # Arguments
+    /// * `message` - The message to append.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the message has been appended or not.
+    pub fn push_open_chat_message(&self, message: MessageEntry) -> bool {
+        let mut open_chat_messages = self.open_chat_messages();
+        if open_chat_messages.iter().any(|m| m.id() == message.id()) {
+            return false;
+        }
+        open_chat_messages.push(message);
+        true
+    }
+
+    /// Replace the temporary message identified by `old_message_id` with the
+    /// message confirmed by the server.
+    /// If the confirmed message is already present, the temporary one is
+    /// removed instead, so that the message is not displayed twice.
+    ///
+    /// # Arguments
+    /// * `old_message_id` - The temporary identifier of the message.
+    /// * `message` - The message confirmed by the server.
+    pub fn replace_open_chat_message(&self, old_message_id: i64, message: MessageEntry) {
+        let mut open_chat_messages = self.open_chat_messages();
+        let already_present = open_chat_messages.iter().any(|m| m.id() == message.id());
+        match open_chat_messages
+            .iter()
+            .position(|m| m.id() == old_message_id)
+        {
+            Some(i) if already_present => {
+                open_chat_messages.remove(i);
+            }
+            Some(i) => open_chat_messages[i] = message,
+            None => {}
+        }
     }
 
     pub fn delete_message(&self, message_id: i64) {
@@ -143,23 +1066,292 @@ impl TgContext {
         open_chat_messages.retain(|message| message.id() != message_id);
     }
 
+    /// Get the number of members of the open chat, shown in the title bar.
+    /// It is empty if the open chat is not a group.
+    ///
+    /// # Returns
+    /// * `String` - The number of members and of online members of the open
+    ///   chat.
+    pub fn open_chat_members_status(&self) -> String {
+        let chat_id = self.open_chat_id();
+        let chat_type = self.chats().get(&chat_id).map(|chat| chat.r#type.clone());
+        let online_member_count = self
+            .chats_online_member_count()
+            .get(&chat_id)
+            .copied()
+            .unwrap_or_default();
+        match chat_type {
+            Some(ChatType::Supergroup(supergroup)) => {
+                let member_count = match self.supergroups_full_info().get(&supergroup.supergroup_id)
+                {
+                    Some(full_info) => full_info.member_count,
+                    None => self
+                        .supergroups()
+                        .get(&supergroup.supergroup_id)
+                        .map(|supergroup| supergroup.member_count)
+                        .unwrap_or_default(),
+                };
+                Self::members_status(member_count, online_member_count, supergroup.is_channel)
+            }
+            Some(ChatType::BasicGroup(basic_group)) => {
+                let member_count = self
+                    .basic_groups()
+                    .get(&basic_group.basic_group_id)
+                    .map(|basic_group| basic_group.member_count)
+                    .unwrap_or_default();
+                Self::members_status(member_count, online_member_count, false)
+            }
+            _ => "".to_string(),
+        }
+    }
+
+    /// Get the status of the open chat, shown in the header of the chat.
+    /// For private and secret chats it is the status of the user, groups
+    /// have no status.
+    ///
+    /// # Returns
+    /// * `String` - The status of the open chat.
+    pub fn open_chat_status(&self) -> String {
+        let chat_id = self.open_chat_id();
+        let chat_type = self.chats().get(&chat_id).map(|chat| chat.r#type.clone());
+        match chat_type {
+            Some(ChatType::Supergroup(_)) | Some(ChatType::BasicGroup(_)) => "".to_string(),
+            Some(ChatType::Secret(secret)) => {
+                let state = self
+                    .secret_chats()
+                    .get(&secret.secret_chat_id)
+                    .map_or("pending", |secret_chat| {
+                        Self::secret_chat_state_text(&secret_chat.state)
+                    });
+                format!(
+                    "🔒 secret chat, {} | {}",
+                    state,
+                    self.open_chat_user_status()
+                )
+            }
+            _ if self.is_chat_blocked(chat_id) => {
+                format!("🚫 blocked | {}", self.open_chat_user_status())
+            }
+            _ => self.open_chat_user_status(),
+        }
+    }
+
+    /// Check whether a chat is in the main block list, i.e. the user of a
+    /// private chat is blocked.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the chat is blocked.
+    pub fn is_chat_blocked(&self, chat_id: i64) -> bool {
+        self.chats()
+            .get(&chat_id)
+            .is_some_and(|chat| matches!(chat.block_list, Some(BlockList::Main)))
+    }
+
+    /// Check whether a chat is a private chat with a bot.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    ///
+    /// # Returns
+    /// * `bool` - Whether the chat is a chat with a bot.
+    pub fn is_bot_chat(&self, chat_id: i64) -> bool {
+        let user_id = match self.chats().get(&chat_id).map(|chat| &chat.r#type) {
+            Some(ChatType::Private(private)) => private.user_id,
+            _ => return false,
+        };
+        self.users()
+            .get(&user_id)
+            .is_some_and(|user| matches!(user.r#type, UserType::Bot(_)))
+    }
+
+    /// Get the auto-delete time of the open chat.
+    ///
+    /// # Returns
+    /// * `i32` - The time in seconds after which the new messages are
+    ///   deleted, 0 if it is disabled.
+    pub fn open_chat_auto_delete_time(&self) -> i32 {
+        self.chats()
+            .get(&self.open_chat_id())
+            .map_or(0, |chat| chat.message_auto_delete_time)
+    }
+
+    /// Format an auto-delete time.
+    ///
+    /// # Arguments
+    /// * `seconds` - The time in seconds.
+    ///
+    /// # Returns
+    /// * `String` - The time in the largest unit that divides it, e.g.
+    ///   "1 week" or "3 days".
+    pub fn auto_delete_time_text(seconds: i32) -> String {
+        let units = [
+            (31 * 86400, "month"),
+            (7 * 86400, "week"),
+            (86400, "day"),
+            (3600, "hour"),
+            (60, "minute"),
+        ];
+        if seconds <= 0 {
+            return "off".to_string();
+        }
+        let (count, unit) = units
+            .iter()
+            .find(|(unit_seconds, _)| seconds % unit_seconds == 0)
+            .map_or((seconds, "second"), |(unit_seconds, unit)| {
+                (seconds / unit_seconds, *unit)
+            });
+        format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+    }
+
+    /// Get the text that describes the state of a secret chat.
+    ///
+    /// # Arguments
+    /// * `state` - The state of the secret chat.
+    ///
+    /// # Returns
+    /// * `&'static str` - The description of the state.
+    pub fn secret_chat_state_text(state: &SecretChatState) -> &'static str {
+        match state {
+            SecretChatState::Pending => "pending",
+            SecretChatState::Ready => "end-to-end encrypted",
+            SecretChatState::Closed => "closed",
+        }
+    }
+
+    /// Describe a session of the account: the application, the device and
+    /// the location it is used from. The unknown fields are skipped.
+    ///
+    /// # Arguments
+    /// * `session` - The session.
+    ///
+    /// # Returns
+    /// * `String` - The description of the session, e.g. "Telegram Desktop
+    ///   5.0, Desktop, Linux 6.1, Rome, Italy".
+    pub fn session_description(session: &Session) -> String {
+        [
+            format!(
+                "{} {}",
+                session.application_name, session.application_version
+            ),
+            session.device_model.clone(),
+            format!("{} {}", session.platform, session.system_version),
+            session.location.clone(),
+        ]
+        .iter()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+
+    /// Format the number of members of a group.
+    ///
+    /// # Arguments
+    /// * `member_count` - The number of members, 0 if it is unknown.
+    /// * `online_member_count` - The number of online members, 0 if it is
+    ///   unknown.
+    /// * `is_channel` - Whether the group is a channel, whose members are
+    ///   subscribers.
+    ///
+    /// # Returns
+    /// * `String` - The number of members, e.g. "10 members, 2 online".
+    pub fn members_status(member_count: i32, online_member_count: i32, is_channel: bool) -> String {
+        if member_count <= 0 {
+            return "".to_string();
+        }
+        let noun = match (is_channel, member_count) {
+            (true, 1) => "subscriber",
+            (true, _) => "subscribers",
+            (false, 1) => "member",
+            (false, _) => "members",
+        };
+        if online_member_count > 0 && !is_channel {
+            format!("{member_count} {noun}, {online_member_count} online")
+        } else {
+            format!("{member_count} {noun}")
+        }
+    }
+
+    /// Get the status of the user of the open private chat.
+    /// The user is read from the users updated by Telegram, so that the
+    /// status changes are shown.
+    ///
+    /// # Returns
+    /// * `String` - The status of the user, empty if there is no user or the
+    ///   status is hidden.
     pub fn open_chat_user_status(&self) -> String {
-        if let Some(user) = self.open_chat_user().as_ref() {
-            return match &user.status {
-                tdlib_rs::enums::UserStatus::Empty => "Empty".to_string(),
-                tdlib_rs::enums::UserStatus::Online(_) => "Online".to_string(),
-                tdlib_rs::enums::UserStatus::Offline(offline) => {
-                    format!(
-                        "Last seen {}",
-                        DateTimeEntry::convert_time(offline.was_online)
-                    )
-                }
-                tdlib_rs::enums::UserStatus::Recently(_) => "Last seen recently ".to_string(),
-                tdlib_rs::enums::UserStatus::LastWeek(_) => "Last seen LastWeek".to_string(),
-                tdlib_rs::enums::UserStatus::LastMonth(_) => "Last seen LastMonth ".to_string(),
-            };
+        let Some(user_id) = self.open_chat_user().as_ref().map(|user| user.id) else {
+            return "".to_string();
+        };
+        match self.users().get(&user_id) {
+            Some(user) => Self::user_status(&user.status),
+            None => self
+                .open_chat_user()
+                .as_ref()
+                .map(|user| Self::user_status(&user.status))
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Format the status of a user.
+    ///
+    /// # Arguments
+    /// * `status` - The status of the user.
+    ///
+    /// # Returns
+    /// * `String` - The status of the user, e.g. "online" or "last seen at
+    ///   10:30". It is empty if the status is hidden.
+    pub fn user_status(status: &UserStatus) -> String {
+        match status {
+            UserStatus::Empty => "".to_string(),
+            UserStatus::Online(_) => "online".to_string(),
+            UserStatus::Offline(offline) => Self::last_seen(offline.was_online, Local::now()),
+            UserStatus::Recently(_) => "last seen recently".to_string(),
+            UserStatus::LastWeek(_) => "last seen within a week".to_string(),
+            UserStatus::LastMonth(_) => "last seen within a month".to_string(),
+        }
+    }
+
+    /// Format the time a user was last online, relative to the day of now.
+    ///
+    /// # Arguments
+    /// * `was_online` - The time the user was last online, as a Unix
+    ///   timestamp.
+    /// * `now` - The current time.
+    ///
+    /// # Returns
+    /// * `String` - The time, e.g. "last seen yesterday at 10:30".
+    fn last_seen(was_online: i32, now: DateTime<Local>) -> String {
+        let Some(time) = DateTime::from_timestamp(i64::from(was_online), 0) else {
+            return "last seen recently".to_string();
+        };
+        let time = time.with_timezone(&Local);
+        let days = (now.date_naive() - time.date_naive()).num_days();
+        match days {
+            0 => format!("last seen at {}", time.format("%H:%M")),
+            1 => format!("last seen yesterday at {}", time.format("%H:%M")),
+            _ => format!("last seen on {}", time.format("%Y-%m-%d %H:%M")),
+        }
+    }
+
+    /// Get the text that describes a connection state.
+    ///
+    /// # Arguments
+    /// * `state` - The connection state.
+    ///
+    /// # Returns
+    /// * `&'static str` - The description of the connection state.
+    pub fn connection_state_text(state: &ConnectionState) -> &'static str {
+        match state {
+            ConnectionState::WaitingForNetwork => "Waiting for network",
+            ConnectionState::ConnectingToProxy => "Connecting to proxy",
+            ConnectionState::Connecting => "Connecting",
+            ConnectionState::Updating => "Updating",
+            ConnectionState::Ready => "Ready",
         }
-        "".to_string()
     }
 
     pub fn unread_messages(&self) -> Vec<i64> {
@@ -190,6 +1382,9 @@ impl TgContext {
     }
 
     pub fn try_name_from_chats_or_users(&self, user_id: i64) -> Option<String> {
+        if let Some(alias) = self.alias(user_id) {
+            return Some(alias);
+        }
         if self.name_from_chats(user_id).is_some() {
             return self.name_from_chats(user_id);
         }
@@ -219,10 +1414,107 @@ impl TgContext {
         self.reply_message_id.load(Ordering::Relaxed)
     }
 
+    /// Set the messages to forward to the chat chosen by the user. They are
+    /// forwarded when the user confirms them in that chat.
+    ///
+    /// # Arguments
+    /// * `from_chat_id` - The chat the messages belong to.
+    /// * `message_ids` - The messages to forward.
+    pub fn set_forward_messages(&self, from_chat_id: i64, message_ids: Vec<i64>) {
+        *self.forward_messages.lock().unwrap() = Some((from_chat_id, message_ids));
+    }
+
+    /// Take the messages to forward, if any.
+    ///
+    /// # Returns
+    /// * `Option<(i64, Vec<i64>)>` - The chat the messages belong to and the
+    ///   messages to forward.
+    pub fn take_forward_messages(&self) -> Option<(i64, Vec<i64>)> {
+        self.forward_messages.lock().unwrap().take()
+    }
+
+    /// Get the number of messages to forward.
+    ///
+    /// # Returns
+    /// * `usize` - The number of messages, 0 if there is nothing to forward.
+    pub fn forward_messages_count(&self) -> usize {
+        self.forward_messages
+            .lock()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |(_, message_ids)| message_ids.len())
+    }
+
+    /// Cancel the forward of the messages, if any, and inform the user.
+    pub fn cancel_forward_messages(&self) {
+        if self.take_forward_messages().is_some() {
+            self.set_info_message("Forward canceled");
+        }
+    }
+
+    pub fn reply_quote_length(&self) -> i64 {
+        self.reply_quote_length.load(Ordering::Relaxed)
+    }
+
     pub fn reply_message_text(&self) -> MutexGuard<'_, String> {
         self.reply_message_text.lock().unwrap()
     }
 
+    /// Check whether the notifications of a chat are muted.
+    ///
+    /// # Arguments
+    /// * `chat_id` - The id of the chat.
+    ///
+    /// # Returns
+    /// * `bool` - True if the chat is known and muted.
+    pub fn is_chat_muted(&self, chat_id: i64) -> bool {
+        self.chats()
+            .get(&chat_id)
+            .is_some_and(|chat| self.is_muted(chat))
+    }
+
+    /// Check whether the notifications of a chat are muted, resolving the
+    /// mute time of its scope when it follows the default.
+    /// It does not take the lock of the chats, so that it can be used while
+    /// a chat is updated.
+    ///
+    /// # Arguments
+    /// * `chat` - The chat.
+    ///
+    /// # Returns
+    /// * `bool` - True if the chat is muted.
+    pub fn is_muted(&self, chat: &Chat) -> bool {
+        self.scope_mute_for
+            .lock()
+            .unwrap()
+            .is_muted(&chat.r#type, &chat.notification_settings)
+    }
+
+    /// Set the mute time of a notification scope, moving the unread messages
+    /// of the chats that follow it between the muted and the unmuted ones.
+    ///
+    /// # Arguments
+    /// * `scope` - The notification scope.
+    /// * `mute_for` - The time, in seconds, for which the chats are muted.
+    pub fn set_scope_mute_for(&self, scope: &NotificationSettingsScope, mute_for: i32) {
+        let chats = self.chats();
+        let mut scope_mute_for = self.scope_mute_for.lock().unwrap();
+        let mut new_scope_mute_for = *scope_mute_for;
+        *new_scope_mute_for.get_mut(scope) = mute_for;
+        for chat in chats.values() {
+            let settings = &chat.notification_settings;
+            let (was_muted, is_muted) = (
+                scope_mute_for.is_muted(&chat.r#type, settings),
+                new_scope_mute_for.is_muted(&chat.r#type, settings),
+            );
+            if was_muted != is_muted {
+                self.add_unread_count(-chat.unread_count, was_muted);
+                self.add_unread_count(chat.unread_count, is_muted);
+            }
+        }
+        *scope_mute_for = new_scope_mute_for;
+    }
+
     pub fn name_of_open_chat_id(&self) -> Option<String> {
         if let Some(chat) = self.chats().get(&self.open_chat_id()) {
             return Some(chat.title.clone());
@@ -230,19 +1522,67 @@ impl TgContext {
         None
     }
 
-    pub fn get_chats_index(&self) -> Result<Option<Vec<ChatListEntry>>, AppError<Event>> {
-        let chats_index = self.chats_index();
+    /// Get the summaries of the chats of the main list, in the order of the
+    /// last activity.
+    ///
+    /// # Arguments
+    /// * `limit` - The maximum number of chats, or `None` for all the chats.
+    ///
+    /// # Returns
+    /// * `Vec<ChatSummary>` - The summaries of the chats.
+    pub fn chat_summaries(&self, limit: Option<usize>) -> Vec<ChatSummary> {
+        let chats = self.chats();
+        self.chats_index()
+            .iter()
+            .filter_map(|ord_chat| chats.get(&ord_chat.chat_id))
+            .take(limit.unwrap_or(usize::MAX))
+            .map(|chat| ChatSummary {
+                id: chat.id,
+                title: chat.title.clone(),
+                unread_count: chat.unread_count,
+                last_message: chat
+                    .last_message
+                    .as_ref()
+                    .map(|message| MessageEntry::from(message).message_content_to_string_masked()),
+            })
+            .collect()
+    }
+
+    /// Get the entries of the chat list, in the order of the last activity.
+    ///
+    /// # Arguments
+    /// * `chat_folder_id` - The folder whose chats are returned, or `None`
+    ///   for all the chats.
+    ///
+    /// # Returns
+    /// * `Result<Option<Vec<ChatListEntry>>, AppError>` - The entries of the
+    ///   chat list.
+    pub fn get_chats_index(
+        &self,
+        chat_folder_id: Option<i32>,
+    ) -> Result<Option<Vec<ChatListEntry>>, AppError<Event>> {
+        let chats_index = match chat_folder_id {
+            Some(chat_folder_id) => self
+                .chat_folders_index()
+                .get(&chat_folder_id)
+                .cloned()
+                .unwrap_or_default(),
+            None => self.chats_index().clone(),
+        };
         let chats = self.chats();
         let mut chat_list: Vec<ChatListEntry> = Vec::new();
         for ord_chat in chats_index.iter() {
             let mut chat_list_item = ChatListEntry::new();
             chat_list_item.set_chat_id(ord_chat.chat_id);
+            chat_list_item.set_is_pinned(ord_chat.position.is_pinned);
             if let Some(chat) = chats.get(&ord_chat.chat_id) {
+                chat_list_item.set_is_muted(self.is_muted(chat));
                 chat_list_item.set_is_marked_as_unread(chat.unread_count > 0);
                 chat_list_item.set_chat_name(chat.title.clone());
                 chat_list_item.set_last_read_inbox_message_id(chat.last_read_inbox_message_id);
                 chat_list_item.set_last_read_outbox_message_id(chat.last_read_outbox_message_id);
                 chat_list_item.set_unread_count(chat.unread_count);
+                chat_list_item.set_has_scheduled_messages(chat.has_scheduled_messages);
                 if let Some(chat_message) = &chat.last_message {
                     chat_list_item.set_last_message(MessageEntry::from(chat_message));
                 }
@@ -263,8 +1603,12 @@ impl TgContext {
                         }
                     }
                     ChatType::Secret(s) => {
-                        if let Some(_secret_chat) = self.secret_chats().get(&s.secret_chat_id) {
+                        if let Some(secret_chat) = self.secret_chats().get(&s.secret_chat_id) {
                             chat_list_item.set_chat_name(chat.title.clone());
+                            chat_list_item.set_secret_chat_state(secret_chat.state.clone());
+                        }
+                        if let Some(user) = self.users().get(&s.user_id) {
+                            chat_list_item.set_user(user.clone());
                         }
                     }
                 }
@@ -276,3 +1620,581 @@ impl TgContext {
         Ok(Some(chat_list))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::tg::{
+        message_entry::MessageEntry,
+        td_enums::TdFile,
+        tg_context::{
+            ChatLink, ScopeMuteFor, TgContext, MAX_RECENT_CHATS, MESSAGE_VIEWERS_CACHE_DURATION,
+            STATUS_MESSAGE_DURATION,
+        },
+    };
+    use chrono::{Local, TimeZone};
+    use std::{collections::HashMap, time::Instant};
+    use tdlib_rs::{
+        enums::{
+            ChatMemberStatus, ChatType, ConnectionState, MessageSender, SecretChatState,
+            SessionType, UserStatus,
+        },
+        types::{
+            ChatMember, ChatNotificationSettings, ChatTypeBasicGroup, ChatTypeSupergroup,
+            FormattedText, MessageSenderUser, Session, UserStatusOffline, UserStatusOnline,
+            UserStatusRecently,
+        },
+    };
+
+    fn message(id: i64) -> MessageEntry {
+        MessageEntry::from_text(
+            id,
+            1,
+            &FormattedText {
+                text: format!("message {}", id),
+                entities: vec![],
+            },
+        )
+    }
+
+    #[test]
+    fn test_refresh_snapshot() {
+        let tg_context = TgContext::default();
+        assert!(tg_context.snapshot().open_chat_messages.is_empty());
+        tg_context.set_aliases(HashMap::from([(1, "Alice".to_string())]));
+        tg_context.set_open_chat_id(7);
+        tg_context.insert_open_chat_message(message(1));
+        tg_context.insert_open_chat_message(message(2));
+        tg_context.refresh_snapshot();
+        let snapshot = tg_context.snapshot();
+        assert_eq!(snapshot.open_chat_id, 7);
+        assert_eq!(snapshot.open_chat_messages.len(), 2);
+        assert_eq!(snapshot.names.get(&1), Some(&"Alice".to_string()));
+        // The snapshot does not change until it is refreshed.
+        tg_context.clear_open_chat_messages();
+        assert_eq!(snapshot.open_chat_messages.len(), 2);
+        assert_eq!(tg_context.snapshot().open_chat_messages.len(), 2);
+        tg_context.refresh_snapshot();
+        assert!(tg_context.snapshot().open_chat_messages.is_empty());
+    }
+
+    /// Compare how long the backend waits for the messages of the open chat
+    /// while the user interface draws them, when they are read under the lock
+    /// and when they are read from the snapshot.
+    /// Run it with `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn bench_snapshot_contention() {
+        use std::{
+            sync::{
+                atomic::{AtomicBool, AtomicU64, Ordering},
+                Arc,
+            },
+            thread,
+            time::Duration,
+        };
+
+        const FRAMES: usize = 2000;
+        let tg_context = Arc::new(TgContext::default());
+        for id in 0..500 {
+            tg_context.insert_open_chat_message(message(id));
+        }
+        tg_context.refresh_snapshot();
+        let stop = Arc::new(AtomicBool::new(false));
+        let max_wait_nanos = Arc::new(AtomicU64::new(0));
+        // The backend applying a burst of updates, recording the longest
+        // time it waited for the lock.
+        let writer = {
+            let tg_context = Arc::clone(&tg_context);
+            let stop = Arc::clone(&stop);
+            let max_wait_nanos = Arc::clone(&max_wait_nanos);
+            thread::spawn(move || {
+                let mut id = 500;
+                while !stop.load(Ordering::Relaxed) {
+                    let message = message(id);
+                    let start = Instant::now();
+                    let mut messages = tg_context.open_chat_messages();
+                    max_wait_nanos.fetch_max(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+                    messages.insert(0, message);
+                    messages.pop();
+                    drop(messages);
+                    id += 1;
+                    thread::yield_now();
+                }
+            })
+        };
+        // A frame reads each message once, looking up the replied message.
+        let frame = |messages: &[MessageEntry]| {
+            messages
+                .iter()
+                .filter(|m| messages.iter().any(|r| r.id() == m.id() - 1))
+                .count()
+        };
+        // The time the user interface holds the lock of the messages.
+        let mut held = Duration::ZERO;
+        for _ in 0..FRAMES {
+            let start = Instant::now();
+            let messages = tg_context.open_chat_messages();
+            std::hint::black_box(frame(&messages));
+            drop(messages);
+            held += start.elapsed();
+        }
+        let locked = (held, max_wait_nanos.swap(0, Ordering::Relaxed));
+        let mut held = Duration::ZERO;
+        for i in 0..FRAMES {
+            // The snapshot is refreshed once every 10 frames, like a redraw
+            // after a batch of updates.
+            if i % 10 == 0 {
+                let start = Instant::now();
+                tg_context.refresh_snapshot();
+                held += start.elapsed();
+            }
+            std::hint::black_box(frame(&tg_context.snapshot().open_chat_messages));
+        }
+        let snapshot = (held, max_wait_nanos.load(Ordering::Relaxed));
+        stop.store(true, Ordering::Relaxed);
+        writer.join().unwrap();
+        for (name, (held, max_wait_nanos)) in
+            [("under the lock", locked), ("from the snapshot", snapshot)]
+        {
+            println!(
+                "{FRAMES} frames read {name}: lock held for {held:?}, \
+                 longest wait of the backend {:?}",
+                Duration::from_nanos(max_wait_nanos)
+            );
+        }
+    }
+
+    #[test]
+    fn test_alias_wins_over_name() {
+        let tg_context = TgContext::default();
+        assert_eq!(tg_context.try_name_from_chats_or_users(42), None);
+        tg_context.set_aliases(HashMap::from([(42, "Alice".to_string())]));
+        assert_eq!(
+            tg_context.try_name_from_chats_or_users(42),
+            Some("Alice".to_string())
+        );
+        assert_eq!(tg_context.alias(7), None);
+    }
+
+    #[test]
+    fn test_update_download() {
+        let tg_context = TgContext::default();
+        let file = |id: i32, downloaded_size: i64| TdFile {
+            id,
+            size: 100,
+            downloaded_size,
+            is_downloading_active: downloaded_size < 100,
+            is_downloading_completed: downloaded_size == 100,
+            path: String::new(),
+        };
+        tg_context.add_download("photo", file(1, 0));
+        assert!(tg_context.update_download(file(1, 50)));
+        // The files not started by the user are ignored.
+        assert!(!tg_context.update_download(file(2, 50)));
+        assert_eq!(tg_context.downloads().len(), 1);
+        assert_eq!(tg_context.downloads()[0].file.downloaded_size, 50);
+
+        // Downloading the same file again does not add it twice.
+        tg_context.add_download("photo", file(1, 100));
+        assert_eq!(tg_context.downloads().len(), 1);
+        assert!(tg_context.downloads()[0].file.is_downloading_completed);
+
+        tg_context.remove_download(1);
+        assert!(!tg_context.is_download(1));
+    }
+
+    #[test]
+    fn test_insert_open_chat_message_twice() {
+        let tg_context = TgContext::default();
+        assert!(tg_context.insert_open_chat_message(message(1)));
+        assert!(!tg_context.insert_open_chat_message(message(1)));
+        assert_eq!(tg_context.open_chat_messages().len(), 1);
+    }
+
+    #[test]
+    fn test_new_message_then_chat_history() {
+        let tg_context = TgContext::default();
+        tg_context.insert_open_chat_message(message(2));
+        // The same message is loaded again with the chat history
+        assert!(!tg_context.push_open_chat_message(message(2)));
+        assert!(tg_context.push_open_chat_message(message(1)));
+        let ids: Vec<i64> = tg_context
+            .open_chat_messages()
+            .iter()
+            .map(|m| m.id())
+            .collect();
+        assert_eq!(ids, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_replace_open_chat_message() {
+        let tg_context = TgContext::default();
+        tg_context.insert_open_chat_message(message(1));
+        tg_context.insert_open_chat_message(message(100));
+        tg_context.replace_open_chat_message(100, message(2));
+        let ids: Vec<i64> = tg_context
+            .open_chat_messages()
+            .iter()
+            .map(|m| m.id())
+            .collect();
+        assert_eq!(ids, vec![2, 1]);
+    }
+
+    #[test]
+    fn test_replace_open_chat_message_already_present() {
+        let tg_context = TgContext::default();
+        tg_context.insert_open_chat_message(message(2));
+        tg_context.insert_open_chat_message(message(100));
+        tg_context.replace_open_chat_message(100, message(2));
+        assert_eq!(tg_context.open_chat_messages().len(), 1);
+        assert_eq!(tg_context.open_chat_messages()[0].id(), 2);
+    }
+
+    fn session(application: &str, version: &str, platform: &str, location: &str) -> Session {
+        Session {
+            id: 1,
+            is_current: false,
+            is_password_pending: false,
+            is_unconfirmed: false,
+            can_accept_secret_chats: true,
+            can_accept_calls: true,
+            r#type: SessionType::Linux,
+            api_id: 1,
+            application_name: application.to_string(),
+            application_version: version.to_string(),
+            is_official_application: true,
+            device_model: String::new(),
+            platform: platform.to_string(),
+            system_version: String::new(),
+            log_in_date: 0,
+            last_active_date: 0,
+            ip_address: String::new(),
+            location: location.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_session_description() {
+        let mut desktop = session("Telegram Desktop", "5.0", "Linux", "Rome, Italy");
+        desktop.device_model = "Desktop".to_string();
+        desktop.system_version = "6.1".to_string();
+        assert_eq!(
+            TgContext::session_description(&desktop),
+            "Telegram Desktop 5.0, Desktop, Linux 6.1, Rome, Italy"
+        );
+        assert_eq!(
+            TgContext::session_description(&session("tgt", "", "Linux", "")),
+            "tgt, Linux"
+        );
+    }
+
+    #[test]
+    fn test_members_status() {
+        assert_eq!(TgContext::members_status(0, 0, false), "");
+        assert_eq!(TgContext::members_status(1, 0, false), "1 member");
+        assert_eq!(
+            TgContext::members_status(10, 2, false),
+            "10 members, 2 online"
+        );
+        assert_eq!(TgContext::members_status(10, 2, true), "10 subscribers");
+    }
+
+    #[test]
+    fn test_user_status() {
+        assert_eq!(TgContext::user_status(&UserStatus::Empty), "");
+        assert_eq!(
+            TgContext::user_status(&UserStatus::Online(UserStatusOnline { expires: 0 })),
+            "online"
+        );
+        assert_eq!(
+            TgContext::user_status(&UserStatus::Recently(UserStatusRecently {
+                by_my_privacy_settings: false
+            })),
+            "last seen recently"
+        );
+        let now = chrono::Local::now().timestamp() as i32;
+        assert!(
+            TgContext::user_status(&UserStatus::Offline(UserStatusOffline { was_online: now }))
+                .starts_with("last seen at ")
+        );
+        let now = Local.with_ymd_and_hms(2025, 1, 2, 12, 0, 0).unwrap();
+        let at = |day, hour| {
+            Local
+                .with_ymd_and_hms(2025, 1, day, hour, 30, 0)
+                .unwrap()
+                .timestamp() as i32
+        };
+        assert_eq!(TgContext::last_seen(at(2, 9), now), "last seen at 09:30");
+        assert_eq!(
+            TgContext::last_seen(at(1, 23), now),
+            "last seen yesterday at 23:30"
+        );
+        assert_eq!(
+            TgContext::last_seen(at(1, 0), now),
+            "last seen yesterday at 00:30"
+        );
+        assert_eq!(
+            TgContext::last_seen(at(1, 0) - 2 * 86400, now),
+            "last seen on 2024-12-30 00:30"
+        );
+    }
+
+    #[test]
+    fn test_clear() {
+        let tg_context = TgContext::default();
+        tg_context.set_open_chat_id(1);
+        tg_context.insert_open_chat_message(message(1));
+        tg_context.set_reply_message(1, "message 1".to_string());
+        tg_context.clear();
+        assert_eq!(tg_context.open_chat_id(), 0);
+        assert!(tg_context.open_chat_messages().is_empty());
+        assert_eq!(tg_context.reply_message_id(), 0);
+    }
+
+    fn member() -> ChatMember {
+        ChatMember {
+            member_id: MessageSender::User(MessageSenderUser { user_id: 1 }),
+            inviter_user_id: 0,
+            joined_chat_date: 0,
+            status: ChatMemberStatus::Member,
+        }
+    }
+
+    #[test]
+    fn test_scope_mute_for() {
+        let group = ChatType::BasicGroup(ChatTypeBasicGroup { basic_group_id: 1 });
+        let channel = ChatType::Supergroup(ChatTypeSupergroup {
+            supergroup_id: 1,
+            is_channel: true,
+        });
+        let default = ChatNotificationSettings {
+            use_default_mute_for: true,
+            ..Default::default()
+        };
+        let muted = ChatNotificationSettings {
+            mute_for: 3600,
+            ..Default::default()
+        };
+        let scope_mute_for = ScopeMuteFor {
+            group_chats: i32::MAX,
+            ..Default::default()
+        };
+        // The chats that follow the default are muted by their scope.
+        assert!(scope_mute_for.is_muted(&group, &default));
+        assert!(!scope_mute_for.is_muted(&channel, &default));
+        assert!(scope_mute_for.is_muted(&channel, &muted));
+        assert!(!scope_mute_for.is_muted(&group, &ChatNotificationSettings::default()));
+    }
+
+    #[test]
+    fn test_stale_chat_members() {
+        let tg_context = TgContext::default();
+        tg_context.reset_chat_members();
+        let generation = tg_context.chat_members_generation();
+        assert!(tg_context.add_chat_members(generation, vec![member()]));
+        // The member list is opened again while the members are loaded.
+        tg_context.reset_chat_members();
+        assert!(!tg_context.add_chat_members(generation, vec![member()]));
+        tg_context.set_chat_members_loaded(generation, Ok(()));
+        assert!(tg_context.chat_members().is_empty());
+        assert!(tg_context.chat_members_loaded().is_none());
+        let generation = tg_context.chat_members_generation();
+        assert!(tg_context.add_chat_members(generation, vec![member()]));
+        tg_context.set_chat_members_loaded(generation, Ok(()));
+        assert_eq!(tg_context.chat_members().len(), 1);
+        assert_eq!(*tg_context.chat_members_loaded(), Some(Ok(())));
+    }
+
+    #[test]
+    fn test_unread_count() {
+        let tg_context = TgContext::default();
+        tg_context.add_unread_count(3, false);
+        tg_context.add_unread_count(2, true);
+        assert_eq!(tg_context.unread_count(true), 5);
+        assert_eq!(tg_context.unread_count(false), 3);
+        // The muted chat is read
+        tg_context.add_unread_count(-2, true);
+        assert_eq!(tg_context.unread_count(true), 3);
+        assert_eq!(tg_context.unread_count(false), 3);
+        tg_context.clear();
+        assert_eq!(tg_context.unread_count(true), 0);
+    }
+
+    #[test]
+    fn test_temporary_files() {
+        let dir = std::env::temp_dir().join(format!("tgt_temporary_files_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = |name: &str| {
+            let path = dir.join(name);
+            std::fs::write(&path, "photo").unwrap();
+            path
+        };
+        let tg_context = TgContext::default();
+        // The message is sent after its file is added
+        let first = file("first.png");
+        tg_context.start_temporary_file();
+        tg_context.add_temporary_file(Some(1), first.to_string_lossy().to_string());
+        assert!(first.exists());
+        tg_context.remove_temporary_file(1);
+        assert!(!first.exists());
+        // The message is sent before its file is added
+        let second = file("second.png");
+        tg_context.start_temporary_file();
+        tg_context.remove_temporary_file(2);
+        tg_context.add_temporary_file(Some(2), second.to_string_lossy().to_string());
+        assert!(!second.exists());
+        // The file is kept until the context is cleared
+        let third = file("third.png");
+        tg_context.start_temporary_file();
+        tg_context.add_temporary_file(Some(3), third.to_string_lossy().to_string());
+        tg_context.clear();
+        assert!(!third.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_connection_state() {
+        let tg_context = TgContext::default();
+        assert!(!tg_context.is_connection_ready());
+        *tg_context.connection_state() = Some(ConnectionState::Updating);
+        assert!(!tg_context.is_connection_ready());
+        assert_eq!(
+            TgContext::connection_state_text(&ConnectionState::WaitingForNetwork),
+            "Waiting for network"
+        );
+        *tg_context.connection_state() = Some(ConnectionState::Ready);
+        assert!(tg_context.is_connection_ready());
+    }
+
+    #[test]
+    fn test_recent_chats() {
+        let tg_context = TgContext::default();
+        tg_context.set_open_chat_id(1);
+        tg_context.set_open_chat_id(2);
+        tg_context.set_open_chat_id(1);
+        assert_eq!(tg_context.recent_chats(), vec![1, 2]);
+        for chat_id in 10..30 {
+            tg_context.set_open_chat_id(chat_id);
+        }
+        assert_eq!(tg_context.recent_chats().len(), MAX_RECENT_CHATS);
+        assert_eq!(tg_context.recent_chats()[0], 29);
+        tg_context.set_open_chat_id(0);
+        assert_eq!(tg_context.recent_chats()[0], 29);
+    }
+
+    #[test]
+    fn test_secret_chat_state_text() {
+        assert_eq!(
+            TgContext::secret_chat_state_text(&SecretChatState::Pending),
+            "pending"
+        );
+        assert_eq!(
+            TgContext::secret_chat_state_text(&SecretChatState::Ready),
+            "end-to-end encrypted"
+        );
+        assert_eq!(
+            TgContext::secret_chat_state_text(&SecretChatState::Closed),
+            "closed"
+        );
+    }
+
+    #[test]
+    fn test_auto_delete_time_text() {
+        assert_eq!(TgContext::auto_delete_time_text(0), "off");
+        assert_eq!(TgContext::auto_delete_time_text(86400), "1 day");
+        assert_eq!(TgContext::auto_delete_time_text(3 * 86400), "3 days");
+        assert_eq!(TgContext::auto_delete_time_text(7 * 86400), "1 week");
+        assert_eq!(TgContext::auto_delete_time_text(31 * 86400), "1 month");
+        assert_eq!(TgContext::auto_delete_time_text(90), "90 seconds");
+    }
+
+    #[test]
+    fn test_set_message_pinned() {
+        let tg_context = TgContext::default();
+        let text = FormattedText {
+            text: "pinned".to_string(),
+            entities: vec![],
+        };
+        tg_context.set_open_chat_id(1);
+        tg_context.push_open_chat_message(MessageEntry::from_text(10, 2, &text));
+        tg_context.pinned_messages().insert(1, vec![]);
+        tg_context.set_message_pinned(1, 10, true);
+        assert_eq!(tg_context.pinned_messages()[&1][0].id(), 10);
+        tg_context.set_message_pinned(1, 10, false);
+        assert!(tg_context.pinned_messages()[&1].is_empty());
+        // The pinned messages of a chat that is not open are loaded again.
+        tg_context.pinned_messages().insert(3, vec![]);
+        tg_context.set_message_pinned(3, 20, true);
+        assert!(!tg_context.pinned_messages().contains_key(&3));
+    }
+
+    #[test]
+    fn test_status_message() {
+        let tg_context = TgContext::default();
+        assert_eq!(tg_context.status_message(), None);
+        tg_context.set_error_message("error");
+        assert_eq!(
+            tg_context.status_message(),
+            Some(("error".to_string(), true))
+        );
+        tg_context.set_info_message("info");
+        assert_eq!(
+            tg_context.status_message(),
+            Some(("info".to_string(), false))
+        );
+        *tg_context.status_message.lock().unwrap() = Some((
+            "old".to_string(),
+            true,
+            Instant::now() - STATUS_MESSAGE_DURATION,
+        ));
+        assert_eq!(tg_context.status_message(), None);
+    }
+
+    #[test]
+    fn test_message_viewers() {
+        let tg_context = TgContext::default();
+        assert_eq!(tg_context.message_viewers(1, 2), None);
+        tg_context.set_message_viewers(1, 2, Some(vec![3, 4]));
+        assert_eq!(tg_context.message_viewers(1, 2), Some(Some(vec![3, 4])));
+        tg_context.set_message_viewers(1, 5, None);
+        assert_eq!(tg_context.message_viewers(1, 5), Some(None));
+        // The expired viewers are requested again.
+        tg_context.message_viewers.lock().unwrap().insert(
+            (1, 2),
+            (
+                Some(vec![3]),
+                Instant::now() - MESSAGE_VIEWERS_CACHE_DURATION,
+            ),
+        );
+        assert_eq!(tg_context.message_viewers(1, 2), None);
+    }
+
+    #[test]
+    fn test_chat_link_parse() {
+        let username = |name: &str| Some(ChatLink::Username(name.to_string()));
+        assert_eq!(ChatLink::parse("@rustlang"), username("rustlang"));
+        assert_eq!(ChatLink::parse("rust_lang"), username("rust_lang"));
+        assert_eq!(
+            ChatLink::parse("https://t.me/rustlang"),
+            username("rustlang")
+        );
+        assert_eq!(ChatLink::parse(" t.me/rustlang/42 "), username("rustlang"));
+        assert_eq!(
+            ChatLink::parse("https://telegram.me/rustlang?start=1"),
+            username("rustlang")
+        );
+        assert_eq!(
+            ChatLink::parse("https://t.me/+AbC123"),
+            Some(ChatLink::InviteLink("https://t.me/+AbC123".to_string()))
+        );
+        assert_eq!(
+            ChatLink::parse("t.me/joinchat/AbC123"),
+            Some(ChatLink::InviteLink(
+                "https://t.me/joinchat/AbC123".to_string()
+            ))
+        );
+        assert_eq!(ChatLink::parse(""), None);
+        assert_eq!(ChatLink::parse("@"), None);
+        assert_eq!(ChatLink::parse("not a username"), None);
+    }
+}
@@ -5,7 +5,7 @@ use crate::{
     component_name::ComponentName,
     components::{
         component_traits::Component, core_window::CoreWindow, status_bar::StatusBar,
-        title_bar::TitleBar, SMALL_AREA_HEIGHT, SMALL_AREA_WIDTH,
+        title_bar::TitleBar,
     },
     event::Event,
 };
@@ -127,17 +127,22 @@ impl Tui {
             .unwrap()
             .update(Action::UpdateArea(area));
 
+        let app_config = self.app_context.app_config();
+        let (small_area_width, small_area_height) =
+            (app_config.small_area_width, app_config.small_area_height);
+        drop(app_config);
+
         let core_window: &mut dyn std::any::Any =
             self.components.get_mut(&ComponentName::CoreWindow).unwrap();
         if let Some(core_window) = core_window.downcast_mut::<CoreWindow>() {
-            core_window.with_small_area(area.width < SMALL_AREA_WIDTH);
+            core_window.with_small_area(area.width < small_area_width);
         }
 
         let main_layout = Layout::new(
             Direction::Vertical,
             [
                 Constraint::Length(if self.app_context.app_config().show_title_bar {
-                    if area.height > SMALL_AREA_HEIGHT + 5 {
+                    if area.height > small_area_height + 5 {
                         3
                     } else {
                         0
@@ -145,9 +150,9 @@ impl Tui {
                 } else {
                     0
                 }),
-                Constraint::Min(SMALL_AREA_HEIGHT),
+                Constraint::Min(small_area_height),
                 Constraint::Length(if self.app_context.app_config().show_status_bar {
-                    if area.height > SMALL_AREA_HEIGHT + 5 {
+                    if area.height > small_area_height + 5 {
                         3
                     } else {
                         0
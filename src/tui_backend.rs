@@ -134,6 +134,9 @@ impl TuiBackend {
     /// # Returns
     /// * `Result<(), io::Error>` - An Ok result or an error.
     pub fn exit(&self) {
+        // Stop reading the terminal events, so that entering the user
+        // interface again does not start a second reader.
+        self.task.abort();
         match TuiBackend::force_exit(self.mouse, self.paste) {
             Ok(_) => tracing::info!("Tui backend exited"),
             Err(e) => tracing::error!("Error exiting tui backend: {}", e),
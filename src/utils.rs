@@ -1,8 +1,16 @@
 use dirs;
-use std::{env, io, path::PathBuf};
+use ratatui::text::Line;
+use std::{env, io, ops::Range, path::PathBuf, sync::OnceLock};
+use unicode_width::UnicodeWidthChar;
 
 pub const TGT: &str = "tgt";
 pub const TGT_CONFIG_DIR: &str = "TGT_CONFIG_DIR";
+pub const TGT_DATA_DIR: &str = "TGT_DATA_DIR";
+pub const TGT_LOG_PATH: &str = "TGT_LOG_PATH";
+pub const TGT_PROFILE: &str = "TGT_PROFILE";
+
+/// The profile selected with `--profile`, see `set_profile`.
+static PROFILE: OnceLock<String> = OnceLock::new();
 
 /// Get the project directory.
 ///
@@ -32,6 +40,89 @@ pub fn tgt_config_dir() -> io::Result<PathBuf> {
     Ok(tgt_dir()?.join("config"))
 }
 
+/// Check if a profile name is valid, i.e. if it is not empty and it contains
+/// only ASCII letters, digits, `-` and `_`, so that it can be used as a
+/// directory name.
+///
+/// # Arguments
+/// * `name` - The name of the profile.
+///
+/// # Returns
+/// `true` if the name is valid.
+pub fn is_valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Select the profile of the application, e.g. to use several Telegram
+/// accounts. It must be called before the configurations are read, because
+/// the profile namespaces their directories.
+///
+/// # Arguments
+/// * `name` - The name of the profile.
+///
+/// # Returns
+/// An error if the name is not valid or if a profile is already selected.
+pub fn set_profile(name: &str) -> io::Result<()> {
+    if !is_valid_profile_name(name) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid profile name: {name}"),
+        ));
+    }
+    PROFILE.set(name.to_string()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "The profile is already selected",
+        )
+    })
+}
+
+/// Get the selected profile.
+///
+/// # Returns
+/// The name of the profile, or `None` if no profile is selected.
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+/// Get the directory of the selected profile, where its database, its logs
+/// and its configuration are stored. It is the `profiles/<name>` directory
+/// of the project directory, or the project directory if no profile is
+/// selected.
+///
+/// # Returns
+/// The directory of the profile.
+pub fn tgt_profile_dir() -> io::Result<PathBuf> {
+    match profile() {
+        Some(profile) => {
+            let dir = tgt_dir()?.join("profiles").join(profile);
+            std::fs::create_dir_all(&dir)?;
+            Ok(dir)
+        }
+        None => tgt_dir(),
+    }
+}
+
+/// Get the configuration directory of the selected profile. The
+/// configuration files found here override the ones of the other
+/// directories.
+///
+/// # Returns
+/// The configuration directory of the profile.
+pub fn tgt_profile_config_dir() -> io::Result<PathBuf> {
+    match profile() {
+        Some(_) => {
+            let dir = tgt_profile_dir()?.join("config");
+            std::fs::create_dir_all(&dir)?;
+            Ok(dir)
+        }
+        None => tgt_config_dir(),
+    }
+}
+
 /// Fail with an error message and exit the application.
 ///
 /// # Arguments
@@ -61,3 +152,297 @@ pub fn unwrap_or_fail<T, E: std::fmt::Debug>(result: Result<T, E>, msg: &str) ->
         Err(e) => fail_with(msg, e),
     }
 }
+
+/// The score of each character matched by `fuzzy_match`.
+const FUZZY_MATCH_SCORE: i32 = 16;
+/// The bonus of a matched character that follows the previous matched one.
+const FUZZY_CONSECUTIVE_BONUS: i32 = 16;
+/// The bonus of a matched character at the start of a word.
+const FUZZY_WORD_START_BONUS: i32 = 8;
+/// The penalty of each character skipped before or between the matched ones.
+const FUZZY_GAP_PENALTY: i32 = 1;
+
+/// Match a pattern against a text as a subsequence, e.g. "tgt" matches
+/// "Telegram group test".
+/// Among the matches that start at each occurrence of the first character of
+/// the pattern, the one with the best score is returned. The score is higher
+/// when the matched characters are consecutive or at the start of words, and
+/// lower when they are far apart.
+///
+/// # Arguments
+/// * `pattern` - The pattern to match.
+/// * `text` - The text to match the pattern against.
+/// * `case_sensitive` - Whether the case of the characters must match.
+///
+/// # Returns
+/// * `Option<(i32, Vec<usize>)>` - The score and the indices of the matched
+///   characters of the text, or `None` if the text does not contain all the
+///   characters of the pattern in order.
+pub fn fuzzy_match(pattern: &str, text: &str, case_sensitive: bool) -> Option<(i32, Vec<usize>)> {
+    let eq = |a: char, b: char| {
+        if case_sensitive {
+            a == b
+        } else {
+            a.to_lowercase().eq(b.to_lowercase())
+        }
+    };
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let Some(first) = pattern.first() else {
+        return Some((0, vec![]));
+    };
+
+    let mut best: Option<(i32, Vec<usize>)> = None;
+    for start in (0..text.len()).filter(|i| eq(text[*i], *first)) {
+        let mut indices = vec![start];
+        let mut i = start + 1;
+        for p in pattern.iter().skip(1) {
+            match (i..text.len()).find(|j| eq(text[*j], *p)) {
+                Some(j) => {
+                    indices.push(j);
+                    i = j + 1;
+                }
+                None => break,
+            }
+        }
+        if indices.len() < pattern.len() {
+            // The following starts leave even fewer characters to match.
+            break;
+        }
+        let score = fuzzy_score(&text, &indices);
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _)| score > *best_score)
+        {
+            best = Some((score, indices));
+        }
+    }
+    best
+}
+
+/// Compute the score of the characters of a text matched by `fuzzy_match`.
+///
+/// # Arguments
+/// * `text` - The characters of the text.
+/// * `indices` - The indices of the matched characters, in increasing order.
+///
+/// # Returns
+/// * `i32` - The score of the match.
+fn fuzzy_score(text: &[char], indices: &[usize]) -> i32 {
+    let mut score = -(indices.first().copied().unwrap_or_default() as i32) * FUZZY_GAP_PENALTY;
+    for (k, &i) in indices.iter().enumerate() {
+        score += FUZZY_MATCH_SCORE;
+        let word_start = i == 0
+            || !text[i - 1].is_alphanumeric()
+            || (text[i - 1].is_lowercase() && text[i].is_uppercase());
+        if word_start {
+            score += FUZZY_WORD_START_BONUS;
+        }
+        if k > 0 {
+            let gap = i - indices[k - 1] - 1;
+            if gap == 0 {
+                score += FUZZY_CONSECUTIVE_BONUS;
+            } else {
+                score -= gap as i32 * FUZZY_GAP_PENALTY;
+            }
+        }
+    }
+    score
+}
+
+/// Find the occurrences of a pattern in a text, ignoring the case.
+/// The occurrences do not overlap, and an empty pattern has none.
+///
+/// # Arguments
+/// * `pattern` - The pattern to find.
+/// * `text` - The text to search the pattern in.
+///
+/// # Returns
+/// * `Vec<Range<usize>>` - The ranges of the indices of the characters of the
+///   occurrences, in increasing order.
+pub fn find_ignore_case(pattern: &str, text: &str) -> Vec<Range<usize>> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let mut occurrences = vec![];
+    if pattern.is_empty() {
+        return occurrences;
+    }
+    let mut i = 0;
+    while i + pattern.len() <= text.len() {
+        let matched = pattern
+            .iter()
+            .zip(&text[i..])
+            .all(|(p, t)| p.to_lowercase().eq(t.to_lowercase()));
+        if matched {
+            occurrences.push(i..i + pattern.len());
+            i += pattern.len();
+        } else {
+            i += 1;
+        }
+    }
+    occurrences
+}
+
+/// Format a size in bytes in a human-readable way, e.g. `1.5 MB`.
+///
+/// # Arguments
+/// * `bytes` - The size in bytes.
+///
+/// # Returns
+/// * `String` - The formatted size.
+pub fn format_size(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// Truncate a text to a display width, ending it with an ellipsis if it is
+/// truncated. The width of the characters is measured in terminal columns,
+/// so that the wide characters (e.g. CJK and emoji) are never split.
+///
+/// # Arguments
+/// * `text` - The text to truncate.
+/// * `width` - The maximum display width of the result.
+///
+/// # Returns
+/// * `String` - The text, truncated if it is wider than `width`.
+pub fn truncate_to_width(text: &str, width: usize) -> String {
+    let char_width = |c: char| c.width().unwrap_or_default();
+    if text.chars().map(char_width).sum::<usize>() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    // One column is left for the ellipsis.
+    let mut truncated = String::new();
+    let mut truncated_width = 0;
+    for c in text.chars() {
+        if truncated_width + char_width(c) > width - 1 {
+            break;
+        }
+        truncated_width += char_width(c);
+        truncated.push(c);
+    }
+    truncated.push('…');
+    truncated
+}
+
+/// Obscure a text, replacing each character with block characters of the
+/// same display width. The whitespace is kept, so that the shape of the
+/// text is preserved.
+///
+/// # Arguments
+/// * `text` - The text to obscure.
+///
+/// # Returns
+/// * `String` - The obscured text.
+pub fn obscure_text(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_whitespace() {
+                c.to_string()
+            } else {
+                "█".repeat(c.width().unwrap_or_default())
+            }
+        })
+        .collect()
+}
+
+/// Obscure the content of the spans of a line, keeping their style.
+///
+/// # Arguments
+/// * `line` - The line to obscure.
+pub fn obscure_line(line: &mut Line) {
+    for span in line.spans.iter_mut() {
+        span.content = obscure_text(&span.content).into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_valid_profile_name() {
+        assert!(is_valid_profile_name("work"));
+        assert!(is_valid_profile_name("account_2-old"));
+        assert!(!is_valid_profile_name(""));
+        assert!(!is_valid_profile_name("../work"));
+        assert!(!is_valid_profile_name("my work"));
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        assert_eq!(truncate_to_width("Alice", 5), "Alice");
+        assert_eq!(truncate_to_width("Alice Smith", 6), "Alice…");
+        // A wide character that does not fit is not split.
+        assert_eq!(truncate_to_width("日本語", 4), "日…");
+        assert_eq!(truncate_to_width("日本語", 0), "");
+    }
+
+    #[test]
+    fn test_obscure_text() {
+        assert_eq!(obscure_text("Hi there"), "██ █████");
+        assert_eq!(obscure_text("a\tb\n"), "█\t█\n");
+        // The wide characters are replaced by as many blocks as their width.
+        assert_eq!(obscure_text("日本"), "████");
+        assert_eq!(obscure_text(""), "");
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+
+    #[test]
+    fn test_fuzzy_match_subsequence() {
+        let (_, indices) = fuzzy_match("tgt", "Telegram group test", false).unwrap();
+        assert_eq!(indices, vec![0, 4, 15]);
+        assert!(fuzzy_match("tgx", "Telegram group test", false).is_none());
+        assert!(fuzzy_match("gt", "tg", false).is_none());
+        assert_eq!(fuzzy_match("", "Telegram", false), Some((0, vec![])));
+    }
+
+    #[test]
+    fn test_fuzzy_match_case_sensitive() {
+        assert!(fuzzy_match("tel", "Telegram", false).is_some());
+        assert!(fuzzy_match("tel", "Telegram", true).is_none());
+        assert!(fuzzy_match("Tel", "Telegram", true).is_some());
+    }
+
+    #[test]
+    fn test_fuzzy_match_best_start() {
+        // The match starting at the second "g" is consecutive.
+        let (_, indices) = fuzzy_match("gr", "Telegram group", false).unwrap();
+        assert_eq!(indices, vec![9, 10]);
+    }
+
+    #[test]
+    fn test_fuzzy_match_ranking() {
+        let score = |text| fuzzy_match("dev", text, false).unwrap().0;
+        assert!(score("Developers") > score("Daily events"));
+        assert!(score("Daily events") > score("Old video archive"));
+        assert!(score("Rust Dev") > score("undevelopable"));
+    }
+
+    #[test]
+    fn test_find_ignore_case() {
+        assert_eq!(find_ignore_case("tg", "TGT tgt"), vec![0..2, 4..6]);
+        assert_eq!(find_ignore_case("aa", "aaa"), vec![0..2]);
+        assert_eq!(find_ignore_case("è", "Caffè È"), vec![4..5, 6..7]);
+        assert!(find_ignore_case("", "tgt").is_empty());
+        assert!(find_ignore_case("tgt!", "tgt").is_empty());
+    }
+}